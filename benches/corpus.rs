@@ -0,0 +1,90 @@
+//! Measures lexing, parsing, and printing with criterion, over the same
+//! phases as [`bench_corpus`], but with warm-up, many samples, and a
+//! comparison with the previous run.
+//!
+//! The corpus is the files listed in `DCBOR_PARSE_CORPUS`, separated as in
+//! `PATH`, or a few built-in documents if it isn't set:
+//!
+//! ```sh
+//! DCBOR_PARSE_CORPUS=a.diag:b.diag cargo bench
+//! ```
+
+use std::hint::black_box;
+
+use criterion::{
+    BenchmarkId, Criterion, Throughput, criterion_group, criterion_main,
+};
+use dcbor_parse::{
+    PrintOptions, Token, bench::bench_corpus, format_diagnostic,
+    parse_dcbor_item,
+};
+use logos::Logos;
+
+/// The documents measured when no corpus is given.
+const BUILT_IN: &[&str] = &[
+    r#"{"name": "Alice", "age": 30, "tags": ["a", "b", "c"]}"#,
+    "[1, -2, 3.5, 1e300, NaN, true, false, null, h'00112233', 'isA']",
+    r#"{1: [2023-02-08, 1(1675814400)], 2: {"nested": [[[1], 2], 3]}}"#,
+];
+
+fn corpus() -> Vec<String> {
+    let Some(paths) = std::env::var_os("DCBOR_PARSE_CORPUS") else {
+        return BUILT_IN.iter().map(|src| src.to_string()).collect();
+    };
+    let paths: Vec<_> = std::env::split_paths(&paths).collect();
+    // Reports the failures of the corpus the way `bench_corpus` does.
+    match bench_corpus(&paths) {
+        Ok(report) => println!("{report}"),
+        Err(error) => panic!("can't read the corpus: {error}"),
+    }
+    paths
+        .iter()
+        .map(|path| std::fs::read_to_string(path).unwrap())
+        .collect()
+}
+
+fn bench(c: &mut Criterion) {
+    dcbor::register_tags();
+    let sources = corpus();
+    let parsed: Vec<_> = sources
+        .iter()
+        .filter_map(|src| Some((src.as_str(), parse_dcbor_item(src).ok()?)))
+        .collect();
+    let options = PrintOptions::default();
+    let source_len = sources.iter().map(String::len).sum::<usize>();
+    let parsed_len = parsed.iter().map(|(src, _)| src.len()).sum::<usize>();
+    let printed_len = parsed
+        .iter()
+        .map(|(_, item)| format_diagnostic(item, &options).len())
+        .sum::<usize>();
+
+    let mut group = c.benchmark_group("corpus");
+    group.throughput(Throughput::Bytes(source_len as u64));
+    group.bench_function(BenchmarkId::new("lex", sources.len()), |b| {
+        b.iter(|| {
+            for src in &sources {
+                Token::lexer(src).for_each(|token| drop(black_box(token)));
+            }
+        })
+    });
+    group.throughput(Throughput::Bytes(parsed_len as u64));
+    group.bench_function(BenchmarkId::new("parse", parsed.len()), |b| {
+        b.iter(|| {
+            for (src, _) in &parsed {
+                let _ = black_box(parse_dcbor_item(src));
+            }
+        })
+    });
+    group.throughput(Throughput::Bytes(printed_len as u64));
+    group.bench_function(BenchmarkId::new("print", parsed.len()), |b| {
+        b.iter(|| {
+            for (_, item) in &parsed {
+                black_box(format_diagnostic(item, &options));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -0,0 +1,20 @@
+use dcbor_parse::bench::bench_corpus;
+
+fn main() {
+    // Register dcbor tags so dates and URs are handled properly
+    dcbor::register_tags();
+
+    let paths: Vec<String> = std::env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("usage: bench_corpus <file>...");
+        std::process::exit(2);
+    }
+
+    match bench_corpus(&paths) {
+        Ok(report) => println!("{}", report),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
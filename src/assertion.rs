@@ -0,0 +1,105 @@
+use dcbor::{Simple, prelude::*};
+
+/// A type assertion written after an item, as in `42 :: uint` or
+/// `h'00' :: bstr .size 1`: a type with the names used by
+/// [CDDL](https://www.rfc-editor.org/rfc/rfc8610), and optionally the size
+/// of the item in bytes.
+pub(crate) struct TypeAssertion {
+    ty: Type,
+    size: Option<u64>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Type {
+    Any,
+    Uint,
+    Nint,
+    Int,
+    Float,
+    Number,
+    Bstr,
+    Tstr,
+    Bool,
+    True,
+    False,
+    Null,
+    Array,
+    Map,
+}
+
+impl TypeAssertion {
+    /// Parses the text after `::`, or returns `None` if it isn't a known
+    /// type, optionally followed by `.size` and a number.
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        let mut words = text.split_whitespace();
+        let ty = match words.next()? {
+            "any" => Type::Any,
+            "uint" => Type::Uint,
+            "nint" => Type::Nint,
+            "int" => Type::Int,
+            "float" => Type::Float,
+            "number" => Type::Number,
+            "bstr" | "bytes" => Type::Bstr,
+            "tstr" | "text" => Type::Tstr,
+            "bool" => Type::Bool,
+            "true" => Type::True,
+            "false" => Type::False,
+            "null" | "nil" => Type::Null,
+            "array" => Type::Array,
+            "map" => Type::Map,
+            _ => return None,
+        };
+        let size = match words.next() {
+            None => None,
+            Some(".size")
+                if matches!(ty, Type::Uint | Type::Bstr | Type::Tstr) =>
+            {
+                Some(words.next()?.parse().ok()?)
+            }
+            Some(_) => return None,
+        };
+        if words.next().is_some() {
+            return None;
+        }
+        Some(Self { ty, size })
+    }
+
+    /// Returns `true` if `cbor` has the type and size of the assertion. The
+    /// size of a byte or text string is its length in bytes, which must be
+    /// equal to the size, and an unsigned integer must fit in that many
+    /// bytes, as in CDDL.
+    pub(crate) fn matches(&self, cbor: &CBOR) -> bool {
+        let (ty, size_matches) = match cbor.as_case() {
+            CBORCase::Unsigned(n) => {
+                let bytes = u64::from(n.checked_ilog(256).unwrap_or(0) + 1);
+                (Type::Uint, self.size.is_none_or(|size| bytes <= size))
+            }
+            CBORCase::Negative(_) => (Type::Nint, true),
+            CBORCase::ByteString(bytes) => {
+                (Type::Bstr, self.has_len(bytes.len()))
+            }
+            CBORCase::Text(text) => (Type::Tstr, self.has_len(text.len())),
+            CBORCase::Simple(Simple::True) => (Type::True, true),
+            CBORCase::Simple(Simple::False) => (Type::False, true),
+            CBORCase::Simple(Simple::Null) => (Type::Null, true),
+            CBORCase::Simple(Simple::Float(_)) => (Type::Float, true),
+            CBORCase::Array(_) => (Type::Array, true),
+            CBORCase::Map(_) => (Type::Map, true),
+            CBORCase::Tagged(_, _) => (Type::Any, true),
+        };
+        let type_matches = match self.ty {
+            Type::Any => true,
+            Type::Int => matches!(ty, Type::Uint | Type::Nint),
+            Type::Number => {
+                matches!(ty, Type::Uint | Type::Nint | Type::Float)
+            }
+            Type::Bool => matches!(ty, Type::True | Type::False),
+            expected => expected == ty,
+        };
+        type_matches && size_matches
+    }
+
+    fn has_len(&self, len: usize) -> bool {
+        self.size.is_none_or(|size| len as u64 == size)
+    }
+}
@@ -0,0 +1,200 @@
+use std::ops::Range;
+
+use dcbor::prelude::*;
+
+use crate::{DiagValue, LiteralForm, ParseResult, SourceSpan, parse::Parser};
+
+/// A lossless syntax tree of a dCBOR item in diagnostic notation, as
+/// returned by [`parse_dcbor_ast`].
+///
+/// Every byte of the source is covered by exactly one of the
+/// [`tokens`](Self::tokens) or [`trivia`](Self::trivia), so the source can be
+/// rebuilt from the tree, and each node records the tokens it was parsed
+/// from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ast {
+    /// The node of the top-level item.
+    pub root: AstNode,
+    /// Every token of the source, in source order.
+    pub tokens: Vec<AstToken>,
+    /// The spans of the whitespace and comments between the tokens, in
+    /// source order.
+    pub trivia: Vec<SourceSpan>,
+    /// The value the tree was built from.
+    pub value: DiagValue,
+}
+
+/// A token of an [`Ast`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AstToken {
+    /// The name of the [`TokenKind`](crate::TokenKind) of the token in the
+    /// [`grammar`](crate::grammar), such as `BracketOpen` or `Number`.
+    pub kind: &'static str,
+    /// The span of the token.
+    pub span: SourceSpan,
+}
+
+/// What a node of an [`Ast`] represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AstNodeKind {
+    /// An item written in the given form. The children of an array are its
+    /// items, those of a map are its entries, and that of a tagged value is
+    /// its content.
+    Item(LiteralForm),
+    /// A `key: value` entry of a map, whose children are the key and the
+    /// value.
+    Entry,
+    /// An `!include("name")` directive. It has no children, as the included
+    /// item was parsed from another document.
+    Include,
+}
+
+/// A node of an [`Ast`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AstNode {
+    pub kind: AstNodeKind,
+    /// The span of the source the node was parsed from.
+    pub span: SourceSpan,
+    /// The range of [`Ast::tokens`] the node was parsed from, including the
+    /// tokens of its children.
+    pub tokens: Range<usize>,
+    /// The child nodes, in source order.
+    pub children: Vec<AstNode>,
+}
+
+impl Ast {
+    /// Returns the dCBOR item the tree represents.
+    pub fn to_cbor(&self) -> CBOR { self.value.to_cbor() }
+
+    /// Returns the nodes whose spans contain `offset`, from the root down
+    /// to the innermost one, or an empty path if the offset is outside the
+    /// root.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{AstNodeKind, LiteralForm, parse_dcbor_ast};
+    /// let ast = parse_dcbor_ast(r#"{"a": [1, 2]}"#).unwrap();
+    /// let path = ast.nodes_at(10);
+    /// let kinds: Vec<_> = path.iter().map(|node| node.kind).collect();
+    /// assert_eq!(
+    ///     kinds,
+    ///     [
+    ///         AstNodeKind::Item(LiteralForm::Map),
+    ///         AstNodeKind::Entry,
+    ///         AstNodeKind::Item(LiteralForm::Array),
+    ///         AstNodeKind::Item(LiteralForm::Integer),
+    ///     ]
+    /// );
+    /// ```
+    pub fn nodes_at(&self, offset: usize) -> Vec<&AstNode> {
+        let mut path = Vec::new();
+        let mut node = &self.root;
+        while node.span.contains(offset) {
+            path.push(node);
+            match node.children.iter().find(|c| c.span.contains(offset)) {
+                Some(child) => node = child,
+                None => break,
+            }
+        }
+        path
+    }
+}
+
+/// Parses a dCBOR item from a string input into a lossless syntax tree,
+/// for tools such as editors that must map values back to the source.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{AstNodeKind, LiteralForm, parse_dcbor_ast};
+/// let src = "[1, # one\n 0x10(2)]";
+/// let ast = parse_dcbor_ast(src).unwrap();
+/// assert_eq!(ast.to_cbor().diagnostic_flat(), "[1, 16(2)]");
+/// let tagged = &ast.root.children[1];
+/// assert_eq!(
+///     tagged.kind,
+///     AstNodeKind::Item(LiteralForm::NumberTaggedValue)
+/// );
+/// assert_eq!(&src[tagged.span.range()], "0x10(2)");
+/// let kinds: Vec<_> =
+///     ast.tokens[tagged.tokens.clone()].iter().map(|t| t.kind).collect();
+/// assert_eq!(kinds, ["TagValue", "Number", "ParenthesisClose"]);
+/// assert_eq!(&src[ast.trivia[0].range()], " # one\n ");
+/// ```
+pub fn parse_dcbor_ast(src: &str) -> ParseResult<Ast> {
+    let mut parser = Parser::new(src).with_tokens();
+    let value = parser.parse_all()?;
+    let tokens: Vec<AstToken> = parser
+        .take_tokens()
+        .into_iter()
+        .map(|(kind, span)| AstToken { kind, span })
+        .collect();
+    let mut trivia = Vec::new();
+    let mut offset = 0;
+    for token in &tokens {
+        if token.span.start > offset {
+            trivia.push(SourceSpan::new(offset, token.span.start));
+        }
+        offset = token.span.end;
+    }
+    if src.len() > offset {
+        trivia.push(SourceSpan::new(offset, src.len()));
+    }
+    let root = node(&value, &tokens);
+    Ok(Ast { root, tokens, trivia, value })
+}
+
+//
+// === Private Functions ===
+//
+
+fn node(value: &DiagValue, tokens: &[AstToken]) -> AstNode {
+    let (kind, children) = match value {
+        DiagValue::Tagged { item, .. } => {
+            (value.literal_form(), vec![node(item, tokens)])
+        }
+        DiagValue::Array { items, .. } => (
+            LiteralForm::Array,
+            items.iter().map(|item| node(item, tokens)).collect(),
+        ),
+        DiagValue::Map { entries, .. } => {
+            let entries = entries
+                .iter()
+                .map(|(key, value)| {
+                    let span = key.span().merge(value.span());
+                    AstNode {
+                        kind: AstNodeKind::Entry,
+                        span,
+                        tokens: token_range(span, tokens),
+                        children: vec![node(key, tokens), node(value, tokens)],
+                    }
+                })
+                .collect();
+            (LiteralForm::Map, entries)
+        }
+        DiagValue::Included { span, .. } => {
+            return AstNode {
+                kind: AstNodeKind::Include,
+                span: *span,
+                tokens: token_range(*span, tokens),
+                children: Vec::new(),
+            };
+        }
+        _ => (value.literal_form(), Vec::new()),
+    };
+    let span = value.span();
+    AstNode {
+        kind: AstNodeKind::Item(kind),
+        span,
+        tokens: token_range(span, tokens),
+        children,
+    }
+}
+
+/// Returns the range of `tokens` that lie within `span`.
+fn token_range(span: SourceSpan, tokens: &[AstToken]) -> Range<usize> {
+    let start = tokens.partition_point(|token| token.span.start < span.start);
+    let end = tokens.partition_point(|token| token.span.start < span.end);
+    start..end
+}
@@ -0,0 +1,232 @@
+use std::ops::Range;
+
+use dcbor::prelude::*;
+
+use crate::{
+    DiagValue, LiteralForm, ParseResult, SourceSpan, parse_diag_value,
+    size::{encoded_size, head_len},
+};
+
+/// Records where a leaf of a parsed item came from and where it ended up: the
+/// span and form of its literal, and the range of the item's deterministic
+/// encoding that holds it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    /// The span of the literal.
+    pub span: SourceSpan,
+    /// The form the literal was written in.
+    pub form: LiteralForm,
+    /// The range of `cbor.to_cbor_data()` that encodes the leaf, including
+    /// its head and, for dates and URs, its tag.
+    pub bytes: Range<usize>,
+    /// The name of the included document the span refers to, or `None` if
+    /// it refers to the source that was parsed.
+    pub document: Option<String>,
+}
+
+/// The provenance of every leaf of a parsed item, for audit logs.
+///
+/// A leaf is a literal that isn't an array, map, or tagged value: a number,
+/// string, byte string, date, known value, UR, boolean, or `null`. The
+/// entries are in source order, which differs from encoding order wherever a
+/// map's keys weren't written in deterministic order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditTrail {
+    /// The entry for each leaf, in source order.
+    pub entries: Vec<AuditEntry>,
+}
+
+impl AuditTrail {
+    /// Returns the audit trail of a parsed value.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{AuditTrail, LiteralForm, parse_diag_value};
+    /// let src = r#"{"b": h'00', "a": 1.5}"#;
+    /// let value = parse_diag_value(src).unwrap();
+    /// let trail = AuditTrail::collect(&value);
+    /// assert_eq!(trail.entries.len(), 4);
+    /// let entry = &trail.entries[1];
+    /// assert_eq!(&src[entry.span.range()], "h'00'");
+    /// assert_eq!(entry.form, LiteralForm::HexByteString);
+    /// // `"a"` sorts first, so `h'00'` is encoded last.
+    /// let data = value.to_cbor().to_cbor_data();
+    /// assert_eq!(&data[entry.bytes.clone()], [0x41, 0x00]);
+    /// assert_eq!(entry.bytes.end, data.len());
+    /// ```
+    pub fn collect(value: &DiagValue) -> AuditTrail {
+        let mut entries = Vec::new();
+        layout(value, 0, None, &mut Vec::new(), &mut entries);
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        AuditTrail {
+            entries: entries.into_iter().map(|(_, entry)| entry).collect(),
+        }
+    }
+
+    /// Returns the trail as a CBOR array with a map for each entry, with the
+    /// keys `span` and `bytes`, each a `[start, end]` array, `form`, the
+    /// [`name`](LiteralForm::name) of the form, and `document`, which is
+    /// present only for entries in included documents.
+    pub fn to_cbor(&self) -> CBOR {
+        let entries: Vec<CBOR> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut map = Map::new();
+                map.insert("span", vec![entry.span.start, entry.span.end]);
+                map.insert("form", entry.form.name());
+                let bytes = vec![entry.bytes.start, entry.bytes.end];
+                map.insert("bytes", bytes);
+                if let Some(document) = &entry.document {
+                    map.insert("document", document.as_str());
+                }
+                map.into()
+            })
+            .collect();
+        entries.into()
+    }
+
+    /// Returns the trail as a JSON array with the same structure as
+    /// [`to_cbor`](Self::to_cbor).
+    pub fn to_json(&self) -> String {
+        let entries: Vec<String> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut fields = vec![
+                    format!(
+                        "\"span\": [{}, {}]",
+                        entry.span.start, entry.span.end
+                    ),
+                    format!("\"form\": {:?}", entry.form.name()),
+                    format!(
+                        "\"bytes\": [{}, {}]",
+                        entry.bytes.start, entry.bytes.end
+                    ),
+                ];
+                if let Some(document) = &entry.document {
+                    let document = json_string(document);
+                    fields.push(format!("\"document\": {document}"));
+                }
+                format!("{{{}}}", fields.join(", "))
+            })
+            .collect();
+        format!("[{}]", entries.join(", "))
+    }
+}
+
+/// Parses a dCBOR item from a string input, returning it along with its
+/// [`AuditTrail`].
+///
+/// Signing tools can store the trail beside a signature to show which text
+/// the signer approved produced which of the signed bytes.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::parse_dcbor_item_with_audit;
+/// let src = "[1, \"hi\"]";
+/// let (cbor, trail) = parse_dcbor_item_with_audit(src).unwrap();
+/// assert_eq!(cbor.to_cbor_data(), [0x82, 0x01, 0x62, 0x68, 0x69]);
+/// assert_eq!(trail.entries[1].span.range(), 4..8);
+/// assert_eq!(trail.entries[1].bytes, 2..5);
+/// assert_eq!(
+///     trail.to_json(),
+///     r#"[{"span": [1, 2], "form": "integer", "bytes": [1, 2]}, "#.to_owned()
+///         + r#"{"span": [4, 8], "form": "string", "bytes": [2, 5]}]"#
+/// );
+/// ```
+pub fn parse_dcbor_item_with_audit(
+    src: &str,
+) -> ParseResult<(CBOR, AuditTrail)> {
+    let value = parse_diag_value(src)?;
+    let trail = AuditTrail::collect(&value);
+    Ok((value.to_cbor(), trail))
+}
+
+//
+// === Private Functions ===
+//
+
+/// Adds an entry for each leaf of `value`, which is encoded at `offset`,
+/// keyed by its position in source order, and returns the length of the
+/// encoding of `value`.
+fn layout(
+    value: &DiagValue,
+    offset: usize,
+    document: Option<&str>,
+    order: &mut SourceOrder,
+    entries: &mut Vec<(SourceOrder, AuditEntry)>,
+) -> usize {
+    match value {
+        DiagValue::Tagged { tag, item, .. } => {
+            let head = head_len(tag.value());
+            head + layout(item, offset + head, document, order, entries)
+        }
+        DiagValue::Array { items, .. } => {
+            let mut len = head_len(items.len() as u64);
+            for (index, item) in items.iter().enumerate() {
+                order.push(index);
+                len += layout(item, offset + len, document, order, entries);
+                order.pop();
+            }
+            len
+        }
+        DiagValue::Map { entries: map, .. } => {
+            let mut sorted: Vec<_> = map
+                .iter()
+                .enumerate()
+                .map(|(index, (key, value))| {
+                    (key.to_cbor().to_cbor_data(), index, key, value)
+                })
+                .collect();
+            sorted.sort_by(|a, b| a.0.cmp(&b.0));
+            let mut len = head_len(map.len() as u64);
+            for (_, index, key, value) in sorted {
+                order.push(2 * index);
+                len += layout(key, offset + len, document, order, entries);
+                order.pop();
+                order.push(2 * index + 1);
+                len += layout(value, offset + len, document, order, entries);
+                order.pop();
+            }
+            len
+        }
+        DiagValue::Included { name, item, .. } => {
+            layout(item, offset, Some(name), order, entries)
+        }
+        _ => {
+            let len = encoded_size(&value.to_cbor());
+            let entry = AuditEntry {
+                span: value.span(),
+                form: value.literal_form(),
+                bytes: offset..offset + len,
+                document: document.map(str::to_owned),
+            };
+            entries.push((order.clone(), entry));
+            len
+        }
+    }
+}
+
+/// The position of a leaf in source order: the index of each array item or
+/// map key or value on the way to it. Sorting by these puts leaves in the
+/// order they were written.
+type SourceOrder = Vec<usize>;
+
+fn json_string(s: &str) -> String {
+    let mut result = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            c if c.is_control() => {
+                result.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
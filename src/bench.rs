@@ -0,0 +1,164 @@
+//! Measuring the parser's performance over a corpus of documents.
+//!
+//! This crate is not optimized for performance. These functions let users
+//! find out whether it is fast enough for their own documents, and let
+//! performance regressions be tracked across releases. The
+//! `bench_corpus` example in this repository runs them over the files named
+//! on its command line.
+//!
+//! [`bench_corpus`] gives a quick mean for each phase. For tracking
+//! regressions, the `corpus` bench in this repository measures the same
+//! phases with criterion, which warms up, reports the variance, and compares
+//! each run with the last: `cargo bench` measures the files listed in
+//! `DCBOR_PARSE_CORPUS`, or a few built-in documents.
+//!
+//! ```rust
+//! # use dcbor_parse::bench;
+//! let dir = std::env::temp_dir().join("dcbor-parse-bench-doc");
+//! std::fs::create_dir_all(&dir).unwrap();
+//! let path = dir.join("item.diag");
+//! std::fs::write(&path, r#"{"a": [1, 2, 3], "b": h'0102'}"#).unwrap();
+//! let report = bench::bench_corpus([&path]).unwrap();
+//! assert_eq!((report.files, report.failures), (1, 0));
+//! println!("{report}");
+//! ```
+
+use std::{
+    fmt, fs, io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use dcbor::prelude::*;
+use logos::Logos;
+
+use crate::{PrintOptions, Token, format_diagnostic, parse_dcbor_item};
+
+/// How long each phase is repeated for, at least, to smooth out noise.
+const MIN_DURATION: Duration = Duration::from_millis(100);
+
+/// The time taken by one phase of processing a corpus.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhaseTiming {
+    /// The mean time for one pass over the corpus.
+    pub duration: Duration,
+    /// The number of bytes processed in one pass.
+    pub bytes: usize,
+}
+
+impl PhaseTiming {
+    /// Returns the throughput in bytes per second, or `0.0` if the phase took
+    /// no measurable time.
+    pub fn bytes_per_second(&self) -> f64 {
+        let seconds = self.duration.as_secs_f64();
+        if seconds == 0.0 { 0.0 } else { self.bytes as f64 / seconds }
+    }
+}
+
+impl fmt::Display for PhaseTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} bytes in {:?} ({:.2} MB/s)",
+            self.bytes,
+            self.duration,
+            self.bytes_per_second() / 1_000_000.0
+        )
+    }
+}
+
+/// The results of [`bench_corpus`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchReport {
+    /// The number of files in the corpus.
+    pub files: usize,
+    /// The number of files that failed to parse. These are lexed, but not
+    /// parsed or printed.
+    pub failures: usize,
+    /// Splitting the source of every file into tokens.
+    pub lex: PhaseTiming,
+    /// Parsing every file that parses successfully. The bytes are those of
+    /// the source.
+    pub parse: PhaseTiming,
+    /// Printing every parsed item in diagnostic notation. The bytes are those
+    /// of the printed output.
+    pub print: PhaseTiming,
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "files: {} ({} failed)", self.files, self.failures)?;
+        writeln!(f, "lex:   {}", self.lex)?;
+        writeln!(f, "parse: {}", self.parse)?;
+        write!(f, "print: {}", self.print)
+    }
+}
+
+/// Measures lexing, parsing, and printing over the files at `paths`, each of
+/// which contains a dCBOR item in diagnostic notation.
+///
+/// Each phase is repeated over the whole corpus until it has run for at least
+/// 100 milliseconds, and the mean time for one pass is reported.
+///
+/// # Errors
+///
+/// Returns an error if a file can't be read, or isn't UTF-8.
+pub fn bench_corpus(
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> io::Result<BenchReport> {
+    let sources = paths
+        .into_iter()
+        .map(fs::read_to_string)
+        .collect::<io::Result<Vec<_>>>()?;
+    let parsed: Vec<(&str, CBOR)> = sources
+        .iter()
+        .filter_map(|src| Some((src.as_str(), parse_dcbor_item(src).ok()?)))
+        .collect();
+    let options = PrintOptions::default();
+
+    let lex = time(total_len(sources.iter().map(String::as_str)), || {
+        for src in &sources {
+            Token::lexer(src).for_each(drop);
+        }
+    });
+    let parse = time(total_len(parsed.iter().map(|(src, _)| *src)), || {
+        for (src, _) in &parsed {
+            let _ = parse_dcbor_item(src);
+        }
+    });
+    let printed = parsed
+        .iter()
+        .map(|(_, item)| format_diagnostic(item, &options).len())
+        .sum();
+    let print = time(printed, || {
+        for (_, item) in &parsed {
+            format_diagnostic(item, &options);
+        }
+    });
+
+    Ok(BenchReport {
+        files: sources.len(),
+        failures: sources.len() - parsed.len(),
+        lex,
+        parse,
+        print,
+    })
+}
+
+//
+// === Private Functions ===
+//
+
+fn total_len<'a>(sources: impl Iterator<Item = &'a str>) -> usize {
+    sources.map(str::len).sum()
+}
+
+fn time(bytes: usize, mut pass: impl FnMut()) -> PhaseTiming {
+    let start = Instant::now();
+    let mut passes = 0u32;
+    while passes == 0 || start.elapsed() < MIN_DURATION {
+        pass();
+        passes += 1;
+    }
+    PhaseTiming { duration: start.elapsed() / passes, bytes }
+}
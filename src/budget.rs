@@ -0,0 +1,78 @@
+use crate::{
+    DiagValue, ParseResult, encoded_size, parse::Parser, size::head_len,
+};
+
+/// Limits on how much of a source [`parse_diag_value_budgeted`] parses.
+///
+/// At least one item is always parsed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Budget {
+    /// The number of items after which parsing stops. Every item counts,
+    /// including arrays, maps, tagged items, and their contents.
+    pub max_items: Option<usize>,
+    /// The approximate number of bytes of dCBOR encoding after which parsing
+    /// stops.
+    pub max_bytes: Option<usize>,
+}
+
+impl Budget {
+    pub(crate) fn is_spent(&self, items: usize, bytes: usize) -> bool {
+        self.max_items.is_some_and(|max| items >= max)
+            || self.max_bytes.is_some_and(|max| bytes >= max)
+    }
+}
+
+/// Marks a value that was cut short because the budget was spent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Truncated {
+    /// The offset in the source just past the last item that was parsed.
+    pub offset: usize,
+}
+
+/// The result of [`parse_diag_value_budgeted`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetedValue {
+    /// The value parsed. If the budget was spent, each array, map, and tagged
+    /// item that was still open contains only the items parsed before then.
+    pub value: DiagValue,
+    /// Present if the budget was spent before the end of the source.
+    pub truncated: Option<Truncated>,
+}
+
+/// Parses a dCBOR item from a string input into a [`DiagValue`], stopping once
+/// `budget` is spent.
+///
+/// Useful for a quick look at the structure of an enormous input. Errors in
+/// the source before the budget is spent are reported as usual; the source
+/// after it is not examined.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{Budget, parse_diag_value_budgeted};
+/// let budget = Budget { max_items: Some(3), ..Default::default() };
+/// let result =
+///     parse_diag_value_budgeted("[1, [2, 3, 4], 5]", budget).unwrap();
+/// assert_eq!(result.value.to_cbor().diagnostic_flat(), "[1, [2, 3]]");
+/// assert_eq!(result.truncated.unwrap().offset, 9);
+/// ```
+pub fn parse_diag_value_budgeted(
+    src: &str,
+    budget: Budget,
+) -> ParseResult<BudgetedValue> {
+    let mut parser = Parser::new(src).with_budget(budget);
+    let value = parser.parse_all()?;
+    let truncated = parser.truncated_at.map(|offset| Truncated { offset });
+    Ok(BudgetedValue { value, truncated })
+}
+
+/// Returns the number of bytes an item adds to the dCBOR encoding, not
+/// counting any items it contains.
+pub(crate) fn encoded_len(value: &DiagValue) -> usize {
+    match value {
+        DiagValue::Array { items, .. } => head_len(items.len() as u64),
+        DiagValue::Map { entries, .. } => head_len(entries.len() as u64),
+        DiagValue::Tagged { tag, .. } => head_len(tag.value()),
+        _ => encoded_size(&value.to_cbor()),
+    }
+}
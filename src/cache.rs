@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use dcbor::prelude::*;
+
+use crate::{
+    ComposeResult, ParseResult,
+    compose::{compose_array_with, compose_map_with},
+    parse_dcbor_item,
+};
+
+/// Storage for the items parsed by a [`ParseCache`], keyed by the SHA-256
+/// digest of their source.
+///
+/// Implement this to keep parsed items somewhere other than in memory, or to
+/// bound how many are kept.
+pub trait CacheStore {
+    /// Returns the item parsed from the source with digest `key`, if it is
+    /// stored.
+    fn get(&self, key: &[u8; 32]) -> Option<CBOR>;
+
+    /// Stores the item parsed from the source with digest `key`.
+    fn insert(&mut self, key: [u8; 32], item: CBOR);
+}
+
+/// A [`CacheStore`] that keeps every item in memory.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    items: HashMap<[u8; 32], CBOR>,
+}
+
+impl MemoryStore {
+    /// Returns the number of stored items.
+    pub fn len(&self) -> usize { self.items.len() }
+
+    /// Returns `true` if no items are stored.
+    pub fn is_empty(&self) -> bool { self.items.is_empty() }
+}
+
+impl CacheStore for MemoryStore {
+    fn get(&self, key: &[u8; 32]) -> Option<CBOR> {
+        self.items.get(key).cloned()
+    }
+
+    fn insert(&mut self, key: [u8; 32], item: CBOR) {
+        self.items.insert(key, item);
+    }
+}
+
+/// Parses dCBOR items, reusing the result when the same source is parsed
+/// again.
+///
+/// Sources are identified by their SHA-256 digest, so the cache never holds
+/// on to the source text. Sources that fail to parse aren't cached, and are
+/// parsed again every time.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::ParseCache;
+/// let mut cache = ParseCache::new();
+/// let cbor = cache.compose_dcbor_array(&["[1, 2]", "3", "[1, 2]"]).unwrap();
+/// assert_eq!(cbor.diagnostic_flat(), "[[1, 2], 3, [1, 2]]");
+/// assert_eq!((cache.hits(), cache.misses()), (1, 2));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ParseCache<S = MemoryStore> {
+    store: S,
+    hits: usize,
+    misses: usize,
+}
+
+impl ParseCache {
+    /// Creates a cache that keeps every parsed item in memory.
+    pub fn new() -> Self { Self::default() }
+}
+
+impl<S: CacheStore> ParseCache<S> {
+    /// Creates a cache that keeps parsed items in `store`.
+    pub fn with_store(store: S) -> Self { Self { store, hits: 0, misses: 0 } }
+
+    /// Parses a dCBOR item from a string input, as
+    /// [`parse_dcbor_item`] does.
+    pub fn parse_dcbor_item(&mut self, src: &str) -> ParseResult<CBOR> {
+        let key = bc_crypto::sha256(src.as_bytes());
+        if let Some(item) = self.store.get(&key) {
+            self.hits += 1;
+            return Ok(item);
+        }
+        self.misses += 1;
+        let item = parse_dcbor_item(src)?;
+        self.store.insert(key, item.clone());
+        Ok(item)
+    }
+
+    /// Composes a dCBOR array, as
+    /// [`compose_dcbor_array`](crate::compose_dcbor_array) does.
+    pub fn compose_dcbor_array(
+        &mut self,
+        array: &[&str],
+    ) -> ComposeResult<CBOR> {
+        compose_array_with(array, |src| self.parse_dcbor_item(src))
+    }
+
+    /// Composes a dCBOR map, as
+    /// [`compose_dcbor_map`](crate::compose_dcbor_map) does.
+    pub fn compose_dcbor_map(
+        &mut self,
+        array: &[&str],
+    ) -> ComposeResult<CBOR> {
+        compose_map_with(array, |src| self.parse_dcbor_item(src))
+    }
+
+    /// Returns the number of parses answered from the store.
+    pub fn hits(&self) -> usize { self.hits }
+
+    /// Returns the number of parses that weren't in the store.
+    pub fn misses(&self) -> usize { self.misses }
+
+    /// Returns the store.
+    pub fn store(&self) -> &S { &self.store }
+
+    /// Returns the store, consuming the cache.
+    pub fn into_store(self) -> S { self.store }
+}
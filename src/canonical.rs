@@ -0,0 +1,247 @@
+use std::fmt;
+
+use dcbor::prelude::*;
+use unicode_normalization::is_nfc;
+
+use crate::{
+    DiagValue, MemoryEstimate, NumberValue, ParseError, ParseResult,
+    SourceSpan, parse_diag_value,
+};
+
+/// A way in which the canonical dCBOR encoding of an item differs from a
+/// naive reading of the source it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Canonicalization {
+    /// A floating point literal with no fractional part, such as `1.0` or
+    /// `1e2`, is encoded as an integer.
+    FloatReducedToInteger,
+    /// An integer literal outside the 64-bit range is encoded as a bignum,
+    /// tag 2 or 3.
+    IntegerEncodedAsBignum,
+    /// A floating point literal is encoded in fewer than 64 bits without loss
+    /// of precision.
+    FloatNarrowed {
+        /// The number of bits in the encoding: 16 or 32.
+        bits: u8,
+    },
+    /// `NaN` is encoded as the single canonical half-precision NaN.
+    NaNCanonicalized,
+    /// A text string is normalized to Unicode Normalization Form C.
+    TextNormalized,
+    /// A date literal is encoded as tag 1 with a numeric epoch time.
+    DateAsEpochTime,
+    /// The entries of a map are encoded in an order other than the order in
+    /// which they were written.
+    MapKeysReordered,
+}
+
+impl fmt::Display for Canonicalization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Canonicalization::FloatReducedToInteger => {
+                write!(f, "float with no fractional part encoded as integer")
+            }
+            Canonicalization::IntegerEncodedAsBignum => {
+                write!(f, "integer outside the 64-bit range encoded as bignum")
+            }
+            Canonicalization::FloatNarrowed { bits } => {
+                write!(f, "float encoded losslessly in {bits} bits")
+            }
+            Canonicalization::NaNCanonicalized => {
+                write!(f, "NaN encoded as canonical half-precision NaN")
+            }
+            Canonicalization::TextNormalized => {
+                write!(f, "text normalized to Unicode NFC")
+            }
+            Canonicalization::DateAsEpochTime => {
+                write!(f, "date encoded as tag 1 with numeric epoch time")
+            }
+            Canonicalization::MapKeysReordered => {
+                write!(f, "map entries reordered by encoded key")
+            }
+        }
+    }
+}
+
+/// A [`Canonicalization`] along with the span of the source it applies to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanonicalizationNote {
+    pub kind: Canonicalization,
+    pub span: SourceSpan,
+}
+
+/// Describes every place where the canonical dCBOR encoding of an item will
+/// differ from a naive reading of its source.
+///
+/// Useful for teaching how dCBOR encodes values, and as a strictness gate
+/// that rejects sources that aren't already written in canonical form.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CanonicalizationReport {
+    pub notes: Vec<CanonicalizationNote>,
+    /// An estimate of the memory the parsed item holds, present when the
+    /// report comes from [`parse_dcbor_item_with_report`].
+    pub memory: Option<MemoryEstimate>,
+}
+
+impl CanonicalizationReport {
+    /// Builds the report for a parsed value.
+    pub fn new(value: &DiagValue) -> Self {
+        let mut report = Self::default();
+        report.visit(value);
+        report
+    }
+
+    /// Returns `true` if the source reads exactly as it will be encoded.
+    pub fn is_empty(&self) -> bool { self.notes.is_empty() }
+
+    fn note(&mut self, kind: Canonicalization, span: SourceSpan) {
+        self.notes.push(CanonicalizationNote { kind, span });
+    }
+
+    fn visit(&mut self, value: &DiagValue) {
+        match value {
+            DiagValue::Number { value: number, span, .. } => {
+                let cbor = CBOR::from(number.clone());
+                let data = cbor.to_cbor_data();
+                let written_as_integer =
+                    !matches!(number, NumberValue::Float(_));
+                if number.to_f64().is_nan() {
+                    self.note(Canonicalization::NaNCanonicalized, *span);
+                } else if matches!(
+                    cbor.as_case(),
+                    CBORCase::Unsigned(_) | CBORCase::Negative(_)
+                ) {
+                    if !written_as_integer {
+                        self.note(
+                            Canonicalization::FloatReducedToInteger,
+                            *span,
+                        );
+                    }
+                } else if let CBORCase::Tagged(..) = cbor.as_case() {
+                    self.note(Canonicalization::IntegerEncodedAsBignum, *span);
+                } else {
+                    let bits = match data[0] {
+                        0xf9 => Some(16),
+                        0xfa => Some(32),
+                        _ => None,
+                    };
+                    if let Some(bits) = bits {
+                        self.note(
+                            Canonicalization::FloatNarrowed { bits },
+                            *span,
+                        );
+                    }
+                }
+            }
+            DiagValue::Text { value: text, span, .. } if !is_nfc(text) => {
+                self.note(Canonicalization::TextNormalized, *span);
+            }
+            DiagValue::Date { span, .. } => {
+                self.note(Canonicalization::DateAsEpochTime, *span);
+            }
+            DiagValue::Tagged { item, .. } => self.visit(item),
+            DiagValue::Array { items, .. } => {
+                items.iter().for_each(|item| self.visit(item));
+            }
+            DiagValue::Map { entries, span } => {
+                let keys: Vec<Vec<u8>> = entries
+                    .iter()
+                    .map(|(key, _)| key.to_cbor().to_cbor_data())
+                    .collect();
+                if !keys.is_sorted() {
+                    self.note(Canonicalization::MapKeysReordered, *span);
+                }
+                for (key, value) in entries {
+                    self.visit(key);
+                    self.visit(value);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Parses a dCBOR item from a string input, and reports every place where its
+/// canonical encoding differs from a naive reading of the source, along with
+/// a [`MemoryEstimate`] of the parsed item.
+///
+/// Accepts exactly the same input as
+/// [`parse_dcbor_item`](crate::parse_dcbor_item), and reports the same errors.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{Canonicalization, parse_dcbor_item_with_report};
+/// let (cbor, report) =
+///     parse_dcbor_item_with_report("{2: 1.0, 1: 1.5}").unwrap();
+/// assert_eq!(cbor.diagnostic(), "{1: 1.5, 2: 1}");
+/// let kinds: Vec<_> = report.notes.iter().map(|note| &note.kind).collect();
+/// assert_eq!(
+///     kinds,
+///     [
+///         &Canonicalization::MapKeysReordered,
+///         &Canonicalization::FloatReducedToInteger,
+///         &Canonicalization::FloatNarrowed { bits: 16 },
+///     ]
+/// );
+/// ```
+pub fn parse_dcbor_item_with_report(
+    src: &str,
+) -> ParseResult<(CBOR, CanonicalizationReport)> {
+    let value = parse_diag_value(src)?;
+    let cbor = value.to_cbor();
+    let mut report = CanonicalizationReport::new(&value);
+    report.memory = Some(MemoryEstimate::new(&cbor));
+    Ok((cbor, report))
+}
+
+/// A map entry as it will be ordered in the canonical encoding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortedKey {
+    /// The key, with its original text and span.
+    pub key: DiagValue,
+    /// The span of the entry's value.
+    pub value_span: SourceSpan,
+    /// The position of the entry in the source, counting from zero.
+    pub source_index: usize,
+}
+
+/// Parses a map literal and returns its keys in canonical dCBOR encoding
+/// order, along with their original spans.
+///
+/// Fixture authors and formatters can use this to rewrite a map literal so its
+/// entries are written in the order they will be encoded.
+///
+/// # Errors
+///
+/// Returns the errors of [`parse_diag_value`], or
+/// [`ExpectedMap`](ParseError::ExpectedMap) if the item is not a map.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::sorted_key_preview;
+/// let src = r#"{"b": 1, 10: 2, "a": 3}"#;
+/// let keys = sorted_key_preview(src).unwrap();
+/// let texts: Vec<_> =
+///     keys.iter().map(|k| &src[k.key.span().range()]).collect();
+/// assert_eq!(texts, [r#"10"#, r#""a""#, r#""b""#]);
+/// assert_eq!(keys[0].source_index, 1);
+/// ```
+pub fn sorted_key_preview(src: &str) -> ParseResult<Vec<SortedKey>> {
+    let entries = match parse_diag_value(src)? {
+        DiagValue::Map { entries, .. } => entries,
+        value => return Err(ParseError::ExpectedMap(value.span())),
+    };
+    let mut keys: Vec<(Vec<u8>, SortedKey)> = entries
+        .into_iter()
+        .enumerate()
+        .map(|(source_index, (key, value))| {
+            let data = key.to_cbor().to_cbor_data();
+            let value_span = value.span();
+            (data, SortedKey { key, value_span, source_index })
+        })
+        .collect();
+    keys.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(keys.into_iter().map(|(_, key)| key).collect())
+}
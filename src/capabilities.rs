@@ -0,0 +1,182 @@
+/// A form of literal accepted by the parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LiteralForm {
+    Bool,
+    Null,
+    Integer,
+    Float,
+    /// `NaN`, `Infinity`, and `-Infinity`.
+    FloatKeyword,
+    Text,
+    Date,
+    /// `dec'2.75'`, a decimal fraction, tag 4.
+    DecimalFraction,
+    /// `rat'1/3'`, a rational number, tag 30.
+    Rational,
+    HexByteString,
+    Base64ByteString,
+    /// `b64url'AQIDBA'`, in the URL-safe base64 alphabet without padding.
+    Base64UrlByteString,
+    /// `b45'%69 VD92EX0'`, in the base45 encoding of RFC 9285.
+    Base45ByteString,
+    /// `bw'able tied also webs lung'`, in ByteWords with their checksum, in
+    /// any of the standard, URI, or minimal styles.
+    ByteWordsByteString,
+    /// `!bytes("digest")`, which is only accepted with a
+    /// [`byte_string_loader`](crate::ParseOptions::byte_string_loader), so
+    /// isn't listed by [`syntax_capabilities`].
+    ExternalByteString,
+    /// `<<1, 2>>`, a byte string holding the encodings of the items.
+    EmbeddedByteString,
+    NumberTaggedValue,
+    NameTaggedValue,
+    /// `raw(1234, h'820102')`, a tagged value whose content is written as
+    /// its encoding.
+    RawTaggedValue,
+    KnownValueNumber,
+    KnownValueName,
+    Unit,
+    Ur,
+    Array,
+    Map,
+}
+
+impl LiteralForm {
+    /// Returns a short human-readable name for the form.
+    #[rustfmt::skip]
+    pub fn name(&self) -> &'static str {
+        match self {
+            LiteralForm::Bool => "boolean",
+            LiteralForm::Null => "null",
+            LiteralForm::Integer => "integer",
+            LiteralForm::Float => "float",
+            LiteralForm::FloatKeyword => "float keyword",
+            LiteralForm::Text => "string",
+            LiteralForm::Date => "date",
+            LiteralForm::DecimalFraction => "decimal fraction",
+            LiteralForm::Rational => "rational",
+            LiteralForm::HexByteString => "hex byte string",
+            LiteralForm::Base64ByteString => "base64 byte string",
+            LiteralForm::Base64UrlByteString => "base64url byte string",
+            LiteralForm::Base45ByteString => "base45 byte string",
+            LiteralForm::ByteWordsByteString => "ByteWords byte string",
+            LiteralForm::ExternalByteString => "external byte string",
+            LiteralForm::EmbeddedByteString => "embedded byte string",
+            LiteralForm::NumberTaggedValue => "tagged value",
+            LiteralForm::NameTaggedValue => "name-tagged value",
+            LiteralForm::RawTaggedValue => "raw tagged value",
+            LiteralForm::KnownValueNumber => "known value",
+            LiteralForm::KnownValueName => "named known value",
+            LiteralForm::Unit => "unit known value",
+            LiteralForm::Ur => "UR",
+            LiteralForm::Array => "array",
+            LiteralForm::Map => "map",
+        }
+    }
+
+    /// Returns an example of the form.
+    #[rustfmt::skip]
+    pub fn example(&self) -> &'static str {
+        match self {
+            LiteralForm::Bool => "true",
+            LiteralForm::Null => "null",
+            LiteralForm::Integer => "-42",
+            LiteralForm::Float => "3.14",
+            LiteralForm::FloatKeyword => "Infinity",
+            LiteralForm::Text => r#""hello""#,
+            LiteralForm::Date => "2023-02-08",
+            LiteralForm::DecimalFraction => "dec'2.75'",
+            LiteralForm::Rational => "rat'1/3'",
+            LiteralForm::HexByteString => "h'68656c6c6f'",
+            LiteralForm::Base64ByteString => "b64'AQIDBAUGBwgJCg=='",
+            LiteralForm::Base64UrlByteString => "b64url'AQIDBA'",
+            LiteralForm::Base45ByteString => "b45'%69 VD92EX0'",
+            LiteralForm::ByteWordsByteString => "bw'able tied also webs lung'",
+            LiteralForm::ExternalByteString => r#"!bytes("digest")"#,
+            LiteralForm::EmbeddedByteString => "<<1, 2>>",
+            LiteralForm::NumberTaggedValue => r#"1234("hello")"#,
+            LiteralForm::NameTaggedValue => "date(1675814400)",
+            LiteralForm::RawTaggedValue => "raw(1234, h'820102')",
+            LiteralForm::KnownValueNumber => "'1'",
+            LiteralForm::KnownValueName => "'isA'",
+            LiteralForm::Unit => "Unit",
+            LiteralForm::Ur => "ur:date/cyisdadmlasgtapttl",
+            LiteralForm::Array => "[1, 2, 3]",
+            LiteralForm::Map => r#"{"key": "value"}"#,
+        }
+    }
+}
+
+/// Describes the syntax supported by this build of the parser.
+///
+/// Returned by [`syntax_capabilities`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Every literal form the parser accepts.
+    pub literal_forms: Vec<LiteralForm>,
+    /// The prefixes of quoted byte string literals, such as `h` in `h'00'`.
+    pub byte_string_prefixes: Vec<&'static str>,
+    /// Whether string literals may contain backslash escapes.
+    pub string_escapes: bool,
+    /// Whether date literals may include fractional seconds.
+    pub date_fractional_seconds: bool,
+    /// Whether date literals may include a time zone.
+    pub date_time_zones: bool,
+}
+
+impl Capabilities {
+    /// Returns `true` if the parser accepts the given literal form.
+    pub fn supports(&self, form: LiteralForm) -> bool {
+        self.literal_forms.contains(&form)
+    }
+}
+
+/// Returns a description of the syntax supported by this build of the
+/// parser, taking enabled features into account.
+///
+/// Command line tools can use this to generate accurate `--help` text and to
+/// reject unsupported input early.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{LiteralForm, syntax_capabilities};
+/// let capabilities = syntax_capabilities();
+/// assert!(capabilities.supports(LiteralForm::Ur));
+/// assert!(capabilities.byte_string_prefixes.contains(&"b64"));
+/// ```
+pub fn syntax_capabilities() -> Capabilities {
+    let full_patterns = cfg!(not(feature = "simplified-patterns"));
+    Capabilities {
+        literal_forms: vec![
+            LiteralForm::Bool,
+            LiteralForm::Null,
+            LiteralForm::Integer,
+            LiteralForm::Float,
+            LiteralForm::FloatKeyword,
+            LiteralForm::Text,
+            LiteralForm::Date,
+            LiteralForm::DecimalFraction,
+            LiteralForm::Rational,
+            LiteralForm::HexByteString,
+            LiteralForm::Base64ByteString,
+            LiteralForm::Base64UrlByteString,
+            LiteralForm::Base45ByteString,
+            LiteralForm::ByteWordsByteString,
+            LiteralForm::EmbeddedByteString,
+            LiteralForm::NumberTaggedValue,
+            LiteralForm::NameTaggedValue,
+            LiteralForm::RawTaggedValue,
+            LiteralForm::KnownValueNumber,
+            LiteralForm::KnownValueName,
+            LiteralForm::Unit,
+            LiteralForm::Ur,
+            LiteralForm::Array,
+            LiteralForm::Map,
+        ],
+        byte_string_prefixes: vec!["h", "b64", "b64url", "b45", "bw"],
+        string_escapes: full_patterns,
+        date_fractional_seconds: full_patterns,
+        date_time_zones: full_patterns,
+    }
+}
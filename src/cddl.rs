@@ -0,0 +1,216 @@
+use dcbor::{Simple, prelude::*};
+
+/// Returns a rough [CDDL](https://www.rfc-editor.org/rfc/rfc8610) schema that
+/// every one of the examples conforms to.
+///
+/// The schema is a single rule named `root`. Maps whose keys are all text
+/// strings become groups of named fields, with a field marked optional (`?`)
+/// if it is missing from any example. Other maps become `{* key => value}`.
+/// Arrays become `[* element]`, where the element type covers every element
+/// of every example. Where the examples disagree, the alternatives are
+/// written as a type choice, such as `uint / tstr`, and `uint / nint` is
+/// written as `int`.
+///
+/// The result is meant as a starting point for documentation, and usually
+/// needs editing: it can't know, for example, which fields are meant to be
+/// optional but happen to be present in every example.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{infer_cddl, parse_dcbor_item};
+/// let examples = [
+///     parse_dcbor_item(r#"{"id": 1, "tags": ["a", "b"]}"#).unwrap(),
+///     parse_dcbor_item(r#"{"id": -2, "name": "x", "tags": []}"#).unwrap(),
+/// ];
+/// assert_eq!(
+///     infer_cddl(&examples),
+///     r#"root = {
+///   "id": int,
+///   "tags": [* tstr],
+///   ? "name": tstr,
+/// }"#
+/// );
+/// ```
+pub fn infer_cddl(examples: &[CBOR]) -> String {
+    let mut root = Type::default();
+    examples.iter().for_each(|example| root.add(example));
+    format!("root = {}", root.render(0))
+}
+
+//
+// === Private Functions ===
+//
+
+const INDENT: &str = "  ";
+
+/// The alternatives seen at one position in the examples, in the order they
+/// were first seen.
+#[derive(Default)]
+struct Type {
+    alts: Vec<Alt>,
+}
+
+enum Alt {
+    Prim(&'static str),
+    Array(Type),
+    Struct { fields: Vec<Field>, count: usize },
+    Table(Type, Type),
+    Tagged(TagValue, Type),
+}
+
+struct Field {
+    key: String,
+    ty: Type,
+    count: usize,
+}
+
+impl Type {
+    fn add(&mut self, cbor: &CBOR) {
+        match cbor.as_case() {
+            CBORCase::Unsigned(_) => self.add_prim("uint"),
+            CBORCase::Negative(_) => self.add_prim("nint"),
+            CBORCase::ByteString(_) => self.add_prim("bstr"),
+            CBORCase::Text(_) => self.add_prim("tstr"),
+            CBORCase::Simple(Simple::True | Simple::False) => {
+                self.add_prim("bool")
+            }
+            CBORCase::Simple(Simple::Null) => self.add_prim("null"),
+            CBORCase::Simple(Simple::Float(_)) => self.add_prim("float"),
+            CBORCase::Array(items) => {
+                let element = self.find_or_push(
+                    |alt| matches!(alt, Alt::Array(_)),
+                    || Alt::Array(Type::default()),
+                );
+                let Alt::Array(element) = element else { unreachable!() };
+                items.iter().for_each(|item| element.add(item));
+            }
+            CBORCase::Map(map) => {
+                if map.iter().all(|(key, _)| key.is_text()) {
+                    self.add_struct(map);
+                } else {
+                    let table = self.find_or_push(
+                        |alt| matches!(alt, Alt::Table(..)),
+                        || Alt::Table(Type::default(), Type::default()),
+                    );
+                    let Alt::Table(keys, values) = table else {
+                        unreachable!()
+                    };
+                    for (key, value) in map.iter() {
+                        keys.add(key);
+                        values.add(value);
+                    }
+                }
+            }
+            CBORCase::Tagged(tag, item) => {
+                let value = tag.value();
+                let tagged = self.find_or_push(
+                    |alt| matches!(alt, Alt::Tagged(v, _) if *v == value),
+                    || Alt::Tagged(value, Type::default()),
+                );
+                let Alt::Tagged(_, content) = tagged else { unreachable!() };
+                content.add(item);
+            }
+        }
+    }
+
+    fn add_prim(&mut self, name: &'static str) {
+        self.find_or_push(
+            |alt| matches!(alt, Alt::Prim(n) if *n == name),
+            || Alt::Prim(name),
+        );
+    }
+
+    fn add_struct(&mut self, map: &Map) {
+        let alt = self.find_or_push(
+            |alt| matches!(alt, Alt::Struct { .. }),
+            || Alt::Struct { fields: Vec::new(), count: 0 },
+        );
+        let Alt::Struct { fields, count } = alt else { unreachable!() };
+        *count += 1;
+        for (key, value) in map.iter() {
+            let key = key.clone().try_into_text().unwrap();
+            let index = match fields.iter().position(|f| f.key == key) {
+                Some(index) => index,
+                None => {
+                    fields.push(Field { key, ty: Type::default(), count: 0 });
+                    fields.len() - 1
+                }
+            };
+            fields[index].count += 1;
+            fields[index].ty.add(value);
+        }
+    }
+
+    fn find_or_push(
+        &mut self,
+        found: impl Fn(&Alt) -> bool,
+        new: impl FnOnce() -> Alt,
+    ) -> &mut Alt {
+        let index = match self.alts.iter().position(found) {
+            Some(index) => index,
+            None => {
+                self.alts.push(new());
+                self.alts.len() - 1
+            }
+        };
+        &mut self.alts[index]
+    }
+
+    fn has_prim(&self, name: &str) -> bool {
+        self.alts.iter().any(|alt| matches!(alt, Alt::Prim(n) if *n == name))
+    }
+
+    fn render(&self, level: usize) -> String {
+        if self.alts.is_empty() {
+            return "any".to_string();
+        }
+        let int = self.has_prim("uint") && self.has_prim("nint");
+        let mut result = Vec::new();
+        for alt in &self.alts {
+            match alt {
+                Alt::Prim("uint" | "nint") if int => {
+                    if !result.contains(&"int".to_string()) {
+                        result.push("int".to_string());
+                    }
+                }
+                _ => result.push(alt.render(level)),
+            }
+        }
+        result.join(" / ")
+    }
+}
+
+impl Alt {
+    fn render(&self, level: usize) -> String {
+        match self {
+            Alt::Prim(name) => name.to_string(),
+            Alt::Array(element) if element.alts.is_empty() => "[]".to_string(),
+            Alt::Array(element) => format!("[* {}]", element.render(level)),
+            Alt::Struct { fields, .. } if fields.is_empty() => "{}".to_string(),
+            Alt::Struct { fields, count } => {
+                let indent = INDENT.repeat(level);
+                let mut result = "{\n".to_string();
+                for field in fields {
+                    let optional = if field.count < *count { "? " } else { "" };
+                    result.push_str(&format!(
+                        "{indent}{INDENT}{optional}{:?}: {},\n",
+                        field.key,
+                        field.ty.render(level + 1)
+                    ));
+                }
+                result.push_str(&indent);
+                result.push('}');
+                result
+            }
+            Alt::Table(keys, values) => format!(
+                "{{* {} => {}}}",
+                keys.render(level),
+                values.render(level)
+            ),
+            Alt::Tagged(value, content) => {
+                format!("#6.{}({})", value, content.render(level))
+            }
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use dcbor::prelude::*;
+
+use crate::{
+    SourceSpan,
+    error::Result,
+    parse::Parser,
+    span::line_end,
+};
+
+/// A comment in diagnostic notation, written as `/ text /` or `# text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    /// The text of the comment, without its delimiters and the whitespace
+    /// around it.
+    pub text: String,
+    /// The span of the whole comment, including its delimiters.
+    pub span: SourceSpan,
+}
+
+/// An item parsed from the beginning of a source, along with the comments
+/// attached to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommentedItem {
+    /// The item.
+    pub item: CBOR,
+    /// The comments before the item.
+    pub leading: Vec<Comment>,
+    /// The comments after the item that start on the line the item ends on.
+    pub trailing: Vec<Comment>,
+}
+
+/// Parses a dCBOR item from the beginning of a string, like
+/// [`parse_dcbor_item_partial`](crate::parse_dcbor_item_partial), and returns
+/// it along with the comments attached to it and the number of bytes
+/// consumed.
+///
+/// Comments before the item are its leading comments. Comments after the
+/// item that start on the line it ends on are its trailing comments. The
+/// returned index points past the trailing comments but before any later
+/// comments, so when a source holding a sequence of items is parsed by
+/// calling this repeatedly on what remains, each comment is attached to the
+/// item it describes.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::parse_dcbor_item_partial_with_comments;
+/// let src = "# the answer\n42 / checked / # twice\n/ next / [1]";
+/// let (commented, used) =
+///     parse_dcbor_item_partial_with_comments(src).unwrap();
+/// assert_eq!(commented.item.diagnostic(), "42");
+/// assert_eq!(commented.leading[0].text, "the answer");
+/// let trailing: Vec<_> =
+///     commented.trailing.iter().map(|c| c.text.as_str()).collect();
+/// assert_eq!(trailing, ["checked", "twice"]);
+///
+/// let (commented, _) =
+///     parse_dcbor_item_partial_with_comments(&src[used..]).unwrap();
+/// assert_eq!(commented.item.diagnostic(), "[1]");
+/// assert_eq!(commented.leading[0].text, "next");
+/// ```
+pub fn parse_dcbor_item_partial_with_comments(
+    src: &str,
+) -> Result<(CommentedItem, usize)> {
+    let mut parser = Parser::new(src);
+    let value = parser.parse_first()?;
+    let next = parser.consumed();
+    let span = value.span();
+    let leading = comments(src, 0, span.start);
+    let line_end = line_end(src, span.end);
+    let (trailing, rest): (Vec<_>, Vec<_>) = comments(src, span.end, next)
+        .into_iter()
+        .partition(|comment| comment.span.start < line_end);
+    let used = rest.first().map_or(next, |comment| comment.span.start);
+    let item = value.to_cbor();
+    Ok((CommentedItem { item, leading, trailing }, used))
+}
+
+//
+// === Private Functions ===
+//
+
+/// Returns the comments between `start` and `end`, which must contain only
+/// whitespace and comments.
+fn comments(src: &str, start: usize, end: usize) -> Vec<Comment> {
+    let bytes = src.as_bytes();
+    let mut result = Vec::new();
+    let mut index = start;
+    while index < end {
+        let comment_end = match bytes[index] {
+            b'#' => line_end(src, index),
+            b'/' => bytes[index + 1..end]
+                .iter()
+                .position(|&byte| byte == b'/')
+                .map_or(end, |len| index + len + 2),
+            _ => {
+                index += 1;
+                continue;
+            }
+        };
+        let comment_end = comment_end.min(end);
+        let inner = &src[index + 1..comment_end];
+        let inner = inner.strip_suffix('/').unwrap_or(inner);
+        result.push(Comment {
+            text: inner.trim().to_string(),
+            span: SourceSpan::new(index, comment_end),
+        });
+        index = comment_end;
+    }
+    result
+}
@@ -1,17 +1,23 @@
-use dcbor::prelude::*;
+use std::collections::HashMap;
+
+use bc_ur::prelude::*;
 use thiserror::Error;
 
-use crate::{ParseError, parse_dcbor_item};
+use crate::{ParseError, ParseResult, parse_dcbor_item};
 
 #[derive(Debug, Error, Clone, PartialEq)]
 #[rustfmt::skip]
 pub enum Error {
     #[error("Invalid odd map length")]
     OddMapLength,
-    #[error("Duplicate map key")]
-    DuplicateMapKey,
+    #[error("Duplicate map key at indices {0} and {1}")]
+    DuplicateMapKey(usize, usize),
     #[error("Invalid CBOR item: {0}")]
     ParseError(#[from] ParseError),
+    #[error("Invalid CBOR item at index {0}: {1}")]
+    InvalidFragment(usize, ParseError),
+    #[error("Invalid UR: {0}")]
+    InvalidUR(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -29,9 +35,18 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// assert_eq!(cbor.diagnostic(), "[1, 2, 3]");
 /// ```
 pub fn compose_dcbor_array(array: &[&str]) -> Result<CBOR> {
+    compose_array_with(array, parse_dcbor_item)
+}
+
+/// Composes a dCBOR array as [`compose_dcbor_array`] does, parsing each item
+/// with `parse`.
+pub(crate) fn compose_array_with(
+    array: &[&str],
+    mut parse: impl FnMut(&str) -> ParseResult<CBOR>,
+) -> Result<CBOR> {
     let mut result = Vec::new();
     for item in array {
-        let cbor = parse_dcbor_item(item)?;
+        let cbor = parse(item)?;
         result.push(cbor);
     }
     Ok(result.into())
@@ -43,33 +58,204 @@ pub fn compose_dcbor_array(array: &[&str]) -> Result<CBOR> {
 /// The length of the slice must be even, as each key must have a corresponding
 /// value.
 ///
-/// Each string slice is parsed as a dCBOR item.
+/// Each string slice is parsed as a dCBOR item. Keys that are written
+/// differently but are the same item, such as `1` and `1.0`, are duplicates,
+/// and the error gives the indices in the slice of both keys.
 ///
 /// # Example
 ///
 /// ```rust
-/// # use dcbor_parse::compose_dcbor_map;
+/// # use dcbor_parse::{ComposeError, compose_dcbor_map};
 /// let cbor = compose_dcbor_map(&["1", "2", "3", "4"]).unwrap();
 /// assert_eq!(cbor.diagnostic(), "{1: 2, 3: 4}");
+///
+/// assert_eq!(
+///     compose_dcbor_map(&["1", "2", "3", "4", "1.0", "5"]),
+///     Err(ComposeError::DuplicateMapKey(0, 4))
+/// );
 /// ```
 pub fn compose_dcbor_map(array: &[&str]) -> Result<CBOR> {
+    compose_map_with(array, parse_dcbor_item)
+}
+
+/// Composes a dCBOR map as [`compose_dcbor_map`] does, parsing each key and
+/// value with `parse`.
+pub(crate) fn compose_map_with(
+    array: &[&str],
+    mut parse: impl FnMut(&str) -> ParseResult<CBOR>,
+) -> Result<CBOR> {
+    compose_map_entries(array, |_, src| Ok(parse(src)?)).map(|(cbor, _)| cbor)
+}
+
+/// Composes a dCBOR map as [`compose_dcbor_map`] does, parsing the fragment
+/// at each index with `parse`, and returns it along with the index in
+/// `array` of each key, by its encoding.
+fn compose_map_entries(
+    array: &[&str],
+    mut parse: impl FnMut(usize, &str) -> Result<CBOR>,
+) -> Result<(CBOR, HashMap<Vec<u8>, usize>)> {
     if !array.len().is_multiple_of(2) {
         return Err(Error::OddMapLength);
     }
 
     let mut map = Map::new();
+    // The index of each key, by its encoding.
+    let mut indices = HashMap::new();
 
     for i in (0..array.len()).step_by(2) {
-        let key = parse_dcbor_item(array[i])?;
-        let value = parse_dcbor_item(array[i + 1])?;
+        let key = parse(i, array[i])?;
+        let value = parse(i + 1, array[i + 1])?;
 
         // Check for duplicate key
-        if map.contains_key(key.clone()) {
-            return Err(Error::DuplicateMapKey);
+        if let Some(first) = indices.insert(key.to_cbor_data(), i) {
+            return Err(Error::DuplicateMapKey(first, i));
         }
 
         map.insert(key, value);
     }
 
-    Ok(map.into())
+    Ok((map.into(), indices))
+}
+
+/// The node of a composed result that an input fragment became.
+///
+/// Indexes count items or entries in the order they are encoded, which for
+/// maps is not necessarily the order the fragments were given in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComposedNode {
+    /// The item at this index of the array.
+    ArrayItem(usize),
+    /// The key of the entry at this index of the map.
+    MapKey(usize),
+    /// The value of the entry at this index of the map.
+    MapValue(usize),
+}
+
+impl ComposedNode {
+    /// Returns this node of a composed result, or `None` if `result` doesn't
+    /// have it.
+    pub fn locate(&self, result: &CBOR) -> Option<CBOR> {
+        match (self, result.as_case()) {
+            (ComposedNode::ArrayItem(index), CBORCase::Array(array)) => {
+                array.get(*index).cloned()
+            }
+            (ComposedNode::MapKey(index), CBORCase::Map(map)) => {
+                map.iter().nth(*index).map(|(key, _)| key.clone())
+            }
+            (ComposedNode::MapValue(index), CBORCase::Map(map)) => {
+                map.iter().nth(*index).map(|(_, value)| value.clone())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Like [`compose_dcbor_array`], but also returns the node of the result that
+/// each fragment became, so later failures can be traced back to the fragment
+/// that caused them. A fragment that fails to parse is reported as an
+/// [`InvalidFragment`](Error::InvalidFragment) error with its index.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{ComposedNode, compose_dcbor_array_spanned};
+/// let (cbor, nodes) = compose_dcbor_array_spanned(&["1", "2"]).unwrap();
+/// assert_eq!(nodes, [ComposedNode::ArrayItem(0), ComposedNode::ArrayItem(1)]);
+/// assert_eq!(nodes[1].locate(&cbor).unwrap().diagnostic(), "2");
+/// ```
+pub fn compose_dcbor_array_spanned(
+    array: &[&str],
+) -> Result<(CBOR, Vec<ComposedNode>)> {
+    let items = array
+        .iter()
+        .enumerate()
+        .map(|(index, item)| parse_fragment(index, item))
+        .collect::<Result<Vec<_>>>()?;
+    let nodes = (0..array.len()).map(ComposedNode::ArrayItem).collect();
+    Ok((items.into(), nodes))
+}
+
+/// Like [`compose_dcbor_map`], but also returns the node of the result that
+/// each fragment became, so later failures can be traced back to the fragment
+/// that caused them. A fragment that fails to parse is reported as an
+/// [`InvalidFragment`](Error::InvalidFragment) error with its index.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{ComposedNode, compose_dcbor_map_spanned};
+/// let (cbor, nodes) =
+///     compose_dcbor_map_spanned(&["2", "\"b\"", "1", "\"a\""]).unwrap();
+/// assert_eq!(cbor.diagnostic(), r#"{1: "a", 2: "b"}"#);
+/// assert_eq!(
+///     nodes,
+///     [
+///         ComposedNode::MapKey(1),
+///         ComposedNode::MapValue(1),
+///         ComposedNode::MapKey(0),
+///         ComposedNode::MapValue(0),
+///     ]
+/// );
+/// ```
+pub fn compose_dcbor_map_spanned(
+    array: &[&str],
+) -> Result<(CBOR, Vec<ComposedNode>)> {
+    let (cbor, indices) = compose_map_entries(array, parse_fragment)?;
+    // The map's entries are ordered by the encodings of their keys.
+    let mut keys: Vec<_> = indices.into_iter().collect();
+    keys.sort_unstable();
+    let mut nodes = vec![ComposedNode::MapKey(0); array.len()];
+    for (entry, (_, index)) in keys.into_iter().enumerate() {
+        nodes[index] = ComposedNode::MapKey(entry);
+        nodes[index + 1] = ComposedNode::MapValue(entry);
+    }
+    Ok((cbor, nodes))
+}
+
+/// Parses the fragment at `index` as a dCBOR item, reporting a failure with
+/// the index.
+fn parse_fragment(index: usize, src: &str) -> Result<CBOR> {
+    parse_dcbor_item(src).map_err(|error| Error::InvalidFragment(index, error))
+}
+
+/// Composes a multi-part UR of type `ur_type` from a dCBOR item in diagnostic
+/// notation, and returns the parts of the fountain-coded sequence.
+///
+/// The item is split into fragments of at most `max_fragment_len` bytes. If
+/// `count` is `None`, one part is returned for each fragment, which is enough
+/// for a receiver that sees every part. Otherwise `count` parts are returned,
+/// and the parts past the fragments mix fragments together so that a
+/// receiver can recover the item after missing some parts, as when scanning
+/// an animated QR code. [`qr_fit`](crate::qr_fit) returns the largest
+/// `max_fragment_len` whose parts fit in a QR code.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::compose_multipart_ur;
+/// let parts =
+///     compose_multipart_ur("bytes", "h'000102030405060708090a0b'", 5, None)
+///         .unwrap();
+/// assert_eq!(parts.len(), 3);
+/// assert!(parts[0].starts_with("ur:bytes/1-3/"));
+///
+/// let parts =
+///     compose_multipart_ur("bytes", "h'000102030405060708090a0b'", 5, Some(5))
+///         .unwrap();
+/// assert!(parts[4].starts_with("ur:bytes/5-3/"));
+/// ```
+pub fn compose_multipart_ur(
+    ur_type: &str,
+    src: &str,
+    max_fragment_len: usize,
+    count: Option<usize>,
+) -> Result<Vec<String>> {
+    let invalid = |e: URError| Error::InvalidUR(e.to_string());
+    let ur = UR::new(ur_type, parse_dcbor_item(src)?).map_err(invalid)?;
+    let mut encoder =
+        MultipartEncoder::new(&ur, max_fragment_len).map_err(invalid)?;
+    let count = count.unwrap_or(encoder.parts_count());
+    (0..count)
+        .map(|_| encoder.next_part().map_err(invalid))
+        .collect()
 }
@@ -0,0 +1,371 @@
+//! Named conformance test cases for the parser.
+//!
+//! A test file holds any number of cases. Each case starts with a
+//! `## name: ...` line, followed by more `## key: value` lines, and then its
+//! source, which runs until the next `## name:` line or the end of the file.
+//! Anything before the first case is ignored, so a file can start with a
+//! description of its cases.
+//!
+//! | Key           | Value                                                |
+//! | ------------- | ---------------------------------------------------- |
+//! | `name`        | The name of the case.                                |
+//! | `hex`         | The encoding the source must parse to, in hex.       |
+//! | `error`       | The code of the error the source must fail with.     |
+//! | `options`     | A comma-separated list of the options to parse with. |
+//! | `description` | Ignored.                                             |
+//!
+//! Every case must have either `hex` or `error`, but not both. Error codes
+//! are those returned by [`ParseError::code`](crate::ParseError::code).
+//!
+//! The options start from the defaults of [`ParseOptions`], and each one
+//! changes the option of the same name:
+//!
+//! | Option                            | Sets                                 |
+//! | --------------------------------- | ------------------------------------ |
+//! | `strict`                          | [`ParseOptions::strict`]             |
+//! | `lenient`                         | [`ParseOptions::lenient`]            |
+//! | `data-only`                       | `data_only(true)`                    |
+//! | `registered-known-values-only`    | `registered_known_values_only(true)` |
+//! | `tag-validators`                  | [`TagValidators::standard`]          |
+//! | `validate-envelopes`              | `validate_envelopes(true)`           |
+//! | `cose`                            | `cose(true)`                         |
+//! | `spaced-tags`                     | `spaced_tags(true)`                  |
+//! | `lenient-floats`                  | `lenient_floats(true)`               |
+//! | `integer-map-keys-only`           | `integer_map_keys_only(true)`        |
+//! | `no-date-literals`                | `date_literals(false)`               |
+//! | `no-known-value-literals`         | `known_value_literals(false)`        |
+//! | `duplicate-keys-keep-first`       | [`DuplicateKeys::KeepFirst`]         |
+//! | `duplicate-keys-keep-last`        | [`DuplicateKeys::KeepLast`]          |
+//! | `nan-handling-reject`             | [`NaNHandling::Reject`]              |
+//! | `nan-handling-warn`               | [`NaNHandling::Warn`]                |
+//! | `encoding-indicators-ignore`      | [`EncodingIndicators::Ignore`]       |
+//! | `encoding-indicators-validate`    | [`EncodingIndicators::Validate`]     |
+//! | `control-characters-escape`       | [`ControlCharacters::Escape`]        |
+//! | `control-characters-verbatim`     | [`ControlCharacters::Verbatim`]      |
+//!
+//! The profiles `strict` and `lenient` replace the options named before
+//! them, so they must come first.
+//!
+//! ```rust
+//! # use dcbor_parse::conformance;
+//! let text = [
+//!     "Cases for small arrays.",
+//!     "",
+//!     "## name: empty array",
+//!     "## hex: 80",
+//!     "[]",
+//!     "",
+//!     "## name: missing comma",
+//!     "## error: expected-comma",
+//!     "[1 2]",
+//! ]
+//! .join("\n");
+//! let cases = conformance::parse_test_cases(&text).unwrap();
+//! assert_eq!(cases.len(), 2);
+//! assert_eq!(cases[1].source, "[1 2]");
+//!
+//! let report = conformance::run_test_cases(&cases);
+//! assert!(report.is_success());
+//! assert_eq!(report.to_string(), "2 passed, 0 failed");
+//! ```
+
+use std::{fmt, fs, io, path::Path};
+
+use crate::{
+    ControlCharacters, DuplicateKeys, EncodingIndicators, NaNHandling,
+    ParseOptions, TagValidators, parse_dcbor_item_with_options,
+};
+
+/// What parsing the source of a [`TestCase`] must produce.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expected {
+    /// An item with this encoding, in lowercase hex.
+    Hex(String),
+    /// An error with this [code](crate::ParseError::code).
+    Error(String),
+}
+
+/// A named conformance test case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCase {
+    /// The name of the case.
+    pub name: String,
+    /// The line of the test file on which the case starts.
+    pub line: usize,
+    /// The names of the options the source is parsed with.
+    pub options: Vec<String>,
+    /// The diagnostic notation to parse.
+    pub source: String,
+    /// What parsing the source must produce.
+    pub expected: Expected,
+}
+
+impl TestCase {
+    /// Returns the parse options named by the case, or a message naming
+    /// the first option that isn't known.
+    pub fn parse_options(&self) -> Result<ParseOptions, String> {
+        let mut options = ParseOptions::new();
+        for name in &self.options {
+            options = option_setter(name)?(options);
+        }
+        Ok(options)
+    }
+
+    /// Parses the source of the case, and returns a message describing the
+    /// difference if the result isn't what the case expects.
+    pub fn run(&self) -> Result<(), String> {
+        let result =
+            parse_dcbor_item_with_options(&self.source, &self.parse_options()?);
+        let actual = match &result {
+            Ok(cbor) => format!("{} ({})", cbor.hex(), cbor.diagnostic_flat()),
+            Err(error) => format!("error {}: {error}", error.code()),
+        };
+        let passed = match (&self.expected, &result) {
+            (Expected::Hex(hex), Ok(cbor)) => *hex == cbor.hex(),
+            (Expected::Error(code), Err(error)) => code == error.code(),
+            _ => false,
+        };
+        if passed {
+            return Ok(());
+        }
+        let expected = match &self.expected {
+            Expected::Hex(hex) => hex.clone(),
+            Expected::Error(code) => format!("error {code}"),
+        };
+        Err(format!("expected {expected}, got {actual}"))
+    }
+}
+
+/// A test case whose source didn't parse as expected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestFailure {
+    /// The name of the case.
+    pub name: String,
+    /// The line of the test file on which the case starts.
+    pub line: usize,
+    /// How the result differed from what was expected.
+    pub message: String,
+}
+
+/// The results of running a set of test cases.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestReport {
+    /// The number of cases that passed.
+    pub passed: usize,
+    /// The cases that failed, in the order they were run.
+    pub failures: Vec<TestFailure>,
+}
+
+impl TestReport {
+    /// Returns `true` if every case passed.
+    pub fn is_success(&self) -> bool { self.failures.is_empty() }
+}
+
+impl fmt::Display for TestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for failure in &self.failures {
+            writeln!(
+                f,
+                "{} (line {}): {}",
+                failure.name, failure.line, failure.message
+            )?;
+        }
+        write!(f, "{} passed, {} failed", self.passed, self.failures.len())
+    }
+}
+
+/// Reads the test cases from the file at `path`.
+///
+/// A file that isn't in the test case format is an
+/// [`InvalidData`](io::ErrorKind::InvalidData) error.
+pub fn parse_test_file(path: impl AsRef<Path>) -> io::Result<Vec<TestCase>> {
+    parse_test_cases(&fs::read_to_string(path)?)
+}
+
+/// Reads the test cases from the text of a test file.
+///
+/// Text that isn't in the test case format is an
+/// [`InvalidData`](io::ErrorKind::InvalidData) error whose message starts
+/// with the line number of the problem.
+pub fn parse_test_cases(text: &str) -> io::Result<Vec<TestCase>> {
+    let mut cases = Vec::new();
+    let mut lines = text.lines().enumerate().peekable();
+    while let Some((index, line)) = lines.next() {
+        let Some(name) = metadata(line, "name") else {
+            continue;
+        };
+        let mut case = CaseBuilder::new(name, index + 1);
+        while let Some((index, line)) = lines.next_if(|(_, line)| {
+            line.starts_with("##") && metadata(line, "name").is_none()
+        }) {
+            case.add(line).map_err(|message| invalid(index + 1, &message))?;
+        }
+        let mut source = Vec::new();
+        while let Some((_, line)) =
+            lines.next_if(|(_, line)| metadata(line, "name").is_none())
+        {
+            source.push(line);
+        }
+        cases.push(case.build(source.join("\n").trim())?);
+    }
+    Ok(cases)
+}
+
+/// Runs each of the test cases.
+pub fn run_test_cases(cases: &[TestCase]) -> TestReport {
+    let mut report = TestReport::default();
+    for case in cases {
+        match case.run() {
+            Ok(()) => report.passed += 1,
+            Err(message) => report.failures.push(TestFailure {
+                name: case.name.clone(),
+                line: case.line,
+                message,
+            }),
+        }
+    }
+    report
+}
+
+//
+// === Private Functions ===
+//
+
+/// Changes parse options as a test case option says.
+type OptionSetter = fn(ParseOptions) -> ParseOptions;
+
+/// The options a test case can name, with how each changes the options
+/// named before it.
+const OPTIONS: &[(&str, OptionSetter)] = &[
+    ("strict", |_| ParseOptions::strict()),
+    ("lenient", |_| ParseOptions::lenient()),
+    ("data-only", |o| o.data_only(true)),
+    ("registered-known-values-only", |o| {
+        o.registered_known_values_only(true)
+    }),
+    ("tag-validators", |o| o.tag_validators(TagValidators::standard())),
+    ("validate-envelopes", |o| o.validate_envelopes(true)),
+    ("cose", |o| o.cose(true)),
+    ("spaced-tags", |o| o.spaced_tags(true)),
+    ("lenient-floats", |o| o.lenient_floats(true)),
+    ("integer-map-keys-only", |o| o.integer_map_keys_only(true)),
+    ("no-date-literals", |o| o.date_literals(false)),
+    ("no-known-value-literals", |o| o.known_value_literals(false)),
+    ("duplicate-keys-keep-first", |o| {
+        o.duplicate_keys(DuplicateKeys::KeepFirst)
+    }),
+    ("duplicate-keys-keep-last", |o| {
+        o.duplicate_keys(DuplicateKeys::KeepLast)
+    }),
+    ("nan-handling-reject", |o| o.nan_handling(NaNHandling::Reject)),
+    ("nan-handling-warn", |o| o.nan_handling(NaNHandling::Warn)),
+    ("encoding-indicators-ignore", |o| {
+        o.encoding_indicators(EncodingIndicators::Ignore)
+    }),
+    ("encoding-indicators-validate", |o| {
+        o.encoding_indicators(EncodingIndicators::Validate)
+    }),
+    ("control-characters-escape", |o| {
+        o.control_characters(ControlCharacters::Escape)
+    }),
+    ("control-characters-verbatim", |o| {
+        o.control_characters(ControlCharacters::Verbatim)
+    }),
+];
+
+/// The options that replace the options named before them.
+const PROFILES: &[&str] = &["strict", "lenient"];
+
+/// Returns how the option `name` changes parse options, or a message if
+/// there is no such option.
+fn option_setter(name: &str) -> Result<OptionSetter, String> {
+    OPTIONS
+        .iter()
+        .find(|(option, _)| *option == name)
+        .map(|(_, setter)| *setter)
+        .ok_or_else(|| format!("unknown option `{name}`"))
+}
+
+/// The parts of a test case read so far.
+struct CaseBuilder {
+    name: String,
+    line: usize,
+    options: Vec<String>,
+    hex: Option<String>,
+    error: Option<String>,
+}
+
+impl CaseBuilder {
+    fn new(name: &str, line: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            line,
+            options: Vec::new(),
+            hex: None,
+            error: None,
+        }
+    }
+
+    fn add(&mut self, line: &str) -> Result<(), String> {
+        let Some((key, value)) = line[2..].split_once(':') else {
+            return Err(format!("expected `## key: value`, found `{line}`"));
+        };
+        let value = value.trim();
+        match key.trim() {
+            "hex" => {
+                let hex = value.split_whitespace().collect::<String>();
+                let hex = hex.to_lowercase();
+                if hex::decode(&hex).is_err() {
+                    return Err(format!("invalid hex `{value}`"));
+                }
+                self.hex = Some(hex);
+            }
+            "error" => self.error = Some(value.to_string()),
+            "options" => {
+                for option in value.split(',').map(str::trim) {
+                    option_setter(option)?;
+                    if PROFILES.contains(&option) && !self.options.is_empty() {
+                        return Err(format!(
+                            "the profile `{option}` must be the first option"
+                        ));
+                    }
+                    self.options.push(option.to_string());
+                }
+            }
+            "description" => {}
+            key => return Err(format!("unknown key `{key}`")),
+        }
+        Ok(())
+    }
+
+    fn build(self, source: &str) -> io::Result<TestCase> {
+        let expected = match (self.hex, self.error) {
+            (Some(hex), None) => Expected::Hex(hex),
+            (None, Some(code)) => Expected::Error(code),
+            _ => {
+                return Err(invalid(
+                    self.line,
+                    "the case must have either `## hex:` or `## error:`",
+                ));
+            }
+        };
+        Ok(TestCase {
+            name: self.name,
+            line: self.line,
+            options: self.options,
+            source: source.to_string(),
+            expected,
+        })
+    }
+}
+
+/// Returns the value of a `## key: value` line with the given key.
+fn metadata<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let (k, value) = line.strip_prefix("##")?.split_once(':')?;
+    (k.trim() == key).then(|| value.trim())
+}
+
+fn invalid(line: usize, message: &str) -> io::Error {
+    let message = format!("line {line}: {message}");
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
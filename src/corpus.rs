@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+
+use dcbor::{Simple, prelude::*};
+
+/// Statistics about the items in a corpus, such as a dump of real traffic
+/// converted to diagnostic notation.
+///
+/// String lengths are counted in buckets: each length is counted under the
+/// largest power of two that is at most the length, or under 0 for an empty
+/// string, so `4` counts the strings of 4 to 7 bytes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorpusReport {
+    /// The number of items in the corpus.
+    pub items: usize,
+    /// The number of times each type occurs at any depth, by its CDDL name:
+    /// `uint`, `nint`, `bstr`, `tstr`, `array`, `map`, `tag`, `bool`,
+    /// `null`, or `float`.
+    pub types: BTreeMap<&'static str, usize>,
+    /// The number of items with each nesting depth. Atoms have a depth of 0,
+    /// and each array, map, and tag adds 1.
+    pub depths: BTreeMap<usize, usize>,
+    /// The number of text strings in each length bucket, by length in bytes.
+    pub text_lengths: BTreeMap<usize, usize>,
+    /// The number of byte strings in each length bucket.
+    pub byte_string_lengths: BTreeMap<usize, usize>,
+    /// The number of times each tag is used.
+    pub tags: BTreeMap<TagValue, usize>,
+}
+
+impl CorpusReport {
+    /// Returns the report as a CBOR map with the keys `items`, `types`,
+    /// `depths`, `text_lengths`, `byte_string_lengths`, and `tags`.
+    pub fn to_cbor(&self) -> CBOR {
+        let mut map = Map::new();
+        map.insert("items", self.items);
+        map.insert("types", count_map(&self.types));
+        map.insert("depths", count_map(&self.depths));
+        map.insert("text_lengths", count_map(&self.text_lengths));
+        map.insert("byte_string_lengths", count_map(&self.byte_string_lengths));
+        map.insert("tags", count_map(&self.tags));
+        map.into()
+    }
+
+    /// Returns the report as a JSON object with the same keys as
+    /// [`to_cbor`](Self::to_cbor). Since JSON object keys are strings, the
+    /// depths, lengths, and tags are written as strings.
+    pub fn to_json(&self) -> String {
+        let fields = [
+            ("items", self.items.to_string()),
+            ("types", count_json(&self.types)),
+            ("depths", count_json(&self.depths)),
+            ("text_lengths", count_json(&self.text_lengths)),
+            ("byte_string_lengths", count_json(&self.byte_string_lengths)),
+            ("tags", count_json(&self.tags)),
+        ];
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|(key, value)| format!("{key:?}: {value}"))
+            .collect();
+        format!("{{{}}}", fields.join(", "))
+    }
+}
+
+/// Returns statistics about the items in a corpus: how often each type and
+/// tag occurs, how deeply the items are nested, and how long their strings
+/// are.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{corpus_report, parse_dcbor_item};
+/// let items = [r#"{"id": 1, "tags": ["a", "bc"]}"#, "1(1675854645)", "h''"]
+///     .iter()
+///     .map(|src| parse_dcbor_item(src).unwrap())
+///     .collect::<Vec<_>>();
+/// let report = corpus_report(&items);
+/// assert_eq!(report.items, 3);
+/// assert_eq!(report.types["tstr"], 4);
+/// assert_eq!(report.tags[&1], 1);
+/// assert_eq!(
+///     report.to_json(),
+///     concat!(
+///         r#"{"items": 3, "#,
+///         r#""types": {"array": 1, "bstr": 1, "map": 1, "tag": 1, "#,
+///         r#""tstr": 4, "uint": 2}, "#,
+///         r#""depths": {"0": 1, "1": 1, "2": 1}, "#,
+///         r#""text_lengths": {"1": 1, "2": 2, "4": 1}, "#,
+///         r#""byte_string_lengths": {"0": 1}, "#,
+///         r#""tags": {"1": 1}}"#,
+///     )
+/// );
+/// ```
+pub fn corpus_report(items: &[CBOR]) -> CorpusReport {
+    let mut report = CorpusReport { items: items.len(), ..Default::default() };
+    for item in items {
+        let depth = report.visit(item);
+        *report.depths.entry(depth).or_default() += 1;
+    }
+    report
+}
+
+//
+// === Private Functions ===
+//
+
+impl CorpusReport {
+    /// Counts `cbor` and everything within it, and returns its depth.
+    fn visit(&mut self, cbor: &CBOR) -> usize {
+        let name = match cbor.as_case() {
+            CBORCase::Unsigned(_) => "uint",
+            CBORCase::Negative(_) => "nint",
+            CBORCase::ByteString(bytes) => {
+                let bucket = bucket(bytes.len());
+                *self.byte_string_lengths.entry(bucket).or_default() += 1;
+                "bstr"
+            }
+            CBORCase::Text(text) => {
+                *self.text_lengths.entry(bucket(text.len())).or_default() += 1;
+                "tstr"
+            }
+            CBORCase::Array(_) => "array",
+            CBORCase::Map(_) => "map",
+            CBORCase::Tagged(tag, _) => {
+                *self.tags.entry(tag.value()).or_default() += 1;
+                "tag"
+            }
+            CBORCase::Simple(Simple::True | Simple::False) => "bool",
+            CBORCase::Simple(Simple::Null) => "null",
+            CBORCase::Simple(Simple::Float(_)) => "float",
+        };
+        *self.types.entry(name).or_default() += 1;
+        let children: Vec<&CBOR> = match cbor.as_case() {
+            CBORCase::Array(items) => items.iter().collect(),
+            CBORCase::Map(map) => {
+                map.iter().flat_map(|(key, value)| [key, value]).collect()
+            }
+            CBORCase::Tagged(_, item) => vec![item],
+            _ => return 0,
+        };
+        let depths = children.into_iter().map(|child| self.visit(child));
+        1 + depths.max().unwrap_or(0)
+    }
+}
+
+/// Returns the largest power of two that is at most `len`, or 0.
+fn bucket(len: usize) -> usize {
+    if len == 0 { 0 } else { 1 << len.ilog2() }
+}
+
+fn count_map<K: Clone + Into<CBOR>>(counts: &BTreeMap<K, usize>) -> CBOR {
+    let mut map = Map::new();
+    for (key, count) in counts {
+        map.insert(key.clone(), *count);
+    }
+    map.into()
+}
+
+fn count_json<K: ToString>(counts: &BTreeMap<K, usize>) -> String {
+    let entries: Vec<String> = counts
+        .iter()
+        .map(|(key, count)| format!("{:?}: {count}", key.to_string()))
+        .collect();
+    format!("{{{}}}", entries.join(", "))
+}
@@ -0,0 +1,161 @@
+use dcbor::prelude::*;
+
+/// The names and tags of the COSE messages of RFC 9052, and of the CBOR Web
+/// Token of RFC 8392.
+const COSE_TAGS: [(&str, TagValue); 7] = [
+    ("COSE_Encrypt0", 16),
+    ("COSE_Mac0", 17),
+    ("COSE_Sign1", 18),
+    ("CWT", 61),
+    ("COSE_Encrypt", 96),
+    ("COSE_Mac", 97),
+    ("COSE_Sign", 98),
+];
+
+/// Returns the tag of the COSE message or CWT named `name`.
+pub(crate) fn cose_tag_for_name(name: &str) -> Option<Tag> {
+    COSE_TAGS
+        .iter()
+        .find(|(tag_name, _)| *tag_name == name)
+        .map(|&(name, value)| Tag::new(value, name))
+}
+
+/// Checks that `content`, the content of an item tagged with `tag`, has the
+/// structure of the COSE message or CWT that `tag` identifies. Content of
+/// any other tag is valid.
+///
+/// Only the structure is checked: the protected headers must be byte
+/// strings, but are not decoded, and signatures, MACs, and ciphertexts are
+/// not verified.
+pub(crate) fn check_cose_structure(
+    tag: TagValue,
+    content: &CBOR,
+) -> std::result::Result<(), String> {
+    let Some(&(name, _)) = COSE_TAGS.iter().find(|(_, value)| *value == tag)
+    else {
+        return Ok(());
+    };
+    if tag == 61 {
+        return match content.as_case() {
+            CBORCase::Tagged(tag, item) if tag.value() != 61 => {
+                check_cose_structure(tag.value(), item)
+            }
+            _ => Err("a CWT must be a tagged COSE message".into()),
+        };
+    }
+    let fields: &[Field] = match tag {
+        16 => &[PROTECTED, UNPROTECTED, CIPHERTEXT],
+        17 => &[PROTECTED, UNPROTECTED, PAYLOAD, TAG],
+        18 => &[PROTECTED, UNPROTECTED, PAYLOAD, SIGNATURE],
+        96 => &[PROTECTED, UNPROTECTED, CIPHERTEXT, RECIPIENTS],
+        97 => &[PROTECTED, UNPROTECTED, PAYLOAD, TAG, RECIPIENTS],
+        _ => &[PROTECTED, UNPROTECTED, PAYLOAD, SIGNATURES],
+    };
+    check_fields(name, content, fields)
+}
+
+//
+// === Private Functions ===
+//
+
+/// A field of a COSE structure, and its name in RFC 9052.
+type Field = (&'static str, FieldKind);
+
+/// The type of a field of a COSE structure.
+#[derive(Clone, Copy)]
+enum FieldKind {
+    /// A byte string.
+    Bytes,
+    /// A header map.
+    Header,
+    /// A byte string, or `null` for detached content.
+    Payload,
+    /// A non-empty array of `COSE_Signature`.
+    Signatures,
+    /// A non-empty array of `COSE_recipient`.
+    Recipients,
+}
+
+const PROTECTED: Field = ("protected", FieldKind::Bytes);
+const UNPROTECTED: Field = ("unprotected", FieldKind::Header);
+const PAYLOAD: Field = ("payload", FieldKind::Payload);
+const CIPHERTEXT: Field = ("ciphertext", FieldKind::Payload);
+const SIGNATURE: Field = ("signature", FieldKind::Bytes);
+const TAG: Field = ("tag", FieldKind::Bytes);
+const SIGNATURES: Field = ("signatures", FieldKind::Signatures);
+const RECIPIENTS: Field = ("recipients", FieldKind::Recipients);
+
+/// Checks that `content` is an array of `fields`, as the structure `name`
+/// requires.
+fn check_fields(
+    name: &str,
+    content: &CBOR,
+    fields: &[Field],
+) -> std::result::Result<(), String> {
+    let CBORCase::Array(items) = content.as_case() else {
+        return Err(format!("{name} must be an array"));
+    };
+    if items.len() != fields.len() {
+        return Err(format!(
+            "{name} must have {} elements, not {}",
+            fields.len(),
+            items.len()
+        ));
+    }
+    for (item, (field, kind)) in items.iter().zip(fields) {
+        check_field(name, field, *kind, item)?;
+    }
+    Ok(())
+}
+
+fn check_field(
+    name: &str,
+    field: &str,
+    kind: FieldKind,
+    item: &CBOR,
+) -> std::result::Result<(), String> {
+    let is_valid = match kind {
+        FieldKind::Bytes => item.is_byte_string(),
+        FieldKind::Header => item.is_map(),
+        FieldKind::Payload => item.is_byte_string() || item.is_null(),
+        FieldKind::Signatures | FieldKind::Recipients => {
+            let CBORCase::Array(items) = item.as_case() else {
+                return Err(format!("the {field} of {name} must be an array"));
+            };
+            if items.is_empty() {
+                return Err(format!("the {field} of {name} must not be empty"));
+            }
+            return items.iter().try_for_each(|item| match kind {
+                FieldKind::Signatures => check_fields(
+                    "COSE_Signature",
+                    item,
+                    &[PROTECTED, UNPROTECTED, SIGNATURE],
+                ),
+                _ => check_recipient(item),
+            });
+        }
+    };
+    if is_valid {
+        return Ok(());
+    }
+    let expected = match kind {
+        FieldKind::Bytes => "a byte string",
+        FieldKind::Header => "a map",
+        _ => "a byte string or null",
+    };
+    Err(format!("the {field} of {name} must be {expected}"))
+}
+
+/// Checks a `COSE_recipient`, which has recipients of its own when its key
+/// is itself encrypted.
+fn check_recipient(item: &CBOR) -> std::result::Result<(), String> {
+    const FIELDS: [Field; 4] = [PROTECTED, UNPROTECTED, CIPHERTEXT, RECIPIENTS];
+    match item.as_array().map(|items| items.len()) {
+        Some(3) => check_fields("COSE_recipient", item, &FIELDS[..3]),
+        Some(4) => check_fields("COSE_recipient", item, &FIELDS),
+        Some(len) => Err(format!(
+            "COSE_recipient must have 3 or 4 elements, not {len}"
+        )),
+        None => Err("COSE_recipient must be an array".into()),
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use dcbor::prelude::*;
+
+/// The distinct items in a set of items, and which of the items are
+/// duplicates of each other.
+///
+/// Items are compared by their deterministic encoding, so items that were
+/// written differently in diagnostic notation, such as `1.0` and `1`, or
+/// maps with their keys in a different order, are duplicates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DedupeReport {
+    /// The first occurrence of each distinct item, in the order they were
+    /// first seen.
+    pub unique: Vec<CBOR>,
+    /// For each item, the index in `unique` of the item it is equal to.
+    pub unique_index: Vec<usize>,
+}
+
+impl DedupeReport {
+    /// Returns the indexes of items that are equal to another item, grouped
+    /// by the item they are equal to. Each group is in ascending order, and
+    /// the groups are in the order of their first item.
+    pub fn duplicates(&self) -> Vec<Vec<usize>> {
+        let mut groups = vec![Vec::new(); self.unique.len()];
+        for (index, &unique) in self.unique_index.iter().enumerate() {
+            groups[unique].push(index);
+        }
+        groups.retain(|group| group.len() > 1);
+        groups
+    }
+
+    /// Returns the number of items that could be removed because they are
+    /// equal to an earlier item.
+    pub fn redundant_count(&self) -> usize {
+        self.unique_index.len() - self.unique.len()
+    }
+}
+
+/// Finds the items that are semantically identical in a set of items, such as
+/// the items parsed from a set of fixtures.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{dedupe_items, parse_dcbor_item};
+/// let items = [r#"{"a": 1, "b": 2}"#, "[1]", r#"{"b": 2, "a": 1.0}"#]
+///     .iter()
+///     .map(|src| parse_dcbor_item(src).unwrap());
+/// let report = dedupe_items(items);
+/// assert_eq!(report.unique.len(), 2);
+/// assert_eq!(report.unique_index, [0, 1, 0]);
+/// assert_eq!(report.duplicates(), [vec![0, 2]]);
+/// assert_eq!(report.redundant_count(), 1);
+/// ```
+pub fn dedupe_items(items: impl IntoIterator<Item = CBOR>) -> DedupeReport {
+    let mut seen: HashMap<Vec<u8>, usize> = HashMap::new();
+    let mut unique = Vec::new();
+    let mut unique_index = Vec::new();
+    for item in items {
+        let index = *seen.entry(item.to_cbor_data()).or_insert_with(|| {
+            unique.push(item);
+            unique.len() - 1
+        });
+        unique_index.push(index);
+    }
+    DedupeReport { unique, unique_index }
+}
@@ -0,0 +1,533 @@
+use bc_ur::prelude::*;
+use known_values::KnownValue;
+
+use crate::{LiteralForm, SourceSpan};
+
+/// The encoding in which a byte string literal was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteStringEncoding {
+    /// `h'...'`
+    Hex,
+    /// `b64'...'`
+    Base64,
+    /// `b64url'...'`
+    Base64Url,
+    /// `b45'...'`
+    Base45,
+    /// `bw'...'`
+    ByteWords,
+    /// `!bytes("digest")`, with the bytes loaded by
+    /// [`ParseOptions::byte_string_loader`](crate::ParseOptions::byte_string_loader).
+    External,
+    /// `<<item, ...>>`, holding the concatenated encodings of the items.
+    Embedded,
+}
+
+/// How a numeric literal was written, as returned by
+/// [`DiagValue::number_kind`].
+///
+/// Every numeric literal accepted by this parser is one of these kinds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberKind {
+    /// Decimal digits with no fraction or exponent, such as `42` or `-1`.
+    Integer,
+    /// Hexadecimal digits after `0x`, such as `0x1f` or `-0x10`.
+    HexInteger,
+    /// Octal digits after `0o`, such as `0o17`.
+    OctalInteger,
+    /// Binary digits after `0b`, such as `0b1010`.
+    BinaryInteger,
+    /// A number with a fraction or exponent, such as `1.5` or `1e3`, or
+    /// `NaN`, `Infinity`, or `-Infinity`.
+    Float,
+}
+
+/// The encoding of a number in dCBOR, as returned by
+/// [`DiagValue::number_encoding`].
+///
+/// dCBOR encodes a float with an integral value as an integer, so `1.0` is
+/// encoded as [`Unsigned`](Self::Unsigned) and `-2e0` as
+/// [`Negative`](Self::Negative).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberEncoding {
+    /// An unsigned integer, major type 0.
+    Unsigned,
+    /// A negative integer, major type 1.
+    Negative,
+    /// A floating point number, major type 7, in the shortest of half,
+    /// single, or double precision that represents it exactly.
+    Float,
+    /// A bignum, major type 6: an integer outside the 64-bit range, tagged
+    /// 2 if positive and 3 if negative.
+    Bignum,
+}
+
+impl NumberEncoding {
+    /// Returns the CBOR major type of the encoding.
+    pub fn major_type(self) -> u8 {
+        match self {
+            NumberEncoding::Unsigned => 0,
+            NumberEncoding::Negative => 1,
+            NumberEncoding::Bignum => 6,
+            NumberEncoding::Float => 7,
+        }
+    }
+}
+
+/// The value of a numeric literal.
+///
+/// An integer literal is kept as an integer however large it is, so it keeps
+/// its full precision rather than being rounded to the nearest `f64`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumberValue {
+    /// A non-negative integer literal, such as `18446744073709551615`.
+    Unsigned(u64),
+    /// A negative integer literal, such as `-9223372036854775808`.
+    Negative(i64),
+    /// A non-negative integer literal too large for `u64`, holding the
+    /// big-endian bytes of its value with no leading zeros. It is encoded as
+    /// a positive bignum, tag 2.
+    BigUnsigned(Vec<u8>),
+    /// A negative integer literal too small for `i64`, holding the
+    /// big-endian bytes of `-1 - value` with no leading zeros. It is encoded
+    /// as a negative integer if it fits in 64 bits and as a negative bignum,
+    /// tag 3, otherwise.
+    BigNegative(Vec<u8>),
+    /// Any other numeric literal.
+    Float(f64),
+}
+
+impl NumberValue {
+    /// Parses a numeric literal written as JSON allows, or as an integer in
+    /// hex, octal, or binary, or `None` if it isn't one.
+    pub(crate) fn parse(s: &str) -> Option<NumberValue> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s),
+        };
+        let (radix, digits) = match digits.get(..2) {
+            Some("0x" | "0X") => (16, &digits[2..]),
+            Some("0o" | "0O") => (8, &digits[2..]),
+            Some("0b" | "0B") => (2, &digits[2..]),
+            _ if digits.contains(['.', 'e', 'E']) => {
+                return s.parse::<f64>().ok().map(NumberValue::Float);
+            }
+            _ => (10, digits),
+        };
+        let mut bytes = integer_bytes(digits, radix)?;
+        if bytes.len() <= 8 {
+            let mut be = [0; 8];
+            be[8 - bytes.len()..].copy_from_slice(&bytes);
+            let magnitude = u64::from_be_bytes(be);
+            // `-0` is zero, but isn't negative.
+            if !negative || magnitude == 0 {
+                return Some(NumberValue::Unsigned(magnitude));
+            }
+            if magnitude <= 1 << 63 {
+                let value = (magnitude as i64).wrapping_neg();
+                return Some(NumberValue::Negative(value));
+            }
+        }
+        Some(if negative {
+            decrement(&mut bytes);
+            NumberValue::BigNegative(bytes)
+        } else {
+            NumberValue::BigUnsigned(bytes)
+        })
+    }
+
+    /// Returns the value as an `f64`, which may round a large integer.
+    pub fn to_f64(&self) -> f64 {
+        let big = |bytes: &[u8]| {
+            bytes.iter().fold(0.0, |value, &b| value * 256.0 + b as f64)
+        };
+        match self {
+            NumberValue::Unsigned(value) => *value as f64,
+            NumberValue::Negative(value) => *value as f64,
+            NumberValue::BigUnsigned(bytes) => big(bytes),
+            NumberValue::BigNegative(bytes) => -1.0 - big(bytes),
+            NumberValue::Float(value) => *value,
+        }
+    }
+
+    /// Returns the value as a `u64` if it is a non-negative integer that fits
+    /// in one, however it was written.
+    pub fn to_u64(&self) -> Option<u64> {
+        match self {
+            NumberValue::Unsigned(value) => Some(*value),
+            NumberValue::Float(value) => (value.fract() == 0.0
+                && *value >= 0.0
+                && *value < u64::MAX as f64)
+                .then_some(*value as u64),
+            _ => None,
+        }
+    }
+}
+
+impl From<NumberValue> for CBOR {
+    fn from(value: NumberValue) -> Self {
+        match value {
+            NumberValue::Unsigned(value) => value.into(),
+            NumberValue::Negative(value) => value.into(),
+            NumberValue::BigUnsigned(bytes) => CBOR::to_tagged_value(
+                POSITIVE_BIGNUM_TAG,
+                CBOR::to_byte_string(bytes),
+            ),
+            NumberValue::BigNegative(bytes) if bytes.len() <= 8 => {
+                let mut be = [0; 8];
+                be[8 - bytes.len()..].copy_from_slice(&bytes);
+                CBORCase::Negative(u64::from_be_bytes(be)).into()
+            }
+            NumberValue::BigNegative(bytes) => CBOR::to_tagged_value(
+                NEGATIVE_BIGNUM_TAG,
+                CBOR::to_byte_string(bytes),
+            ),
+            NumberValue::Float(value) => value.into(),
+        }
+    }
+}
+
+/// A dCBOR item parsed from diagnostic notation that retains the lexical form
+/// of every literal it was written with.
+///
+/// A `CBOR` value only records semantics: `1.0` and `1` produce the same
+/// item, as do `h'00'` and `b64'AA=='`. A `DiagValue` additionally keeps the
+/// original text of each literal and the span of the source it was parsed
+/// from, which is what formatters, linters, and rewriters that must preserve
+/// the author's choices need. Convert it to `CBOR` with
+/// [`to_cbor`](Self::to_cbor).
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{DiagValue, parse_diag_value};
+/// # use dcbor::prelude::*;
+/// let value = parse_diag_value("[1.0, b64'AA==']").unwrap();
+/// let DiagValue::Array { items, .. } = &value else { panic!() };
+/// assert_eq!(items[0].text(), Some("1.0"));
+/// assert_eq!(items[1].text(), Some("b64'AA=='"));
+/// assert_eq!(value.to_cbor().diagnostic(), "[1, h'00']");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagValue {
+    /// `true` or `false`.
+    Bool { value: bool, span: SourceSpan },
+    /// `null`.
+    Null { span: SourceSpan },
+    /// A numeric literal, including `NaN`, `Infinity`, and `-Infinity`.
+    Number { text: String, value: NumberValue, span: SourceSpan },
+    /// A text string literal. The `text` includes the quotes, the `value` does
+    /// not.
+    Text { text: String, value: String, span: SourceSpan },
+    /// A byte string literal.
+    ByteString {
+        text: String,
+        encoding: ByteStringEncoding,
+        bytes: Vec<u8>,
+        span: SourceSpan,
+    },
+    /// An ISO-8601 date literal.
+    Date { text: String, date: Date, span: SourceSpan },
+    /// A decimal fraction literal such as `dec'2.75'`, whose value is
+    /// `mantissa * 10^exponent`. The mantissa keeps every digit as written,
+    /// so `dec'2.50'` has the exponent -2 and the mantissa 250.
+    DecimalFraction {
+        text: String,
+        exponent: i64,
+        mantissa: NumberValue,
+        span: SourceSpan,
+    },
+    /// A rational number literal such as `rat'1/3'`. The fraction is kept
+    /// as written, so `rat'2/6'` isn't reduced to `rat'1/3'`.
+    Rational {
+        text: String,
+        numerator: NumberValue,
+        denominator: NumberValue,
+        span: SourceSpan,
+    },
+    /// A known value written as `'1'`, `'isA'`, `''`, or `Unit`.
+    KnownValue { text: String, value: KnownValue, span: SourceSpan },
+    /// A `ur:` literal, along with the tag its type resolved to.
+    Ur { text: String, ur: UR, tag: Tag, span: SourceSpan },
+    /// A tagged item written as `1234(...)`, `0x4d2(...)`, or
+    /// `tag-name(...)`. The `tag_text` is the number or name as written.
+    Tagged {
+        tag: Tag,
+        tag_text: String,
+        item: Box<DiagValue>,
+        span: SourceSpan,
+    },
+    /// A tagged item written as `raw(1234, h'820102')`, whose content is
+    /// written as its deterministic encoding.
+    RawTagged {
+        text: String,
+        tag: Tag,
+        content: CBOR,
+        span: SourceSpan,
+    },
+    /// An array, with its items in source order.
+    Array { items: Vec<DiagValue>, span: SourceSpan },
+    /// A map, with its entries in source order.
+    Map {
+        entries: Vec<(DiagValue, DiagValue)>,
+        span: SourceSpan,
+    },
+    /// An item spliced in from another document with `!include("name")`.
+    /// The spans within `item` are offsets into the included document, not
+    /// the one containing the directive.
+    Included {
+        name: String,
+        item: Box<DiagValue>,
+        span: SourceSpan,
+    },
+}
+
+impl DiagValue {
+    /// Returns the span of the source this value was parsed from.
+    pub fn span(&self) -> SourceSpan {
+        match self {
+            DiagValue::Bool { span, .. }
+            | DiagValue::Null { span }
+            | DiagValue::Number { span, .. }
+            | DiagValue::Text { span, .. }
+            | DiagValue::ByteString { span, .. }
+            | DiagValue::Date { span, .. }
+            | DiagValue::DecimalFraction { span, .. }
+            | DiagValue::Rational { span, .. }
+            | DiagValue::KnownValue { span, .. }
+            | DiagValue::Ur { span, .. }
+            | DiagValue::Tagged { span, .. }
+            | DiagValue::RawTagged { span, .. }
+            | DiagValue::Array { span, .. }
+            | DiagValue::Map { span, .. }
+            | DiagValue::Included { span, .. } => *span,
+        }
+    }
+
+    /// Returns the original text of a literal, or `None` for `true`, `false`,
+    /// `null`, and containers.
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            DiagValue::Number { text, .. }
+            | DiagValue::Text { text, .. }
+            | DiagValue::ByteString { text, .. }
+            | DiagValue::Date { text, .. }
+            | DiagValue::DecimalFraction { text, .. }
+            | DiagValue::Rational { text, .. }
+            | DiagValue::KnownValue { text, .. }
+            | DiagValue::Ur { text, .. }
+            | DiagValue::RawTagged { text, .. } => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Returns how a numeric literal was written, or `None` if this isn't a
+    /// number.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{NumberEncoding, NumberKind, parse_diag_value};
+    /// let value = parse_diag_value("2.0").unwrap();
+    /// assert_eq!(value.number_kind(), Some(NumberKind::Float));
+    /// assert_eq!(value.number_encoding(), Some(NumberEncoding::Unsigned));
+    /// ```
+    pub fn number_kind(&self) -> Option<NumberKind> {
+        let DiagValue::Number { text, .. } = self else {
+            return None;
+        };
+        let digits = text.strip_prefix('-').unwrap_or(text);
+        Some(match digits.get(..2) {
+            Some("0x" | "0X") => NumberKind::HexInteger,
+            Some("0o" | "0O") => NumberKind::OctalInteger,
+            Some("0b" | "0B") => NumberKind::BinaryInteger,
+            _ if digits.bytes().all(|b| b.is_ascii_digit()) => {
+                NumberKind::Integer
+            }
+            _ => NumberKind::Float,
+        })
+    }
+
+    /// Returns how a number is encoded in dCBOR, or `None` if this isn't a
+    /// number.
+    pub fn number_encoding(&self) -> Option<NumberEncoding> {
+        let DiagValue::Number { .. } = self else {
+            return None;
+        };
+        Some(match self.to_cbor().into_case() {
+            CBORCase::Unsigned(_) => NumberEncoding::Unsigned,
+            CBORCase::Negative(_) => NumberEncoding::Negative,
+            CBORCase::Tagged(..) => NumberEncoding::Bignum,
+            _ => NumberEncoding::Float,
+        })
+    }
+
+    /// Returns the form this value was written in. An included item has the
+    /// form of the item in the included document.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{LiteralForm, parse_diag_value};
+    /// let form = |src| parse_diag_value(src).unwrap().literal_form();
+    /// assert_eq!(form("-Infinity"), LiteralForm::FloatKeyword);
+    /// assert_eq!(form("b64'AA=='"), LiteralForm::Base64ByteString);
+    /// assert_eq!(form("'1'"), LiteralForm::KnownValueNumber);
+    /// assert_eq!(form("0x4d2(1)"), LiteralForm::NumberTaggedValue);
+    /// ```
+    pub fn literal_form(&self) -> LiteralForm {
+        match self {
+            DiagValue::Bool { .. } => LiteralForm::Bool,
+            DiagValue::Null { .. } => LiteralForm::Null,
+            DiagValue::Number { text, .. } => match self.number_kind() {
+                Some(
+                    NumberKind::Integer
+                    | NumberKind::HexInteger
+                    | NumberKind::OctalInteger
+                    | NumberKind::BinaryInteger,
+                ) => LiteralForm::Integer,
+                _ if text.ends_with("NaN") || text.ends_with("Infinity") => {
+                    LiteralForm::FloatKeyword
+                }
+                _ => LiteralForm::Float,
+            },
+            DiagValue::Text { .. } => LiteralForm::Text,
+            DiagValue::ByteString { encoding, .. } => match encoding {
+                ByteStringEncoding::Hex => LiteralForm::HexByteString,
+                ByteStringEncoding::Base64 => LiteralForm::Base64ByteString,
+                ByteStringEncoding::Base64Url => {
+                    LiteralForm::Base64UrlByteString
+                }
+                ByteStringEncoding::Base45 => LiteralForm::Base45ByteString,
+                ByteStringEncoding::ByteWords => {
+                    LiteralForm::ByteWordsByteString
+                }
+                ByteStringEncoding::External => {
+                    LiteralForm::ExternalByteString
+                }
+                ByteStringEncoding::Embedded => {
+                    LiteralForm::EmbeddedByteString
+                }
+            },
+            DiagValue::Date { .. } => LiteralForm::Date,
+            DiagValue::DecimalFraction { .. } => LiteralForm::DecimalFraction,
+            DiagValue::Rational { .. } => LiteralForm::Rational,
+            DiagValue::KnownValue { text, .. } => match text.as_str() {
+                "Unit" | "''" => LiteralForm::Unit,
+                _ if text[1..].starts_with(|c: char| c.is_ascii_digit()) => {
+                    LiteralForm::KnownValueNumber
+                }
+                _ => LiteralForm::KnownValueName,
+            },
+            DiagValue::Ur { .. } => LiteralForm::Ur,
+            DiagValue::Tagged { tag_text, .. } => {
+                if tag_text.starts_with(|c: char| c.is_ascii_digit()) {
+                    LiteralForm::NumberTaggedValue
+                } else {
+                    LiteralForm::NameTaggedValue
+                }
+            }
+            DiagValue::RawTagged { .. } => LiteralForm::RawTaggedValue,
+            DiagValue::Array { .. } => LiteralForm::Array,
+            DiagValue::Map { .. } => LiteralForm::Map,
+            DiagValue::Included { item, .. } => item.literal_form(),
+        }
+    }
+
+    /// Returns the dCBOR item this value represents.
+    pub fn to_cbor(&self) -> CBOR {
+        match self {
+            DiagValue::Bool { value, .. } => (*value).into(),
+            DiagValue::Null { .. } => CBOR::null(),
+            DiagValue::Number { value, .. } => value.clone().into(),
+            DiagValue::Text { value, .. } => value.as_str().into(),
+            DiagValue::ByteString { bytes, .. } => CBOR::to_byte_string(bytes),
+            DiagValue::Date { date, .. } => (*date).into(),
+            DiagValue::DecimalFraction { exponent, mantissa, .. } => {
+                let mantissa = CBOR::from(mantissa.clone());
+                let array = vec![CBOR::from(*exponent), mantissa];
+                CBOR::to_tagged_value(DECIMAL_FRACTION_TAG, array)
+            }
+            DiagValue::Rational { numerator, denominator, .. } => {
+                let array = vec![
+                    CBOR::from(numerator.clone()),
+                    CBOR::from(denominator.clone()),
+                ];
+                CBOR::to_tagged_value(RATIONAL_TAG, array)
+            }
+            DiagValue::KnownValue { value, .. } => value.clone().into(),
+            DiagValue::Ur { ur, tag, .. } => {
+                CBOR::to_tagged_value(tag.clone(), ur.cbor())
+            }
+            DiagValue::Tagged { tag, item, .. } => {
+                CBOR::to_tagged_value(tag.clone(), item.to_cbor())
+            }
+            DiagValue::RawTagged { tag, content, .. } => {
+                CBOR::to_tagged_value(tag.clone(), content.clone())
+            }
+            DiagValue::Array { items, .. } => items
+                .iter()
+                .map(DiagValue::to_cbor)
+                .collect::<Vec<_>>()
+                .into(),
+            DiagValue::Map { entries, .. } => {
+                let mut map = Map::new();
+                for (key, value) in entries {
+                    map.insert(key.to_cbor(), value.to_cbor());
+                }
+                map.into()
+            }
+            DiagValue::Included { item, .. } => item.to_cbor(),
+        }
+    }
+}
+
+impl From<DiagValue> for CBOR {
+    fn from(value: DiagValue) -> Self { value.to_cbor() }
+}
+
+impl From<&DiagValue> for CBOR {
+    fn from(value: &DiagValue) -> Self { value.to_cbor() }
+}
+
+//
+// === Private Functions ===
+//
+
+const POSITIVE_BIGNUM_TAG: TagValue = 2;
+const DECIMAL_FRACTION_TAG: TagValue = 4;
+const NEGATIVE_BIGNUM_TAG: TagValue = 3;
+const RATIONAL_TAG: TagValue = 30;
+
+/// Returns the big-endian bytes of a non-negative integer written in
+/// `radix` with no leading zeros, or `None` if `digits` isn't one.
+fn integer_bytes(digits: &str, radix: u32) -> Option<Vec<u8>> {
+    let mut bytes: Vec<u8> = Vec::new();
+    for digit in digits.chars() {
+        let mut carry = digit.to_digit(radix)?;
+        for byte in bytes.iter_mut().rev() {
+            let value = *byte as u32 * radix + carry;
+            *byte = value as u8;
+            carry = value >> 8;
+        }
+        if carry > 0 {
+            bytes.insert(0, carry as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// Subtracts one from the non-zero big-endian integer in `bytes`, keeping it
+/// free of leading zeros.
+fn decrement(bytes: &mut Vec<u8>) {
+    for byte in bytes.iter_mut().rev() {
+        let (value, borrow) = byte.overflowing_sub(1);
+        *byte = value;
+        if !borrow {
+            break;
+        }
+    }
+    if bytes.first() == Some(&0) {
+        bytes.remove(0);
+    }
+}
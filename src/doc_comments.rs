@@ -0,0 +1,93 @@
+use dcbor::prelude::*;
+use logos::Logos;
+
+use crate::{
+    Comment, SourceSpan, Token, error::Result,
+    parse_dcbor_item_partial_with_comments,
+};
+
+/// A top-level item from a self-describing fixture file, along with the
+/// metadata written in the `## key: value` comments before it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentedItem {
+    /// The item.
+    pub item: CBOR,
+    /// The metadata of the item, in the order it was written.
+    pub metadata: Vec<(String, String)>,
+}
+
+impl DocumentedItem {
+    /// Returns the first value written for `key`, if any.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parses each of the whitespace-separated top-level items in `src`, along
+/// with the metadata written before it as `## key: value` comment lines.
+///
+/// Keys and values are trimmed of surrounding whitespace. Other comments,
+/// including `## ` lines without a colon, are ignored. The offsets of errors
+/// are relative to the whole of `src`.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::parse_documented_items;
+/// let src = [
+///     "## name: empty array",
+///     "## expect: 80",
+///     "[]",
+///     "",
+///     "# An ordinary comment.",
+///     "## name: small map",
+///     "{1: 2}",
+/// ]
+/// .join("\n");
+/// let items = parse_documented_items(&src).unwrap();
+/// assert_eq!(items.len(), 2);
+/// assert_eq!(items[0].get("name"), Some("empty array"));
+/// assert_eq!(items[0].get("expect"), Some("80"));
+/// assert_eq!(items[1].item.diagnostic(), "{1: 2}");
+/// assert_eq!(
+///     items[1].metadata,
+///     [("name".to_string(), "small map".to_string())]
+/// );
+/// ```
+pub fn parse_documented_items(src: &str) -> Result<Vec<DocumentedItem>> {
+    let mut items = Vec::new();
+    let mut offset = 0;
+    while Token::lexer(&src[offset..]).next().is_some() {
+        let rest = &src[offset..];
+        let (commented, used) = parse_dcbor_item_partial_with_comments(rest)
+            .map_err(|mut error| {
+                if let Some(span) = error.span_mut() {
+                    *span =
+                        SourceSpan::new(span.start + offset, span.end + offset);
+                }
+                error
+            })?;
+        let metadata = commented
+            .leading
+            .iter()
+            .filter_map(|comment| metadata_entry(rest, comment))
+            .collect();
+        items.push(DocumentedItem { item: commented.item, metadata });
+        offset += used;
+    }
+    Ok(items)
+}
+
+//
+// === Private Functions ===
+//
+
+/// Returns the key and value of a `## key: value` comment.
+fn metadata_entry(src: &str, comment: &Comment) -> Option<(String, String)> {
+    let text = src[comment.span.range()].strip_prefix("##")?;
+    let (key, value) = text.split_once(':')?;
+    Some((key.trim().to_string(), value.trim().to_string()))
+}
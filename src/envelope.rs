@@ -0,0 +1,88 @@
+use dcbor::prelude::*;
+
+/// The tag of a Gordian Envelope, and of an envelope wrapped in another.
+pub(crate) const ENVELOPE_TAG: TagValue = 200;
+
+/// The tag of an envelope leaf, which holds an arbitrary dCBOR item.
+const LEAF_TAG: TagValue = 201;
+
+/// The tag of an encrypted envelope.
+const ENCRYPTED_TAG: TagValue = 40002;
+
+/// The tag of a compressed envelope.
+const COMPRESSED_TAG: TagValue = 40003;
+
+/// Checks that `content`, the content of an envelope tag, has the structure
+/// of a Gordian Envelope: a leaf, known value, wrapped envelope, elided
+/// digest, encrypted or compressed envelope, assertion, or node.
+///
+/// Only the structure is checked: the content of leaves, encrypted, and
+/// compressed envelopes is not examined, and digests are not verified.
+pub(crate) fn check_envelope_shape(
+    content: &CBOR,
+) -> std::result::Result<(), String> {
+    match content.as_case() {
+        CBORCase::Tagged(tag, item) => match tag.value() {
+            LEAF_TAG => Ok(()),
+            ENVELOPE_TAG => check_envelope_shape(item),
+            ENCRYPTED_TAG | COMPRESSED_TAG if item.is_array() => Ok(()),
+            ENCRYPTED_TAG => {
+                Err("an encrypted envelope must be an array".into())
+            }
+            COMPRESSED_TAG => {
+                Err("a compressed envelope must be an array".into())
+            }
+            value => Err(format!("unexpected tag {value} in an envelope")),
+        },
+        CBORCase::Unsigned(_) => Ok(()),
+        CBORCase::ByteString(digest) if digest.len() == 32 => Ok(()),
+        CBORCase::ByteString(_) => {
+            Err("an elided envelope must be a 32-byte digest".into())
+        }
+        CBORCase::Map(map) => check_assertion(map),
+        CBORCase::Array(items) => {
+            let Some((subject, assertions)) = items.split_first() else {
+                return Err("a node must not be empty".into());
+            };
+            if assertions.is_empty() {
+                return Err("a node must have at least one assertion".into());
+            }
+            check_envelope_shape(subject)?;
+            assertions.iter().try_for_each(check_node_assertion)
+        }
+        _ => Err("expected a leaf, node, assertion, known value, or \
+                  wrapped, elided, encrypted, or compressed envelope"
+            .into()),
+    }
+}
+
+//
+// === Private Functions ===
+//
+
+fn check_assertion(map: &Map) -> std::result::Result<(), String> {
+    let mut entries = map.iter();
+    let (Some((predicate, object)), None) = (entries.next(), entries.next())
+    else {
+        return Err("an assertion must be a map with exactly one entry".into());
+    };
+    check_envelope_shape(predicate)?;
+    check_envelope_shape(object)
+}
+
+/// Checks one of the assertions of a node, which may also be elided,
+/// encrypted, or compressed.
+fn check_node_assertion(item: &CBOR) -> std::result::Result<(), String> {
+    match item.as_case() {
+        CBORCase::Map(map) => check_assertion(map),
+        CBORCase::ByteString(_) => check_envelope_shape(item),
+        CBORCase::Tagged(tag, _)
+            if matches!(tag.value(), ENCRYPTED_TAG | COMPRESSED_TAG) =>
+        {
+            check_envelope_shape(item)
+        }
+        _ => Err("each assertion of a node must be an assertion, or an \
+                  elided, encrypted, or compressed assertion"
+            .into()),
+    }
+}
@@ -1,7 +1,9 @@
-use logos::Span;
 use thiserror::Error;
 
-use crate::Token;
+use crate::{
+    SourceSpan, Token,
+    span::{line_end, line_start},
+};
 
 #[derive(Debug, Error, Clone, PartialEq)]
 #[rustfmt::skip]
@@ -11,41 +13,105 @@ pub enum Error {
     #[error("Unexpected end of input")]
     UnexpectedEndOfInput,
     #[error("Extra data at end of input")]
-    ExtraData(Span),
+    ExtraData(SourceSpan),
     #[error("Unexpected token {0:?}")]
-    UnexpectedToken(Box<Token>, Span),
+    UnexpectedToken(Box<Token>, SourceSpan),
     #[error("Unrecognized token")]
-    UnrecognizedToken(Span),
+    UnrecognizedToken(SourceSpan),
     #[error("Expected comma")]
-    ExpectedComma(Span),
+    ExpectedComma(SourceSpan),
     #[error("Expected colon")]
-    ExpectedColon(Span),
+    ExpectedColon(SourceSpan),
     #[error("Unmatched parentheses")]
-    UnmatchedParentheses(Span),
+    UnmatchedParentheses(SourceSpan),
     #[error("Unmatched braces")]
-    UnmatchedBraces(Span),
+    UnmatchedBraces(SourceSpan),
     #[error("Expected map key")]
-    ExpectedMapKey(Span),
+    ExpectedMapKey(SourceSpan),
     #[error("Invalid tag value '{0}'")]
-    InvalidTagValue(String, Span),
+    InvalidTagValue(String, SourceSpan),
     #[error("Unknown tag name '{0}'")]
-    UnknownTagName(String, Span),
+    UnknownTagName(String, SourceSpan),
+    #[error("Whitespace between tag and opening parenthesis")]
+    SpaceBeforeParenthesis(SourceSpan),
     #[error("Invalid hex string")]
-    InvalidHexString(Span),
+    InvalidHexString(SourceSpan),
     #[error("Invalid base64 string")]
-    InvalidBase64String(Span),
+    InvalidBase64String(SourceSpan),
+    #[error("Invalid base45 string")]
+    InvalidBase45String(SourceSpan),
+    #[error("Invalid ByteWords: {0}")]
+    InvalidByteWords(String, SourceSpan),
+    #[error("Invalid escape sequence '{0}'")]
+    InvalidEscapeSequence(String, SourceSpan),
+    #[error("Invalid Unicode escape '{0}'")]
+    InvalidUnicodeEscape(String, SourceSpan),
+    #[error("Control character {0} in string")]
+    ControlCharacter(String, SourceSpan),
+    #[error("Token longer than {0} bytes")]
+    TokenTooLong(usize, SourceSpan),
+    #[error("Nesting deeper than {0} levels")]
+    NestingTooDeep(usize, SourceSpan),
+    #[error("Invalid UTF-8")]
+    InvalidUtf8(SourceSpan),
+    #[error("NUL character")]
+    NulCharacter(SourceSpan),
     #[error("Unknown UR type '{0}'")]
-    UnknownUrType(String, Span),
+    UnknownUrType(String, SourceSpan),
     #[error("Invalid UR '{0}'")]
-    InvalidUr(String, Span),
+    InvalidUr(String, SourceSpan),
     #[error("Invalid known value '{0}'")]
-    InvalidKnownValue(String, Span),
+    InvalidKnownValue(String, SourceSpan),
     #[error("Unknown known value name '{0}'")]
-    UnknownKnownValueName(String, Span),
+    UnknownKnownValueName(String, SourceSpan),
     #[error("Invalid date string '{0}'")]
-    InvalidDateString(String, Span),
+    InvalidDateString(String, SourceSpan),
     #[error("Duplicate map key")]
-    DuplicateMapKey(Span),
+    DuplicateMapKey(SourceSpan),
+    #[error("Map key must be an integer, not {0}")]
+    NonIntegerMapKey(String, SourceSpan),
+    #[error("Expected map")]
+    ExpectedMap(SourceSpan),
+    #[error("Parsing stopped by visitor")]
+    VisitorStopped(SourceSpan),
+    #[error("Rejected by visitor: {0}")]
+    VisitorRejected(String, SourceSpan),
+    #[error("Include directives are not enabled")]
+    IncludeNotEnabled(SourceSpan),
+    #[error("Include failed: {0}")]
+    IncludeFailed(String, SourceSpan),
+    #[error("Undefined variable '{0}'")]
+    UndefinedVariable(String, SourceSpan),
+    #[error("Known value {0} is not allowed")]
+    DisallowedKnownValue(String, SourceSpan),
+    #[error("Invalid content for tag {0}")]
+    InvalidTagContent(String, SourceSpan),
+    #[error("External byte strings are not enabled")]
+    ExternalBytesNotEnabled(SourceSpan),
+    #[error("Loading external byte string failed: {0}")]
+    ExternalBytesFailed(String, SourceSpan),
+    #[error("The tags registry is unavailable")]
+    TagsRegistryUnavailable(SourceSpan),
+    #[error("The known values registry is unavailable")]
+    KnownValuesRegistryUnavailable(SourceSpan),
+    #[error("{0} are disabled")]
+    SyntaxDisabled(String, SourceSpan),
+    #[error("No item at path {0}")]
+    PathNotFound(String, SourceSpan),
+    #[error("Invalid known value range {0}")]
+    InvalidKnownValueRange(String, SourceSpan),
+    #[error("Invalid decimal fraction '{0}'")]
+    InvalidDecimalFraction(String, SourceSpan),
+    #[error("Invalid rational '{0}'")]
+    InvalidRational(String, SourceSpan),
+    #[error("Encoding indicator {0} doesn't match the deterministic encoding")]
+    EncodingIndicatorMismatch(String, SourceSpan),
+    #[error("Invalid raw tag content: {0}")]
+    InvalidRawContent(String, SourceSpan),
+    #[error("Invalid type assertion '{0}'")]
+    InvalidTypeAssertion(String, SourceSpan),
+    #[error("Item doesn't match type assertion '{0}'")]
+    TypeMismatch(String, SourceSpan),
 }
 
 impl Error {
@@ -53,66 +119,485 @@ impl Error {
         matches!(self, Error::UnrecognizedToken(_))
     }
 
-    fn format_message(
-        message: &dyn ToString,
-        source: &str,
-        range: &Span,
-    ) -> String {
-        let message = message.to_string();
-        let start = range.start;
-        let end = range.end;
-        // Walk through the bytes up to `start` to find line number and line
-        // start offset
-        let mut line_number = 1;
-        let mut line_start = 0;
-        for (idx, ch) in source.char_indices() {
-            if idx >= start {
-                break;
+    /// Returns a stable identifier for the kind of this error, suitable for
+    /// looking up a localized message.
+    #[rustfmt::skip]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::EmptyInput => "empty-input",
+            Error::UnexpectedEndOfInput => "unexpected-end-of-input",
+            Error::ExtraData(_) => "extra-data",
+            Error::UnexpectedToken(_, _) => "unexpected-token",
+            Error::UnrecognizedToken(_) => "unrecognized-token",
+            Error::ExpectedComma(_) => "expected-comma",
+            Error::ExpectedColon(_) => "expected-colon",
+            Error::UnmatchedParentheses(_) => "unmatched-parentheses",
+            Error::UnmatchedBraces(_) => "unmatched-braces",
+            Error::ExpectedMapKey(_) => "expected-map-key",
+            Error::InvalidTagValue(_, _) => "invalid-tag-value",
+            Error::UnknownTagName(_, _) => "unknown-tag-name",
+            Error::SpaceBeforeParenthesis(_) => "space-before-parenthesis",
+            Error::InvalidHexString(_) => "invalid-hex-string",
+            Error::InvalidBase64String(_) => "invalid-base64-string",
+            Error::InvalidBase45String(_) => "invalid-base45-string",
+            Error::InvalidByteWords(_, _) => "invalid-bytewords",
+            Error::InvalidEscapeSequence(_, _) => "invalid-escape-sequence",
+            Error::InvalidUnicodeEscape(_, _) => "invalid-unicode-escape",
+            Error::ControlCharacter(_, _) => "control-character",
+            Error::TokenTooLong(_, _) => "token-too-long",
+            Error::NestingTooDeep(_, _) => "nesting-too-deep",
+            Error::InvalidUtf8(_) => "invalid-utf8",
+            Error::NulCharacter(_) => "nul-character",
+            Error::UnknownUrType(_, _) => "unknown-ur-type",
+            Error::InvalidUr(_, _) => "invalid-ur",
+            Error::InvalidKnownValue(_, _) => "invalid-known-value",
+            Error::UnknownKnownValueName(_, _) => "unknown-known-value-name",
+            Error::InvalidDateString(_, _) => "invalid-date-string",
+            Error::DuplicateMapKey(_) => "duplicate-map-key",
+            Error::NonIntegerMapKey(_, _) => "non-integer-map-key",
+            Error::ExpectedMap(_) => "expected-map",
+            Error::VisitorStopped(_) => "visitor-stopped",
+            Error::VisitorRejected(_, _) => "visitor-rejected",
+            Error::IncludeNotEnabled(_) => "include-not-enabled",
+            Error::IncludeFailed(_, _) => "include-failed",
+            Error::UndefinedVariable(_, _) => "undefined-variable",
+            Error::DisallowedKnownValue(_, _) => "disallowed-known-value",
+            Error::InvalidTagContent(_, _) => "invalid-tag-content",
+            Error::ExternalBytesNotEnabled(_) => "external-bytes-not-enabled",
+            Error::ExternalBytesFailed(_, _) => "external-bytes-failed",
+            Error::TagsRegistryUnavailable(_) => "tags-registry-unavailable",
+            Error::KnownValuesRegistryUnavailable(_) => "known-values-registry-unavailable",
+            Error::SyntaxDisabled(_, _) => "syntax-disabled",
+            Error::PathNotFound(_, _) => "path-not-found",
+            Error::InvalidKnownValueRange(_, _) => "invalid-known-value-range",
+            Error::InvalidDecimalFraction(_, _) => "invalid-decimal-fraction",
+            Error::InvalidRational(_, _) => "invalid-rational",
+            Error::EncodingIndicatorMismatch(_, _) => {
+                "encoding-indicator-mismatch"
             }
-            if ch == '\n' {
-                line_number += 1;
-                line_start = idx + 1;
+            Error::InvalidRawContent(_, _) => "invalid-raw-content",
+            Error::InvalidTypeAssertion(_, _) => "invalid-type-assertion",
+            Error::TypeMismatch(_, _) => "type-mismatch",
+        }
+    }
+
+    /// Returns the values interpolated into the message of this error, in the
+    /// order they appear in the English message.
+    pub fn params(&self) -> Vec<String> {
+        match self {
+            Error::UnexpectedToken(token, _) => vec![format!("{token:?}")],
+            Error::TokenTooLong(max, _) | Error::NestingTooDeep(max, _) => {
+                vec![max.to_string()]
             }
+            Error::InvalidTagValue(s, _)
+            | Error::UnknownTagName(s, _)
+            | Error::UnknownUrType(s, _)
+            | Error::InvalidUr(s, _)
+            | Error::InvalidKnownValue(s, _)
+            | Error::UnknownKnownValueName(s, _)
+            | Error::InvalidDateString(s, _)
+            | Error::VisitorRejected(s, _)
+            | Error::IncludeFailed(s, _)
+            | Error::UndefinedVariable(s, _)
+            | Error::DisallowedKnownValue(s, _)
+            | Error::InvalidTagContent(s, _)
+            | Error::ExternalBytesFailed(s, _)
+            | Error::NonIntegerMapKey(s, _)
+            | Error::InvalidEscapeSequence(s, _)
+            | Error::InvalidUnicodeEscape(s, _)
+            | Error::ControlCharacter(s, _)
+            | Error::SyntaxDisabled(s, _)
+            | Error::PathNotFound(s, _)
+            | Error::InvalidKnownValueRange(s, _)
+            | Error::InvalidDecimalFraction(s, _)
+            | Error::InvalidRational(s, _)
+            | Error::EncodingIndicatorMismatch(s, _)
+            | Error::InvalidRawContent(s, _)
+            | Error::InvalidByteWords(s, _)
+            | Error::InvalidTypeAssertion(s, _)
+            | Error::TypeMismatch(s, _) => vec![s.clone()],
+            _ => vec![],
         }
-        // Grab the exact line text (or empty if out of bounds)
-        let line = source.lines().nth(line_number - 1).unwrap_or("");
-        // Column is byte-offset into that line
-        let column = start.saturating_sub(line_start);
-        // Underline at least one caret, even for zero-width spans
-        let underline_len = end.saturating_sub(start).max(1);
-        let caret = " ".repeat(column) + &"^".repeat(underline_len);
-        format!("line {line_number}: {message}\n{line}\n{caret}")
     }
 
+    /// Returns the span of the source the error applies to, or `None` for
+    /// [`EmptyInput`](Error::EmptyInput) and
+    /// [`UnexpectedEndOfInput`](Error::UnexpectedEndOfInput).
     #[rustfmt::skip]
-    pub fn full_message(&self, source: &str) -> String {
+    pub fn span(&self) -> Option<SourceSpan> {
+        match self {
+            Error::EmptyInput | Error::UnexpectedEndOfInput => None,
+            Error::ExtraData(span)
+            | Error::UnexpectedToken(_, span)
+            | Error::UnrecognizedToken(span)
+            | Error::ExpectedComma(span)
+            | Error::ExpectedColon(span)
+            | Error::UnmatchedParentheses(span)
+            | Error::UnmatchedBraces(span)
+            | Error::ExpectedMapKey(span)
+            | Error::InvalidTagValue(_, span)
+            | Error::UnknownTagName(_, span)
+            | Error::SpaceBeforeParenthesis(span)
+            | Error::InvalidHexString(span)
+            | Error::InvalidBase64String(span)
+            | Error::InvalidBase45String(span)
+            | Error::InvalidByteWords(_, span)
+            | Error::InvalidEscapeSequence(_, span)
+            | Error::InvalidUnicodeEscape(_, span)
+            | Error::ControlCharacter(_, span)
+            | Error::TokenTooLong(_, span)
+            | Error::NestingTooDeep(_, span)
+            | Error::InvalidUtf8(span)
+            | Error::NulCharacter(span)
+            | Error::UnknownUrType(_, span)
+            | Error::InvalidUr(_, span)
+            | Error::InvalidKnownValue(_, span)
+            | Error::UnknownKnownValueName(_, span)
+            | Error::InvalidDateString(_, span)
+            | Error::DuplicateMapKey(span)
+            | Error::NonIntegerMapKey(_, span)
+            | Error::ExpectedMap(span)
+            | Error::VisitorStopped(span)
+            | Error::VisitorRejected(_, span)
+            | Error::IncludeNotEnabled(span)
+            | Error::IncludeFailed(_, span)
+            | Error::UndefinedVariable(_, span)
+            | Error::DisallowedKnownValue(_, span)
+            | Error::InvalidTagContent(_, span)
+            | Error::ExternalBytesNotEnabled(span)
+            | Error::ExternalBytesFailed(_, span)
+            | Error::TagsRegistryUnavailable(span)
+            | Error::KnownValuesRegistryUnavailable(span)
+            | Error::SyntaxDisabled(_, span)
+            | Error::PathNotFound(_, span)
+            | Error::InvalidKnownValueRange(_, span)
+            | Error::InvalidDecimalFraction(_, span)
+            | Error::InvalidRational(_, span)
+            | Error::EncodingIndicatorMismatch(_, span)
+            | Error::InvalidRawContent(_, span)
+            | Error::InvalidTypeAssertion(_, span)
+            | Error::TypeMismatch(_, span) => Some(*span),
+        }
+    }
+
+    #[rustfmt::skip]
+    pub(crate) fn span_mut(&mut self) -> Option<&mut SourceSpan> {
         match self {
-            Error::EmptyInput => Self::format_message(self, source, &Span::default()),
-            Error::UnexpectedEndOfInput => Self::format_message(self, source, &(source.len()..source.len())),
-            Error::ExtraData(range) => Self::format_message(self, source, range),
-            Error::UnexpectedToken(_, range) => Self::format_message(self, source, range),
-            Error::UnrecognizedToken(range) => Self::format_message(self, source, range),
-            Error::UnknownUrType(_, range) => Self::format_message(self, source, range),
-            Error::UnmatchedParentheses(range) => Self::format_message(self, source, range),
-            Error::ExpectedComma(range) => Self::format_message(self, source, range),
-            Error::ExpectedColon(range) => Self::format_message(self, source, range),
-            Error::ExpectedMapKey(range) => Self::format_message(self, source, range),
-            Error::UnmatchedBraces(range) => Self::format_message(self, source, range),
-            Error::UnknownTagName(_, range) => Self::format_message(self, source, range),
-            Error::InvalidHexString(range) => Self::format_message(self, source, range),
-            Error::InvalidBase64String(range) => Self::format_message(self, source, range),
-            Error::InvalidTagValue(_, range) => Self::format_message(self, source, range),
-            Error::InvalidUr(_, range) => Self::format_message(self, source, range),
-            Error::InvalidKnownValue(_, range) => Self::format_message(self, source, range),
-            Error::UnknownKnownValueName(_, range) => Self::format_message(self, source, range),
-            Error::InvalidDateString(_, range) => Self::format_message(self, source, range),
-            Error::DuplicateMapKey(range) => Self::format_message(self, source, range),
+            Error::EmptyInput | Error::UnexpectedEndOfInput => None,
+            Error::ExtraData(span)
+            | Error::UnexpectedToken(_, span)
+            | Error::UnrecognizedToken(span)
+            | Error::ExpectedComma(span)
+            | Error::ExpectedColon(span)
+            | Error::UnmatchedParentheses(span)
+            | Error::UnmatchedBraces(span)
+            | Error::ExpectedMapKey(span)
+            | Error::InvalidTagValue(_, span)
+            | Error::UnknownTagName(_, span)
+            | Error::SpaceBeforeParenthesis(span)
+            | Error::InvalidHexString(span)
+            | Error::InvalidBase64String(span)
+            | Error::InvalidBase45String(span)
+            | Error::InvalidByteWords(_, span)
+            | Error::InvalidEscapeSequence(_, span)
+            | Error::InvalidUnicodeEscape(_, span)
+            | Error::ControlCharacter(_, span)
+            | Error::TokenTooLong(_, span)
+            | Error::NestingTooDeep(_, span)
+            | Error::InvalidUtf8(span)
+            | Error::NulCharacter(span)
+            | Error::UnknownUrType(_, span)
+            | Error::InvalidUr(_, span)
+            | Error::InvalidKnownValue(_, span)
+            | Error::UnknownKnownValueName(_, span)
+            | Error::InvalidDateString(_, span)
+            | Error::DuplicateMapKey(span)
+            | Error::NonIntegerMapKey(_, span)
+            | Error::ExpectedMap(span)
+            | Error::VisitorStopped(span)
+            | Error::VisitorRejected(_, span)
+            | Error::IncludeNotEnabled(span)
+            | Error::IncludeFailed(_, span)
+            | Error::UndefinedVariable(_, span)
+            | Error::DisallowedKnownValue(_, span)
+            | Error::InvalidTagContent(_, span)
+            | Error::ExternalBytesNotEnabled(span)
+            | Error::ExternalBytesFailed(_, span)
+            | Error::TagsRegistryUnavailable(span)
+            | Error::KnownValuesRegistryUnavailable(span)
+            | Error::SyntaxDisabled(_, span)
+            | Error::PathNotFound(_, span)
+            | Error::InvalidKnownValueRange(_, span)
+            | Error::InvalidDecimalFraction(_, span)
+            | Error::InvalidRational(_, span)
+            | Error::EncodingIndicatorMismatch(_, span)
+            | Error::InvalidRawContent(_, span)
+            | Error::InvalidTypeAssertion(_, span)
+            | Error::TypeMismatch(_, span) => Some(span),
+        }
+    }
+
+    /// Returns an actionable hint for fixing this error, if there is one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::parse_dcbor_item;
+    /// let error = parse_dcbor_item("h'01020'").unwrap_err();
+    /// assert_eq!(
+    ///     error.help(),
+    ///     Some("hex byte strings must contain an even number of hex digits")
+    /// );
+    /// ```
+    pub fn help(&self) -> Option<&'static str> { help_for_code(self.code()) }
+
+    fn format_message(
+        message: &str,
+        help: Option<String>,
+        source: &str,
+        range: &SourceSpan,
+        tab_width: usize,
+    ) -> String {
+        let (line_start, line_number) = line_start(source, range.start);
+        // Grab the exact line text, without its line break
+        let line_end = line_end(source, line_start);
+        // Underline only the part of the range on this line
+        let start = range.start.clamp(line_start, line_end);
+        let end = range.end.clamp(start, line_end);
+        // Columns count characters, with tabs expanded to the next tab stop,
+        // so the carets line up under the line as it is displayed
+        let before = expand_tabs(&source[line_start..start], 0, tab_width);
+        let column = before.chars().count();
+        let underlined = expand_tabs(&source[start..end], column, tab_width);
+        // Underline at least one caret, even for zero-width spans
+        let underline_len = underlined.chars().count().max(1);
+        let line = expand_tabs(&source[line_start..line_end], 0, tab_width);
+        let caret = " ".repeat(column) + &"^".repeat(underline_len);
+        let mut result =
+            format!("line {line_number}: {message}\n{line}\n{caret}");
+        if let Some(help) = help {
+            result.push_str(&format!("\nhelp: {help}"));
         }
+        result
+    }
+
+    /// Returns the English message for this error, followed by the line of
+    /// `source` where it occurred with the offending range underlined.
+    pub fn full_message(&self, source: &str) -> String {
+        self.full_message_with(source, &EnglishMessages)
+    }
+
+    /// Like [`full_message`](Self::full_message), but with the message
+    /// produced by `renderer`.
+    pub fn full_message_with(
+        &self,
+        source: &str,
+        renderer: &dyn MessageRenderer,
+    ) -> String {
+        let message = &renderer.render(self.code(), &self.params());
+        let help = || renderer.render_help(self.code());
+        let tab_width = renderer.tab_width();
+        let range = self.span().unwrap_or(match self {
+            Error::UnexpectedEndOfInput => {
+                SourceSpan::new(source.len(), source.len())
+            }
+            _ => SourceSpan::default(),
+        });
+        Self::format_message(message, help(), source, &range, tab_width)
     }
 }
 
 impl Default for Error {
-    fn default() -> Self { Error::UnrecognizedToken(Span::default()) }
+    fn default() -> Self { Error::UnrecognizedToken(SourceSpan::default()) }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// Produces the message displayed for an error from its
+/// [`code`](Error::code) and [`params`](Error::params).
+///
+/// Implement this trait to localize or re-word parser errors without matching
+/// on their English text, and pass it to
+/// [`full_message_with`](Error::full_message_with).
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{EnglishMessages, MessageRenderer, parse_dcbor_item};
+/// struct French;
+///
+/// impl MessageRenderer for French {
+///     fn render(&self, code: &str, params: &[String]) -> String {
+///         match code {
+///             "expected-comma" => "Virgule attendue".to_string(),
+///             _ => EnglishMessages.render(code, params),
+///         }
+///     }
+/// }
+///
+/// let src = "[1 2]";
+/// let error = parse_dcbor_item(src).unwrap_err();
+/// assert_eq!(
+///     error.full_message_with(src, &French),
+///     "line 1: Virgule attendue\n[1 2]\n   ^\n\
+///      help: separate array items and map entries with commas"
+/// );
+/// ```
+pub trait MessageRenderer {
+    /// Returns the message for the error identified by `code`, whose
+    /// parameters are `params`.
+    fn render(&self, code: &str, params: &[String]) -> String;
+
+    /// Returns the hint for fixing the error identified by `code`, if there is
+    /// one. Defaults to the English hint returned by [`Error::help`].
+    fn render_help(&self, code: &str) -> Option<String> {
+        help_for_code(code).map(str::to_string)
+    }
+
+    /// Returns the number of columns between tab stops, used to expand tabs
+    /// in the source line so the carets line up under the error. Defaults to
+    /// 4.
+    fn tab_width(&self) -> usize { 4 }
+}
+
+/// Returns `text` with each tab replaced by spaces up to the next multiple of
+/// `tab_width`, given that it starts at `column`.
+fn expand_tabs(text: &str, column: usize, tab_width: usize) -> String {
+    let tab_width = tab_width.max(1);
+    let mut result = String::new();
+    let mut column = column;
+    for ch in text.chars() {
+        if ch == '\t' {
+            let spaces = tab_width - column % tab_width;
+            result.push_str(&" ".repeat(spaces));
+            column += spaces;
+        } else {
+            result.push(ch);
+            column += 1;
+        }
+    }
+    result
+}
+
+#[rustfmt::skip]
+fn help_for_code(code: &str) -> Option<&'static str> {
+    Some(match code {
+        "empty-input" => "provide a single dCBOR item, such as `42` or `[1, 2, 3]`",
+        "unexpected-end-of-input" => "the input ended before the item was complete; check for a missing `]`, `}`, or `)`",
+        "extra-data" => "only one top-level item is allowed; wrap multiple items in an array",
+        "unrecognized-token" => "check for typos, unquoted strings, or unsupported syntax",
+        "expected-comma" => "separate array items and map entries with commas",
+        "expected-colon" => "separate each map key from its value with a colon",
+        "unmatched-parentheses" => "a tagged value is written as `tag(item)` with exactly one item",
+        "unmatched-braces" => "close the map with `}`",
+        "expected-map-key" => "each map key must be followed by a value",
+        "invalid-tag-value" => "tag values must fit in an unsigned 64-bit integer",
+        "unknown-tag-name" => "register the tag name in the global tags registry before parsing, or use the numeric tag value",
+        "space-before-parenthesis" => "remove the whitespace, as in `date(1)`, or enable `ParseOptions::spaced_tags`",
+        "invalid-hex-string" => "hex byte strings must contain an even number of hex digits",
+        "invalid-base64-string" => "base64 byte strings must use the standard alphabet with correct padding, and base64url byte strings the URL-safe alphabet without padding",
+        "invalid-base45-string" => "base45 byte strings must use the alphabet of RFC 9285, with each group of three characters encoding a value up to 65535 and a final group of two encoding one up to 255",
+        "invalid-bytewords" => "write ByteWords with their four-byte checksum, as four-letter words separated by spaces or dashes, or as two-letter minimal words without separators, such as `bw'aetdaowslg'`",
+        "invalid-escape-sequence" => "the escapes in strings are `\\\"`, `\\\\`, `\\/`, `\\b`, `\\f`, `\\n`, `\\r`, `\\t`, and `\\u` followed by four hex digits",
+        "invalid-unicode-escape" => "escape a character outside the Basic Multilingual Plane as a surrogate pair, such as `\\ud83c\\udf0e`, or write the character itself",
+        "control-character" => "escape control characters in strings, such as `\\t` or `\\u001b`, or accept them with `ParseOptions::control_characters`",
+        "token-too-long" => "check for an unterminated string or byte string, or raise the limit set with `ParseOptions::max_token_len`",
+        "nesting-too-deep" => "check for unclosed groups, or raise the limit set with `ParseOptions::max_depth`",
+        "invalid-utf8" => "the source must be UTF-8; use `parse_dcbor_item_from_bytes_lossy` to replace invalid bytes",
+        "nul-character" => "remove the NUL character, which often means the source is binary or UTF-16 text",
+        "unknown-ur-type" => "register the UR type as a tag name in the global tags registry before parsing",
+        "invalid-ur" => "check that the UR was copied completely; its last eight letters are a checksum",
+        "invalid-known-value" => "known values must fit in an unsigned 64-bit integer",
+        "unknown-known-value-name" => "use a name from the known values registry, or the known value's number, such as `'1'`",
+        "invalid-date-string" => "dates are written in ISO-8601 form, such as `2023-02-08` or `2023-02-08T15:30:45Z`",
+        "duplicate-map-key" => "each key may appear only once in a map; keys that encode identically, such as `1` and `1.0`, are duplicates",
+        "non-integer-map-key" => "integer map keys are required; write the key as an integer, or disable `ParseOptions::integer_map_keys_only`",
+        "expected-map" => "write the item as a map, such as `{1: 2}`",
+        "include-not-enabled" => "enable includes with `ParseOptions::include_loader` or `ParseOptions::include_dir`",
+        "include-failed" => "check that the included document exists, parses, and doesn't include itself",
+        "undefined-variable" => "define the variable in the lookup passed to `ParseOptions::env_lookup`",
+        "disallowed-known-value" => "use a registered known value, or allow the codepoint with `ParseOptions::allow_known_values`",
+        "invalid-tag-content" => "check the specification of the tag for the content it requires",
+        "external-bytes-not-enabled" => "enable external byte strings with `ParseOptions::byte_string_loader` or `ParseOptions::byte_string_dir`",
+        "external-bytes-failed" => "check that the file named by the digest exists and hasn't been modified",
+        "tags-registry-unavailable" => "a thread panicked while using the tags registry; restart the process",
+        "known-values-registry-unavailable" => "supply a registry with `ParseOptions::known_values`, or restart the process if a thread panicked while using the global one",
+        "syntax-disabled" => "write the item without the syntax, as in `1(1675814400)` for a date or `40000(1)` for a known value, or enable it in the `ParseOptions`",
+        "path-not-found" => "check the path against the item: each segment must lead to an item of an array, a value of a map, or the content of a tagged item, and not into an included document",
+        "invalid-known-value-range" => "write the range as `'start..end'` with integer bounds, such as `'40000..40999'`, and a start no greater than its end",
+        "invalid-decimal-fraction" => "write the number in decimal with an optional sign, fraction, and exponent, such as `dec'-2.75'` or `dec'1.5e-3'`",
+        "invalid-rational" => "write the number as `numerator/denominator` with decimal integers and a denominator greater than zero, such as `rat'-1/3'`",
+        "encoding-indicator-mismatch" => "remove the encoding indicator, or write the one for the number's deterministic encoding: `_i` for an argument in the first byte of its head, and `_0` to `_3` for one of 1, 2, 4, or 8 bytes",
+        "invalid-raw-content" => "write the content as the deterministic encoding of a single dCBOR item, such as `h'820102'` for `[1, 2]`",
+        "invalid-type-assertion" => "write a type such as `uint`, `int`, `float`, `bstr`, `tstr`, `bool`, `null`, `array`, `map`, or `any` after `::`, optionally followed by `.size` and a number of bytes, as in `h'00' :: bstr .size 1`",
+        "type-mismatch" => "change the item to match the type after `::`, or change the type",
+        _ => return None,
+    })
+}
+
+/// The default [`MessageRenderer`], which produces the same English messages
+/// as the `Display` implementation of [`enum@Error`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnglishMessages;
+
+impl MessageRenderer for EnglishMessages {
+    #[rustfmt::skip]
+    fn render(&self, code: &str, params: &[String]) -> String {
+        let param = params.first().map(String::as_str).unwrap_or_default();
+        match code {
+            "empty-input" => "Empty input".to_string(),
+            "unexpected-end-of-input" => "Unexpected end of input".to_string(),
+            "extra-data" => "Extra data at end of input".to_string(),
+            "unexpected-token" => format!("Unexpected token {param}"),
+            "unrecognized-token" => "Unrecognized token".to_string(),
+            "expected-comma" => "Expected comma".to_string(),
+            "expected-colon" => "Expected colon".to_string(),
+            "unmatched-parentheses" => "Unmatched parentheses".to_string(),
+            "unmatched-braces" => "Unmatched braces".to_string(),
+            "expected-map-key" => "Expected map key".to_string(),
+            "invalid-tag-value" => format!("Invalid tag value '{param}'"),
+            "unknown-tag-name" => format!("Unknown tag name '{param}'"),
+            "space-before-parenthesis" => "Whitespace between tag and opening parenthesis".to_string(),
+            "invalid-hex-string" => "Invalid hex string".to_string(),
+            "invalid-base64-string" => "Invalid base64 string".to_string(),
+            "invalid-base45-string" => "Invalid base45 string".to_string(),
+            "invalid-bytewords" => format!("Invalid ByteWords: {param}"),
+            "invalid-escape-sequence" => format!("Invalid escape sequence '{param}'"),
+            "invalid-unicode-escape" => format!("Invalid Unicode escape '{param}'"),
+            "control-character" => format!("Control character {param} in string"),
+            "token-too-long" => format!("Token longer than {param} bytes"),
+            "nesting-too-deep" => format!("Nesting deeper than {param} levels"),
+            "invalid-utf8" => "Invalid UTF-8".to_string(),
+            "nul-character" => "NUL character".to_string(),
+            "unknown-ur-type" => format!("Unknown UR type '{param}'"),
+            "invalid-ur" => format!("Invalid UR '{param}'"),
+            "invalid-known-value" => format!("Invalid known value '{param}'"),
+            "unknown-known-value-name" => format!("Unknown known value name '{param}'"),
+            "invalid-date-string" => format!("Invalid date string '{param}'"),
+            "duplicate-map-key" => "Duplicate map key".to_string(),
+            "non-integer-map-key" => format!("Map key must be an integer, not {param}"),
+            "expected-map" => "Expected map".to_string(),
+            "visitor-stopped" => "Parsing stopped by visitor".to_string(),
+            "visitor-rejected" => format!("Rejected by visitor: {param}"),
+            "include-not-enabled" => "Include directives are not enabled".to_string(),
+            "include-failed" => format!("Include failed: {param}"),
+            "undefined-variable" => format!("Undefined variable '{param}'"),
+            "disallowed-known-value" => format!("Known value {param} is not allowed"),
+            "invalid-tag-content" => format!("Invalid content for tag {param}"),
+            "external-bytes-not-enabled" => "External byte strings are not enabled".to_string(),
+            "external-bytes-failed" => format!("Loading external byte string failed: {param}"),
+            "tags-registry-unavailable" => "The tags registry is unavailable".to_string(),
+            "known-values-registry-unavailable" => "The known values registry is unavailable".to_string(),
+            "syntax-disabled" => format!("{param} are disabled"),
+            "path-not-found" => format!("No item at path {param}"),
+            "invalid-known-value-range" => format!("Invalid known value range {param}"),
+            "invalid-decimal-fraction" => format!("Invalid decimal fraction '{param}'"),
+            "invalid-rational" => format!("Invalid rational '{param}'"),
+            "encoding-indicator-mismatch" => format!("Encoding indicator {param} doesn't match the deterministic encoding"),
+            "invalid-raw-content" => format!("Invalid raw tag content: {param}"),
+            "invalid-type-assertion" => format!("Invalid type assertion '{param}'"),
+            "type-mismatch" => format!("Item doesn't match type assertion '{param}'"),
+            _ => code.to_string(),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use dcbor::prelude::*;
+
+use crate::{
+    ParseResult, PrintOptions, Warning, format_diagnostic, parse_diag_value,
+};
+
+/// Everything an interactive front end shows about an item: the item itself,
+/// its encoding, and how it is written back out.
+///
+/// Returned by [`evaluate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalResult {
+    /// The parsed item.
+    pub cbor: CBOR,
+    /// The deterministic encoding of the item, in lowercase hex.
+    pub hex: String,
+    /// The item reprinted in diagnostic notation, as `CBOR::diagnostic`
+    /// writes it.
+    pub diagnostic: String,
+    /// The length of the encoding in bytes.
+    pub size: usize,
+    /// Any warnings about the source.
+    pub warnings: Vec<Warning>,
+}
+
+/// Parses a dCBOR item from a string input, and returns it along with its
+/// encoding, its canonical diagnostic notation, and any warnings about the
+/// source.
+///
+/// Accepts exactly the same input as
+/// [`parse_dcbor_item`](crate::parse_dcbor_item), and reports the same errors.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::evaluate;
+/// let result = evaluate("[1.0, '45']").unwrap();
+/// assert_eq!(result.hex, "8201d99c40182d");
+/// assert_eq!(result.diagnostic, "[\n    1,\n    40000(45)\n]");
+/// assert_eq!(result.size, 7);
+/// assert_eq!(result.warnings.len(), 1);
+/// ```
+pub fn evaluate(src: &str) -> ParseResult<EvalResult> {
+    let value = parse_diag_value(src)?;
+    let warnings = Warning::collect(&value);
+    let cbor = value.to_cbor();
+    let data = cbor.to_cbor_data();
+    Ok(EvalResult {
+        hex: hex::encode(&data),
+        diagnostic: format_diagnostic(&cbor, &PrintOptions::default()),
+        size: data.len(),
+        warnings,
+        cbor,
+    })
+}
@@ -0,0 +1,84 @@
+use dcbor::prelude::*;
+
+use crate::{ParseResult, parse_dcbor_item};
+
+/// Returns the SHA-256 digest of the deterministic encoding of a CBOR item.
+///
+/// Semantically identical items have the same fingerprint however they were
+/// written, so the fingerprint can be used as a cache key, or stored in place
+/// of the encoding to detect when a fixture changes.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{fingerprint, parse_dcbor_item};
+/// let cbor = parse_dcbor_item("[1, 2, 3]").unwrap();
+/// assert_eq!(
+///     hex::encode(fingerprint(&cbor)),
+///     "4abc31137ce738d9aceb8a1d1d51f15cad42c2b08dcb7ed179f737a194b355e7"
+/// );
+/// ```
+pub fn fingerprint(cbor: &CBOR) -> [u8; 32] {
+    bc_crypto::sha256(cbor.to_cbor_data())
+}
+
+/// Parses a dCBOR item from a string input and returns its
+/// [`fingerprint`].
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::fingerprint_diag;
+/// assert_eq!(
+///     fingerprint_diag(r#"{"b": 2, "a": 1}"#).unwrap(),
+///     fingerprint_diag(r#"{"a": 1.0, "b": 2}"#).unwrap()
+/// );
+/// ```
+pub fn fingerprint_diag(src: &str) -> ParseResult<[u8; 32]> {
+    Ok(fingerprint(&parse_dcbor_item(src)?))
+}
+
+/// The checksums of the deterministic encoding of a CBOR item, as returned
+/// by [`checksum_diag`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Checksum {
+    /// The CRC-32 checksum of the encoding.
+    pub crc32: u32,
+    /// The SHA-256 digest of the encoding, which is the item's
+    /// [`fingerprint`].
+    pub sha256: [u8; 32],
+}
+
+/// Returns the [`Checksum`] of the deterministic encoding of a CBOR item.
+pub fn checksum(cbor: &CBOR) -> Checksum {
+    let data = cbor.to_cbor_data();
+    Checksum {
+        crc32: bc_crypto::hash::crc32(&data),
+        sha256: bc_crypto::sha256(&data),
+    }
+}
+
+/// Parses a dCBOR item from a string input and returns its [`Checksum`].
+///
+/// The checksums cover the encoding rather than the text, so a fixture file
+/// can carry them as an integrity trailer that still matches after the text
+/// is reformatted.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::checksum_diag;
+/// let checksum = checksum_diag("[1, 2, 3]").unwrap();
+/// assert_eq!(checksum.crc32, 0x74559fc6);
+/// assert_eq!(
+///     hex::encode(checksum.sha256),
+///     "4abc31137ce738d9aceb8a1d1d51f15cad42c2b08dcb7ed179f737a194b355e7"
+/// );
+/// assert_eq!(
+///     checksum_diag("[1, # one\n 2, 3.0]").unwrap(),
+///     checksum
+/// );
+/// ```
+pub fn checksum_diag(src: &str) -> ParseResult<Checksum> {
+    Ok(checksum(&parse_dcbor_item(src)?))
+}
@@ -0,0 +1,265 @@
+use std::{fmt::Display, io::Read};
+
+use dcbor::prelude::*;
+use unicode_normalization::is_nfc;
+
+use crate::{
+    PrintOptions,
+    print::format_float,
+    validate::{Error, InputFormat, Result},
+};
+
+/// Writes the input in diagnostic notation as far as it can be decoded,
+/// with each violation of the dCBOR rules written as an `/ERROR: …/`
+/// comment after the part it applies to, so users can see what a
+/// non-conforming encoder actually produced.
+///
+/// Where [`validate_dcbor`](crate::validate_dcbor) stops at the first
+/// violation, this goes on past violations that leave the structure
+/// readable, such as misordered map keys, heads that aren't as short as
+/// they can be, and strings that aren't valid UTF-8 or in NFC, which are
+/// written as byte strings or as they are. It stops at a violation that
+/// doesn't, such as the end of the input within an item or an
+/// indefinite-length item, leaving the arrays and maps around it open. The
+/// items of a sequence are written one per line.
+///
+/// Unlike [`validate_dcbor`](crate::validate_dcbor), this reads the whole
+/// input into memory. Only I/O errors and invalid hex are returned as
+/// errors.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{InputFormat, forensic_diagnostic};
+/// let hex = "a2 03 18 04 01 02";
+/// assert_eq!(
+///     forensic_diagnostic(hex.as_bytes(), InputFormat::Hex).unwrap(),
+///     "{3: 4 /ERROR: a CBOR numeric value was encoded in non-canonical \
+///      form/, 1 /ERROR: the decoded CBOR map has keys that are not in \
+///      canonical order/: 2}"
+/// );
+/// ```
+pub fn forensic_diagnostic(
+    mut reader: impl Read,
+    format: InputFormat,
+) -> Result<String> {
+    let mut input = Vec::new();
+    reader.read_to_end(&mut input)?;
+    let data = match format {
+        InputFormat::Binary => input,
+        InputFormat::Hex => decode_hex(&input)?,
+    };
+    let mut renderer = Renderer { data: &data, offset: 0 };
+    let mut lines = Vec::new();
+    while renderer.offset < data.len() {
+        let mut line = String::new();
+        let complete = renderer.item(&mut line, 0).is_some();
+        lines.push(line);
+        if !complete {
+            break;
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+//
+// === Private Functions ===
+//
+
+/// Items nested more deeply than this aren't written, so that hostile input
+/// can't exhaust the stack.
+const MAX_DEPTH: usize = 512;
+
+struct Renderer<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Renderer<'a> {
+    /// Writes the next item to `out`, and returns `None` if decoding can't
+    /// go on after it.
+    fn item(&mut self, out: &mut String, depth: usize) -> Option<()> {
+        if depth > MAX_DEPTH {
+            annotate(out, "items nested too deeply to write");
+            return None;
+        }
+        let header = self.take(1, out)?[0];
+        let info = header & 31;
+        let (len, minimum) = match info {
+            0..=23 => (0, 0),
+            24 => (1, 24),
+            25 => (2, 0x100),
+            26 => (4, 0x1_0000),
+            27 => (8, 0x1_0000_0000),
+            _ => {
+                annotate(out, dcbor::Error::UnsupportedHeaderValue(info));
+                return None;
+            }
+        };
+        let argument = self.take(len, out)?;
+        let value = match len {
+            0 => u64::from(info),
+            _ => argument
+                .iter()
+                .fold(0, |value, byte| value << 8 | u64::from(*byte)),
+        };
+        let mut violation = None;
+        match header >> 5 {
+            0 => out.push_str(&value.to_string()),
+            1 => out.push_str(&(-1 - i128::from(value)).to_string()),
+            2 => {
+                let bytes = self.take_len(value, out)?;
+                out.push_str(&format!("h'{}'", hex::encode(bytes)));
+            }
+            3 => {
+                let bytes = self.take_len(value, out)?;
+                match std::str::from_utf8(bytes) {
+                    Ok(text) => {
+                        let quoted = text.replace('"', "\\\"");
+                        out.push_str(&format!("\"{quoted}\""));
+                        if !is_nfc(text) {
+                            violation = Some(dcbor::Error::NonCanonicalString);
+                        }
+                    }
+                    Err(e) => {
+                        out.push_str(&format!("h'{}'", hex::encode(bytes)));
+                        violation = Some(dcbor::Error::InvalidString(e));
+                    }
+                }
+            }
+            4 => {
+                out.push('[');
+                for index in 0..value {
+                    if index > 0 {
+                        out.push_str(", ");
+                    }
+                    self.item(out, depth + 1)?;
+                }
+                out.push(']');
+            }
+            5 => {
+                out.push('{');
+                let mut previous: Option<&[u8]> = None;
+                for index in 0..value {
+                    if index > 0 {
+                        out.push_str(", ");
+                    }
+                    let start = self.offset;
+                    self.item(out, depth + 1)?;
+                    let key = &self.data[start..self.offset];
+                    match previous.map(|previous| key.cmp(previous)) {
+                        Some(std::cmp::Ordering::Less) => {
+                            annotate(out, dcbor::Error::MisorderedMapKey);
+                        }
+                        Some(std::cmp::Ordering::Equal) => {
+                            annotate(out, dcbor::Error::DuplicateMapKey);
+                        }
+                        _ => {}
+                    }
+                    previous = Some(key);
+                    out.push_str(": ");
+                    self.item(out, depth + 1)?;
+                }
+                out.push('}');
+            }
+            6 => {
+                out.push_str(&format!("{value}("));
+                self.item(out, depth + 1)?;
+                out.push(')');
+            }
+            _ => {
+                let mut data = vec![header];
+                data.extend(argument);
+                match CBOR::try_from_data(data) {
+                    Ok(cbor) => out.push_str(&cbor.diagnostic_flat()),
+                    Err(e) => {
+                        out.push_str(&simple_value(info, value));
+                        violation = Some(e);
+                    }
+                }
+            }
+        }
+        if header >> 5 != 7 && value < minimum {
+            annotate(out, dcbor::Error::NonCanonicalNumeric);
+        }
+        if let Some(violation) = violation {
+            annotate(out, violation);
+        }
+        Some(())
+    }
+
+    /// Returns the next `len` bytes, or writes that the input ended and
+    /// returns `None` if there aren't that many.
+    fn take(&mut self, len: usize, out: &mut String) -> Option<&'a [u8]> {
+        let data = self.data;
+        if data.len() - self.offset < len {
+            self.offset = data.len();
+            annotate(out, dcbor::Error::Underrun);
+            return None;
+        }
+        let bytes = &data[self.offset..self.offset + len];
+        self.offset += len;
+        Some(bytes)
+    }
+
+    /// Returns the next `len` bytes, where `len` is the argument of a head.
+    fn take_len(&mut self, len: u64, out: &mut String) -> Option<&'a [u8]> {
+        let len = usize::try_from(len).unwrap_or(usize::MAX);
+        self.take(len, out)
+    }
+}
+
+/// Writes a violation as a comment.
+fn annotate(out: &mut String, violation: impl Display) {
+    if !out.is_empty() && !out.ends_with(['[', '{', '(', ' ']) {
+        out.push(' ');
+    }
+    out.push_str(&format!("/ERROR: {violation}/"));
+}
+
+/// Returns the diagnostic notation of a float or simple value that dCBOR
+/// doesn't allow, with additional information `info` and argument `value`.
+fn simple_value(info: u8, value: u64) -> String {
+    let float = match info {
+        25 => half_to_f64(value as u16),
+        26 => f64::from(f32::from_bits(value as u32)),
+        27 => f64::from_bits(value),
+        _ => return format!("simple({value})"),
+    };
+    format_float(float, &PrintOptions::default())
+}
+
+/// Returns the value of a half-precision float.
+fn half_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 == 0 { 1.0 } else { -1.0 };
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let fraction = f64::from(bits & 0x3ff);
+    sign * match exponent {
+        0 => fraction * 2f64.powi(-24),
+        31 if fraction == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (1024.0 + fraction) * 2f64.powi(exponent - 25),
+    }
+}
+
+/// Decodes hex that may be broken up by whitespace.
+fn decode_hex(text: &[u8]) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut high = None;
+    for (offset, c) in text.iter().enumerate() {
+        if c.is_ascii_whitespace() {
+            continue;
+        }
+        let Some(digit) = char::from(*c).to_digit(16) else {
+            return Err(Error::InvalidHex(offset as u64));
+        };
+        match high.take() {
+            None => high = Some(digit as u8),
+            Some(high) => bytes.push(high << 4 | digit as u8),
+        }
+    }
+    if high.is_some() {
+        return Err(Error::InvalidHex(text.len() as u64));
+    }
+    Ok(bytes)
+}
@@ -0,0 +1,110 @@
+//! Entry points for fuzzing the parser.
+//!
+//! These functions accept arbitrary bytes, as produced by a fuzzing engine,
+//! and drive the public parsing API with them. They are used by the
+//! `cargo-fuzz` targets in the `fuzz` directory of this repository, and may be
+//! called from any other fuzzing harness.
+//!
+//! ```rust
+//! # use dcbor_parse::fuzz;
+//! fuzz::parse(b"[1, 2, 3]");
+//! fuzz::roundtrip(b"{\"key\": [1.5, h'0102']}");
+//! fuzz::entry_points(b"[1, 'isA', date(1)]");
+//! ```
+//!
+//! # Panics
+//!
+//! No public parsing entry point panics, whatever its input: malformed
+//! input, input nested more deeply than
+//! [`ParseOptions::max_depth`](crate::ParseOptions::max_depth) allows, and a
+//! tags or known values registry that can't be read, are reported as
+//! errors. [`parse`] and [`entry_points`] check this guarantee;
+//! a panic in either is a bug.
+
+use dcbor::prelude::*;
+
+use crate::{
+    Budget, ControlCharacters, ParseOptions, ParseResult, TagValidators,
+    parse_dcbor_entry, parse_dcbor_item, parse_dcbor_item_from_bytes,
+    parse_dcbor_item_from_bytes_lossy, parse_dcbor_item_partial,
+    parse_dcbor_item_partial_detailed, parse_dcbor_item_partial_with_comments,
+    parse_dcbor_item_with_audit, parse_dcbor_item_with_options,
+    parse_dcbor_item_with_report, parse_dcbor_item_with_warnings,
+    parse_dcbor_sequence, parse_diag_value_budgeted, parse_documented_items,
+};
+
+/// Parses arbitrary bytes as diagnostic notation.
+///
+/// Returns `None` if the bytes are not valid UTF-8, otherwise the result of
+/// parsing them with [`parse_dcbor_item`]. The partial parser is exercised on
+/// the same input.
+///
+/// This function never panics: any input, however malformed, produces either
+/// `None` or a [`ParseResult`].
+pub fn parse(data: &[u8]) -> Option<ParseResult<CBOR>> {
+    let src = std::str::from_utf8(data).ok()?;
+    let _ = parse_dcbor_item_partial(src);
+    Some(parse_dcbor_item(src))
+}
+
+/// Checks that arbitrary bytes that parse successfully survive a
+/// parse → print → parse round trip.
+///
+/// Input that is not valid UTF-8 or that does not parse is ignored.
+///
+/// # Panics
+///
+/// Panics if the diagnostic notation printed for a parsed item cannot be
+/// parsed again, or if it parses to an item with a different encoding. Both
+/// indicate a bug that the fuzzing engine should report.
+pub fn roundtrip(data: &[u8]) {
+    let Some(Ok(cbor)) = parse(data) else {
+        return;
+    };
+    let diag = cbor.diagnostic();
+    match parse_dcbor_item(&diag) {
+        Ok(cbor2) => assert_eq!(
+            cbor.to_cbor_data(),
+            cbor2.to_cbor_data(),
+            "round trip changed the item: {diag}"
+        ),
+        Err(e) => panic!(
+            "printed item failed to parse: {}",
+            e.full_message(&diag)
+        ),
+    }
+}
+
+/// Drives every public parsing entry point with arbitrary bytes, including
+/// the byte entry points, which accept input that isn't valid UTF-8, and
+/// [`parse_dcbor_item_with_options`] with the strictest options.
+///
+/// This function never panics: any input, however malformed, produces
+/// either a value or an error from each entry point.
+pub fn entry_points(data: &[u8]) {
+    let _ = parse_dcbor_item_from_bytes(data);
+    let _ = parse_dcbor_item_from_bytes_lossy(data);
+    let Ok(src) = std::str::from_utf8(data) else {
+        return;
+    };
+    let _ = parse(data);
+    let _ = parse_dcbor_entry(src);
+    let _ = parse_dcbor_sequence(src);
+    let _ = parse_dcbor_item_partial_with_comments(src);
+    let _ = parse_dcbor_item_partial_detailed(src);
+    let _ = parse_documented_items(src);
+    let _ = parse_dcbor_item_with_warnings(src);
+    let _ = parse_dcbor_item_with_report(src);
+    let _ = parse_dcbor_item_with_audit(src);
+    let budget = Budget { max_items: Some(8), max_bytes: Some(64) };
+    let _ = parse_diag_value_budgeted(src, budget);
+    let options = ParseOptions::default()
+        .registered_known_values_only(true)
+        .tag_validators(TagValidators::standard())
+        .validate_envelopes(true)
+        .cose(true)
+        .control_characters(ControlCharacters::Escape)
+        .max_token_len(64)
+        .integer_map_keys_only(true);
+    let _ = parse_dcbor_item_with_options(src, &options);
+}
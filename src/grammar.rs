@@ -0,0 +1,141 @@
+/// A kind of token recognized by the lexer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenKind {
+    /// The name of the corresponding [`Token`](crate::Token) variant.
+    pub name: &'static str,
+    /// A short description of the token.
+    pub description: &'static str,
+    /// A regular expression matching exactly the text of the token.
+    pub pattern: &'static str,
+    /// An example of the token.
+    pub example: &'static str,
+}
+
+/// A production of the grammar, written in EBNF.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrammarRule {
+    pub name: &'static str,
+    pub definition: &'static str,
+}
+
+/// A machine-readable description of the diagnostic notation grammar
+/// accepted by this build of the parser.
+///
+/// Returned by [`grammar`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grammar {
+    /// Every kind of token, in the order the lexer declares them.
+    pub tokens: Vec<TokenKind>,
+    /// A regular expression matching whitespace and comments, which may
+    /// appear between any two tokens.
+    pub trivia_pattern: &'static str,
+    /// The productions of the grammar, starting with the top-level `item`.
+    pub rules: Vec<GrammarRule>,
+}
+
+impl Grammar {
+    /// Returns the token kind with the given name.
+    pub fn token(&self, name: &str) -> Option<&TokenKind> {
+        self.tokens.iter().find(|token| token.name == name)
+    }
+}
+
+#[cfg(not(feature = "simplified-patterns"))]
+mod patterns {
+    pub const BASE64: &str = r"b64'([A-Za-z0-9+/=]{2,})'";
+    pub const DATE: &str = r"\d{4}-\d{2}-\d{2}(?:T\d{2}:\d{2}:\d{2}(?:\.\d+)?(?:Z|[+-]\d{2}:\d{2})?)?";
+    pub const STRING: &str = r#""([^"\\]|\\[^\x00-\x1F])*""#;
+}
+
+#[cfg(feature = "simplified-patterns")]
+mod patterns {
+    pub const BASE64: &str = r"b64'[A-Za-z0-9+/=]*'";
+    pub const DATE: &str = r"\d{4}-\d{2}-\d{2}(?:T\d{2}:\d{2}:\d{2})?";
+    pub const STRING: &str = r#""[^"]*""#;
+}
+
+macro_rules! token_kind {
+    ($name:literal, $description:literal, $pattern:expr, $example:literal) => {
+        TokenKind {
+            name: $name,
+            description: $description,
+            pattern: $pattern,
+            example: $example,
+        }
+    };
+}
+
+/// Returns a machine-readable description of the grammar accepted by this
+/// build of the parser, taking enabled features into account.
+///
+/// Editor plugins and documentation generators can use this to stay in sync
+/// with the parser.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::grammar;
+/// let grammar = grammar();
+/// let number = grammar.token("Number").unwrap();
+/// assert_eq!(number.example, "-1.5e3");
+/// assert_eq!(grammar.rules[0].name, "item");
+/// ```
+#[rustfmt::skip]
+pub fn grammar() -> Grammar {
+    Grammar {
+        tokens: vec![
+            token_kind!("Bool", "boolean", r"true|false", "true"),
+            token_kind!("BraceOpen", "start of a map", r"\{", "{"),
+            token_kind!("BraceClose", "end of a map", r"\}", "}"),
+            token_kind!("BracketOpen", "start of an array", r"\[", "["),
+            token_kind!("BracketClose", "end of an array", r"\]", "]"),
+            token_kind!("ParenthesisOpen", "opening parenthesis", r"\(", "("),
+            token_kind!("ParenthesisClose", "end of a tagged item", r"\)", ")"),
+            token_kind!("Colon", "map key separator", r":", ":"),
+            token_kind!("Comma", "item separator", r",", ","),
+            token_kind!("TypeAssertion", "type assertion after an item", r"::(?:[ \t]*[^ \t\r\n,:\]})>#/]+)*", ":: bstr .size 32"),
+            token_kind!("EmbeddedOpen", "start of embedded CBOR", r"<<", "<<"),
+            token_kind!("EmbeddedClose", "end of embedded CBOR", r">>", ">>"),
+            token_kind!("Null", "null", r"null", "null"),
+            token_kind!("NaN", "floating point NaN", r"NaN", "NaN"),
+            token_kind!("Infinity", "positive infinity", r"Infinity", "Infinity"),
+            token_kind!("NegInfinity", "negative infinity", r"-Infinity", "-Infinity"),
+            token_kind!("ByteStringHex", "hex byte string", r"h'[^']*'", "h'0102 /two/ 0304'"),
+            token_kind!("ByteStringBase64", "base64 byte string", patterns::BASE64, "b64'AQI='"),
+            token_kind!("ByteStringBase64Url", "base64url byte string", r"b64url'[^']*'", "b64url'AQI'"),
+            token_kind!("ByteStringBase45", "base45 byte string", r"b45'[^']*'", "b45'BB8'"),
+            token_kind!("ByteStringByteWords", "ByteWords byte string", r"bw'[^']*'", "bw'aetdaowslg'"),
+            token_kind!("DateLiteral", "ISO-8601 date", patterns::DATE, "2023-02-08T15:30:45"),
+            token_kind!("DecimalFraction", "decimal fraction", r"dec'[^']*'", "dec'2.75'"),
+            token_kind!("Rational", "rational number", r"rat'[^']*'", "rat'1/3'"),
+            token_kind!("Number", "number", r"-?(?:0|[1-9]\d*)(?:\.\d+)?(?:[eE][+-]?\d+)?|-?0(?:[xX][0-9a-fA-F]+|[oO][0-7]+|[bB][01]+)", "-1.5e3"),
+            token_kind!("IndicatedNumber", "number with an encoding indicator, accepted with encoding indicators", r"(?:-?(?:0|[1-9]\d*)(?:\.\d+)?(?:[eE][+-]?\d+)?|-?0(?:[xX][0-9a-fA-F]+|[oO][0-7]+|[bB][01]+))_[0-3i]", "1.5_1"),
+            token_kind!("LenientNumber", "number spelled as JSON doesn't allow, accepted with lenient floats", r"(?i:[+-]?(?:nan|inf(?:inity)?))|[+-]?\.\d+(?:[eE][+-]?\d+)?|-?\d+\.(?:[eE][+-]?\d+)?|\+\d+(?:\.\d*)?(?:[eE][+-]?\d+)?", "+1.5"),
+            token_kind!("String", "text string", patterns::STRING, r#""hello""#),
+            token_kind!("TagValue", "tag number and opening parenthesis", r"0\(|[1-9][0-9]*\(|0[xX][0-9a-fA-F]+\(|0[oO][0-7]+\(|0[bB][01]+\(", "1234("),
+            token_kind!("TagName", "tag name and opening parenthesis", r"[a-zA-Z_][a-zA-Z0-9_-]*\(", "date("),
+            token_kind!("Raw", "raw tagged value and opening parenthesis", r"raw\(", "raw("),
+            token_kind!("Include", "include directive and opening parenthesis", r"!include\(", "!include("),
+            token_kind!("ExternalBytes", "external byte string directive and opening parenthesis", r"!bytes\(", "!bytes("),
+            token_kind!("KnownValueNumber", "numeric known value", r"'0'|'[1-9][0-9]*'|'0[xX][0-9a-fA-F]+'|'0[oO][0-7]+'|'0[bB][01]+'", "'1'"),
+            token_kind!("KnownValueName", "named known value", r"''|'[a-zA-Z_][a-zA-Z0-9_-]*'", "'isA'"),
+            token_kind!("Unit", "unit known value", r"Unit", "Unit"),
+            token_kind!("UR", "uniform resource", r"ur:([a-zA-Z0-9][a-zA-Z0-9-]*)/([a-zA-Z]{8,})", "ur:date/cyisdadmlasgtapttl"),
+        ],
+        trivia_pattern: r"(?:[ \t\r\n\f]|/[^/]*/|#[^\r\n]*)+",
+        rules: vec![
+            GrammarRule { name: "item", definition: "( atom | array | map | tagged | raw | embedded | include | external ) [ TypeAssertion ]" },
+            GrammarRule { name: "atom", definition: "Bool | Null | NaN | Infinity | NegInfinity | bytes | DateLiteral | DecimalFraction | Rational | Number | IndicatedNumber | LenientNumber | String | KnownValueNumber | KnownValueName | Unit | UR" },
+            GrammarRule { name: "bytes", definition: "segment { segment }" },
+            GrammarRule { name: "segment", definition: "ByteStringHex | ByteStringBase64 | ByteStringBase64Url | ByteStringBase45 | ByteStringByteWords" },
+            GrammarRule { name: "array", definition: "BracketOpen [ item { Comma item } ] BracketClose" },
+            GrammarRule { name: "map", definition: "BraceOpen [ entry { Comma entry } ] BraceClose" },
+            GrammarRule { name: "entry", definition: "item Colon item" },
+            GrammarRule { name: "tagged", definition: "( TagValue | TagName ) item ParenthesisClose" },
+            GrammarRule { name: "embedded", definition: "EmbeddedOpen [ item { Comma item } ] EmbeddedClose" },
+            GrammarRule { name: "raw", definition: "Raw Number Comma bytes ParenthesisClose" },
+            GrammarRule { name: "include", definition: "Include String ParenthesisClose" },
+            GrammarRule { name: "external", definition: "ExternalBytes String ParenthesisClose" },
+        ],
+    }
+}
@@ -0,0 +1,195 @@
+use std::{fmt, ops::RangeInclusive};
+
+use known_values::{KnownValue, KnownValuesStore};
+
+use crate::{
+    ParseError, ParseResult, SourceSpan, registry::with_known_values,
+    token::parse_integer,
+};
+
+/// The known values assigned by the Blockchain Commons registry. Codepoints in
+/// this range that aren't in the registry are reported by
+/// [`parse_dcbor_item_with_warnings`](crate::parse_dcbor_item_with_warnings).
+pub const RESERVED_KNOWN_VALUES: RangeInclusive<u64> = 0..=999;
+
+/// A range of known values set aside for a single purpose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownValueRange {
+    /// The first known value in the range.
+    pub start: u64,
+    /// The last known value in the range.
+    pub end: u64,
+    /// What the known values in the range are used for.
+    pub description: &'static str,
+}
+
+impl KnownValueRange {
+    /// Returns `true` if `value` lies within the range.
+    pub fn contains(&self, value: u64) -> bool {
+        self.start <= value && value <= self.end
+    }
+}
+
+impl fmt::Display for KnownValueRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}-{}", self.description, self.start, self.end)
+    }
+}
+
+macro_rules! range {
+    ($start:literal, $end:literal, $description:literal) => {
+        KnownValueRange { start: $start, end: $end, description: $description }
+    };
+}
+
+/// The assigned ranges of the Blockchain Commons known values registry.
+#[rustfmt::skip]
+pub const KNOWN_VALUE_RANGES: &[KnownValueRange] = &[
+    range!(0, 49, "General"),
+    range!(50, 59, "Attachments"),
+    range!(60, 69, "XID Documents"),
+    range!(70, 99, "XID Privileges"),
+    range!(100, 199, "Expression and Function Calls"),
+    range!(200, 299, "Cryptography"),
+    range!(300, 399, "Cryptocurrency Assets"),
+    range!(400, 499, "Cryptocurrency Networks"),
+    range!(500, 599, "Bitcoin"),
+    range!(600, 799, "Graphs"),
+    range!(800, 999, "Reserved"),
+];
+
+/// What is known about a known value codepoint.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::known_value_info;
+/// let info = known_value_info(1);
+/// assert_eq!(info.name.as_deref(), Some("isA"));
+/// assert_eq!(info.to_string(), "'isA' (1, General 0-49)");
+///
+/// let info = known_value_info(45);
+/// assert!(info.is_reserved() && !info.is_registered());
+/// assert_eq!(info.to_string(), "'45' (unregistered, General 0-49)");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownValueInfo {
+    /// The codepoint.
+    pub value: u64,
+    /// The name the registry assigns to the codepoint, if any.
+    pub name: Option<String>,
+    /// The range the codepoint belongs to, if any.
+    pub range: Option<KnownValueRange>,
+}
+
+impl KnownValueInfo {
+    /// Returns `true` if the codepoint is in the known values registry.
+    pub fn is_registered(&self) -> bool { self.name.is_some() }
+
+    /// Returns `true` if the codepoint is in [`RESERVED_KNOWN_VALUES`].
+    pub fn is_reserved(&self) -> bool {
+        RESERVED_KNOWN_VALUES.contains(&self.value)
+    }
+}
+
+impl fmt::Display for KnownValueInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "'{}' ({}", name, self.value)?,
+            None => write!(f, "'{}' (unregistered", self.value)?,
+        }
+        if let Some(range) = &self.range {
+            write!(f, ", {}", range)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Returns the registered name and assigned range of a known value
+/// codepoint.
+///
+/// The name is `None` if the codepoint isn't registered, or if the known
+/// values registry is unavailable.
+pub fn known_value_info(value: u64) -> KnownValueInfo {
+    known_value_info_in(value, None)
+}
+
+/// Parses a range of known values written as `'start..end'`, such as
+/// `'40000..40999'`, for tools that document the known values registry.
+///
+/// Both bounds are included, and each may be written in hex, octal, or
+/// binary as in a known value literal. Whitespace may surround the literal.
+/// The range is [`InvalidKnownValueRange`](ParseError::InvalidKnownValueRange)
+/// if it isn't written this way or if its start is greater than its end, and
+/// a bound that isn't a valid codepoint is
+/// [`InvalidKnownValue`](ParseError::InvalidKnownValue).
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{ParseError, parse_known_value_range};
+/// let range = parse_known_value_range("'40000..40999'").unwrap();
+/// assert_eq!(range, 40000..=40999);
+/// assert_eq!(parse_known_value_range(" '0x64..0x64' ").unwrap(), 100..=100);
+///
+/// let error = parse_known_value_range("'9..1'").unwrap_err();
+/// assert_eq!(error.to_string(), "Invalid known value range '9..1'");
+/// let error = parse_known_value_range("'1..x'").unwrap_err();
+/// assert_eq!(
+///     error,
+///     ParseError::InvalidKnownValue("x".to_string(), (4..5).into())
+/// );
+/// ```
+pub fn parse_known_value_range(src: &str) -> ParseResult<RangeInclusive<u64>> {
+    let literal = src.trim();
+    if literal.is_empty() {
+        return Err(ParseError::EmptyInput);
+    }
+    let start = src.len() - src.trim_start().len();
+    let span = SourceSpan::new(start, start + literal.len());
+    let invalid =
+        || ParseError::InvalidKnownValueRange(literal.to_string(), span);
+    let (first, last) = literal
+        .strip_prefix('\'')
+        .and_then(|body| body.strip_suffix('\''))
+        .and_then(|body| body.split_once(".."))
+        .ok_or_else(invalid)?;
+    let bound = |text: &str, offset: usize| {
+        let leading_zero = text.len() > 1
+            && text.starts_with('0')
+            && text[1..].starts_with(|c: char| c.is_ascii_digit());
+        parse_integer(text)
+            .ok()
+            .filter(|_| !text.starts_with('+') && !leading_zero)
+            .ok_or_else(|| {
+                let span = SourceSpan::new(offset, offset + text.len());
+                ParseError::InvalidKnownValue(text.to_string(), span)
+            })
+    };
+    let first = bound(first, span.start + 1)?;
+    let last = bound(last, span.end - 1 - last.len())?;
+    if first > last {
+        return Err(invalid());
+    }
+    Ok(first..=last)
+}
+
+/// Returns the [`known_value_info`] of a codepoint, with the name assigned by
+/// `store`, or by the global registry if `store` is `None`.
+pub(crate) fn known_value_info_in(
+    value: u64,
+    store: Option<&KnownValuesStore>,
+) -> KnownValueInfo {
+    let assigned_name = |store: &KnownValuesStore| {
+        store.assigned_name(&KnownValue::new(value)).map(str::to_string)
+    };
+    let name = match store {
+        Some(store) => assigned_name(store),
+        None => with_known_values(assigned_name).flatten(),
+    };
+    let range = KNOWN_VALUE_RANGES
+        .iter()
+        .find(|range| range.contains(value))
+        .copied();
+    KnownValueInfo { value, name, range }
+}
@@ -15,6 +15,13 @@
 //!
 //! - `parse_dcbor_item`: Parses a string in CBOR diagnostic notation into a
 //!   `CBOR` object.
+//! - `parse_diag_value`: Parses a string in CBOR diagnostic notation into a
+//!   `DiagValue`, which retains the original text and source span of every
+//!   literal.
+//! - `format_diagnostic`: Formats a `CBOR` object in diagnostic notation, with
+//!   control over layout and the formatting of floating point numbers.
+//! - `format_diag_value`: Formats a `DiagValue` in diagnostic notation,
+//!   keeping `ur:` literals, dates, and known values in their written form.
 //! - `compose_dcbor_array`: Composes a `CBOR` array from a slice of strings
 //!   representing dCBOR items in diagnostic notation.
 //! - `compose_dcbor_map`: Composes a `CBOR` map from a slice of strings
@@ -24,20 +31,50 @@
 //! | ------------------- | ----------------------------------------------------------- |
 //! | Boolean             | `true`<br>`false`                                           |
 //! | Null                | `null`                                                      |
-//! | Integers            | `0`<br>`1`<br>`-1`<br>`42`                                  |
+//! | Integers            | `0`<br>`1`<br>`-1`<br>`42`<br>`0x1F`<br>`0o17`<br>`0b1010`  |
+//! | Bignums             | `18446744073709551616`<br>`-18446744073709551617`           |
 //! | Floats              | `3.14`<br>`-2.5`<br>`Infinity`<br>`-Infinity`<br>`NaN`      |
 //! | Strings             | `"hello"`<br>`"🌎"`                                      |
 //! | Date Literals       | `2023-02-08`<br>`2023-02-08T15:30:45Z`<br>`1965-05-15`   |
-//! | Hex Byte Strings    | `h'68656c6c6f'`                                             |
+//! | Decimal Fractions   | `dec'2.75'`<br>`dec'-1.5e-3'`                               |
+//! | Rationals           | `rat'1/3'`<br>`rat'-22/7'`                                  |
+//! | Hex Byte Strings    | `h'68656c6c6f'`<br>`h'0102 /two/ 0304'`                     |
 //! | Base64 Byte Strings | `b64'AQIDBAUGBwgJCg=='`                                     |
+//! | Base64url Bytes     | `b64url'AQIDBA'`                                            |
+//! | Base45 Bytes        | `b45'%69 VD92EX0'`                                          |
+//! | ByteWords Bytes     | `bw'able tied also webs lung'`<br>`bw'aetdaowslg'`          |
+//! | Embedded CBOR       | `<<1, 2>>`<br>`<<>>`                                        |
 //! | Tagged Values       | `1234("hello")`<br>`5678(3.14)`                             |
 //! | Name-Tagged Values  | `tag-name("hello")`<br>`tag-name(3.14)`                     |
+//! | Raw Tagged Values   | `raw(1234, h'820102')`                                      |
 //! | Known Values        | `'1'`<br>`'isA'`                                            |
 //! | Unit Known Value    | `Unit`<br>`''`<br>`'0'`                                     |
 //! | URs                 | `ur:date/cyisdadmlasgtapttl`                                |
 //! | Arrays              | `[1, 2, 3]`<br>`["hello", "world"]`<br>`[1, [2, 3]]`        |
 //! | Maps                | `{1: 2, 3: 4}`<br>`{"key": "value"}`<br>`{1: [2, 3], 4: 5}` |
 //!
+//! ## Type Assertions
+//!
+//! Any item may be followed by a type assertion, written as `::` and a type
+//! with a name from [CDDL](https://www.rfc-editor.org/rfc/rfc8610), as in
+//! `42 :: uint` or `h'…' :: bstr .size 32`. The assertion is checked when the
+//! item is parsed, and isn't part of the item, so fixtures can document and
+//! enforce what they contain. The types are `any`, `uint`, `nint`, `int`,
+//! `float`, `number`, `bstr`, `tstr`, `bool`, `true`, `false`, `null`,
+//! `array`, and `map`. A `.size` after `bstr` or `tstr` is the length in
+//! bytes, and after `uint` the number of bytes the integer fits in.
+//!
+//! ```rust
+//! # use dcbor_parse::parse_dcbor_item;
+//! let src = r#"{"id": 7 :: uint, "key": h'0102' :: bstr .size 2}"#;
+//! let cbor = parse_dcbor_item(src).unwrap();
+//! assert_eq!(cbor.diagnostic_flat(), r#"{"id": 7, "key": h'0102'}"#);
+//! assert_eq!(
+//!     parse_dcbor_item("[1, -2 :: uint]").unwrap_err().to_string(),
+//!     "Item doesn't match type assertion 'uint'"
+//! );
+//! ```
+//!
 //! ## Parsing Named Tags and Uniform Resources (URs)
 //!
 //! A [Uniform Resource
@@ -57,16 +94,151 @@
 //! for examples of how to register your own tags.
 
 mod parse;
-pub use parse::{parse_dcbor_item, parse_dcbor_item_partial};
+pub use parse::{
+    parse_dcbor_entry, parse_dcbor_implicit_array, parse_dcbor_item,
+    parse_dcbor_item_partial, parse_dcbor_item_with_options,
+    parse_dcbor_sequence, parse_diag_entry, parse_diag_value,
+    parse_diag_value_with_options,
+};
+
+mod comments;
+pub use comments::{
+    Comment, CommentedItem, parse_dcbor_item_partial_with_comments,
+};
+
+mod partial;
+pub use partial::{EndReason, Partial, parse_dcbor_item_partial_detailed};
+
+mod doc_comments;
+pub use doc_comments::{DocumentedItem, parse_documented_items};
+
+mod options;
+pub use options::{
+    ByteStringLoader, ControlCharacters, DuplicateKeys, EncodingIndicators,
+    EnvLookup, IncludeLoader, NaNHandling, ParseOptions,
+};
+mod substitute;
+
+mod tag_validators;
+pub use tag_validators::{TagValidator, TagValidators};
+
+mod envelope;
+mod cose;
+mod registry;
+mod assertion;
+
+mod diag_value;
+pub use diag_value::{
+    ByteStringEncoding, DiagValue, NumberEncoding, NumberKind, NumberValue,
+};
+
+mod visitor;
+pub use visitor::{
+    GroupKind, ParseVisitor, VisitAction, parse_diag_value_with_visitor,
+};
+
+mod budget;
+pub use budget::{Budget, BudgetedValue, Truncated, parse_diag_value_budgeted};
+
+mod path;
+pub use path::{PathSegment, extract_first};
+
+mod provenance;
+pub use provenance::UrProvenance;
+
+mod audit;
+pub use audit::{AuditEntry, AuditTrail, parse_dcbor_item_with_audit};
+
+mod ast;
+pub use ast::{Ast, AstNode, AstNodeKind, AstToken, parse_dcbor_ast};
+
+mod canonical;
+pub use canonical::{
+    Canonicalization, CanonicalizationNote, CanonicalizationReport, SortedKey,
+    parse_dcbor_item_with_report, sorted_key_preview,
+};
+
+mod span;
+pub use span::{LineColumn, SourceSpan};
 
 mod token;
 pub use token::Token;
 
 mod error;
-pub use error::{Error as ParseError, Result as ParseResult};
+pub use error::{
+    EnglishMessages, Error as ParseError, MessageRenderer,
+    Result as ParseResult,
+};
 
 mod compose;
 pub use compose::{
-    Error as ComposeError, Result as ComposeResult, compose_dcbor_array,
-    compose_dcbor_map,
+    ComposedNode, Error as ComposeError, Result as ComposeResult,
+    compose_dcbor_array, compose_dcbor_array_spanned, compose_dcbor_map,
+    compose_dcbor_map_spanned, compose_multipart_ur,
 };
+
+mod capabilities;
+pub use capabilities::{Capabilities, LiteralForm, syntax_capabilities};
+mod known_value_info;
+pub use known_value_info::{
+    KNOWN_VALUE_RANGES, KnownValueInfo, KnownValueRange, RESERVED_KNOWN_VALUES,
+    known_value_info, parse_known_value_range,
+};
+mod warning;
+pub use warning::{Warning, WarningKind, parse_dcbor_item_with_warnings};
+mod utf8;
+pub use utf8::{parse_dcbor_item_from_bytes, parse_dcbor_item_from_bytes_lossy};
+mod print;
+pub use print::{
+    ExponentNotation, FloatPrecision, PrintOptions, Redaction,
+    external_byte_strings, format_diag_value, format_diagnostic,
+    parse_and_format, preview, write_diagnostic,
+};
+mod grammar;
+pub use grammar::{Grammar, GrammarRule, TokenKind, grammar};
+mod rust_literal;
+pub use rust_literal::to_rust_literal;
+mod cddl;
+pub use cddl::infer_cddl;
+mod dedupe;
+pub use dedupe::{DedupeReport, dedupe_items};
+mod corpus;
+pub use corpus::{CorpusReport, corpus_report};
+mod fingerprint;
+pub use fingerprint::{
+    Checksum, checksum, checksum_diag, fingerprint, fingerprint_diag,
+};
+mod size;
+pub use size::{encoded_size, encoded_size_diag};
+mod memory;
+pub use memory::MemoryEstimate;
+mod qr;
+pub use qr::{QrErrorCorrection, QrFit, qr_fit};
+mod validate;
+pub use validate::{
+    Error as ValidateError, InputFormat, Result as ValidateResult,
+    validate_dcbor,
+};
+mod forensic;
+pub use forensic::forensic_diagnostic;
+mod cache;
+pub use cache::{CacheStore, MemoryStore, ParseCache};
+mod eval;
+pub use eval::{EvalResult, evaluate};
+mod session;
+pub use session::{Session, SessionDiagnostic};
+
+mod reformat;
+pub use reformat::{reformat_edited, replace_at};
+
+mod normalize;
+pub use normalize::{normalize_hex_case, normalize_string_escapes};
+
+mod lint;
+pub use lint::{DOCUMENT_DELIMITER, FileDiagnostics, lint_reader};
+
+mod shell;
+pub use shell::{Shell, escape_for_single_arg, shell_quote_diag};
+pub mod bench;
+pub mod conformance;
+pub mod fuzz;
@@ -0,0 +1,170 @@
+use std::io::BufRead;
+
+use crate::{ParseError, SourceSpan, Warning, parse_diag_value};
+
+/// The line that separates the documents of a stream read by
+/// [`lint_reader`]. Trailing whitespace on the line is ignored.
+pub const DOCUMENT_DELIMITER: &str = "---";
+
+/// The diagnostics for one document of a stream read by [`lint_reader`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDiagnostics {
+    /// The position of the document in the stream, starting at 0.
+    pub index: usize,
+    /// The line of the stream the document starts on, starting at 1.
+    pub line: usize,
+    /// The source of the document, without the delimiter lines around it.
+    pub source: String,
+    /// The error, if the document failed to parse. Its span is relative to
+    /// [`source`](Self::source).
+    pub error: Option<ParseError>,
+    /// The warnings, if the document parsed.
+    pub warnings: Vec<Warning>,
+}
+
+impl FileDiagnostics {
+    /// Returns `true` if the document parsed without warnings.
+    pub fn is_clean(&self) -> bool {
+        self.error.is_none() && self.warnings.is_empty()
+    }
+
+    /// Returns the diagnostics formatted one per line as
+    /// `line:column: severity: message`, with the line and column in the
+    /// whole stream, as editors and pre-commit hooks expect.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::lint_reader;
+    /// let stream = "[1, 2]\n---\n{1: 2,\n 1: 3}\n";
+    /// let reports: Vec<_> = lint_reader(stream.as_bytes())
+    ///     .map(|diagnostics| diagnostics.report())
+    ///     .collect();
+    /// assert_eq!(reports, ["", "4:2: error: Duplicate map key"]);
+    /// ```
+    pub fn report(&self) -> String {
+        let mut lines = Vec::new();
+        let mut push = |span: SourceSpan, severity: &str, message: String| {
+            let (start, _) = span.line_columns(&self.source);
+            let line = self.line + start.line - 1;
+            let column = start.column;
+            lines.push(format!("{line}:{column}: {severity}: {message}"));
+        };
+        if let Some(error) = &self.error {
+            let end = self.source.len();
+            let span = error.span().unwrap_or(SourceSpan::new(end, end));
+            push(span, "error", error.to_string());
+        }
+        for warning in &self.warnings {
+            push(warning.span, "warning", warning.kind.to_string());
+        }
+        lines.join("\n")
+    }
+}
+
+/// Reads a stream of documents separated by [`DOCUMENT_DELIMITER`] lines,
+/// and returns the diagnostics of each as soon as it has been read, for
+/// editor save hooks and pre-commit integrations that lint many documents
+/// through a pipe.
+///
+/// Documents that are empty or only whitespace are skipped, so the stream
+/// may begin or end with a delimiter. A document that isn't valid UTF-8 is
+/// an [`InvalidUtf8`](ParseError::InvalidUtf8) error, with its source
+/// decoded lossily. Reading stops at the first I/O error, as with
+/// [`BufRead::lines`].
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::lint_reader;
+/// let stream = "---\n[1, 2]\n---\n'45'\n---\n[1 2]\n";
+/// let mut diagnostics = lint_reader(stream.as_bytes());
+/// let first = diagnostics.next().unwrap();
+/// assert!(first.is_clean());
+/// assert_eq!(first.line, 2);
+/// let second = diagnostics.next().unwrap();
+/// assert_eq!(second.warnings.len(), 1);
+/// let third = diagnostics.next().unwrap();
+/// assert_eq!(third.index, 2);
+/// assert_eq!(third.report(), "6:4: error: Expected comma");
+/// assert!(diagnostics.next().is_none());
+/// ```
+pub fn lint_reader(
+    reader: impl BufRead,
+) -> impl Iterator<Item = FileDiagnostics> {
+    LintReader { reader, index: 0, next_line: 1, done: false }
+}
+
+//
+// === Private Functions ===
+//
+
+struct LintReader<R> {
+    reader: R,
+    index: usize,
+    /// The line of the stream that is read next, starting at 1.
+    next_line: usize,
+    done: bool,
+}
+
+impl<R: BufRead> LintReader<R> {
+    /// Reads the lines of the next document, up to the next delimiter or the
+    /// end of the stream, and returns them with the line they start on, or
+    /// `None` at the end of the stream.
+    fn read_document(&mut self) -> Option<(Vec<u8>, usize)> {
+        if self.done {
+            return None;
+        }
+        let line = self.next_line;
+        let mut source = Vec::new();
+        loop {
+            let mut buffer = Vec::new();
+            match self.reader.read_until(b'\n', &mut buffer) {
+                Ok(0) | Err(_) => {
+                    self.done = true;
+                    return Some((source, line));
+                }
+                Ok(_) => {}
+            }
+            self.next_line += 1;
+            if buffer.trim_ascii_end() == DOCUMENT_DELIMITER.as_bytes() {
+                return Some((source, line));
+            }
+            source.extend(buffer);
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for LintReader<R> {
+    type Item = FileDiagnostics;
+
+    fn next(&mut self) -> Option<FileDiagnostics> {
+        let (source, line) = loop {
+            let (source, line) = self.read_document()?;
+            if !source.trim_ascii().is_empty() {
+                break (source, line);
+            }
+        };
+        let (source, result) = match String::from_utf8(source) {
+            Ok(source) => {
+                let result = parse_diag_value(&source);
+                (source, result)
+            }
+            Err(e) => {
+                let error = e.utf8_error();
+                let start = error.valid_up_to();
+                let end = start + error.error_len().unwrap_or(1);
+                let source = String::from_utf8_lossy(e.as_bytes());
+                let span = SourceSpan::new(start, end);
+                (source.into_owned(), Err(ParseError::InvalidUtf8(span)))
+            }
+        };
+        let (error, warnings) = match result {
+            Ok(value) => (None, Warning::collect(&value)),
+            Err(error) => (Some(error), Vec::new()),
+        };
+        let index = self.index;
+        self.index += 1;
+        Some(FileDiagnostics { index, line, source, error, warnings })
+    }
+}
@@ -0,0 +1,72 @@
+use dcbor::prelude::*;
+
+/// An estimate of the memory a parsed item holds on the heap, in bytes,
+/// independent of the allocator in use.
+///
+/// Services that enforce quotas on submitted documents can compare the
+/// [`total`](Self::total), or any one of its parts, against their limits and
+/// reject an oversized document with a reason that says where its size comes
+/// from. The estimate doesn't count allocator overhead or spare capacity, so
+/// it is the same on every platform with the same pointer width.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct MemoryEstimate {
+    /// The bytes of the contents of text strings, in UTF-8.
+    pub strings: usize,
+    /// The bytes of the contents of byte strings.
+    pub byte_strings: usize,
+    /// The bytes of the items held by arrays, maps, and tagged values, each
+    /// counted as the size of a [`CBOR`].
+    pub containers: usize,
+}
+
+impl MemoryEstimate {
+    /// Returns the estimate for a CBOR item and everything it contains.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor::prelude::*;
+    /// # use dcbor_parse::{MemoryEstimate, parse_dcbor_item};
+    /// let cbor = parse_dcbor_item(r#"{"key": [h'0102', "abc"]}"#).unwrap();
+    /// let estimate = MemoryEstimate::new(&cbor);
+    /// assert_eq!(estimate.strings, 6);
+    /// assert_eq!(estimate.byte_strings, 2);
+    /// assert_eq!(estimate.containers, 4 * size_of::<CBOR>());
+    /// ```
+    pub fn new(cbor: &CBOR) -> Self {
+        let mut estimate = Self::default();
+        estimate.visit(cbor);
+        estimate
+    }
+
+    /// Returns the sum of the parts of the estimate.
+    pub fn total(&self) -> usize {
+        self.strings + self.byte_strings + self.containers
+    }
+
+    fn visit(&mut self, cbor: &CBOR) {
+        let slot = size_of::<CBOR>();
+        match cbor.as_case() {
+            CBORCase::Text(text) => self.strings += text.len(),
+            CBORCase::ByteString(bytes) => self.byte_strings += bytes.len(),
+            CBORCase::Array(items) => {
+                self.containers += items.len() * slot;
+                items.iter().for_each(|item| self.visit(item));
+            }
+            CBORCase::Map(map) => {
+                self.containers += map.len() * 2 * slot;
+                for (key, value) in map.iter() {
+                    self.visit(key);
+                    self.visit(value);
+                }
+            }
+            CBORCase::Tagged(_, item) => {
+                self.containers += slot;
+                self.visit(item);
+            }
+            CBORCase::Unsigned(_)
+            | CBORCase::Negative(_)
+            | CBORCase::Simple(_) => {}
+        }
+    }
+}
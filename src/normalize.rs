@@ -0,0 +1,100 @@
+use crate::{
+    ControlCharacters, ParseOptions, ParseResult,
+    parse::{Parser, escape_control_characters},
+    token::hex_comments,
+};
+
+/// Returns `src` with the digits of every hex byte string literal in lower
+/// case, as dCBOR writes them, leaving the rest of the text, including the
+/// comments within hex byte strings, as it was.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::normalize_hex_case;
+/// let src = "[h'00FF /KEY/', # KEEP\n 0xAB(h'Ab')]";
+/// assert_eq!(
+///     normalize_hex_case(src).unwrap(),
+///     "[h'00ff /KEY/', # KEEP\n 0xAB(h'ab')]"
+/// );
+/// ```
+pub fn normalize_hex_case(src: &str) -> ParseResult<String> {
+    let options = ParseOptions::default();
+    rewrite_tokens(src, &options, "ByteStringHex", lowercase_hex_digits)
+}
+
+/// Returns `src` with every control character written raw in a string
+/// literal replaced by its escape sequence, leaving the rest of the text as
+/// it was.
+///
+/// The source is read with
+/// [`ControlCharacters::Escape`](crate::ControlCharacters::Escape), which
+/// reads a raw control character as its escape sequence, so the result
+/// parses to the same item, and also parses with the default options, which
+/// reject raw control characters. Other escape sequences are left as they
+/// are, since a string keeps them as they were written.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{
+/// #     ControlCharacters, ParseOptions, normalize_string_escapes,
+/// #     parse_dcbor_item, parse_dcbor_item_with_options,
+/// # };
+/// let src = "[\"a\tb\", \"line\\n\"]";
+/// let normalized = normalize_string_escapes(src).unwrap();
+/// assert_eq!(normalized, "[\"a\\tb\", \"line\\n\"]");
+/// let options =
+///     ParseOptions::default().control_characters(ControlCharacters::Escape);
+/// assert_eq!(
+///     parse_dcbor_item(&normalized).unwrap(),
+///     parse_dcbor_item_with_options(src, &options).unwrap()
+/// );
+/// ```
+pub fn normalize_string_escapes(src: &str) -> ParseResult<String> {
+    let options =
+        ParseOptions::default().control_characters(ControlCharacters::Escape);
+    rewrite_tokens(src, &options, "String", escape_control_characters)
+}
+
+//
+// === Private Functions ===
+//
+
+/// Returns a hex byte string literal with its digits in lower case, and its
+/// comments as they were.
+fn lowercase_hex_digits(text: &str) -> String {
+    let comments = hex_comments(&text[2..text.len() - 1]).unwrap_or_default();
+    let mut result = String::with_capacity(text.len());
+    let mut offset = 0;
+    for comment in comments.iter().map(|c| c.start + 2..c.end + 2) {
+        result.push_str(&text[offset..comment.start].to_ascii_lowercase());
+        result.push_str(&text[comment.clone()]);
+        offset = comment.end;
+    }
+    result.push_str(&text[offset..].to_ascii_lowercase());
+    result
+}
+
+/// Parses `src` and returns it with the text of each token of `kind`
+/// replaced by `rewrite` of it.
+fn rewrite_tokens(
+    src: &str,
+    options: &ParseOptions,
+    kind: &str,
+    rewrite: impl Fn(&str) -> String,
+) -> ParseResult<String> {
+    let mut parser = Parser::new(src).with_options(options).with_tokens();
+    parser.parse_all()?;
+    let mut result = String::with_capacity(src.len());
+    let mut offset = 0;
+    for (token_kind, span) in parser.take_tokens() {
+        if token_kind == kind {
+            result.push_str(&src[offset..span.start]);
+            result.push_str(&rewrite(&src[span.range()]));
+            offset = span.end;
+        }
+    }
+    result.push_str(&src[offset..]);
+    Ok(result)
+}
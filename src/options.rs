@@ -0,0 +1,983 @@
+use std::{
+    fmt,
+    ops::RangeInclusive,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use known_values::KnownValuesStore;
+
+use crate::{
+    KnownValueInfo, TagValidators, known_value_info::known_value_info_in,
+};
+
+/// Loads the source of a document named by an `!include("name")` directive,
+/// or returns a message saying why it can't.
+pub type IncludeLoader =
+    dyn Fn(&str) -> std::result::Result<String, String> + Send + Sync;
+
+/// Loads the bytes of an external byte string written as
+/// `!bytes("digest")`, or returns a message saying why it can't.
+pub type ByteStringLoader =
+    dyn Fn(&str) -> std::result::Result<Vec<u8>, String> + Send + Sync;
+
+/// Looks up the value substituted for `${NAME}`, or returns `None` if the
+/// variable isn't defined.
+pub type EnvLookup = dyn Fn(&str) -> Option<String> + Send + Sync;
+
+/// What the parser does with raw control characters (U+0000 to U+001F) in
+/// string literals, as set by
+/// [`ParseOptions::control_characters`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ControlCharacters {
+    /// Rejects them with a
+    /// [`ControlCharacter`](crate::ParseError::ControlCharacter) error.
+    #[default]
+    Reject,
+    /// Accepts them, and stores each one as its escape sequence, as if it
+    /// had been written as `\t` or `\u001b`.
+    Escape,
+    /// Accepts them, and stores them as they are.
+    Verbatim,
+}
+
+/// What the parser does with a map key that appears more than once in the
+/// same map, as set by [`ParseOptions::duplicate_keys`].
+///
+/// Keys are compared by their deterministic encoding, so `1` and `1.0` are
+/// the same key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Rejects the map with a
+    /// [`DuplicateMapKey`](crate::ParseError::DuplicateMapKey) error at the
+    /// repeated key.
+    #[default]
+    Reject,
+    /// Keeps the first entry with the key, and ignores later ones.
+    KeepFirst,
+    /// Keeps the last entry with the key, in place of earlier ones, as
+    /// JavaScript does.
+    KeepLast,
+}
+
+/// What the parser does with the EDN encoding indicators that may follow a
+/// number, such as the `_1` of `1.5_1`, as set by
+/// [`ParseOptions::encoding_indicators`].
+///
+/// An indicator says how long the argument in the head of the number's
+/// encoding is: `_i` for an argument in the head's first byte, and `_0`,
+/// `_1`, `_2`, or `_3` for one of 1, 2, 4, or 8 bytes. For floats, these are
+/// half, single, and double precision.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EncodingIndicators {
+    /// Rejects them with a
+    /// [`SyntaxDisabled`](crate::ParseError::SyntaxDisabled) error.
+    #[default]
+    Reject,
+    /// Accepts them, and ignores them, since dCBOR has a single encoding
+    /// for each number.
+    Ignore,
+    /// Accepts them if they match the deterministic encoding of the number,
+    /// and rejects them with an
+    /// [`EncodingIndicatorMismatch`](crate::ParseError::EncodingIndicatorMismatch)
+    /// error otherwise.
+    Validate,
+}
+
+/// What the parser does with `NaN` literals, as set by
+/// [`ParseOptions::nan_handling`].
+///
+/// dCBOR has a single NaN, so every NaN is encoded as the canonical
+/// half-precision NaN, whatever was written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NaNHandling {
+    /// Accepts them, and encodes them as the canonical NaN.
+    #[default]
+    Canonicalize,
+    /// Rejects them with a
+    /// [`SyntaxDisabled`](crate::ParseError::SyntaxDisabled) error.
+    Reject,
+    /// Accepts them as with [`Canonicalize`](Self::Canonicalize), and keeps
+    /// the sign written in the parsed value, so that
+    /// [`Warning::collect`](crate::Warning::collect) reports the NaNs whose
+    /// sign is lost in the encoding.
+    Warn,
+}
+
+/// Options that change what the parser accepts.
+///
+/// The default options accept exactly the syntax of
+/// [`parse_dcbor_item`](crate::parse_dcbor_item). Pass options to
+/// [`parse_dcbor_item_with_options`](crate::parse_dcbor_item_with_options) or
+/// [`parse_diag_value_with_options`](crate::parse_diag_value_with_options).
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{ParseOptions, parse_dcbor_item_with_options};
+/// let options = ParseOptions::default().include_loader(|name| match name {
+///     "common" => Ok("[1, 2]".to_string()),
+///     _ => Err("no such fragment".to_string()),
+/// });
+/// let cbor =
+///     parse_dcbor_item_with_options(r#"{"a": !include("common")}"#, &options)
+///         .unwrap();
+/// assert_eq!(cbor.diagnostic_flat(), r#"{"a": [1, 2]}"#);
+/// ```
+///
+/// [`strict`](Self::strict) and [`lenient`](Self::lenient) return profiles
+/// for checking hand-written fixtures and for accepting sloppy input, which
+/// can be adjusted further with the other methods.
+#[derive(Clone)]
+pub struct ParseOptions {
+    include_loader: Option<Arc<IncludeLoader>>,
+    env_lookup: Option<Arc<EnvLookup>>,
+    registered_known_values_only: bool,
+    reserved_known_values: Vec<RangeInclusive<u64>>,
+    allowed_known_values: Vec<RangeInclusive<u64>>,
+    tag_validators: Option<TagValidators>,
+    validate_envelopes: bool,
+    byte_string_loader: Option<Arc<ByteStringLoader>>,
+    control_characters: ControlCharacters,
+    max_token_len: Option<usize>,
+    max_depth: usize,
+    integer_map_keys_only: bool,
+    cose: bool,
+    known_values: Option<Arc<KnownValuesStore>>,
+    spaced_tags: bool,
+    duplicate_keys: DuplicateKeys,
+    date_literals: bool,
+    known_value_literals: bool,
+    lenient_floats: bool,
+    nan_handling: NaNHandling,
+    encoding_indicators: EncodingIndicators,
+    data_only: bool,
+}
+
+impl ParseOptions {
+    /// Returns the default options.
+    pub const fn new() -> Self {
+        Self {
+            include_loader: None,
+            env_lookup: None,
+            registered_known_values_only: false,
+            reserved_known_values: Vec::new(),
+            allowed_known_values: Vec::new(),
+            tag_validators: None,
+            validate_envelopes: false,
+            byte_string_loader: None,
+            control_characters: ControlCharacters::Reject,
+            max_token_len: None,
+            max_depth: DEFAULT_MAX_DEPTH,
+            integer_map_keys_only: false,
+            cose: false,
+            known_values: None,
+            spaced_tags: false,
+            duplicate_keys: DuplicateKeys::Reject,
+            date_literals: true,
+            known_value_literals: true,
+            lenient_floats: false,
+            nan_handling: NaNHandling::Canonicalize,
+            encoding_indicators: EncodingIndicators::Reject,
+            data_only: false,
+        }
+    }
+
+    /// Returns options for checking hand-written fixtures, which accept the
+    /// default syntax but reject known values that aren't in the registry
+    /// and the content of commonly used tags that doesn't match their
+    /// specification, with [`TagValidators::standard`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{ParseOptions, parse_dcbor_item_with_options};
+    /// let options = ParseOptions::strict();
+    /// let parse = |src| parse_dcbor_item_with_options(src, &options);
+    /// assert!(parse("['isA', 1(1675814400)]").is_ok());
+    /// assert!(parse("'123456'").is_err());
+    /// assert!(parse(r#"1("yesterday")"#).is_err());
+    /// ```
+    pub fn strict() -> Self {
+        Self::new()
+            .registered_known_values_only(true)
+            .tag_validators(TagValidators::standard())
+    }
+
+    /// Returns options for accepting sloppy input, such as text pasted from
+    /// JavaScript or a log: a repeated map key replaces earlier entries with
+    /// [`DuplicateKeys::KeepLast`], and lenient floats, spaced tags, and raw
+    /// control characters in strings are accepted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{ParseOptions, parse_dcbor_item_with_options};
+    /// let options = ParseOptions::lenient();
+    /// let cbor =
+    ///     parse_dcbor_item_with_options("{1: .5, 2: +inf, 1: 1.}", &options)
+    ///         .unwrap();
+    /// assert_eq!(cbor.diagnostic_flat(), "{1: 1, 2: Infinity}");
+    /// ```
+    pub fn lenient() -> Self {
+        Self::new()
+            .duplicate_keys(DuplicateKeys::KeepLast)
+            .lenient_floats(true)
+            .spaced_tags(true)
+            .control_characters(ControlCharacters::Escape)
+    }
+
+    /// Enables `!include("name")` directives, which splice in the item parsed
+    /// from the document that `loader` returns for `name`.
+    ///
+    /// Includes are disabled by default, and the parser never reads files
+    /// itself: the loader decides which names can be included, and from
+    /// where. Included documents may include others, up to a depth of 32.
+    /// A document that includes itself, directly or indirectly, is an error.
+    pub fn include_loader(
+        mut self,
+        loader: impl Fn(&str) -> std::result::Result<String, String>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.include_loader = Some(Arc::new(loader));
+        self
+    }
+
+    /// Enables `!include("name")` directives that load the file `name`
+    /// relative to `dir`.
+    ///
+    /// Names that are absolute or contain `..` are rejected, and so are
+    /// files that resolve outside `dir` through a symbolic link, so included
+    /// documents can't reach files outside `dir`.
+    pub fn include_dir(self, dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        self.include_loader(move |name| {
+            let outside =
+                || format!("'{name}' is outside the include directory");
+            let path = Path::new(name);
+            let is_relative = path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+            if !is_relative {
+                return Err(outside());
+            }
+            let dir = dir.canonicalize().map_err(|e| e.to_string())?;
+            let path =
+                dir.join(path).canonicalize().map_err(|e| e.to_string())?;
+            if !path.starts_with(&dir) {
+                return Err(outside());
+            }
+            std::fs::read_to_string(path).map_err(|e| e.to_string())
+        })
+    }
+
+    /// Enables `${NAME}` substitution, which replaces each `${NAME}` in the
+    /// source with the value `lookup` returns for `NAME` before the source is
+    /// parsed.
+    ///
+    /// `NAME` is a letter or underscore followed by letters, digits, and
+    /// underscores. Substitution applies anywhere in the source, including
+    /// inside strings, and to included documents. A variable for which
+    /// `lookup` returns `None` is an error. The spans of parsed items and
+    /// errors refer to the source as it was written, so an item that came
+    /// from a substituted value has the span of the `${NAME}` it replaced.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{ParseOptions, parse_dcbor_item_with_options};
+    /// let options = ParseOptions::default().env_lookup(|name| match name {
+    ///     "PORT" => Some("8080".to_string()),
+    ///     _ => None,
+    /// });
+    /// let src = r#"{"host": "${USER}", "port": ${PORT}}"#;
+    /// let error = parse_dcbor_item_with_options(src, &options).unwrap_err();
+    /// assert_eq!(error.to_string(), "Undefined variable 'USER'");
+    ///
+    /// let src = r#"{"port": ${PORT}}"#;
+    /// let cbor = parse_dcbor_item_with_options(src, &options).unwrap();
+    /// assert_eq!(cbor.diagnostic_flat(), r#"{"port": 8080}"#);
+    /// ```
+    pub fn env_lookup(
+        mut self,
+        lookup: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.env_lookup = Some(Arc::new(lookup));
+        self
+    }
+
+    /// Enables `${NAME}` substitution from the environment variables of the
+    /// process, as with [`env_lookup`](Self::env_lookup).
+    pub fn env_vars(self) -> Self {
+        self.env_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Rejects known values that aren't in the known values registry, unless
+    /// they are allowed by [`allow_known_values`](Self::allow_known_values).
+    ///
+    /// Known values written as `'1'`, `'isA'`, or `''`, and as the tagged
+    /// integer `40000(1)`, are checked. A rejected known value is a
+    /// [`DisallowedKnownValue`](crate::ParseError::DisallowedKnownValue)
+    /// error.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{ParseOptions, parse_dcbor_item_with_options};
+    /// let options = ParseOptions::default()
+    ///     .registered_known_values_only(true)
+    ///     .allow_known_values(100_000..=199_999);
+    /// let parse = |src| parse_dcbor_item_with_options(src, &options);
+    /// assert!(parse("['isA', '100001']").is_ok());
+    /// assert_eq!(
+    ///     parse("['isA', '45']").unwrap_err().to_string(),
+    ///     "Known value '45' (unregistered, General 0-49) is not allowed"
+    /// );
+    /// ```
+    pub fn registered_known_values_only(mut self, only: bool) -> Self {
+        self.registered_known_values_only = only;
+        self
+    }
+
+    /// Rejects known values in `range`, whether or not they are registered,
+    /// unless they are allowed by
+    /// [`allow_known_values`](Self::allow_known_values). May be called more
+    /// than once to reserve several ranges.
+    pub fn reserve_known_values(mut self, range: RangeInclusive<u64>) -> Self {
+        self.reserved_known_values.push(range);
+        self
+    }
+
+    /// Accepts known values in `range` even if they are unregistered or
+    /// reserved. May be called more than once to allow several ranges.
+    pub fn allow_known_values(mut self, range: RangeInclusive<u64>) -> Self {
+        self.allowed_known_values.push(range);
+        self
+    }
+
+    /// Checks the content of each tagged item with `validators` as it is
+    /// parsed.
+    pub fn tag_validators(mut self, validators: TagValidators) -> Self {
+        self.tag_validators = Some(validators);
+        self
+    }
+
+    /// Checks that the content of each envelope, written as `200(...)`,
+    /// `envelope(...)`, or a `ur:envelope` literal, has the structure of a
+    /// Gordian Envelope.
+    ///
+    /// Only the structure is checked: each node must have a subject and at
+    /// least one assertion, each assertion must be a map with one entry,
+    /// elided envelopes must be 32-byte digests, and so on. Content with the
+    /// wrong structure is an
+    /// [`InvalidTagContent`](crate::ParseError::InvalidTagContent) error.
+    /// `ur:envelope` literals can only be parsed once the `envelope` tag has
+    /// been registered, for example with `bc_envelope::register_tags`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{ParseOptions, parse_dcbor_item_with_options};
+    /// let options = ParseOptions::default().validate_envelopes(true);
+    /// let parse = |src| parse_dcbor_item_with_options(src, &options);
+    /// assert!(parse(r#"200([201("Alice"), {1: 201("Person")}])"#).is_ok());
+    /// assert_eq!(
+    ///     parse(r#"200([201("Alice")])"#).unwrap_err().to_string(),
+    ///     "Invalid content for tag 200: a node must have at least one \
+    ///      assertion"
+    /// );
+    /// ```
+    pub fn validate_envelopes(mut self, validate: bool) -> Self {
+        self.validate_envelopes = validate;
+        self
+    }
+
+    /// Enables the names of the COSE messages of RFC 9052 and of the CBOR Web
+    /// Token as tag names, and checks the structure of the content of each
+    /// item with their tags.
+    ///
+    /// The names are `COSE_Sign1` (18), `COSE_Sign` (98), `COSE_Mac0` (17),
+    /// `COSE_Mac` (97), `COSE_Encrypt0` (16), `COSE_Encrypt` (96), and `CWT`
+    /// (61). Names in the tags registry take precedence. Each message must
+    /// be an array with the number of elements its structure has, with byte
+    /// string protected headers, map unprotected headers, byte string or
+    /// `null` payloads, and non-empty arrays of signatures or recipients. A
+    /// CWT must contain a tagged COSE message. Content with the wrong
+    /// structure is an
+    /// [`InvalidTagContent`](crate::ParseError::InvalidTagContent) error.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{ParseOptions, parse_dcbor_item_with_options};
+    /// let options = ParseOptions::default().cose(true);
+    /// let parse = |src| parse_dcbor_item_with_options(src, &options);
+    /// let cbor = parse("COSE_Sign1([h'a10126', {4: h'3131'}, h'00', h'01'])")
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     cbor.diagnostic_flat(),
+    ///     "18([h'a10126', {4: h'3131'}, h'00', h'01'])"
+    /// );
+    /// assert_eq!(
+    ///     parse("COSE_Sign1([h'', {}, null])").unwrap_err().to_string(),
+    ///     "Invalid content for tag 18: COSE_Sign1 must have 4 elements, not 3"
+    /// );
+    /// ```
+    pub fn cose(mut self, enable: bool) -> Self {
+        self.cose = enable;
+        self
+    }
+
+    /// Looks up the names of known values, such as `isA` in `'isA'`, in
+    /// `store` rather than in the global known values registry.
+    ///
+    /// The store also decides which known values
+    /// [`registered_known_values_only`](Self::registered_known_values_only)
+    /// accepts. Without it, a global registry that can't be read is a
+    /// [`KnownValuesRegistryUnavailable`](crate::ParseError::KnownValuesRegistryUnavailable)
+    /// error for a named known value.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{
+    /// #     ParseError, ParseOptions, parse_dcbor_item_with_options,
+    /// # };
+    /// # use known_values::{KnownValue, KnownValuesStore};
+    /// let store = KnownValuesStore::new([KnownValue::new_with_name(
+    ///     1000u64,
+    ///     "approvedBy".to_string(),
+    /// )]);
+    /// let options = ParseOptions::default().known_values(store);
+    /// let parse = |src| parse_dcbor_item_with_options(src, &options);
+    /// let cbor = parse("'approvedBy'").unwrap();
+    /// assert_eq!(cbor.diagnostic_flat(), "40000(1000)");
+    /// assert_eq!(
+    ///     parse("'isA'"),
+    ///     Err(ParseError::UnknownKnownValueName("isA".into(), (1..4).into()))
+    /// );
+    /// ```
+    pub fn known_values(mut self, store: KnownValuesStore) -> Self {
+        self.known_values = Some(Arc::new(store));
+        self
+    }
+
+    /// Accepts whitespace between a tag number or name and its opening
+    /// parenthesis, as in `date (1675814400)`.
+    ///
+    /// Without this option the whitespace is a
+    /// [`SpaceBeforeParenthesis`](crate::ParseError::SpaceBeforeParenthesis)
+    /// error.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{
+    /// #     ParseError, ParseOptions, parse_dcbor_item,
+    /// #     parse_dcbor_item_with_options,
+    /// # };
+    /// dcbor::register_tags();
+    /// let src = "[1234 (1), date\n(2)]";
+    /// assert_eq!(
+    ///     parse_dcbor_item(src),
+    ///     Err(ParseError::SpaceBeforeParenthesis((5..6).into()))
+    /// );
+    /// let options = ParseOptions::default().spaced_tags(true);
+    /// let cbor = parse_dcbor_item_with_options(src, &options).unwrap();
+    /// assert_eq!(cbor.diagnostic_flat(), "[1234(1), 1(2)]");
+    /// ```
+    pub fn spaced_tags(mut self, allow: bool) -> Self {
+        self.spaced_tags = allow;
+        self
+    }
+
+    /// Sets what the parser does with a map key that appears more than once
+    /// in the same map.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{
+    /// #     DuplicateKeys, ParseOptions, parse_dcbor_item_with_options,
+    /// # };
+    /// let src = r#"{"a": 1, "b": 2, "a": 3}"#;
+    /// let parse = |policy| {
+    ///     let options = ParseOptions::default().duplicate_keys(policy);
+    ///     parse_dcbor_item_with_options(src, &options)
+    /// };
+    /// assert!(parse(DuplicateKeys::Reject).is_err());
+    /// assert_eq!(
+    ///     parse(DuplicateKeys::KeepFirst).unwrap().diagnostic_flat(),
+    ///     r#"{"a": 1, "b": 2}"#
+    /// );
+    /// assert_eq!(
+    ///     parse(DuplicateKeys::KeepLast).unwrap().diagnostic_flat(),
+    ///     r#"{"a": 3, "b": 2}"#
+    /// );
+    /// ```
+    pub fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    /// Sets whether date literals such as `2023-02-08` are accepted. They
+    /// are by default.
+    ///
+    /// A rejected literal is a
+    /// [`SyntaxDisabled`](crate::ParseError::SyntaxDisabled) error. Dates
+    /// can still be written as tagged items, as in `1(1675814400)`.
+    pub fn date_literals(mut self, enable: bool) -> Self {
+        self.date_literals = enable;
+        self
+    }
+
+    /// Sets whether known value literals, written as `'isA'`, `'1'`, `''`,
+    /// or `Unit`, are accepted. They are by default.
+    ///
+    /// A rejected literal is a
+    /// [`SyntaxDisabled`](crate::ParseError::SyntaxDisabled) error. Known
+    /// values can still be written as tagged items, as in `40000(1)`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{
+    /// #     ParseError, ParseOptions, parse_dcbor_item_with_options,
+    /// # };
+    /// let options = ParseOptions::default().known_value_literals(false);
+    /// let parse = |src| parse_dcbor_item_with_options(src, &options);
+    /// assert!(parse("40000(1)").is_ok());
+    /// assert_eq!(
+    ///     parse("[1, 'isA']"),
+    ///     Err(ParseError::SyntaxDisabled(
+    ///         "Known value literals".into(),
+    ///         (4..9).into()
+    ///     ))
+    /// );
+    /// ```
+    pub fn known_value_literals(mut self, enable: bool) -> Self {
+        self.known_value_literals = enable;
+        self
+    }
+
+    /// Accepts spellings of numbers that JSON doesn't allow: a leading `+`,
+    /// as in `+1`, a missing digit before or after the decimal point, as in
+    /// `.5` or `1.`, and `nan`, `inf`, and `infinity` in any case, with an
+    /// optional sign.
+    ///
+    /// Without this option these spellings are a
+    /// [`SyntaxDisabled`](crate::ParseError::SyntaxDisabled) error.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{ParseOptions, parse_dcbor_item_with_options};
+    /// let options = ParseOptions::default().lenient_floats(true);
+    /// let src = "[+1, .5, 2., -INF, nan]";
+    /// let cbor = parse_dcbor_item_with_options(src, &options).unwrap();
+    /// assert_eq!(cbor.diagnostic_flat(), "[1, 0.5, 2, -Infinity, NaN]");
+    /// ```
+    pub fn lenient_floats(mut self, enable: bool) -> Self {
+        self.lenient_floats = enable;
+        self
+    }
+
+    /// Sets what the parser does with `NaN` literals.
+    ///
+    /// By default they are accepted, and encoded as the single NaN of
+    /// dCBOR. Numerically sensitive fixtures can reject them instead, or
+    /// have a warning for each one written with a sign, such as `-nan` with
+    /// [`lenient_floats`](Self::lenient_floats), whose sign the encoding
+    /// doesn't keep.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{
+    /// #     NaNHandling, ParseOptions, Warning, WarningKind,
+    /// #     parse_diag_value_with_options,
+    /// # };
+    /// let parse = |src, policy| {
+    ///     let options = ParseOptions::default()
+    ///         .lenient_floats(true)
+    ///         .nan_handling(policy);
+    ///     parse_diag_value_with_options(src, &options)
+    /// };
+    /// assert_eq!(
+    ///     parse("[1.5, NaN]", NaNHandling::Reject).unwrap_err().to_string(),
+    ///     "NaN values are disabled"
+    /// );
+    /// let value = parse("[NaN, -nan]", NaNHandling::Warn).unwrap();
+    /// assert_eq!(value.to_cbor().diagnostic_flat(), "[NaN, NaN]");
+    /// let warnings = Warning::collect(&value);
+    /// assert_eq!(warnings.len(), 1);
+    /// assert_eq!(warnings[0].kind, WarningKind::NaNSignLost);
+    /// assert_eq!(warnings[0].span, 6..10);
+    /// ```
+    pub fn nan_handling(mut self, policy: NaNHandling) -> Self {
+        self.nan_handling = policy;
+        self
+    }
+
+    /// Sets what the parser does with EDN encoding indicators on numbers,
+    /// such as `1.5_1` or `0_0`, so that diagnostic notation written by
+    /// other tools can be parsed without editing. They are rejected by
+    /// default.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{
+    /// #     EncodingIndicators, ParseOptions, parse_dcbor_item_with_options,
+    /// # };
+    /// let parse = |src, policy| {
+    ///     let options = ParseOptions::default().encoding_indicators(policy);
+    ///     parse_dcbor_item_with_options(src, &options)
+    /// };
+    /// let src = "[1.5_1, 1000_1, 0_3]";
+    /// assert_eq!(
+    ///     parse(src, EncodingIndicators::Reject).unwrap_err().to_string(),
+    ///     "Encoding indicators are disabled"
+    /// );
+    /// let cbor = parse(src, EncodingIndicators::Ignore).unwrap();
+    /// assert_eq!(cbor.diagnostic_flat(), "[1.5, 1000, 0]");
+    /// assert_eq!(
+    ///     parse(src, EncodingIndicators::Validate).unwrap_err().to_string(),
+    ///     "Encoding indicator _3 doesn't match the deterministic encoding"
+    /// );
+    /// ```
+    pub fn encoding_indicators(mut self, policy: EncodingIndicators) -> Self {
+        self.encoding_indicators = policy;
+        self
+    }
+
+    /// Accepts only literals of plain data: numbers, strings, byte strings,
+    /// booleans, `null`, arrays, maps, and items tagged with tag numbers.
+    ///
+    /// URs, known value literals, tag names, date literals, and directives
+    /// are [`SyntaxDisabled`](crate::ParseError::SyntaxDisabled) errors,
+    /// and variables aren't substituted, whatever the other options say. A
+    /// tagged item is only data, so `40000(1)` isn't checked against the
+    /// known value policy.
+    ///
+    /// In this mode the parser never reads the global tags or known values
+    /// registries, and never calls the include, byte string, or variable
+    /// loaders, so its behavior depends only on the source and on any tag
+    /// validators. This makes it suitable for embedding where the behavior
+    /// of the parser must be easy to audit.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{
+    /// #     ParseError, ParseOptions, parse_dcbor_item_with_options,
+    /// # };
+    /// let options = ParseOptions::default().data_only(true);
+    /// let parse = |src| parse_dcbor_item_with_options(src, &options);
+    /// assert!(parse(r#"{1: [h'00', "a", 1(2.5), 40000(1)]}"#).is_ok());
+    /// assert_eq!(
+    ///     parse("[1, date(2)]"),
+    ///     Err(ParseError::SyntaxDisabled("Tag names".into(), (4..8).into()))
+    /// );
+    /// ```
+    pub fn data_only(mut self, enable: bool) -> Self {
+        self.data_only = enable;
+        self
+    }
+
+    /// Enables `!bytes("digest")` directives, which stand for the byte string
+    /// that `loader` returns for `digest`.
+    ///
+    /// The directives are written in place of large byte strings by
+    /// [`PrintOptions::externalize_byte_strings`](crate::PrintOptions::externalize_byte_strings),
+    /// so that huge binary blobs can be kept out of text fixtures. The digest
+    /// is the SHA-256 digest of the bytes in lowercase hex, and bytes that
+    /// don't match their digest are an error.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{ParseOptions, parse_dcbor_item_with_options};
+    /// const DIGEST: &str =
+    ///     "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d";
+    /// let options = ParseOptions::default().byte_string_loader(|name| {
+    ///     match name {
+    ///         DIGEST => Ok(vec![0]),
+    ///         _ => Err("no such file".to_string()),
+    ///     }
+    /// });
+    /// let src = format!(r#"{{1: !bytes("{DIGEST}")}}"#);
+    /// let cbor = parse_dcbor_item_with_options(&src, &options).unwrap();
+    /// assert_eq!(cbor.diagnostic_flat(), "{1: h'00'}");
+    /// ```
+    pub fn byte_string_loader(
+        mut self,
+        loader: impl Fn(&str) -> std::result::Result<Vec<u8>, String>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.byte_string_loader = Some(Arc::new(loader));
+        self
+    }
+
+    /// Enables `!bytes("digest")` directives that load the file `digest` in
+    /// `dir`, as with [`byte_string_loader`](Self::byte_string_loader).
+    pub fn byte_string_dir(self, dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        self.byte_string_loader(move |digest| {
+            if !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("'{digest}' is not a hex digest"));
+            }
+            std::fs::read(dir.join(digest)).map_err(|e| e.to_string())
+        })
+    }
+
+    /// Sets what the parser does with raw control characters, such as tabs
+    /// and the ESC of terminal escape sequences, in string literals.
+    ///
+    /// They are rejected by default, since they are invisible or disruptive
+    /// when the source is displayed. Logs pasted into fixtures may contain
+    /// them legitimately, and can be accepted without editing with
+    /// [`Escape`](ControlCharacters::Escape) or
+    /// [`Verbatim`](ControlCharacters::Verbatim). Each accepted control
+    /// character is reported by [`Warning::collect`](crate::Warning::collect)
+    /// as a [`ControlCharacter`](crate::WarningKind::ControlCharacter)
+    /// warning.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{
+    /// #     ControlCharacters, ParseOptions, Warning, WarningKind,
+    /// #     parse_diag_value_with_options,
+    /// # };
+    /// let src = "\"a\tb\"";
+    /// let parse = |policy| {
+    ///     let options = ParseOptions::default().control_characters(policy);
+    ///     parse_diag_value_with_options(src, &options)
+    /// };
+    /// assert_eq!(
+    ///     parse(ControlCharacters::Reject).unwrap_err().to_string(),
+    ///     "Control character U+0009 in string"
+    /// );
+    /// let value = parse(ControlCharacters::Escape).unwrap();
+    /// assert_eq!(value.to_cbor().diagnostic_flat(), r#""a\tb""#);
+    /// let value = parse(ControlCharacters::Verbatim).unwrap();
+    /// assert_eq!(value.to_cbor().diagnostic_flat(), "\"a\tb\"");
+    ///
+    /// let warnings = Warning::collect(&value);
+    /// assert_eq!(
+    ///     warnings[0].kind,
+    ///     WarningKind::ControlCharacter { character: '\t' }
+    /// );
+    /// assert_eq!(warnings[0].span, 2..3);
+    /// ```
+    pub fn control_characters(mut self, policy: ControlCharacters) -> Self {
+        self.control_characters = policy;
+        self
+    }
+
+    /// Rejects tokens longer than `max` bytes.
+    ///
+    /// A string or byte string that doesn't close within `max` bytes is
+    /// rejected before the lexer scans it, so an unterminated string in a
+    /// large document is reported as a
+    /// [`TokenTooLong`](crate::ParseError::TokenTooLong) error at its opening
+    /// quote, spanning its first `max + 1` bytes, rather than being scanned
+    /// to the end of the document and reported as an unrecognized token.
+    /// There is no limit by default.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{ParseOptions, parse_dcbor_item_with_options};
+    /// let options = ParseOptions::default().max_token_len(8);
+    /// let parse = |src| parse_dcbor_item_with_options(src, &options);
+    /// assert!(parse(r#"["short", h'0102']"#).is_ok());
+    /// let error = parse(r#"[1, "unterminated, 2, 3]"#).unwrap_err();
+    /// assert_eq!(error.to_string(), "Token longer than 8 bytes");
+    /// assert_eq!(error.span().unwrap().start, 4);
+    /// ```
+    pub fn max_token_len(mut self, max: usize) -> Self {
+        self.max_token_len = Some(max);
+        self
+    }
+
+    /// Rejects arrays, maps, tagged items, and embedded items nested more
+    /// than `max` levels deep, with a
+    /// [`NestingTooDeep`](crate::ParseError::NestingTooDeep) error at the
+    /// group that is too deep. Items in included documents count the levels
+    /// of the groups they are included in.
+    ///
+    /// The parser recurses into each group, so this keeps deeply nested
+    /// input from overflowing the stack. The default is 256.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{ParseError, ParseOptions};
+    /// # use dcbor_parse::parse_dcbor_item_with_options;
+    /// let options = ParseOptions::default().max_depth(2);
+    /// let parse = |src| parse_dcbor_item_with_options(src, &options);
+    /// assert!(parse("[[1], {2: 3}]").is_ok());
+    /// assert_eq!(
+    ///     parse("[[1(2)]]"),
+    ///     Err(ParseError::NestingTooDeep(2, (2..4).into()))
+    /// );
+    /// ```
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.max_depth = max;
+        self
+    }
+
+    /// Rejects map keys that aren't integers, as some protocols require.
+    ///
+    /// A key is an integer if it is encoded as one, so `1.0` is accepted. A
+    /// rejected key is a
+    /// [`NonIntegerMapKey`](crate::ParseError::NonIntegerMapKey) error with
+    /// the span of the key and the name of its type.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{ParseOptions, parse_dcbor_item_with_options};
+    /// let options = ParseOptions::default().integer_map_keys_only(true);
+    /// let parse = |src| parse_dcbor_item_with_options(src, &options);
+    /// assert!(parse(r#"{1: "a", -2: {3: "b"}}"#).is_ok());
+    /// let error = parse(r#"{1: {"b": 2}}"#).unwrap_err();
+    /// assert_eq!(error.to_string(), "Map key must be an integer, not text");
+    /// assert_eq!(error.span().unwrap(), 5..8);
+    /// ```
+    pub fn integer_map_keys_only(mut self, only: bool) -> Self {
+        self.integer_map_keys_only = only;
+        self
+    }
+
+    /// Returns `true` if the known value policy accepts `value`.
+    pub(crate) fn allows_known_value(&self, value: u64) -> bool {
+        if self.allowed_known_values.iter().any(|r| r.contains(&value)) {
+            return true;
+        }
+        if self.reserved_known_values.iter().any(|r| r.contains(&value)) {
+            return false;
+        }
+        !self.registered_known_values_only
+            || self.known_value_info(value).is_registered()
+    }
+
+    /// Returns the [`KnownValueInfo`] of `value`, with its name from the
+    /// store set by [`known_values`](Self::known_values), if any.
+    pub(crate) fn known_value_info(&self, value: u64) -> KnownValueInfo {
+        known_value_info_in(value, self.known_value_store())
+    }
+
+    pub(crate) fn known_value_store(&self) -> Option<&KnownValuesStore> {
+        self.known_values.as_deref()
+    }
+
+    pub(crate) fn loader(&self) -> Option<&IncludeLoader> {
+        self.include_loader.as_deref()
+    }
+
+    pub(crate) fn byte_loader(&self) -> Option<&ByteStringLoader> {
+        self.byte_string_loader.as_deref()
+    }
+
+    pub(crate) fn lookup(&self) -> Option<&EnvLookup> {
+        self.env_lookup.as_deref().filter(|_| !self.data_only)
+    }
+
+    pub(crate) fn validators(&self) -> Option<&TagValidators> {
+        self.tag_validators.as_ref()
+    }
+
+    pub(crate) fn validates_envelopes(&self) -> bool {
+        self.validate_envelopes
+    }
+
+    pub(crate) fn control_character_policy(&self) -> ControlCharacters {
+        self.control_characters
+    }
+
+    pub(crate) fn token_len_limit(&self) -> Option<usize> {
+        self.max_token_len
+    }
+
+    pub(crate) fn depth_limit(&self) -> usize {
+        self.max_depth
+    }
+
+    pub(crate) fn requires_integer_map_keys(&self) -> bool {
+        self.integer_map_keys_only
+    }
+
+    pub(crate) fn enables_cose(&self) -> bool { self.cose }
+
+    pub(crate) fn allows_spaced_tags(&self) -> bool { self.spaced_tags }
+
+    pub(crate) fn duplicate_key_policy(&self) -> DuplicateKeys {
+        self.duplicate_keys
+    }
+
+    pub(crate) fn allows_date_literals(&self) -> bool {
+        self.date_literals && !self.data_only
+    }
+
+    pub(crate) fn allows_known_value_literals(&self) -> bool {
+        self.known_value_literals && !self.data_only
+    }
+
+    pub(crate) fn is_data_only(&self) -> bool { self.data_only }
+
+    pub(crate) fn allows_lenient_floats(&self) -> bool {
+        self.lenient_floats
+    }
+
+    pub(crate) fn nan_policy(&self) -> NaNHandling { self.nan_handling }
+
+    pub(crate) fn encoding_indicator_policy(&self) -> EncodingIndicators {
+        self.encoding_indicators
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self { Self::new() }
+}
+
+impl fmt::Debug for ParseOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParseOptions")
+            .field("include_loader", &self.include_loader.is_some())
+            .field("env_lookup", &self.env_lookup.is_some())
+            .field(
+                "registered_known_values_only",
+                &self.registered_known_values_only,
+            )
+            .field("reserved_known_values", &self.reserved_known_values)
+            .field("allowed_known_values", &self.allowed_known_values)
+            .field("tag_validators", &self.tag_validators)
+            .field("validate_envelopes", &self.validate_envelopes)
+            .field("byte_string_loader", &self.byte_string_loader.is_some())
+            .field("control_characters", &self.control_characters)
+            .field("max_token_len", &self.max_token_len)
+            .field("max_depth", &self.max_depth)
+            .field("integer_map_keys_only", &self.integer_map_keys_only)
+            .field("cose", &self.cose)
+            .field("known_values", &self.known_values.is_some())
+            .field("spaced_tags", &self.spaced_tags)
+            .field("duplicate_keys", &self.duplicate_keys)
+            .field("date_literals", &self.date_literals)
+            .field("known_value_literals", &self.known_value_literals)
+            .field("lenient_floats", &self.lenient_floats)
+            .field("nan_handling", &self.nan_handling)
+            .field("encoding_indicators", &self.encoding_indicators)
+            .field("data_only", &self.data_only)
+            .finish()
+    }
+}
+
+/// The number of levels groups may be nested by default.
+const DEFAULT_MAX_DEPTH: usize = 256;
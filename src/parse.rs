@@ -1,10 +1,21 @@
+use std::collections::HashMap;
+
 use bc_ur::prelude::*;
-use known_values::KnownValue;
-use logos::{Lexer, Logos, Span};
+use known_values::{KnownValue, KnownValuesStore};
+use logos::{Lexer, Logos};
 
 use crate::{
-    Token,
+    Budget, ByteStringEncoding, ControlCharacters, DiagValue, DuplicateKeys,
+    EncodingIndicators, GroupKind, NaNHandling, NumberValue, ParseOptions,
+    ParseVisitor, SourceSpan, Token,
+    assertion::TypeAssertion,
+    budget::encoded_len,
+    cose::{check_cose_structure, cose_tag_for_name},
+    envelope::{ENVELOPE_TAG, check_envelope_shape},
     error::{Error, Result},
+    registry::{with_known_values, with_tags_store},
+    substitute::Substituted,
+    token::parse_integer,
 };
 
 /// Parses a dCBOR item from a string input.
@@ -37,23 +48,7 @@ use crate::{
 /// assert_eq!(cbor.diagnostic(), "[1, 2, 3]");
 /// ```
 pub fn parse_dcbor_item(src: &str) -> Result<CBOR> {
-    let mut lexer = Token::lexer(src);
-    let first_token = expect_token(&mut lexer);
-    match first_token {
-        Ok(token) => parse_item_token(&token, &mut lexer).and_then(|cbor| {
-            if lexer.next().is_some() {
-                Err(Error::ExtraData(lexer.span()))
-            } else {
-                Ok(cbor)
-            }
-        }),
-        Err(e) => {
-            if e == Error::UnexpectedEndOfInput {
-                return Err(Error::EmptyInput);
-            }
-            Err(e)
-        }
-    }
+    parse_diag_value(src).map(|value| value.to_cbor())
 }
 
 /// Parses a dCBOR item from the beginning of a string and returns the parsed
@@ -73,328 +68,1398 @@ pub fn parse_dcbor_item(src: &str) -> Result<CBOR> {
 /// assert_eq!(used, 5);
 /// ```
 pub fn parse_dcbor_item_partial(src: &str) -> Result<(CBOR, usize)> {
-    let mut lexer = Token::lexer(src);
-    let first_token = expect_token(&mut lexer);
-    match first_token {
-        Ok(token) => parse_item_token(&token, &mut lexer).map(|cbor| {
-            let consumed = match lexer.next() {
-                Some(_) => lexer.span().start,
-                None => src.len(),
-            };
-            (cbor, consumed)
-        }),
-        Err(e) => {
-            if e == Error::UnexpectedEndOfInput {
-                Err(Error::EmptyInput)
-            } else {
-                Err(e)
-            }
-        }
-    }
+    let mut parser = Parser::new(src);
+    let value = parser.parse_first()?;
+    Ok((value.to_cbor(), parser.consumed()))
+}
+
+/// Parses a sequence of dCBOR items separated by whitespace or comments, as
+/// in a log file or test corpus with many items per file.
+///
+/// This is the diagnostic notation of an [RFC 8742] CBOR sequence: where
+/// [`parse_dcbor_item`] reports anything after the first item as
+/// [`ExtraData`](crate::ParseError::ExtraData), this parses it as the next
+/// item. A source with no items is an empty sequence. Adjacent byte string
+/// literals, such as `h'01' h'02'`, are the segments of a single byte
+/// string, not separate items.
+///
+/// [RFC 8742]: https://www.rfc-editor.org/rfc/rfc8742
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::parse_dcbor_sequence;
+/// let items = parse_dcbor_sequence("1 [2, 3]\n# comment\n\"four\"").unwrap();
+/// let diags: Vec<_> = items.iter().map(|item| item.diagnostic()).collect();
+/// assert_eq!(diags, ["1", "[2, 3]", r#""four""#]);
+/// assert_eq!(parse_dcbor_sequence(" # nothing").unwrap(), []);
+/// ```
+pub fn parse_dcbor_sequence(src: &str) -> Result<Vec<CBOR>> {
+    let values = Parser::new(src).parse_sequence()?;
+    Ok(values.iter().map(DiagValue::to_cbor).collect())
+}
+
+/// Parses a sequence of dCBOR items separated by whitespace or comments, as
+/// with [`parse_dcbor_sequence`], and collects them into a single array.
+///
+/// This suits test corpora and command-line tools that write one item after
+/// another, as `xargs` passes one argument after another, where the consumer
+/// expects a single item. A single item is an array of one item, and a
+/// source with no items is an empty array.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::parse_dcbor_implicit_array;
+/// let cbor = parse_dcbor_implicit_array("1\n\"two\"\n[3]\n").unwrap();
+/// assert_eq!(cbor.diagnostic_flat(), r#"[1, "two", [3]]"#);
+/// let cbor = parse_dcbor_implicit_array("[1, 2]").unwrap();
+/// assert_eq!(cbor.diagnostic_flat(), "[[1, 2]]");
+/// ```
+pub fn parse_dcbor_implicit_array(src: &str) -> Result<CBOR> {
+    Ok(parse_dcbor_sequence(src)?.into())
+}
+
+/// Parses a single map entry, written as `key: value`, from a string input.
+///
+/// This accepts what could appear between the commas of a map, so tools that
+/// take one assertion per command-line flag can validate each on its own.
+/// Anything after the value, including a trailing comma, is an
+/// [`ExtraData`](crate::ParseError::ExtraData) error.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{ParseError, parse_dcbor_entry};
+/// # use dcbor::prelude::*;
+/// let (key, value) = parse_dcbor_entry(r#""name": "Alice""#).unwrap();
+/// assert_eq!(key, CBOR::from("name"));
+/// assert_eq!(value, CBOR::from("Alice"));
+///
+/// assert_eq!(
+///     parse_dcbor_entry(r#""name" "Alice""#),
+///     Err(ParseError::ExpectedColon((7..14).into()))
+/// );
+/// ```
+pub fn parse_dcbor_entry(src: &str) -> Result<(CBOR, CBOR)> {
+    let (key, value) = parse_diag_entry(src)?;
+    Ok((key.to_cbor(), value.to_cbor()))
+}
+
+/// Parses a single map entry, written as `key: value`, from a string input
+/// into a pair of [`DiagValue`]s, which retain the source span of the key and
+/// the value.
+///
+/// Accepts exactly the same input as [`parse_dcbor_entry`], and reports the
+/// same errors.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::parse_diag_entry;
+/// let (key, value) = parse_diag_entry("'isA': [1, 2]").unwrap();
+/// assert_eq!(key.span(), 0..5);
+/// assert_eq!(value.span(), 7..13);
+/// ```
+pub fn parse_diag_entry(src: &str) -> Result<(DiagValue, DiagValue)> {
+    Parser::new(src).parse_entry()
+}
+
+/// Parses a dCBOR item from a string input into a [`DiagValue`], which retains
+/// the lexical form of every literal along with its source span.
+///
+/// Accepts exactly the same input as [`parse_dcbor_item`], and reports the
+/// same errors.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{DiagValue, parse_diag_value};
+/// let value = parse_diag_value("  1.50 ").unwrap();
+/// assert_eq!(value.text(), Some("1.50"));
+/// assert_eq!(value.span(), 2..6);
+/// ```
+pub fn parse_diag_value(src: &str) -> Result<DiagValue> {
+    Parser::new(src).parse_all()
+}
+
+/// Parses a dCBOR item from a string input, accepting the extra syntax
+/// enabled by `options`.
+///
+/// With the default options, this is the same as [`parse_dcbor_item`].
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{
+/// #     ParseError, ParseOptions, parse_dcbor_item_with_options,
+/// # };
+/// let src = r#"[!include("other.diag")]"#;
+/// let options = ParseOptions::default();
+/// let error = parse_dcbor_item_with_options(src, &options).unwrap_err();
+/// assert_eq!(error, ParseError::IncludeNotEnabled((1..23).into()));
+/// ```
+pub fn parse_dcbor_item_with_options(
+    src: &str,
+    options: &ParseOptions,
+) -> Result<CBOR> {
+    parse_diag_value_with_options(src, options).map(|value| value.to_cbor())
+}
+
+/// Parses a dCBOR item from a string input into a [`DiagValue`], accepting
+/// the extra syntax enabled by `options`.
+///
+/// With the default options, this is the same as [`parse_diag_value`].
+pub fn parse_diag_value_with_options(
+    src: &str,
+    options: &ParseOptions,
+) -> Result<DiagValue> {
+    parse_source(src, options, Vec::new(), 0)
 }
 
 //
 // === Private Functions ===
 //
 
-fn parse_item(lexer: &mut Lexer<'_, Token>) -> Result<CBOR> {
-    let token = expect_token(lexer)?;
-    parse_item_token(&token, lexer)
+/// Recursive-descent parser over the token stream of a single source string.
+pub(crate) struct Parser<'a> {
+    lexer: Lexer<'a, Token>,
+    options: &'a ParseOptions,
+    /// The names of the documents being included, outermost first.
+    includes: Vec<String>,
+    visitor: Option<&'a mut dyn ParseVisitor>,
+    depth: usize,
+    budget: Budget,
+    spent_items: usize,
+    spent_bytes: usize,
+    /// The end of the item that spent the budget, after which each open
+    /// group is closed with the items parsed so far.
+    pub(crate) truncated_at: Option<usize>,
+    /// The name and span of each token read, if they are being recorded.
+    tokens: Option<Vec<(&'static str, SourceSpan)>>,
 }
 
-fn expect_token(lexer: &mut Lexer<'_, Token>) -> Result<Token> {
-    let span = lexer.span();
-    match lexer.next() {
-        Some(token_or_err) => match token_or_err {
-            Ok(token) => Ok(token),
-            Err(e) => {
-                if e.is_default() {
-                    Err(Error::UnrecognizedToken(span))
-                } else {
-                    Err(e)
-                }
-            }
-        },
-        None => Err(Error::UnexpectedEndOfInput),
+impl<'a> Parser<'a> {
+    pub(crate) fn new(src: &'a str) -> Self {
+        Self {
+            lexer: Token::lexer(src),
+            options: &DEFAULT_OPTIONS,
+            includes: Vec::new(),
+            visitor: None,
+            depth: 0,
+            budget: Budget::default(),
+            spent_items: 0,
+            spent_bytes: 0,
+            truncated_at: None,
+            tokens: None,
+        }
     }
-}
 
-fn parse_item_token(
-    token: &Token,
-    lexer: &mut Lexer<'_, Token>,
-) -> Result<CBOR> {
-    // Handle embedded lexing errors in token payloads
-    if let Token::ByteStringHex(Err(e)) = token {
-        return Err(e.clone());
+    /// Records the name and span of each token as it is read, to be
+    /// returned by [`take_tokens`](Self::take_tokens).
+    pub(crate) fn with_tokens(mut self) -> Self {
+        self.tokens = Some(Vec::new());
+        self
     }
-    if let Token::ByteStringBase64(Err(e)) = token {
-        return Err(e.clone());
+
+    /// Returns the tokens read so far, if they are being recorded.
+    pub(crate) fn take_tokens(&mut self) -> Vec<(&'static str, SourceSpan)> {
+        self.tokens.take().unwrap_or_default()
     }
-    if let Token::DateLiteral(Err(e)) = token {
-        return Err(e.clone());
+
+    /// Accepts the extra syntax enabled by `options`.
+    pub(crate) fn with_options(mut self, options: &'a ParseOptions) -> Self {
+        self.options = options;
+        self
     }
-    if let Token::TagValue(Err(e)) = token {
-        return Err(e.clone());
+
+    /// Stops parsing once `budget` is spent.
+    pub(crate) fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
     }
-    if let Token::UR(Err(e)) = token {
-        return Err(e.clone());
+
+    /// Calls `visitor` as each item is parsed.
+    pub(crate) fn with_visitor(
+        mut self,
+        visitor: &'a mut dyn ParseVisitor,
+    ) -> Self {
+        self.visitor = Some(visitor);
+        self
     }
-    if let Token::KnownValueNumber(Err(e)) = token {
-        return Err(e.clone());
+
+    /// Parses the only item of the source.
+    pub(crate) fn parse_all(&mut self) -> Result<DiagValue> {
+        let value = self.parse_first()?;
+        if self.truncated_at.is_none() && self.lexer.next().is_some() {
+            return Err(Error::ExtraData(self.span()));
+        }
+        Ok(value)
     }
 
-    match token {
-        Token::Bool(b) => Ok((*b).into()),
-        Token::Null => Ok(CBOR::null()),
-        Token::ByteStringHex(Ok(bytes)) => Ok(CBOR::to_byte_string(bytes)),
-        Token::ByteStringBase64(Ok(bytes)) => Ok(CBOR::to_byte_string(bytes)),
-        Token::DateLiteral(Ok(date)) => Ok((*date).into()),
-        Token::Number(num) => Ok((*num).into()),
-        Token::NaN => Ok(f64::NAN.into()),
-        Token::Infinity => Ok(f64::INFINITY.into()),
-        Token::NegInfinity => Ok(f64::NEG_INFINITY.into()),
-        Token::String(s) => parse_string(s, lexer.span()),
-        Token::UR(Ok(ur)) => parse_ur(ur, lexer.span()),
-        Token::TagValue(Ok(tag_value)) => parse_number_tag(*tag_value, lexer),
-        Token::TagName(name) => parse_name_tag(name, lexer),
-        Token::KnownValueNumber(Ok(value)) => {
-            Ok(KnownValue::new(*value).into())
-        }
-        Token::KnownValueName(name) => {
-            if let Some(known_value) = known_value_for_name(name) {
-                Ok(known_value.into())
-            } else {
-                let span = lexer.span().start + 1..lexer.span().end - 1;
-                Err(Error::UnknownKnownValueName(name.clone(), span))
-            }
-        }
-        Token::Unit => Ok(KnownValue::new(0).into()),
-        Token::BracketOpen => parse_array(lexer),
-        Token::BraceOpen => parse_map(lexer),
-        _ => Err(Error::UnexpectedToken(
-            Box::new(token.clone()),
-            lexer.span(),
-        )),
+    /// Parses the source as a sequence of items separated by whitespace or
+    /// comments.
+    pub(crate) fn parse_sequence(&mut self) -> Result<Vec<DiagValue>> {
+        let mut values = Vec::new();
+        loop {
+            match self.expect_token() {
+                Ok(token) => values.push(self.parse_item_token(token)?),
+                Err(Error::UnexpectedEndOfInput) => return Ok(values),
+                Err(e) => return Err(e),
+            }
+        }
     }
-}
 
-fn parse_string(s: &str, span: Span) -> Result<CBOR> {
-    if s.starts_with('"') && s.ends_with('"') {
-        let s = &s[1..s.len() - 1];
-        Ok(s.into())
-    } else {
-        Err(Error::UnrecognizedToken(span))
+    /// Parses the source as a single `key: value` map entry.
+    pub(crate) fn parse_entry(&mut self) -> Result<(DiagValue, DiagValue)> {
+        let key = self.parse_first()?;
+        if !matches!(self.expect_token(), Ok(Token::Colon)) {
+            return Err(Error::ExpectedColon(self.span()));
+        }
+        let value = self.parse_item()?;
+        if self.lexer.next().is_some() {
+            return Err(Error::ExtraData(self.span()));
+        }
+        Ok((key, value))
     }
-}
 
-fn tag_for_name(name: &str) -> Option<Tag> {
-    with_tags!(|tags: &TagsStore| tags.tag_for_name(name))
-}
+    /// Parses the first item of the source, reporting an empty source as
+    /// `EmptyInput`.
+    pub(crate) fn parse_first(&mut self) -> Result<DiagValue> {
+        match self.expect_token() {
+            Ok(token) => self.parse_item_token(token),
+            Err(Error::UnexpectedEndOfInput) => Err(Error::EmptyInput),
+            Err(e) => Err(e),
+        }
+    }
 
-fn known_value_for_name(name: &str) -> Option<KnownValue> {
-    let binding = known_values::KNOWN_VALUES.get();
-    let known_values = binding.as_ref().unwrap();
-    known_values.known_value_named(name).cloned()
-}
+    /// Returns the offset of the first token after the items parsed so far,
+    /// or the length of the source if there is none.
+    pub(crate) fn consumed(&mut self) -> usize {
+        match self.lexer.next() {
+            Some(_) => self.span().start,
+            None => self.lexer.source().len(),
+        }
+    }
 
-fn parse_ur(ur: &UR, span: Span) -> Result<CBOR> {
-    let ur_type = ur.ur_type_str();
-    if let Some(tag) = tag_for_name(ur_type) {
-        Ok(CBOR::to_tagged_value(tag, ur.cbor()))
-    } else {
-        Err(Error::UnknownUrType(
-            ur_type.to_string(),
-            span.start + 3..span.start + 3 + ur_type.len(),
-        ))
+    /// Rejects a string or other quoted token that doesn't close within
+    /// `max` bytes of its start, before the lexer scans it, so an
+    /// unterminated string isn't scanned to the end of the source.
+    fn check_quoted_len(&self, max: usize) -> Result<()> {
+        let remainder = self.lexer.remainder();
+        let skipped = leading_trivia_len(remainder);
+        let token = &remainder.as_bytes()[skipped..];
+        // The prefix of a quoted token, such as `h` or `b64url`.
+        let prefix = token
+            .iter()
+            .take(max)
+            .take_while(|b| b.is_ascii_alphanumeric())
+            .count();
+        let Some(&quote @ (b'"' | b'\'')) = token.get(prefix) else {
+            return Ok(());
+        };
+        let mut index = prefix + 1;
+        while index < max.min(token.len()) {
+            match token[index] {
+                b'\\' if quote == b'"' => index += 2,
+                b if b == quote => return Ok(()),
+                _ => index += 1,
+            }
+        }
+        if index >= token.len() {
+            // The lexer reports a token that ends with the source.
+            return Ok(());
+        }
+        let start = self.lexer.span().end + skipped;
+        let source = self.lexer.source();
+        let mut end = (start + max + 1).min(source.len());
+        while !source.is_char_boundary(end) {
+            end += 1;
+        }
+        Err(Error::TokenTooLong(max, SourceSpan::new(start, end)))
     }
-}
 
-fn parse_number_tag(
-    tag_value: TagValue,
-    lexer: &mut Lexer<'_, Token>,
-) -> Result<CBOR> {
-    let item = parse_item(lexer)?;
-    match expect_token(lexer) {
-        Ok(Token::ParenthesisClose) => {
-            Ok(CBOR::to_tagged_value(tag_value, item))
+    fn parse_item(&mut self) -> Result<DiagValue> {
+        let token = self.expect_token()?;
+        self.parse_item_token(token)
+    }
+
+    fn expect_token(&mut self) -> Result<Token> {
+        let span = self.span();
+        if let Some(max) = self.options.token_len_limit() {
+            self.check_quoted_len(max)?;
+        }
+        let next = self.lexer.next();
+        if let Some(max) = self.options.token_len_limit() {
+            let scanned = self.span();
+            if next.is_some() && scanned.len() > max {
+                return Err(Error::TokenTooLong(max, scanned));
+            }
         }
-        Ok(_) => Err(Error::UnmatchedParentheses(lexer.span())),
-        Err(e) => {
-            if e == Error::UnexpectedEndOfInput {
-                return Err(Error::UnmatchedParentheses(lexer.span()));
+        let token = match next {
+            Some(token_or_err) => {
+                let spaced_tag = match token_or_err {
+                    Ok(Token::Number(_)) | Err(_) => self.spaced_tag(),
+                    Ok(_) => None,
+                };
+                match (spaced_tag, token_or_err) {
+                    (Some(token), _) | (None, Ok(token)) => Ok(token),
+                    (None, Err(e)) => {
+                        if let Some(index) = self.lexer.slice().find('\0') {
+                            let start = self.span().start + index;
+                            let span = SourceSpan::new(start, start + 1);
+                            Err(Error::NulCharacter(span))
+                        } else if e.is_default() {
+                            Err(Error::UnrecognizedToken(span))
+                        } else {
+                            Err(e)
+                        }
+                    }
+                }
             }
-            Err(e)
+            None => Err(Error::UnexpectedEndOfInput),
+        };
+        if let (Ok(token), Some(tokens)) = (&token, self.tokens.as_mut()) {
+            tokens.push((token.name(), self.lexer.span().into()));
         }
+        token
     }
-}
 
-fn parse_name_tag(name: &str, lexer: &mut Lexer<'_, Token>) -> Result<CBOR> {
-    let span = lexer.span().start..lexer.span().end - 1;
-    let item = parse_item(lexer)?;
-    match expect_token(lexer)? {
-        Token::ParenthesisClose => {
-            if let Some(tag) = tag_for_name(name) {
-                Ok(CBOR::to_tagged_value(tag, item))
-            } else {
-                Err(Error::UnknownTagName(name.to_string(), span))
+    /// Recognizes the current token as a tag number or name if it is
+    /// followed by whitespace and an opening parenthesis, as in `date (1)`,
+    /// which the lexer only recognizes without the whitespace. The token is
+    /// extended to the parenthesis.
+    fn spaced_tag(&mut self) -> Option<Token> {
+        let slice = self.lexer.slice();
+        let remainder = self.lexer.remainder();
+        let spacing = remainder.len()
+            - remainder
+                .trim_start_matches([' ', '\t', '\r', '\n', '\x0c'])
+                .len();
+        if spacing == 0 || !remainder[spacing..].starts_with('(') {
+            return None;
+        }
+        let mut chars = slice.chars();
+        let token = match chars.next()? {
+            c if c.is_ascii_digit()
+                && slice.bytes().all(|b| b.is_ascii_alphanumeric()) =>
+            {
+                let span = self.span();
+                Token::TagValue(parse_integer(slice).map_err(|_| {
+                    Error::InvalidTagValue(slice.to_string(), span)
+                }))
+            }
+            c if (c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| {
+                    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+                }) =>
+            {
+                Token::TagName(slice.to_string())
             }
+            _ => return None,
+        };
+        self.lexer.bump(spacing + 1);
+        Some(token)
+    }
+
+    fn span(&self) -> SourceSpan { self.lexer.span().into() }
+
+    fn text(&self) -> String { self.lexer.slice().to_string() }
+
+    fn parse_item_token(&mut self, token: Token) -> Result<DiagValue> {
+        let value = self.parse_value(token)?;
+        self.check_type_assertion(&value)?;
+        let depth = self.depth;
+        if let Some(visitor) = self.visitor.as_mut() {
+            visitor.item(&value, depth).into_result(value.span())?;
         }
-        _ => Err(Error::UnmatchedParentheses(lexer.span())),
+        self.spend(&value);
+        Ok(value)
     }
-}
 
-fn parse_array(lexer: &mut Lexer<'_, Token>) -> Result<CBOR> {
-    let mut items = Vec::new();
-    let mut awaits_comma = false;
-    let mut awaits_item = false;
+    /// Reads the type assertion that follows `value`, if any, as in
+    /// `42 :: uint`, and checks that `value` matches it. The assertion isn't
+    /// part of the item.
+    fn check_type_assertion(&mut self, value: &DiagValue) -> Result<()> {
+        let next = self
+            .lexer
+            .remainder()
+            .trim_start_matches([' ', '\t', '\r', '\n', '\x0c']);
+        // Only an assertion or a comment, which the lexer skips, can start
+        // with these, so other items aren't scanned twice.
+        if !next.starts_with("::") && !next.starts_with(['/', '#']) {
+            return Ok(());
+        }
+        let Some(Ok(Token::TypeAssertion(_))) = self.lexer.clone().next()
+        else {
+            return Ok(());
+        };
+        let text = match self.expect_token()? {
+            Token::TypeAssertion(text) => text,
+            token => {
+                let span = self.span();
+                return Err(Error::UnexpectedToken(Box::new(token), span));
+            }
+        };
+        let span = self.span();
+        let Some(assertion) = TypeAssertion::parse(&text) else {
+            return Err(Error::InvalidTypeAssertion(text, span));
+        };
+        if !assertion.matches(&value.to_cbor()) {
+            return Err(Error::TypeMismatch(text, value.span()));
+        }
+        Ok(())
+    }
 
-    loop {
-        match expect_token(lexer)? {
-            Token::Bool(b) if !awaits_comma => {
-                items.push(b.into());
-                awaits_item = false;
-            }
-            Token::Null if !awaits_comma => {
-                items.push(CBOR::null());
-                awaits_item = false;
-            }
-            Token::ByteStringHex(Ok(bytes)) if !awaits_comma => {
-                items.push(CBOR::to_byte_string(bytes));
-                awaits_item = false;
-            }
-            Token::ByteStringBase64(Ok(bytes)) if !awaits_comma => {
-                items.push(CBOR::to_byte_string(bytes));
-                awaits_item = false;
-            }
-            Token::DateLiteral(Ok(date)) if !awaits_comma => {
-                items.push(date.into());
-                awaits_item = false;
-            }
-            Token::Number(num) if !awaits_comma => {
-                items.push(num.into());
-                awaits_item = false;
-            }
-            Token::NaN if !awaits_comma => {
-                items.push(f64::NAN.into());
-                awaits_item = false;
-            }
-            Token::Infinity if !awaits_comma => {
-                items.push(f64::INFINITY.into());
-                awaits_item = false;
-            }
-            Token::NegInfinity if !awaits_comma => {
-                items.push(f64::NEG_INFINITY.into());
-                awaits_item = false;
-            }
-            Token::String(s) if !awaits_comma => {
-                items.push(parse_string(&s, lexer.span())?);
-                awaits_item = false;
-            }
-            Token::UR(Ok(ur)) if !awaits_comma => {
-                items.push(parse_ur(&ur, lexer.span())?);
-                awaits_item = false;
-            }
-            Token::TagValue(Ok(tag_value)) if !awaits_comma => {
-                items.push(parse_number_tag(tag_value, lexer)?);
-                awaits_item = false;
-            }
-            Token::TagName(name) if !awaits_comma => {
-                items.push(parse_name_tag(&name, lexer)?);
-                awaits_item = false;
-            }
-            Token::KnownValueNumber(Ok(value)) if !awaits_comma => {
-                items.push(KnownValue::new(value).into());
-                awaits_item = false;
-            }
-            Token::KnownValueName(name) if !awaits_comma => {
-                if let Some(known_value) = known_value_for_name(&name) {
-                    items.push(known_value.into());
-                } else {
-                    return Err(Error::UnknownKnownValueName(
-                        name,
-                        lexer.span(),
-                    ));
+    /// Accounts for a completed item, and notes whether the budget is spent.
+    fn spend(&mut self, value: &DiagValue) {
+        self.spent_items += 1;
+        self.spent_bytes += encoded_len(value);
+        if self.truncated_at.is_none()
+            && self.budget.is_spent(self.spent_items, self.spent_bytes)
+        {
+            self.truncated_at = Some(value.span().end);
+        }
+    }
+
+    /// Calls the visitor, if any, as a group begins, and enters it, unless
+    /// it is nested too deeply.
+    fn begin_group(&mut self, kind: GroupKind) -> Result<()> {
+        let span = self.span();
+        let depth = self.depth;
+        let max = self.options.depth_limit();
+        if depth >= max {
+            return Err(Error::NestingTooDeep(max, span));
+        }
+        if let Some(visitor) = self.visitor.as_mut() {
+            visitor.begin_group(kind, span, depth).into_result(span)?;
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn end_group(&mut self) { self.depth -= 1; }
+
+    fn parse_value(&mut self, token: Token) -> Result<DiagValue> {
+        match token {
+            Token::BracketOpen => self.parse_array(),
+            Token::EmbeddedOpen => self.parse_embedded(),
+            Token::BraceOpen => self.parse_map(),
+            Token::TagValue(tag_value) => self.parse_number_tag(tag_value?),
+            Token::TagName(name) => self.parse_name_tag(name),
+            token => self.parse_atom(token),
+        }
+    }
+
+    /// Parses an item that isn't a group. This is kept out of
+    /// [`parse_value`](Self::parse_value), which is on the stack once for
+    /// each level of nesting, as its frame is large.
+    #[inline(never)]
+    fn parse_atom(&mut self, token: Token) -> Result<DiagValue> {
+        let span = self.span();
+        match token {
+            Token::Bool(value) => Ok(DiagValue::Bool { value, span }),
+            Token::Null => Ok(DiagValue::Null { span }),
+            Token::ByteStringHex(bytes) => {
+                self.byte_string(ByteStringEncoding::Hex, bytes?)
+            }
+            Token::ByteStringBase64(bytes) => {
+                self.byte_string(ByteStringEncoding::Base64, bytes?)
+            }
+            Token::ByteStringBase64Url(bytes) => {
+                self.byte_string(ByteStringEncoding::Base64Url, bytes?)
+            }
+            Token::ByteStringBase45(bytes) => {
+                self.byte_string(ByteStringEncoding::Base45, bytes?)
+            }
+            Token::ByteStringByteWords(bytes) => {
+                self.byte_string(ByteStringEncoding::ByteWords, bytes?)
+            }
+            Token::DateLiteral(date) => {
+                let enabled = self.options.allows_date_literals();
+                check_enabled(enabled, "Date literals", span)?;
+                Ok(DiagValue::Date { text: self.text(), date: date?, span })
+            }
+            Token::DecimalFraction(fraction) => {
+                let (exponent, mantissa) = fraction?;
+                let value = DiagValue::DecimalFraction {
+                    text: self.text(),
+                    exponent,
+                    mantissa,
+                    span,
+                };
+                let cbor = value.to_cbor();
+                if let CBORCase::Tagged(tag, content) = cbor.as_case() {
+                    self.check_content(tag.value(), content, span)?;
                 }
-                awaits_item = false;
+                Ok(value)
             }
-            Token::BracketOpen if !awaits_comma => {
-                items.push(parse_array(lexer)?);
-                awaits_item = false;
+            Token::Rational(fraction) => {
+                let (numerator, denominator) = fraction?;
+                let value = DiagValue::Rational {
+                    text: self.text(),
+                    numerator,
+                    denominator,
+                    span,
+                };
+                let cbor = value.to_cbor();
+                if let CBORCase::Tagged(tag, content) = cbor.as_case() {
+                    self.check_content(tag.value(), content, span)?;
+                }
+                Ok(value)
             }
-            Token::BraceOpen if !awaits_comma => {
-                items.push(parse_map(lexer)?);
-                awaits_item = false;
+            Token::Number(value) => {
+                Ok(DiagValue::Number { text: self.text(), value, span })
             }
-            Token::Comma if awaits_comma => {
-                awaits_item = true;
+            Token::IndicatedNumber((value, indicator)) => {
+                let text = self.text();
+                match self.options.encoding_indicator_policy() {
+                    EncodingIndicators::Reject => {
+                        check_enabled(false, "Encoding indicators", span)?;
+                    }
+                    EncodingIndicators::Ignore => {}
+                    EncodingIndicators::Validate => {
+                        let cbor = CBOR::from(value.clone());
+                        if encoding_indicator(&cbor) != indicator {
+                            let indicator = format!("_{indicator}");
+                            return Err(Error::EncodingIndicatorMismatch(
+                                indicator, span,
+                            ));
+                        }
+                    }
+                }
+                Ok(DiagValue::Number { text, value, span })
             }
-            Token::BracketClose if !awaits_item => {
-                return Ok(items.into());
+            Token::LenientNumber(value) => {
+                let enabled = self.options.allows_lenient_floats();
+                check_enabled(enabled, "Lenient floats", span)?;
+                self.number(value)
             }
-            token => {
-                if awaits_comma {
-                    return Err(Error::ExpectedComma(lexer.span()));
+            Token::NaN => self.number(f64::NAN),
+            Token::Infinity => self.number(f64::INFINITY),
+            Token::NegInfinity => self.number(f64::NEG_INFINITY),
+            Token::String(s) => {
+                let policy = self.options.control_character_policy();
+                let value = parse_string(&s, span, policy)?;
+                Ok(DiagValue::Text { text: s, value, span })
+            }
+            Token::UR(ur) => {
+                check_enabled(!self.options.is_data_only(), "URs", span)?;
+                let ur = ur?;
+                let tag = tag_for_ur(&ur, span)?;
+                self.check_content(tag.value(), &ur.cbor(), span)?;
+                Ok(DiagValue::Ur { text: self.text(), ur, tag, span })
+            }
+            Token::Include | Token::ExternalBytes
+                if self.options.is_data_only() =>
+            {
+                Err(Error::SyntaxDisabled("Directives".to_string(), span))
+            }
+            Token::Raw => self.parse_raw(),
+            Token::Include => self.parse_include(),
+            Token::ExternalBytes => self.parse_external_bytes(),
+            Token::KnownValueNumber(value) => {
+                self.check_known_value_literal(span)?;
+                let value = value?;
+                self.check_known_value(value, span)?;
+                Ok(DiagValue::KnownValue {
+                    text: self.text(),
+                    value: KnownValue::new(value),
+                    span,
+                })
+            }
+            Token::KnownValueName(name) => {
+                self.check_known_value_literal(span)?;
+                if let Some(value) = self.known_value_named(&name, span)? {
+                    self.check_known_value(value.value(), span)?;
+                    let text = self.text();
+                    Ok(DiagValue::KnownValue { text, value, span })
+                } else {
+                    let span = SourceSpan::new(span.start + 1, span.end - 1);
+                    Err(Error::UnknownKnownValueName(name, span))
                 }
-                return Err(Error::UnexpectedToken(
-                    Box::new(token),
-                    lexer.span(),
-                ));
             }
+            Token::Unit => {
+                self.check_known_value_literal(span)?;
+                self.check_known_value(0, span)?;
+                Ok(DiagValue::KnownValue {
+                    text: self.text(),
+                    value: KnownValue::new(0),
+                    span,
+                })
+            }
+            _ => Err(Error::UnexpectedToken(Box::new(token), span)),
         }
-        awaits_comma = !awaits_item;
     }
-}
 
-fn parse_map(lexer: &mut Lexer<'_, Token>) -> Result<CBOR> {
-    let mut map = Map::new();
-    let mut awaits_comma = false;
-    let mut awaits_key = false;
+    fn number(&self, value: f64) -> Result<DiagValue> {
+        let span = self.span();
+        let value = match self.options.nan_policy() {
+            _ if !value.is_nan() => value,
+            NaNHandling::Canonicalize => f64::NAN,
+            NaNHandling::Reject => {
+                return Err(Error::SyntaxDisabled("NaN values".into(), span));
+            }
+            NaNHandling::Warn => value,
+        };
+        let value = NumberValue::Float(value);
+        Ok(DiagValue::Number { text: self.text(), value, span })
+    }
 
-    loop {
-        let token = match expect_token(lexer) {
-            Ok(tok) => tok,
+    fn parse_number_tag(&mut self, tag_value: TagValue) -> Result<DiagValue> {
+        let opening = self.span();
+        let text = self.text();
+        let tag_text = text[..text.len() - 1].trim_end().to_string();
+        self.check_tag_spacing(tag_text.len())?;
+        self.begin_group(GroupKind::Tagged)?;
+        let item = self.parse_item()?;
+        self.end_group();
+        if self.truncated_at.is_some() {
+            return Ok(DiagValue::Tagged {
+                tag: tag_value.into(),
+                tag_text,
+                span: SourceSpan::new(opening.start, item.span().end),
+                item: Box::new(item),
+            });
+        }
+        self.close_tag(opening)?;
+        self.check_tagged(DiagValue::Tagged {
+            tag: tag_value.into(),
+            tag_text,
+            item: Box::new(item),
+            span: SourceSpan::new(opening.start, self.span().end),
+        })
+    }
+
+    /// Consumes the closing parenthesis of a tagged item whose tag and
+    /// opening parenthesis are at `opening`.
+    ///
+    /// A parenthesis left open at the end of the input is reported at
+    /// `opening`, so that in a chain such as `a(b(1)` the error points at the
+    /// tag that wasn't closed rather than at the end of the input.
+    fn close_tag(&mut self, opening: SourceSpan) -> Result<()> {
+        match self.expect_token() {
+            Ok(Token::ParenthesisClose) => Ok(()),
+            Ok(_) => Err(Error::UnmatchedParentheses(self.span())),
             Err(Error::UnexpectedEndOfInput) => {
-                return Err(Error::UnmatchedBraces(lexer.span()));
+                Err(Error::UnmatchedParentheses(opening))
             }
-            Err(e) => {
-                return Err(e);
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Rejects a known value literal if the options disable them.
+    fn check_known_value_literal(&self, span: SourceSpan) -> Result<()> {
+        let enabled = self.options.allows_known_value_literals();
+        check_enabled(enabled, "Known value literals", span)
+    }
+
+    /// Rejects a known value that the known value policy of the options
+    /// doesn't allow.
+    fn check_known_value(&self, value: u64, span: SourceSpan) -> Result<()> {
+        if self.options.allows_known_value(value) {
+            return Ok(());
+        }
+        let info = self.options.known_value_info(value).to_string();
+        Err(Error::DisallowedKnownValue(info, span))
+    }
+
+    /// Checks a tagged item against the known value policy and tag
+    /// validators of the options.
+    fn check_tagged(&self, tagged: DiagValue) -> Result<DiagValue> {
+        let DiagValue::Tagged { tag, item, span, .. } = &tagged else {
+            return Ok(tagged);
+        };
+        if tag.value() == KNOWN_VALUE_TAG && !self.options.is_data_only() {
+            if let DiagValue::Number { value, .. } = item.as_ref() {
+                if let Some(value) = value.to_u64() {
+                    self.check_known_value(value, *span)?;
+                }
             }
+        }
+        self.check_content(tag.value(), &item.to_cbor(), item.span())?;
+        Ok(tagged)
+    }
+
+    /// Checks the content of an item tagged with `tag` against the envelope
+    /// and COSE checks and tag validators of the options, reporting a failure
+    /// at `span`.
+    fn check_content(
+        &self,
+        tag: TagValue,
+        content: &CBOR,
+        span: SourceSpan,
+    ) -> Result<()> {
+        let invalid = |message: String| {
+            Error::InvalidTagContent(format!("{tag}: {message}"), span)
         };
-        match token {
-            Token::BraceClose if !awaits_key => {
-                return Ok(map.into());
+        if tag == ENVELOPE_TAG && self.options.validates_envelopes() {
+            check_envelope_shape(content).map_err(invalid)?;
+        }
+        if self.options.enables_cose() {
+            check_cose_structure(tag, content).map_err(invalid)?;
+        }
+        if let Some(validators) = self.options.validators() {
+            validators.validate(tag, content).map_err(invalid)?;
+        }
+        Ok(())
+    }
+
+    /// Rejects whitespace between a tag number or name of `tag_len` bytes and
+    /// its opening parenthesis, the current token, unless the options allow
+    /// it.
+    fn check_tag_spacing(&self, tag_len: usize) -> Result<()> {
+        let span = self.span();
+        if span.len() == tag_len + 1 || self.options.allows_spaced_tags() {
+            return Ok(());
+        }
+        let spacing = SourceSpan::new(span.start + tag_len, span.end - 1);
+        Err(Error::SpaceBeforeParenthesis(spacing))
+    }
+
+    /// Returns the known value named `name` in the store of the options, or
+    /// in the global registry if the options have none.
+    fn known_value_named(
+        &self,
+        name: &str,
+        span: SourceSpan,
+    ) -> Result<Option<KnownValue>> {
+        let named = |store: &KnownValuesStore| {
+            store.known_value_named(name).cloned()
+        };
+        match self.options.known_value_store() {
+            Some(store) => Ok(named(store)),
+            None => with_known_values(named)
+                .ok_or(Error::KnownValuesRegistryUnavailable(span)),
+        }
+    }
+
+    /// Returns the tag named `name` in the tags registry, or the COSE tag
+    /// named `name` if the options enable them.
+    fn tag_named(&self, name: &str, span: SourceSpan) -> Result<Option<Tag>> {
+        Ok(tag_for_name(name, span)?.or_else(|| {
+            self.options.enables_cose().then(|| cose_tag_for_name(name))?
+        }))
+    }
+
+    /// Parses an item tagged with a tag name. The name is resolved before the
+    /// content is parsed, so each tag of a chain such as `a(b(1))` is
+    /// resolved on its own and an unknown name is reported before any error
+    /// within its content.
+    fn parse_name_tag(&mut self, name: String) -> Result<DiagValue> {
+        let opening = self.span();
+        let name_span =
+            SourceSpan::new(opening.start, opening.start + name.len());
+        check_enabled(!self.options.is_data_only(), "Tag names", name_span)?;
+        self.check_tag_spacing(name.len())?;
+        let Some(tag) = self.tag_named(&name, name_span)? else {
+            return Err(Error::UnknownTagName(name, name_span));
+        };
+        self.begin_group(GroupKind::Tagged)?;
+        let item = self.parse_item()?;
+        self.end_group();
+        if self.truncated_at.is_some() {
+            return Ok(DiagValue::Tagged {
+                tag,
+                tag_text: name,
+                span: name_span.merge(item.span()),
+                item: Box::new(item),
+            });
+        }
+        self.close_tag(opening)?;
+        self.check_tagged(DiagValue::Tagged {
+            tag,
+            tag_text: name,
+            item: Box::new(item),
+            span: name_span.merge(self.span()),
+        })
+    }
+
+    /// Parses a tagged item written as `raw(1234, h'820102')`, whose content
+    /// must be the deterministic encoding of a single dCBOR item.
+    fn parse_raw(&mut self) -> Result<DiagValue> {
+        let start = self.span().start;
+        let tag_value = match self.expect_token()? {
+            Token::Number(NumberValue::Unsigned(value)) => value,
+            token => {
+                let span = self.span();
+                return Err(Error::UnexpectedToken(Box::new(token), span));
             }
-            Token::Comma if awaits_comma => {
-                awaits_key = true;
+        };
+        if !matches!(self.expect_token()?, Token::Comma) {
+            return Err(Error::ExpectedComma(self.span()));
+        }
+        let token = self.expect_token()?;
+        let bytes_start = self.span().start;
+        let mut bytes = match quoted_bytes(token) {
+            Ok(bytes) => bytes?,
+            Err(token) => {
+                let span = self.span();
+                return Err(Error::UnexpectedToken(Box::new(token), span));
+            }
+        };
+        self.append_byte_strings(&mut bytes)?;
+        let bytes_span = SourceSpan::new(bytes_start, self.span().end);
+        let content = CBOR::try_from_data(bytes).map_err(|e| {
+            Error::InvalidRawContent(e.to_string(), bytes_span)
+        })?;
+        match self.expect_token() {
+            Ok(Token::ParenthesisClose) => {}
+            Ok(_) | Err(Error::UnexpectedEndOfInput) => {
+                return Err(Error::UnmatchedParentheses(self.span()));
+            }
+            Err(e) => return Err(e),
+        }
+        let span = SourceSpan::new(start, self.span().end);
+        if tag_value == KNOWN_VALUE_TAG && !self.options.is_data_only() {
+            if let CBORCase::Unsigned(value) = content.as_case() {
+                self.check_known_value(*value, span)?;
+            }
+        }
+        self.check_content(tag_value, &content, bytes_span)?;
+        Ok(DiagValue::RawTagged {
+            text: self.lexer.source()[span.range()].to_string(),
+            tag: tag_value.into(),
+            content,
+            span,
+        })
+    }
+
+    /// Returns a byte string literal, whose first segment has just been read,
+    /// with the bytes of the segments right after it appended.
+    fn byte_string(
+        &mut self,
+        encoding: ByteStringEncoding,
+        mut bytes: Vec<u8>,
+    ) -> Result<DiagValue> {
+        let start = self.span().start;
+        self.append_byte_strings(&mut bytes)?;
+        let span = SourceSpan::new(start, self.span().end);
+        Ok(DiagValue::ByteString {
+            text: self.lexer.source()[span.range()].to_string(),
+            encoding,
+            bytes,
+            span,
+        })
+    }
+
+    /// Reads the quoted byte strings that follow, as in `h'0102' h'0304'`,
+    /// and appends their bytes to `bytes`, so that a long byte string can be
+    /// split across lines.
+    fn append_byte_strings(&mut self, bytes: &mut Vec<u8>) -> Result<()> {
+        loop {
+            let Some(Ok(next)) = self.lexer.clone().next() else {
+                return Ok(());
+            };
+            if quoted_bytes(next).is_err() {
+                return Ok(());
+            }
+            if let Ok(more) = quoted_bytes(self.expect_token()?) {
+                bytes.extend(more?);
+            }
+        }
+    }
+
+    /// Parses the string argument and closing parenthesis of a directive,
+    /// and returns the argument and the span of the whole directive.
+    fn parse_directive_argument(&mut self) -> Result<(String, SourceSpan)> {
+        let start = self.span().start;
+        let argument = match self.expect_token()? {
+            Token::String(s) => {
+                let policy = self.options.control_character_policy();
+                parse_string(&s, self.span(), policy)?
             }
-            _ => {
-                if awaits_comma {
-                    return Err(Error::ExpectedComma(lexer.span()));
+            token => {
+                let span = self.span();
+                return Err(Error::UnexpectedToken(Box::new(token), span));
+            }
+        };
+        match self.expect_token() {
+            Ok(Token::ParenthesisClose) => {}
+            Ok(_) | Err(Error::UnexpectedEndOfInput) => {
+                return Err(Error::UnmatchedParentheses(self.span()));
+            }
+            Err(e) => return Err(e),
+        }
+        Ok((argument, SourceSpan::new(start, self.span().end)))
+    }
+
+    fn parse_include(&mut self) -> Result<DiagValue> {
+        let (name, span) = self.parse_directive_argument()?;
+        let Some(loader) = self.options.loader() else {
+            return Err(Error::IncludeNotEnabled(span));
+        };
+        let failed = |message: String| {
+            Error::IncludeFailed(format!("'{name}': {message}"), span)
+        };
+        if self.includes.contains(&name) {
+            return Err(failed("the document includes itself".to_string()));
+        }
+        if self.includes.len() >= MAX_INCLUDE_DEPTH {
+            return Err(failed("includes are nested too deeply".to_string()));
+        }
+        let src = loader(&name).map_err(failed)?;
+        let mut includes = self.includes.clone();
+        includes.push(name.clone());
+        let item = parse_source(&src, self.options, includes, self.depth)
+            .map_err(|e| match e.span() {
+                Some(error_span) => {
+                    let (position, _) = error_span.line_columns(&src);
+                    failed(format!(
+                        "{}:{}: {e}",
+                        position.line, position.column
+                    ))
                 }
-                let key = parse_item_token(&token, lexer)?;
-                let key_span = lexer.span();
+                None => failed(e.to_string()),
+            })?;
+        Ok(DiagValue::Included { name, item: Box::new(item), span })
+    }
 
-                // Check for duplicate key
-                if map.contains_key(key.clone()) {
-                    return Err(Error::DuplicateMapKey(key_span));
+    fn parse_external_bytes(&mut self) -> Result<DiagValue> {
+        let (digest, span) = self.parse_directive_argument()?;
+        let Some(loader) = self.options.byte_loader() else {
+            return Err(Error::ExternalBytesNotEnabled(span));
+        };
+        let failed = |message: String| {
+            Error::ExternalBytesFailed(format!("'{digest}': {message}"), span)
+        };
+        let bytes = loader(&digest).map_err(failed)?;
+        if hex::encode(bc_crypto::sha256(&bytes)) != digest {
+            return Err(failed("the bytes don't match the digest".to_string()));
+        }
+        Ok(DiagValue::ByteString {
+            text: self.lexer.source()[span.start..span.end].to_string(),
+            encoding: ByteStringEncoding::External,
+            bytes,
+            span,
+        })
+    }
+
+    fn parse_array(&mut self) -> Result<DiagValue> {
+        let start = self.span().start;
+        self.begin_group(GroupKind::Array)?;
+        let mut items = Vec::new();
+        let mut awaits_comma = false;
+        let mut awaits_item = false;
+
+        loop {
+            match self.expect_token()? {
+                Token::Comma if awaits_comma => {
+                    awaits_item = true;
                 }
+                Token::BracketClose if !awaits_item => {
+                    self.end_group();
+                    let span = SourceSpan::new(start, self.span().end);
+                    return Ok(DiagValue::Array { items, span });
+                }
+                token => {
+                    if awaits_comma {
+                        return Err(Error::ExpectedComma(self.span()));
+                    }
+                    items.push(self.parse_item_token(token)?);
+                    awaits_item = false;
+                    if self.truncated_at.is_some() {
+                        self.end_group();
+                        let span = SourceSpan::new(start, self.span().end);
+                        return Ok(DiagValue::Array { items, span });
+                    }
+                }
+            }
+            awaits_comma = !awaits_item;
+        }
+    }
+
+    /// Parses the items of embedded CBOR, written as `<<item, ...>>`, into a
+    /// byte string holding their concatenated encodings, which is a CBOR
+    /// sequence.
+    fn parse_embedded(&mut self) -> Result<DiagValue> {
+        let start = self.span().start;
+        self.begin_group(GroupKind::Embedded)?;
+        let mut bytes = Vec::new();
+        let mut awaits_comma = false;
+        let mut awaits_item = false;
+
+        loop {
+            match self.expect_token()? {
+                Token::Comma if awaits_comma => {
+                    awaits_item = true;
+                }
+                Token::EmbeddedClose if !awaits_item => break,
+                token => {
+                    if awaits_comma {
+                        return Err(Error::ExpectedComma(self.span()));
+                    }
+                    let item = self.parse_item_token(token)?;
+                    bytes.extend(item.to_cbor().to_cbor_data());
+                    awaits_item = false;
+                    if self.truncated_at.is_some() {
+                        break;
+                    }
+                }
+            }
+            awaits_comma = !awaits_item;
+        }
+        self.end_group();
+        let span = SourceSpan::new(start, self.span().end);
+        Ok(DiagValue::ByteString {
+            text: self.lexer.source()[span.range()].to_string(),
+            encoding: ByteStringEncoding::Embedded,
+            bytes,
+            span,
+        })
+    }
+
+    fn parse_map(&mut self) -> Result<DiagValue> {
+        let start = self.span().start;
+        self.begin_group(GroupKind::Map)?;
+        let mut entries: Vec<(DiagValue, DiagValue)> = Vec::new();
+        // The index in `entries` of each key, by its encoding.
+        let mut keys: HashMap<Vec<u8>, usize> = HashMap::new();
+        let mut awaits_comma = false;
+        let mut awaits_key = false;
+
+        loop {
+            let token = match self.expect_token() {
+                Ok(tok) => tok,
+                Err(Error::UnexpectedEndOfInput) => {
+                    return Err(Error::UnmatchedBraces(self.span()));
+                }
+                Err(e) => {
+                    return Err(e);
+                }
+            };
+            match token {
+                Token::BraceClose if !awaits_key => {
+                    self.end_group();
+                    let span = SourceSpan::new(start, self.span().end);
+                    return Ok(DiagValue::Map { entries, span });
+                }
+                Token::Comma if awaits_comma => {
+                    awaits_key = true;
+                }
+                _ => {
+                    if awaits_comma {
+                        return Err(Error::ExpectedComma(self.span()));
+                    }
+                    let key = self.parse_item_token(token)?;
+                    if self.truncated_at.is_some() {
+                        self.end_group();
+                        let end = entries
+                            .last()
+                            .map_or(start + 1, |(_, value)| value.span().end);
+                        let span = SourceSpan::new(start, end);
+                        return Ok(DiagValue::Map { entries, span });
+                    }
 
-                if let Ok(Token::Colon) = expect_token(lexer) {
-                    let value = match parse_item(lexer) {
-                        Err(Error::UnexpectedToken(token, span))
-                            if *token == Token::BraceClose =>
-                        {
-                            return Err(Error::ExpectedMapKey(span));
+                    // Check for duplicate key
+                    let key_cbor = key.to_cbor();
+                    let policy = self.options.duplicate_key_policy();
+                    let data = key_cbor.to_cbor_data();
+                    let duplicate = keys.get(&data).copied();
+                    let is_duplicate = duplicate.is_some();
+                    if is_duplicate && policy == DuplicateKeys::Reject {
+                        return Err(Error::DuplicateMapKey(key.span()));
+                    }
+                    if self.options.requires_integer_map_keys()
+                        && !matches!(
+                            key_cbor.as_case(),
+                            CBORCase::Unsigned(_) | CBORCase::Negative(_)
+                        )
+                    {
+                        let type_name = type_name(&key).to_string();
+                        return Err(Error::NonIntegerMapKey(
+                            type_name,
+                            key.span(),
+                        ));
+                    }
+
+                    if let Ok(Token::Colon) = self.expect_token() {
+                        let value = match self.parse_item() {
+                            Err(Error::UnexpectedToken(token, span))
+                                if *token == Token::BraceClose =>
+                            {
+                                return Err(Error::ExpectedMapKey(span));
+                            }
+                            other => other?,
+                        };
+                        match duplicate {
+                            None => {
+                                keys.insert(data, entries.len());
+                                entries.push((key, value));
+                            }
+                            Some(index)
+                                if policy == DuplicateKeys::KeepLast =>
+                            {
+                                entries[index] = (key, value);
+                            }
+                            Some(_) => {}
                         }
-                        other => other?,
-                    };
-                    map.insert(key, value);
-                    awaits_key = false;
-                } else {
-                    return Err(Error::ExpectedColon(lexer.span()));
+                        awaits_key = false;
+                        if self.truncated_at.is_some() {
+                            self.end_group();
+                            let span = SourceSpan::new(start, self.span().end);
+                            return Ok(DiagValue::Map { entries, span });
+                        }
+                    } else {
+                        return Err(Error::ExpectedColon(self.span()));
+                    }
+                }
+            }
+            awaits_comma = !awaits_key;
+        }
+    }
+}
+
+/// Parses the only item in `src`, a document nested within the includes
+/// `includes` and within `depth` groups, substituting variables first if
+/// `options` enables them.
+fn parse_source(
+    src: &str,
+    options: &ParseOptions,
+    includes: Vec<String>,
+    depth: usize,
+) -> Result<DiagValue> {
+    let Some(lookup) = options.lookup() else {
+        let mut parser = Parser::new(src).with_options(options);
+        parser.includes = includes;
+        parser.depth = depth;
+        return parser.parse_all();
+    };
+    let substituted = Substituted::new(src, lookup)?;
+    let mut parser = Parser::new(&substituted.text).with_options(options);
+    parser.includes = includes;
+    parser.depth = depth;
+    match parser.parse_all() {
+        Ok(mut value) => {
+            substituted.remap_value(&mut value);
+            Ok(value)
+        }
+        Err(error) => Err(substituted.remap_error(error)),
+    }
+}
+
+/// Returns the character of the EDN encoding indicator that matches the head
+/// of the deterministic encoding of `cbor`.
+fn encoding_indicator(cbor: &CBOR) -> char {
+    match cbor.to_cbor_data()[0] & 0x1f {
+        24 => '0',
+        25 => '1',
+        26 => '2',
+        27 => '3',
+        _ => 'i',
+    }
+}
+
+/// Returns the bytes of a quoted byte string token, such as `h'00'`, or the
+/// token if it isn't one.
+fn quoted_bytes(token: Token) -> std::result::Result<Result<Vec<u8>>, Token> {
+    match token {
+        Token::ByteStringHex(bytes)
+        | Token::ByteStringBase64(bytes)
+        | Token::ByteStringBase64Url(bytes)
+        | Token::ByteStringBase45(bytes)
+        | Token::ByteStringByteWords(bytes) => Ok(bytes),
+        token => Err(token),
+    }
+}
+
+/// Rejects syntax described by `syntax`, such as "Date literals", at `span`
+/// unless it is `enabled`.
+fn check_enabled(enabled: bool, syntax: &str, span: SourceSpan) -> Result<()> {
+    if enabled {
+        Ok(())
+    } else {
+        Err(Error::SyntaxDisabled(syntax.to_string(), span))
+    }
+}
+
+/// Returns the length of the whitespace and comments at the start of `s`,
+/// which the lexer skips.
+fn leading_trivia_len(s: &str) -> usize {
+    let mut rest = s;
+    loop {
+        let trimmed =
+            rest.trim_start_matches([' ', '\t', '\r', '\n', '\x0c']);
+        let skipped = if let Some(comment) = trimmed.strip_prefix('/') {
+            match comment.find('/') {
+                Some(end) => &comment[end + 1..],
+                None => trimmed,
+            }
+        } else if trimmed.starts_with('#') {
+            trimmed.find(['\r', '\n']).map_or("", |end| &trimmed[end..])
+        } else {
+            trimmed
+        };
+        if skipped.len() == rest.len() {
+            return s.len() - rest.len();
+        }
+        rest = skipped;
+    }
+}
+
+/// The options used when none are given.
+static DEFAULT_OPTIONS: ParseOptions = ParseOptions::new();
+
+/// The tag of a known value written as a tagged integer, such as `40000(1)`.
+const KNOWN_VALUE_TAG: TagValue = 40000;
+
+/// The number of includes that may be nested within each other.
+const MAX_INCLUDE_DEPTH: usize = 32;
+
+/// Returns the text of a string token at `span`.
+///
+/// Escape sequences are kept as written, but must be valid, and `\\u`
+/// escapes of UTF-16 surrogates must form pairs. Raw control characters are
+/// handled according to `policy`.
+fn parse_string(
+    s: &str,
+    span: SourceSpan,
+    policy: ControlCharacters,
+) -> Result<String> {
+    let Some(body) = s.strip_prefix('"').and_then(|s| s.strip_suffix('"'))
+    else {
+        return Err(Error::UnrecognizedToken(span));
+    };
+    let mut chars = body.char_indices();
+    while let Some((index, c)) = chars.next() {
+        if is_control_character(c) && policy == ControlCharacters::Reject {
+            let start = span.start + 1 + index;
+            let span = SourceSpan::new(start, start + 1);
+            if c == '\0' {
+                return Err(Error::NulCharacter(span));
+            }
+            return Err(Error::ControlCharacter(
+                format!("U+{:04X}", u32::from(c)),
+                span,
+            ));
+        }
+        if c != '\\' {
+            continue;
+        }
+        let rest = &body[index + 1..];
+        let len = match chars.next().map(|(_, c)| c) {
+            Some('"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't') => continue,
+            Some('u') => match unicode_escape(&body[index..]) {
+                Some(0xd800..=0xdbff) => {
+                    let low = unicode_escape(&body[index + 6..]);
+                    if !matches!(low, Some(0xdc00..=0xdfff)) {
+                        return Err(invalid_unicode_escape(body, index, span));
+                    }
+                    // Skip the digits and the low surrogate's escape.
+                    chars.nth(9);
+                    continue;
+                }
+                Some(0xdc00..=0xdfff) => {
+                    return Err(invalid_unicode_escape(body, index, span));
                 }
+                Some(_) => continue,
+                None => {
+                    1 + rest[1..]
+                        .chars()
+                        .take(3)
+                        .take_while(char::is_ascii_hexdigit)
+                        .count()
+                }
+            },
+            Some(c) => c.len_utf8(),
+            None => 0,
+        };
+        let start = span.start + 1 + index;
+        return Err(Error::InvalidEscapeSequence(
+            body[index..index + 1 + len].to_string(),
+            SourceSpan::new(start, start + 1 + len),
+        ));
+    }
+    if policy != ControlCharacters::Escape {
+        return Ok(body.to_string());
+    }
+    Ok(escape_control_characters(body))
+}
+
+/// Returns `s` with each control character replaced by its escape sequence.
+pub(crate) fn escape_control_characters(s: &str) -> String {
+    let mut value = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\u{8}' => value.push_str("\\b"),
+            '\u{c}' => value.push_str("\\f"),
+            '\n' => value.push_str("\\n"),
+            '\r' => value.push_str("\\r"),
+            '\t' => value.push_str("\\t"),
+            c if is_control_character(c) => {
+                value.push_str(&format!("\\u{:04x}", u32::from(c)));
             }
+            c => value.push(c),
         }
-        awaits_comma = !awaits_key;
     }
+    value
+}
+
+/// Returns `true` if `c` is a control character that must be escaped in a
+/// string.
+pub(crate) fn is_control_character(c: char) -> bool {
+    matches!(c, '\0'..='\u{1f}')
+}
+
+/// Returns the code unit of the `\uXXXX` escape that `s` starts with, if
+/// there is one.
+fn unicode_escape(s: &str) -> Option<u32> {
+    let digits = s.strip_prefix("\\u")?.get(..4)?;
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(digits, 16).ok()
+}
+
+/// Returns the error for the surrogate `\uXXXX` escape at `index` of the body
+/// of the string token at `span`, which isn't part of a surrogate pair.
+fn invalid_unicode_escape(body: &str, index: usize, span: SourceSpan) -> Error {
+    let start = span.start + 1 + index;
+    Error::InvalidUnicodeEscape(
+        body[index..index + 6].to_string(),
+        SourceSpan::new(start, start + 6),
+    )
+}
+
+/// Returns the name of the type of `value`, as written.
+fn type_name(value: &DiagValue) -> &'static str {
+    match value {
+        DiagValue::Bool { .. } => "boolean",
+        DiagValue::Null { .. } => "null",
+        DiagValue::Number { value, .. } => match value {
+            NumberValue::BigUnsigned(_) | NumberValue::BigNegative(_) => {
+                "bignum"
+            }
+            _ => "float",
+        },
+        DiagValue::Text { .. } => "text",
+        DiagValue::ByteString { .. } => "byte string",
+        DiagValue::Date { .. } => "date",
+        DiagValue::DecimalFraction { .. } => "decimal fraction",
+        DiagValue::Rational { .. } => "rational",
+        DiagValue::KnownValue { .. } => "known value",
+        DiagValue::Ur { .. } => "UR",
+        DiagValue::Tagged { .. } | DiagValue::RawTagged { .. } => "tagged item",
+        DiagValue::Array { .. } => "array",
+        DiagValue::Map { .. } => "map",
+        DiagValue::Included { item, .. } => type_name(item),
+    }
+}
+
+/// Returns the tag named `name` in the tags registry, or a
+/// [`TagsRegistryUnavailable`](Error::TagsRegistryUnavailable) error at
+/// `span` if the registry can't be read.
+fn tag_for_name(name: &str, span: SourceSpan) -> Result<Option<Tag>> {
+    with_tags_store(|tags| tags.tag_for_name(name))
+        .ok_or(Error::TagsRegistryUnavailable(span))
+}
+
+fn tag_for_ur(ur: &UR, span: SourceSpan) -> Result<Tag> {
+    let ur_type = ur.ur_type_str();
+    tag_for_name(ur_type, span)?.ok_or_else(|| {
+        Error::UnknownUrType(
+            ur_type.to_string(),
+            SourceSpan::new(span.start + 3, span.start + 3 + ur_type.len()),
+        )
+    })
 }
@@ -0,0 +1,75 @@
+use dcbor::prelude::*;
+
+use crate::{SourceSpan, error::Result, parse::Parser};
+
+/// Why [`parse_dcbor_item_partial_detailed`] stopped after the item it
+/// parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndReason {
+    /// Nothing but whitespace follows the item.
+    EndOfInput,
+    /// Nothing but whitespace and at least one comment follows the item.
+    TrailingComment,
+    /// Another token follows the item, starting at
+    /// [`Partial::consumed`]. It may begin the next item of a sequence, or
+    /// be a token that can't begin an item at all, such as `)`.
+    Token,
+}
+
+/// An item parsed from the beginning of a source, along with where and why
+/// parsing stopped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Partial {
+    /// The item.
+    pub value: CBOR,
+    /// The number of bytes consumed: the offset of the first token after the
+    /// item, or the length of the source if there is none.
+    pub consumed: usize,
+    /// The span of the whitespace and comments between the end of the item
+    /// and [`consumed`](Self::consumed), which may be empty.
+    pub trailing_span: SourceSpan,
+    /// Why parsing stopped.
+    pub ended_with: EndReason,
+}
+
+/// Parses a dCBOR item from the beginning of a string, like
+/// [`parse_dcbor_item_partial`](crate::parse_dcbor_item_partial), and returns
+/// it along with where and why parsing stopped.
+///
+/// Shell pipelines that concatenate items can use
+/// [`ended_with`](Partial::ended_with) to tell an item followed by more
+/// input from one followed only by a trailing comment, which
+/// `parse_dcbor_item_partial` reports the same way, as having consumed the
+/// whole source.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{EndReason, parse_dcbor_item_partial_detailed};
+/// let partial = parse_dcbor_item_partial_detailed("[1] # done\n").unwrap();
+/// assert_eq!(partial.value.diagnostic(), "[1]");
+/// assert_eq!(partial.consumed, 11);
+/// assert_eq!(partial.trailing_span.range(), 3..11);
+/// assert_eq!(partial.ended_with, EndReason::TrailingComment);
+///
+/// let partial = parse_dcbor_item_partial_detailed("[1] )").unwrap();
+/// assert_eq!(partial.consumed, 4);
+/// assert_eq!(partial.ended_with, EndReason::Token);
+/// ```
+pub fn parse_dcbor_item_partial_detailed(src: &str) -> Result<Partial> {
+    let mut parser = Parser::new(src);
+    let value = parser.parse_first()?;
+    let consumed = parser.consumed();
+    let trailing_span = SourceSpan::new(value.span().end, consumed);
+    let ended_with = if consumed < src.len() {
+        EndReason::Token
+    } else if src[trailing_span.range()]
+        .trim_start_matches([' ', '\t', '\r', '\n', '\x0c'])
+        .is_empty()
+    {
+        EndReason::EndOfInput
+    } else {
+        EndReason::TrailingComment
+    };
+    Ok(Partial { value: value.to_cbor(), consumed, trailing_span, ended_with })
+}
@@ -0,0 +1,244 @@
+use std::{fmt, ops::Range};
+
+use dcbor::prelude::*;
+
+use crate::{
+    DiagValue, GroupKind, ParseError, ParseResult, ParseVisitor, SourceSpan,
+    VisitAction, encoded_size, parse_diag_value_with_visitor,
+};
+
+/// One step of a path from an item to an item nested within it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathSegment {
+    /// The item at this index of an array.
+    Index(usize),
+    /// The value of the map entry with this key.
+    Key(CBOR),
+    /// The content of a tagged item.
+    Content,
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathSegment::Index(index) => write!(f, "[{index}]"),
+            PathSegment::Key(key) => write!(f, "[{}]", key.diagnostic_flat()),
+            PathSegment::Content => write!(f, "()"),
+        }
+    }
+}
+
+impl DiagValue {
+    /// Returns the item at `path` within this value, or `None` if there is
+    /// none.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{PathSegment, parse_diag_value};
+    /// # use dcbor::prelude::*;
+    /// let value = parse_diag_value(r#"{"a": [1, 2(3)]}"#).unwrap();
+    /// let path = [
+    ///     PathSegment::Key(CBOR::from("a")),
+    ///     PathSegment::Index(1),
+    ///     PathSegment::Content,
+    /// ];
+    /// assert_eq!(value.at(&path).unwrap().text(), Some("3"));
+    /// ```
+    pub fn at(&self, path: &[PathSegment]) -> Option<&DiagValue> {
+        if let DiagValue::Included { item, .. } = self {
+            return item.at(path);
+        }
+        let Some((segment, rest)) = path.split_first() else {
+            return Some(self);
+        };
+        let child = match (segment, self) {
+            (PathSegment::Index(index), DiagValue::Array { items, .. }) => {
+                items.get(*index)?
+            }
+            (PathSegment::Key(key), DiagValue::Map { entries, .. }) => {
+                &entries.iter().find(|(k, _)| k.to_cbor() == *key)?.1
+            }
+            (PathSegment::Content, DiagValue::Tagged { item, .. }) => item,
+            _ => return None,
+        };
+        child.at(rest)
+    }
+
+    /// Returns the range of bytes that encode the item at `path` within the
+    /// deterministic encoding of this value, or `None` if there is no item
+    /// at `path`.
+    ///
+    /// Together with the [`span`](Self::span) of the item returned by
+    /// [`at`](Self::at), this relates a field as it is written in diagnostic
+    /// notation to the bytes that encode it, such as the bytes of a signing
+    /// payload that a hardware wallet displays. Map entries are encoded in
+    /// the deterministic order of their keys, which may differ from the
+    /// order they are written in.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{PathSegment, parse_diag_value};
+    /// # use dcbor::prelude::*;
+    /// let value = parse_diag_value(r#"{"b": [1, h'0102'], "a": 2}"#).unwrap();
+    /// let path = [PathSegment::Key(CBOR::from("b")), PathSegment::Index(1)];
+    /// let range = value.encoded_range(&path).unwrap();
+    /// assert_eq!(range, 8..11);
+    ///
+    /// let data = value.to_cbor().to_cbor_data();
+    /// assert_eq!(hex::encode(&data), "a261610261628201420102");
+    /// assert_eq!(hex::encode(&data[range]), "420102");
+    /// ```
+    pub fn encoded_range(&self, path: &[PathSegment]) -> Option<Range<usize>> {
+        encoded_range_in(&self.to_cbor(), path, 0)
+    }
+}
+
+/// Parses a dCBOR item from a string input just far enough to find the item
+/// at `path`, and returns it, or `None` if there is no such item.
+///
+/// Parsing stops as soon as the item is complete, so extracting a field near
+/// the start of a huge document avoids the cost of parsing the rest of it.
+/// Errors in the source before the end of the item are reported as usual;
+/// the source after it is not examined.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{PathSegment, extract_first};
+/// # use dcbor::prelude::*;
+/// let src = r#"{"id": 7, "data": [1, 2, 3] this is never parsed"#;
+/// let path = [PathSegment::Key(CBOR::from("id"))];
+/// let value = extract_first(src, &path).unwrap().unwrap();
+/// assert_eq!(value.text(), Some("7"));
+/// ```
+pub fn extract_first(
+    src: &str,
+    path: &[PathSegment],
+) -> ParseResult<Option<DiagValue>> {
+    let mut extractor = Extractor { path, frames: Vec::new(), found: None };
+    match parse_diag_value_with_visitor(src, &mut extractor) {
+        Ok(value) if path.is_empty() => Ok(Some(value)),
+        Ok(_) => Ok(None),
+        Err(ParseError::VisitorStopped(_)) => Ok(extractor.found),
+        Err(e) => Err(e),
+    }
+}
+
+//
+// === Private Functions ===
+//
+
+/// Returns the range of the item at `path` within `cbor`, whose encoding
+/// starts at `start`.
+fn encoded_range_in(
+    cbor: &CBOR,
+    path: &[PathSegment],
+    start: usize,
+) -> Option<Range<usize>> {
+    let len = encoded_size(cbor);
+    let Some((segment, rest)) = path.split_first() else {
+        return Some(start..start + len);
+    };
+    let children: Vec<&CBOR> = match cbor.as_case() {
+        CBORCase::Array(items) => items.iter().collect(),
+        CBORCase::Map(map) => {
+            map.iter().flat_map(|(key, value)| [key, value]).collect()
+        }
+        CBORCase::Tagged(_, item) => vec![item],
+        _ => return None,
+    };
+    let index = match (segment, cbor.as_case()) {
+        (PathSegment::Index(index), CBORCase::Array(_)) => *index,
+        (PathSegment::Key(key), CBORCase::Map(map)) => {
+            2 * map.iter().position(|(k, _)| k == key)? + 1
+        }
+        (PathSegment::Content, CBORCase::Tagged(..)) => 0,
+        _ => return None,
+    };
+    let lens: Vec<usize> =
+        children.iter().map(|child| encoded_size(child)).collect();
+    let header = len - lens.iter().sum::<usize>();
+    let offset = start + header + lens.iter().take(index).sum::<usize>();
+    encoded_range_in(children.get(index)?, rest, offset)
+}
+
+/// An open group, and the segment that leads to it from its parent.
+struct Frame {
+    kind: GroupKind,
+    segment: Option<PathSegment>,
+    children: usize,
+    key: Option<CBOR>,
+}
+
+struct Extractor<'a> {
+    path: &'a [PathSegment],
+    frames: Vec<Frame>,
+    found: Option<DiagValue>,
+}
+
+impl Extractor<'_> {
+    /// Returns the segment that leads from the innermost open group to its
+    /// next child, or `None` if that child is a map key, which no path leads
+    /// to.
+    fn next_segment(&self) -> Option<PathSegment> {
+        let frame = self.frames.last()?;
+        match frame.kind {
+            GroupKind::Array => Some(PathSegment::Index(frame.children)),
+            GroupKind::Map if frame.children % 2 == 0 => None,
+            GroupKind::Map => frame.key.clone().map(PathSegment::Key),
+            GroupKind::Tagged => Some(PathSegment::Content),
+            // The items of embedded CBOR are within a byte string, which no
+            // path leads into.
+            GroupKind::Embedded => None,
+        }
+    }
+
+    /// Returns `true` if the open groups, followed by `segment`, lead along
+    /// `path` to the target.
+    fn matches(&self, segment: Option<&PathSegment>) -> bool {
+        let mut segments = self
+            .frames
+            .iter()
+            .skip(1)
+            .map(|frame| frame.segment.as_ref())
+            .chain(std::iter::once(segment));
+        self.path.len() == self.frames.len()
+            && self
+                .path
+                .iter()
+                .all(|expected| segments.next().flatten() == Some(expected))
+    }
+}
+
+impl ParseVisitor for Extractor<'_> {
+    fn begin_group(
+        &mut self,
+        kind: GroupKind,
+        _span: SourceSpan,
+        _depth: usize,
+    ) -> VisitAction {
+        let segment = self.next_segment();
+        self.frames.push(Frame { kind, segment, children: 0, key: None });
+        VisitAction::Continue
+    }
+
+    fn item(&mut self, item: &DiagValue, depth: usize) -> VisitAction {
+        if self.frames.len() > depth {
+            self.frames.pop();
+        }
+        let segment = self.next_segment();
+        if !self.frames.is_empty() && self.matches(segment.as_ref()) {
+            self.found = Some(item.clone());
+            return VisitAction::Stop;
+        }
+        if let Some(frame) = self.frames.last_mut() {
+            if frame.kind == GroupKind::Map && frame.children % 2 == 0 {
+                frame.key = Some(item.to_cbor());
+            }
+            frame.children += 1;
+        }
+        VisitAction::Continue
+    }
+}
@@ -0,0 +1,1084 @@
+use std::{collections::BTreeMap, fmt};
+
+use dcbor::{Simple, prelude::*};
+
+use crate::{
+    ByteStringEncoding, DiagValue, ParseResult, known_value_info,
+    parse_dcbor_item, registry::with_tags_store,
+};
+
+/// When floating point numbers are written in exponent notation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExponentNotation {
+    /// Matches `CBOR::diagnostic`: exponent notation is used when the decimal
+    /// exponent is less than -4 or at least 16.
+    #[default]
+    Auto,
+    /// Exponent notation is never used.
+    Never,
+    /// Exponent notation is always used.
+    Always,
+    /// Exponent notation is used when the decimal exponent is less than `min`
+    /// or at least `max`.
+    Outside { min: i32, max: i32 },
+}
+
+/// How many digits of a floating point number are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FloatPrecision {
+    /// The fewest digits that parse back to exactly the same value.
+    #[default]
+    Shortest,
+    /// At most this many significant digits, rounding if necessary. Trailing
+    /// zeros are not written. The output may not parse back to exactly the
+    /// same value. More than 17 digits, all that an `f64` holds, are written
+    /// as 17.
+    Significant(usize),
+}
+
+/// Whether long byte strings and text strings are written in full.
+///
+/// Redacted output lets logs and bug reports show the structure of an item
+/// without leaking secrets or growing too large. It can't be parsed back into
+/// the same item.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Redaction {
+    /// Strings are written in full.
+    #[default]
+    None,
+    /// Strings longer than `threshold` bytes are written with their middle
+    /// replaced by their length, as in `h'a1b2…(4096 bytes)…ff00'`.
+    Elide { threshold: usize },
+    /// Strings longer than `threshold` bytes are replaced by their SHA-256
+    /// digest, as in `ELIDED(sha256: 5f70bf18…)`.
+    Hash { threshold: usize },
+}
+
+/// Options for [`format_diagnostic`].
+///
+/// The default options produce the same output as `CBOR::diagnostic`.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{ExponentNotation, FloatPrecision, PrintOptions};
+/// let options = PrintOptions::default()
+///     .flat(true)
+///     .exponent_notation(ExponentNotation::Never)
+///     .float_precision(FloatPrecision::Significant(6));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrintOptions {
+    flat: bool,
+    exponent_notation: ExponentNotation,
+    float_precision: FloatPrecision,
+    annotate_known_values: bool,
+    redaction: Redaction,
+    max_depth: Option<usize>,
+    max_items: Option<usize>,
+    deterministic: bool,
+    externalize_byte_strings: Option<usize>,
+    preserve_map_order: bool,
+    known_value_names: bool,
+    collapse_tag_chains: bool,
+}
+
+impl PrintOptions {
+    /// Sets whether the output is written on a single line.
+    pub fn flat(mut self, flat: bool) -> Self {
+        self.flat = flat;
+        self
+    }
+
+    /// Sets when floating point numbers are written in exponent notation.
+    pub fn exponent_notation(mut self, notation: ExponentNotation) -> Self {
+        self.exponent_notation = notation;
+        self
+    }
+
+    /// Sets how many digits of floating point numbers are written.
+    pub fn float_precision(mut self, precision: FloatPrecision) -> Self {
+        self.float_precision = precision;
+        self
+    }
+
+    /// Sets whether known values are followed by a comment with their
+    /// registered name and assigned range.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{PrintOptions, format_diagnostic, parse_dcbor_item};
+    /// let cbor = parse_dcbor_item("'isA'").unwrap();
+    /// let options = PrintOptions::default().annotate_known_values(true);
+    /// assert_eq!(
+    ///     format_diagnostic(&cbor, &options),
+    ///     "40000(1)   / 'isA' (1, General 0-49) /"
+    /// );
+    /// ```
+    pub fn annotate_known_values(mut self, annotate: bool) -> Self {
+        self.annotate_known_values = annotate;
+        self
+    }
+
+    /// Sets whether known values are written as `'name'`, or as `'1'` if they
+    /// are unregistered, rather than as `40000(1)`, and whether maps whose
+    /// keys and values are all known values are written with each entry on
+    /// one line, as Gordian metadata maps are written by hand. Known values
+    /// written this way are not annotated.
+    ///
+    /// In [`deterministic`](Self::deterministic) mode, every known value is
+    /// written as `'1'`, since names come from a registry populated at run
+    /// time.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{PrintOptions, format_diagnostic, parse_dcbor_item};
+    /// let cbor = parse_dcbor_item(
+    ///     "{'isA': 'Seed', 'controller': 'Seed', '100000': 'isA'}",
+    /// )
+    /// .unwrap();
+    /// let options = PrintOptions::default().known_value_names(true);
+    /// assert_eq!(
+    ///     format_diagnostic(&cbor, &options),
+    ///     "{\n    'isA': 'Seed',\n    'controller': 'Seed',\n    \
+    ///      '100000': 'isA'\n}"
+    /// );
+    /// ```
+    pub fn known_value_names(mut self, names: bool) -> Self {
+        self.known_value_names = names;
+        self
+    }
+
+    /// Sets whether long byte strings and text strings are written in full.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{PrintOptions, Redaction, format_diagnostic};
+    /// # use dcbor::prelude::*;
+    /// let cbor = CBOR::to_byte_string(vec![0xab; 4096]);
+    /// let options = PrintOptions::default()
+    ///     .redaction(Redaction::Elide { threshold: 16 });
+    /// assert_eq!(
+    ///     format_diagnostic(&cbor, &options),
+    ///     "h'abab…(4096 bytes)…abab'"
+    /// );
+    /// ```
+    pub fn redaction(mut self, redaction: Redaction) -> Self {
+        self.redaction = redaction;
+        self
+    }
+
+    /// Sets the depth below which the contents of arrays, maps, and tagged
+    /// items are replaced by `…`. The top-level item is at depth 0.
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the number of items of each array, and entries of each map, that
+    /// are written before the rest are replaced by `…`.
+    pub fn max_items(mut self, max_items: Option<usize>) -> Self {
+        self.max_items = max_items;
+        self
+    }
+
+    /// Sets whether the output depends only on the item and the other
+    /// options, so that fixtures generated from it are byte-identical on
+    /// every platform and in every process.
+    ///
+    /// The printer never uses platform line endings or locale-dependent
+    /// number formatting, but the names of known values come from a registry
+    /// populated at run time. In deterministic mode, known values are not
+    /// annotated, even if
+    /// [`annotate_known_values`](Self::annotate_known_values) is set.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{PrintOptions, format_diagnostic, parse_dcbor_item};
+    /// let cbor = parse_dcbor_item("['isA', 1.5]").unwrap();
+    /// let options = PrintOptions::default()
+    ///     .flat(true)
+    ///     .annotate_known_values(true)
+    ///     .deterministic(true);
+    /// assert_eq!(format_diagnostic(&cbor, &options), "[40000(1), 1.5]");
+    /// ```
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Sets the length in bytes above which byte strings are replaced by a
+    /// `!bytes("digest")` directive naming their SHA-256 digest, or `None`
+    /// to write every byte string in full.
+    ///
+    /// Unlike [`redaction`](Self::redaction), the output can be parsed back
+    /// into the same item, given
+    /// [`ParseOptions::byte_string_loader`](crate::ParseOptions::byte_string_loader)
+    /// or [`ParseOptions::byte_string_dir`](crate::ParseOptions::byte_string_dir).
+    /// [`external_byte_strings`] returns the byte strings to store alongside
+    /// the output.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{
+    /// #     ParseOptions, PrintOptions, external_byte_strings,
+    /// #     format_diagnostic, parse_dcbor_item_with_options,
+    /// # };
+    /// # use dcbor::prelude::*;
+    /// let cbor = CBOR::from(vec![
+    ///     CBOR::to_byte_string([1, 2]),
+    ///     CBOR::to_byte_string(vec![0; 1000]),
+    /// ]);
+    /// let options =
+    ///     PrintOptions::default().flat(true).externalize_byte_strings(Some(16));
+    /// let diag = format_diagnostic(&cbor, &options);
+    /// assert_eq!(
+    ///     diag,
+    ///     "[h'0102', !bytes(\"541b3e9daa09b20bf85fa273e5cbd3e8\
+    ///      0185aa4ec298e765db87742b70138a53\")]"
+    /// );
+    ///
+    /// let blobs = external_byte_strings(&cbor, 16);
+    /// let options = ParseOptions::default().byte_string_loader(move |name| {
+    ///     let (_, bytes) = blobs.iter().find(|(digest, _)| digest == name)
+    ///         .ok_or("no such blob")?;
+    ///     Ok(bytes.clone())
+    /// });
+    /// assert_eq!(parse_dcbor_item_with_options(&diag, &options).unwrap(), cbor);
+    /// ```
+    pub fn externalize_byte_strings(mut self, threshold: Option<usize>) -> Self {
+        self.externalize_byte_strings = threshold;
+        self
+    }
+
+    /// Sets whether a chain of items tagged with registered tags, such as
+    /// `1(1(content))`, is written as a single group headed by the names of
+    /// its tags, as in `date(date(content))`, rather than as a group nested
+    /// in a group for each tag. The output parses back into the same item.
+    ///
+    /// A chain ends at the first tag that isn't registered. In
+    /// [`deterministic`](Self::deterministic) mode, no chains are collapsed,
+    /// since the tags registry is populated at run time.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{PrintOptions, format_diagnostic, parse_dcbor_item};
+    /// dcbor::register_tags();
+    /// let cbor =
+    ///     parse_dcbor_item(r#"date(date(["a long string", "another"]))"#)
+    ///         .unwrap();
+    /// let options = PrintOptions::default().collapse_tag_chains(true);
+    /// assert_eq!(
+    ///     format_diagnostic(&cbor, &options),
+    ///     "date(date(\n    [\n        \"a long string\",\n        \
+    ///      \"another\"\n    ]\n))"
+    /// );
+    /// ```
+    pub fn collapse_tag_chains(mut self, collapse: bool) -> Self {
+        self.collapse_tag_chains = collapse;
+        self
+    }
+
+    /// Sets whether [`format_diag_value`] writes the entries of each map in
+    /// the order they were written in the source, rather than in
+    /// deterministic order.
+    ///
+    /// This only changes how a value is displayed, so that fixtures whose
+    /// authors grouped related entries aren't reshuffled when reformatted.
+    /// The value still encodes canonically. [`format_diagnostic`] has no
+    /// source order to keep, and ignores this option.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{PrintOptions, format_diag_value, parse_diag_value};
+    /// let value = parse_diag_value(r#"{"name": "Alice", "age": 30}"#).unwrap();
+    /// let options = PrintOptions::default().flat(true);
+    /// assert_eq!(
+    ///     format_diag_value(&value, &options),
+    ///     r#"{"age": 30, "name": "Alice"}"#
+    /// );
+    /// assert_eq!(
+    ///     format_diag_value(&value, &options.preserve_map_order(true)),
+    ///     r#"{"name": "Alice", "age": 30}"#
+    /// );
+    /// ```
+    pub fn preserve_map_order(mut self, preserve: bool) -> Self {
+        self.preserve_map_order = preserve;
+        self
+    }
+}
+
+/// Returns a representation of a dCBOR item in diagnostic notation.
+///
+/// Unlike `CBOR::diagnostic`, the formatting of floating point numbers can be
+/// controlled, so output is stable and matches a team's preferred style.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{
+/// #     ExponentNotation, FloatPrecision, PrintOptions, format_diagnostic,
+/// #     parse_dcbor_item,
+/// # };
+/// let cbor = parse_dcbor_item("[1e20, 1.23456, 0.00001]").unwrap();
+/// assert_eq!(
+///     format_diagnostic(&cbor, &PrintOptions::default()),
+///     cbor.diagnostic()
+/// );
+/// let options = PrintOptions::default()
+///     .exponent_notation(ExponentNotation::Always)
+///     .float_precision(FloatPrecision::Significant(3));
+/// assert_eq!(
+///     format_diagnostic(&cbor, &options),
+///     "[1e20, 1.23e0, 1e-5]"
+/// );
+/// ```
+pub fn format_diagnostic(cbor: &CBOR, options: &PrintOptions) -> String {
+    let mut result = String::new();
+    write_diagnostic(cbor, &mut result, options).unwrap();
+    result
+}
+
+/// Writes a representation of a dCBOR item in diagnostic notation to `w`.
+///
+/// Produces the same text as [`format_diagnostic`], but emits it
+/// incrementally rather than building it in memory first, so it is suitable
+/// for writing very large items to files.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{PrintOptions, parse_dcbor_item, write_diagnostic};
+/// let cbor = parse_dcbor_item("[h'00ff', \"a\"]").unwrap();
+/// let mut output = String::new();
+/// write_diagnostic(&cbor, &mut output, &PrintOptions::default()).unwrap();
+/// assert_eq!(output, "[h'00ff', \"a\"]");
+/// ```
+pub fn write_diagnostic(
+    cbor: &CBOR,
+    w: &mut impl fmt::Write,
+    options: &PrintOptions,
+) -> fmt::Result {
+    Printer { w, options }.item(Node::new(cbor), 0, "")
+}
+
+/// Parses `src` as [`parse_dcbor_item`] does, and returns the item along
+/// with its text as written by [`format_diagnostic`] with `options`.
+///
+/// Formatters and command line tools almost always need both. This is a
+/// convenience wrapper: it parses the source once, but still converts and
+/// formats the item in two separate walks, as calling [`parse_dcbor_item`]
+/// and [`format_diagnostic`] does, since the layout depends on the
+/// deterministic order of map keys, which is known only once the whole item
+/// is converted.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{PrintOptions, parse_and_format};
+/// let options = PrintOptions::default().flat(true);
+/// let (cbor, text) =
+///     parse_and_format("{2: 'isA', 1: [0x10, 1.0]}", &options).unwrap();
+/// assert_eq!(text, "{1: [16, 1], 2: 40000(1)}");
+/// assert_eq!(cbor.diagnostic_flat(), text);
+/// ```
+pub fn parse_and_format(
+    src: &str,
+    options: &PrintOptions,
+) -> ParseResult<(CBOR, String)> {
+    let cbor = parse_dcbor_item(src)?;
+    let text = format_diagnostic(&cbor, options);
+    Ok((cbor, text))
+}
+
+/// Returns a representation of a parsed value in diagnostic notation that
+/// keeps the surface form of `ur:` literals, date literals, known values, and
+/// `!bytes("digest")` directives.
+///
+/// [`format_diagnostic`] writes every tagged item as `tag(content)`, so a
+/// value parsed from `ur:date/...`, `2023-02-08`, or `'isA'` comes back as
+/// `1(1675814400)` or `40000(1)`. This writes each such literal exactly as it
+/// was in the source, so parsed input can be re-emitted without losing the
+/// forms its author chose. Everything else is written as `format_diagnostic`
+/// writes it, with map entries in deterministic order unless
+/// [`PrintOptions::preserve_map_order`] is set.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{PrintOptions, format_diag_value, parse_diag_value};
+/// let value = parse_diag_value("{'isA': 2023-02-08, 1: '2'}").unwrap();
+/// let options = PrintOptions::default().flat(true);
+/// assert_eq!(
+///     format_diag_value(&value, &options),
+///     "{1: '2', 'isA': 2023-02-08}"
+/// );
+/// assert_eq!(
+///     dcbor_parse::format_diagnostic(&value.to_cbor(), &options),
+///     "{1: 40000(2), 40000(1): 1(1675814400)}"
+/// );
+/// ```
+pub fn format_diag_value(value: &DiagValue, options: &PrintOptions) -> String {
+    let (cbor, forms) = cbor_with_forms(value);
+    let mut result = String::new();
+    let node = Node { cbor: &cbor, forms: Some(&forms) };
+    Printer { w: &mut result, options }.item(node, 0, "").unwrap();
+    result
+}
+
+/// Returns a truncated, single-line representation of a dCBOR item in
+/// diagnostic notation, suitable for log lines and REPL echoes of large
+/// items.
+///
+/// Groups nested `max_depth` or more levels deep have their contents replaced
+/// by `…`, as do all but the first `max_items` items of each array and
+/// entries of each map.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{parse_dcbor_item, preview};
+/// let cbor = parse_dcbor_item("[1, [2, [3]], {4: 5, 6: 7}, 8]").unwrap();
+/// assert_eq!(preview(&cbor, 2, 3), "[1, [2, […]], {4: 5, 6: 7}, …]");
+/// assert_eq!(preview(&cbor, 1, 1), "[1, …]");
+/// assert_eq!(preview(&cbor, 0, 10), "[…]");
+/// ```
+pub fn preview(cbor: &CBOR, max_depth: usize, max_items: usize) -> String {
+    let options = PrintOptions::default()
+        .flat(true)
+        .max_depth(Some(max_depth))
+        .max_items(Some(max_items));
+    format_diagnostic(cbor, &options)
+}
+
+/// Returns the byte strings within `cbor` that are longer than `threshold`
+/// bytes, each with its SHA-256 digest in hex, in the order they first
+/// appear.
+///
+/// These are the byte strings that
+/// [`PrintOptions::externalize_byte_strings`] replaces with
+/// `!bytes("digest")` directives, to be stored, for example, in files named
+/// by their digests.
+pub fn external_byte_strings(
+    cbor: &CBOR,
+    threshold: usize,
+) -> Vec<(String, Vec<u8>)> {
+    fn collect(
+        cbor: &CBOR,
+        threshold: usize,
+        result: &mut Vec<(String, Vec<u8>)>,
+    ) {
+        match cbor.as_case() {
+            CBORCase::ByteString(bytes) if bytes.len() > threshold => {
+                let digest = hex::encode(bc_crypto::sha256(bytes.data()));
+                if !result.iter().any(|(d, _)| *d == digest) {
+                    result.push((digest, bytes.to_vec()));
+                }
+            }
+            _ => {}
+        }
+        children(cbor).for_each(|child| collect(child, threshold, result));
+    }
+    let mut result = Vec::new();
+    collect(cbor, threshold, &mut result);
+    result
+}
+
+/// Formats a floating point number according to the options.
+pub(crate) fn format_float(value: f64, options: &PrintOptions) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        let sign = if value.is_sign_negative() { "-" } else { "" };
+        return format!("{sign}Infinity");
+    }
+
+    // Scientific notation yields the significant digits and the exponent.
+    let scientific = match options.float_precision {
+        FloatPrecision::Shortest => format!("{:e}", value.abs()),
+        FloatPrecision::Significant(digits) => {
+            let digits = digits.clamp(1, MAX_SIGNIFICANT_DIGITS);
+            format!("{:.*e}", digits - 1, value.abs())
+        }
+    };
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let digits = match digits.trim_end_matches('0') {
+        "" => "0",
+        trimmed => trimmed,
+    };
+
+    let use_exponent = match options.exponent_notation {
+        ExponentNotation::Auto => !(-4..16).contains(&exponent),
+        ExponentNotation::Never => false,
+        ExponentNotation::Always => true,
+        ExponentNotation::Outside { min, max } => {
+            !(min..max).contains(&exponent)
+        }
+    };
+
+    let sign = if value.is_sign_negative() { "-" } else { "" };
+    let body = if use_exponent {
+        let (first, rest) = digits.split_at(1);
+        if rest.is_empty() {
+            format!("{first}e{exponent}")
+        } else {
+            format!("{first}.{rest}e{exponent}")
+        }
+    } else if exponent < 0 {
+        let zeros = "0".repeat((-exponent - 1) as usize);
+        format!("0.{zeros}{digits}")
+    } else {
+        let integer_len = exponent as usize + 1;
+        if digits.len() > integer_len {
+            let (integer, fraction) = digits.split_at(integer_len);
+            format!("{integer}.{fraction}")
+        } else {
+            let zeros = "0".repeat(integer_len - digits.len());
+            format!("{digits}{zeros}.0")
+        }
+    };
+    format!("{sign}{body}")
+}
+
+//
+// === Private Functions ===
+//
+
+/// The tag of a known value.
+const KNOWN_VALUE_TAG: TagValue = 40000;
+
+/// The number of bytes of a byte string written at a time.
+const CHUNK_LEN: usize = 4096;
+
+/// The most significant digits a float is written with, which are enough to
+/// tell any two `f64` values apart.
+const MAX_SIGNIFICANT_DIGITS: usize = 17;
+
+/// Writes diagnostic notation directly to its output, deciding the layout of
+/// each group with the same rules as `CBOR::diagnostic` but without
+/// formatting the group first.
+struct Printer<'a, W: ?Sized> {
+    w: &'a mut W,
+    options: &'a PrintOptions,
+}
+
+/// An item being written, with what is kept from its source, if it was
+/// parsed.
+#[derive(Clone, Copy)]
+struct Node<'c> {
+    cbor: &'c CBOR,
+    forms: Option<&'c Forms>,
+}
+
+/// What [`format_diag_value`] keeps from the source of a parsed item and the
+/// items within it.
+#[derive(Default)]
+struct Forms {
+    /// The surface form of the item, if it is written as it was in the
+    /// source.
+    text: Option<String>,
+    /// The indices of the entries of a map, in the order they were written
+    /// in the source.
+    entry_order: Option<Vec<usize>>,
+    /// What is kept for each child of the item, in the order of `children`.
+    children: Vec<Forms>,
+}
+
+impl<'c> Node<'c> {
+    fn new(cbor: &'c CBOR) -> Self { Self { cbor, forms: None } }
+
+    /// Returns the source text of an item written in its surface form.
+    fn form(self) -> Option<&'c str> { self.forms?.text.as_deref() }
+
+    /// Returns the child of the item at `index` in the order of `children`.
+    fn child(self, cbor: &'c CBOR, index: usize) -> Node<'c> {
+        let forms = self.forms.and_then(|forms| forms.children.get(index));
+        Node { cbor, forms }
+    }
+}
+
+/// Converts a parsed value to CBOR, and returns it along with the source
+/// text of each item whose surface form is kept by [`format_diag_value`].
+fn cbor_with_forms(value: &DiagValue) -> (CBOR, Forms) {
+    let mut forms = Forms::default();
+    let cbor = match value {
+        DiagValue::Tagged { tag, item, .. } => {
+            let (item, item_forms) = cbor_with_forms(item);
+            forms.children.push(item_forms);
+            CBOR::to_tagged_value(tag.clone(), item)
+        }
+        DiagValue::Array { items, .. } => {
+            let mut array = Vec::with_capacity(items.len());
+            for item in items {
+                let (item, item_forms) = cbor_with_forms(item);
+                array.push(item);
+                forms.children.push(item_forms);
+            }
+            array.into()
+        }
+        DiagValue::Map { entries, .. } => {
+            let mut map = Map::new();
+            // The index in the source and forms of each entry, by the
+            // encoding of its key, which is the order of the map's entries.
+            let mut sorted = BTreeMap::new();
+            for (index, (key, value)) in entries.iter().enumerate() {
+                let (key, key_forms) = cbor_with_forms(key);
+                let (value, value_forms) = cbor_with_forms(value);
+                let entry = (index, key_forms, value_forms);
+                sorted.insert(key.to_cbor_data(), entry);
+                map.insert(key, value);
+            }
+            let mut order = vec![0; entries.len()];
+            let is_unique = sorted.len() == entries.len();
+            for (entry, (_, (index, key_forms, value_forms))) in
+                sorted.into_iter().enumerate()
+            {
+                order[index] = entry;
+                forms.children.extend([key_forms, value_forms]);
+            }
+            if is_unique {
+                forms.entry_order = Some(order);
+            }
+            map.into()
+        }
+        DiagValue::Included { item, .. } => return cbor_with_forms(item),
+        _ => value.to_cbor(),
+    };
+    if let DiagValue::Date { text, .. }
+    | DiagValue::DecimalFraction { text, .. }
+    | DiagValue::Rational { text, .. }
+    | DiagValue::KnownValue { text, .. }
+    | DiagValue::Ur { text, .. }
+    | DiagValue::RawTagged { text, .. }
+    | DiagValue::ByteString {
+        text,
+        encoding: ByteStringEncoding::External,
+        ..
+    } = value
+    {
+        forms.text = Some(text.clone());
+    }
+    (cbor, forms)
+}
+
+impl<'a, W: fmt::Write + ?Sized> Printer<'a, W> {
+    fn item(
+        &mut self,
+        node: Node<'_>,
+        level: usize,
+        separator: &str,
+    ) -> fmt::Result {
+        if !self.options.flat {
+            write!(self.w, "{:1$}", "", level * 4)?;
+        }
+        if !self.options.flat && self.is_multiline(node, level) {
+            let (begin, end, is_pairs) = self.delimiters(node).unwrap();
+            write!(self.w, "{begin}")?;
+            self.comment(node, "   ")?;
+            let (shown, omitted) = self.shown_len(node.cbor, level);
+            // The entries of a map of known values are written on one line
+            // each.
+            let is_compact = is_pairs && self.is_known_value_map(node.cbor);
+            for (index, child) in self.children(node).take(shown).enumerate() {
+                let separator = if index == shown - 1 && !omitted {
+                    ""
+                } else if is_pairs && index % 2 == 0 {
+                    ":"
+                } else {
+                    ","
+                };
+                if is_compact && index % 2 == 1 {
+                    write!(self.w, " ")?;
+                    self.atom(child)?;
+                    write!(self.w, "{separator}")?;
+                    continue;
+                }
+                writeln!(self.w)?;
+                self.item(child, level + 1, separator)?;
+            }
+            if omitted {
+                writeln!(self.w)?;
+                write!(self.w, "{:1$}…", "", (level + 1) * 4)?;
+            }
+            writeln!(self.w)?;
+            write!(self.w, "{:1$}{end}{separator}", "", level * 4)
+        } else {
+            self.single_line(node, level)?;
+            write!(self.w, "{separator}")?;
+            self.comment(node, "   ")
+        }
+    }
+
+    fn single_line(&mut self, node: Node<'_>, depth: usize) -> fmt::Result {
+        let Some((begin, end, is_pairs)) = self.delimiters(node) else {
+            return self.atom(node);
+        };
+        write!(self.w, "{begin}")?;
+        let (shown, omitted) = self.shown_len(node.cbor, depth);
+        for (index, child) in self.children(node).take(shown).enumerate() {
+            if index > 0 {
+                if is_pairs && index % 2 == 1 {
+                    write!(self.w, ": ")?;
+                } else {
+                    write!(self.w, ", ")?;
+                }
+            }
+            self.single_line(child, depth + 1)?;
+            self.comment(child, " ")?;
+        }
+        if omitted {
+            if shown > 0 {
+                write!(self.w, ", ")?;
+            }
+            write!(self.w, "…")?;
+        }
+        write!(self.w, "{end}")
+    }
+
+    /// Returns the number of children of a group at `depth` that are written,
+    /// and whether any are omitted because of the depth or item limits.
+    fn shown_len(&self, cbor: &CBOR, depth: usize) -> (usize, bool) {
+        let len = children_len(cbor);
+        if self.options.max_depth.is_some_and(|max| depth >= max) {
+            return (0, len > 0);
+        }
+        let shown = match (self.options.max_items, cbor.as_case()) {
+            (Some(max), CBORCase::Map(_)) => len.min(max.saturating_mul(2)),
+            (Some(max), _) => len.min(max),
+            (None, _) => len,
+        };
+        (shown, shown < len)
+    }
+
+    /// Returns the delimiters of a group, or `None` if the item is an atom
+    /// or is written in its surface form.
+    fn delimiters(&self, node: Node<'_>) -> Option<(String, String, bool)> {
+        if node.form().is_some() || self.known_value_name(node.cbor).is_some()
+        {
+            return None;
+        }
+        if let Some((names, _)) = self.tag_chain(node) {
+            let begin = names.iter().map(|name| format!("{name}(")).collect();
+            return Some((begin, ")".repeat(names.len()), false));
+        }
+        group_delimiters(node.cbor)
+    }
+
+    /// Returns the names of the tags of a chain of tagged items written as a
+    /// single group, and the content of the innermost one, or `None` if the
+    /// item doesn't start a chain of at least two registered tags or tag
+    /// chains aren't collapsed.
+    fn tag_chain<'c>(
+        &self,
+        node: Node<'c>,
+    ) -> Option<(Vec<String>, Node<'c>)> {
+        if !self.options.collapse_tag_chains || self.options.deterministic {
+            return None;
+        }
+        let mut names = Vec::new();
+        let mut content = node;
+        while let CBORCase::Tagged(tag, item) = content.cbor.as_case() {
+            if !names.is_empty()
+                && (content.form().is_some()
+                    || self.known_value_name(content.cbor).is_some())
+            {
+                break;
+            }
+            let Some(name) =
+                with_tags_store(|tags| tags.assigned_name_for_tag(tag))
+                    .flatten()
+            else {
+                break;
+            };
+            names.push(name);
+            content = content.child(item, 0);
+        }
+        (names.len() > 1).then_some((names, content))
+    }
+
+    /// Returns the name a known value is written with, if known value names
+    /// are written.
+    fn known_value_name(&self, cbor: &CBOR) -> Option<String> {
+        if !self.options.known_value_names {
+            return None;
+        }
+        let CBORCase::Tagged(tag, item) = cbor.as_case() else {
+            return None;
+        };
+        let CBORCase::Unsigned(value) = item.as_case() else {
+            return None;
+        };
+        if tag.value() != KNOWN_VALUE_TAG {
+            return None;
+        }
+        let name = known_value_info(*value)
+            .name
+            .filter(|_| !self.options.deterministic);
+        Some(format!("'{}'", name.unwrap_or_else(|| value.to_string())))
+    }
+
+    /// Returns `true` if known value names are written, and `cbor` is a
+    /// non-empty map whose keys and values are all known values.
+    fn is_known_value_map(&self, cbor: &CBOR) -> bool {
+        let CBORCase::Map(map) = cbor.as_case() else {
+            return false;
+        };
+        !map.is_empty()
+            && map.iter().all(|(key, value)| {
+                self.known_value_name(key).is_some()
+                    && self.known_value_name(value).is_some()
+            })
+    }
+
+    /// Returns the children of a group, with the entries of a map in the
+    /// order they were written if that order is kept.
+    fn children<'c>(
+        &self,
+        node: Node<'c>,
+    ) -> Box<dyn Iterator<Item = Node<'c>> + 'c> {
+        let order = node
+            .forms
+            .filter(|_| self.options.preserve_map_order)
+            .and_then(|forms| forms.entry_order.as_ref());
+        if let Some((_, content)) = self.tag_chain(node) {
+            return Box::new(std::iter::once(content));
+        }
+        match (node.cbor.as_case(), order) {
+            (CBORCase::Map(map), Some(order)) => {
+                let entries: Vec<_> = map.iter().collect();
+                Box::new(order.iter().flat_map(move |&index| {
+                    let (key, value) = entries[index];
+                    [
+                        node.child(key, index * 2),
+                        node.child(value, index * 2 + 1),
+                    ]
+                }))
+            }
+            _ => Box::new(
+                children(node.cbor)
+                    .enumerate()
+                    .map(move |(index, child)| node.child(child, index)),
+            ),
+        }
+    }
+
+    fn atom(&mut self, node: Node<'_>) -> fmt::Result {
+        if let Some(form) = node.form() {
+            return write!(self.w, "{form}");
+        }
+        let cbor = node.cbor;
+        if let Some(name) = self.known_value_name(cbor) {
+            return write!(self.w, "{name}");
+        }
+        if let Some(directive) = self.externalized(cbor) {
+            return write!(self.w, "{directive}");
+        }
+        if let Some(redacted) = self.redacted(cbor) {
+            return write!(self.w, "{redacted}");
+        }
+        match cbor.as_case() {
+            CBORCase::ByteString(bytes) => {
+                write!(self.w, "h'")?;
+                for chunk in bytes.chunks(CHUNK_LEN) {
+                    write!(self.w, "{}", hex::encode(chunk))?;
+                }
+                write!(self.w, "'")
+            }
+            CBORCase::Text(text) => {
+                write!(self.w, "\"")?;
+                for (index, part) in text.split('"').enumerate() {
+                    if index > 0 {
+                        write!(self.w, "\\\"")?;
+                    }
+                    write!(self.w, "{part}")?;
+                }
+                write!(self.w, "\"")
+            }
+            CBORCase::Simple(Simple::Float(value)) => {
+                write!(self.w, "{}", format_float(*value, self.options))
+            }
+            _ => write!(self.w, "{cbor}"),
+        }
+    }
+
+    /// Returns the directive written in place of a byte string that is
+    /// externalized, or `None` if it is written in the source.
+    fn externalized(&self, cbor: &CBOR) -> Option<String> {
+        let threshold = self.options.externalize_byte_strings?;
+        let CBORCase::ByteString(bytes) = cbor.as_case() else {
+            return None;
+        };
+        if bytes.len() <= threshold {
+            return None;
+        }
+        let digest = hex::encode(bc_crypto::sha256(bytes.data()));
+        Some(format!("!bytes(\"{digest}\")"))
+    }
+
+    /// Returns the text written in place of a string that is redacted, or
+    /// `None` if it is written in full.
+    fn redacted(&self, cbor: &CBOR) -> Option<String> {
+        let threshold = match self.options.redaction {
+            Redaction::None => return None,
+            Redaction::Elide { threshold } | Redaction::Hash { threshold } => {
+                threshold
+            }
+        };
+        let (bytes, is_text) = match cbor.as_case() {
+            CBORCase::ByteString(bytes) => (bytes.data(), false),
+            CBORCase::Text(text) => (text.as_bytes(), true),
+            _ => return None,
+        };
+        if bytes.len() <= threshold {
+            return None;
+        }
+        let len = bytes.len();
+        if let Redaction::Hash { .. } = self.options.redaction {
+            let digest = hex::encode(bc_crypto::sha256(bytes));
+            return Some(format!("ELIDED(sha256: {digest})"));
+        }
+        if is_text {
+            let text = std::str::from_utf8(bytes).unwrap();
+            let head: String = text.chars().take(4).collect();
+            let tail: String = text.chars().rev().take(4).collect();
+            let tail: String = tail.chars().rev().collect();
+            let escape = |s: &str| s.replace('"', "\\\"");
+            Some(format!(
+                "\"{}…({len} bytes)…{}\"",
+                escape(&head),
+                escape(&tail)
+            ))
+        } else {
+            let head = hex::encode(&bytes[..2.min(len)]);
+            let tail = hex::encode(&bytes[len.saturating_sub(2)..]);
+            Some(format!("h'{head}…({len} bytes)…{tail}'"))
+        }
+    }
+
+    fn comment(&mut self, node: Node<'_>, padding: &str) -> fmt::Result {
+        match self.comment_text(node) {
+            Some(comment) => write!(self.w, "{padding}/ {comment} /"),
+            None => Ok(()),
+        }
+    }
+
+    fn comment_text(&self, node: Node<'_>) -> Option<String> {
+        let CBORCase::Tagged(tag, item) = node.cbor.as_case() else {
+            return None;
+        };
+        if !self.options.annotate_known_values
+            || self.options.deterministic
+            || node.form().is_some()
+            || self.known_value_name(node.cbor).is_some()
+            || tag.value() != KNOWN_VALUE_TAG
+        {
+            return None;
+        }
+        let CBORCase::Unsigned(value) = item.as_case() else {
+            return None;
+        };
+        Some(known_value_info(*value).to_string())
+    }
+
+    /// Returns `true` if the written part of the group contains another
+    /// group, or if its atoms together are longer than 20 bytes.
+    fn is_multiline(&self, node: Node<'_>, depth: usize) -> bool {
+        if self.delimiters(node).is_none() {
+            return false;
+        }
+        let (shown, _) = self.shown_len(node.cbor, depth);
+        self.children(node)
+            .take(shown)
+            .any(|child| self.delimiters(child).is_some())
+            || self.atoms_len(node, depth, MULTILINE_LEN) > MULTILINE_LEN
+    }
+
+    /// Returns the total length of the written atoms within an item,
+    /// stopping early once it exceeds `limit`.
+    fn atoms_len(&self, node: Node<'_>, depth: usize, limit: usize) -> usize {
+        if self.delimiters(node).is_none() {
+            return self.atom_len(node);
+        }
+        let (shown, _) = self.shown_len(node.cbor, depth);
+        let mut len = 0;
+        for child in self.children(node).take(shown) {
+            len += self.atoms_len(child, depth + 1, limit - len.min(limit));
+            if len > limit {
+                break;
+            }
+        }
+        len
+    }
+
+    fn atom_len(&self, node: Node<'_>) -> usize {
+        if let Some(form) = node.form() {
+            return form.len();
+        }
+        let cbor = node.cbor;
+        if let Some(name) = self.known_value_name(cbor) {
+            return name.len();
+        }
+        if let Some(directive) = self.externalized(cbor) {
+            return directive.len();
+        }
+        if let Some(redacted) = self.redacted(cbor) {
+            return redacted.len();
+        }
+        match cbor.as_case() {
+            CBORCase::ByteString(bytes) => bytes.len() * 2 + 3,
+            CBORCase::Text(text) => {
+                text.len() + text.matches('"').count() + 2
+            }
+            CBORCase::Simple(Simple::Float(value)) => {
+                format_float(*value, self.options).len()
+            }
+            _ => cbor.to_string().len(),
+        }
+    }
+}
+
+/// A group whose atoms are longer than this is written on multiple lines.
+const MULTILINE_LEN: usize = 20;
+
+/// Returns the opening and closing delimiters of a group, and whether its
+/// children are key-value pairs, or `None` if `cbor` is an atom.
+fn group_delimiters(cbor: &CBOR) -> Option<(String, String, bool)> {
+    match cbor.as_case() {
+        CBORCase::Array(_) => Some(("[".to_string(), "]".to_string(), false)),
+        CBORCase::Map(_) => Some(("{".to_string(), "}".to_string(), true)),
+        CBORCase::Tagged(tag, _) => {
+            Some((format!("{}(", tag.value()), ")".to_string(), false))
+        }
+        _ => None,
+    }
+}
+
+fn children_len(cbor: &CBOR) -> usize {
+    match cbor.as_case() {
+        CBORCase::Array(array) => array.len(),
+        CBORCase::Map(map) => map.len() * 2,
+        CBORCase::Tagged(_, _) => 1,
+        _ => 0,
+    }
+}
+
+fn children(cbor: &CBOR) -> Box<dyn Iterator<Item = &CBOR> + '_> {
+    match cbor.as_case() {
+        CBORCase::Array(array) => Box::new(array.iter()),
+        CBORCase::Map(map) => {
+            Box::new(map.iter().flat_map(|(key, value)| [key, value]))
+        }
+        CBORCase::Tagged(_, item) => Box::new(std::iter::once(item)),
+        _ => Box::new(std::iter::empty()),
+    }
+}
@@ -0,0 +1,69 @@
+use dcbor::prelude::*;
+
+use crate::{DiagValue, SourceSpan};
+
+/// Records that a tagged value in a parsed item was written as a `ur:`
+/// literal, so tools can re-emit it as a UR rather than as a generic tagged
+/// value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrProvenance {
+    /// The UR type, such as `date` in `ur:date/...`.
+    pub ur_type: String,
+    /// The tag the UR type resolved to.
+    pub tag: Tag,
+    /// The sequence number and count of the part, or `None` for a single-part
+    /// UR.
+    pub part: Option<(usize, usize)>,
+    /// The span of the `ur:` literal.
+    pub span: SourceSpan,
+}
+
+impl UrProvenance {
+    /// Returns the provenance of every UR in a parsed value, in source order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{UrProvenance, parse_diag_value};
+    /// dcbor::register_tags();
+    /// let src = "[1, ur:date/cyisdadmlasgtapttl]";
+    /// let value = parse_diag_value(src).unwrap();
+    /// let urs = UrProvenance::collect(&value);
+    /// assert_eq!(urs.len(), 1);
+    /// assert_eq!(urs[0].ur_type, "date");
+    /// assert_eq!(urs[0].tag.value(), 1);
+    /// assert_eq!(urs[0].part, None);
+    /// assert_eq!(&src[urs[0].span.range()], "ur:date/cyisdadmlasgtapttl");
+    /// ```
+    pub fn collect(value: &DiagValue) -> Vec<UrProvenance> {
+        let mut result = Vec::new();
+        visit(value, &mut result);
+        result
+    }
+}
+
+//
+// === Private Functions ===
+//
+
+fn visit(value: &DiagValue, result: &mut Vec<UrProvenance>) {
+    match value {
+        DiagValue::Ur { ur, tag, span, .. } => result.push(UrProvenance {
+            ur_type: ur.ur_type_str().to_string(),
+            tag: tag.clone(),
+            part: None,
+            span: *span,
+        }),
+        DiagValue::Tagged { item, .. } => visit(item, result),
+        DiagValue::Array { items, .. } => {
+            items.iter().for_each(|item| visit(item, result));
+        }
+        DiagValue::Map { entries, .. } => {
+            for (key, value) in entries {
+                visit(key, result);
+                visit(value, result);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,134 @@
+use bc_ur::prelude::*;
+
+/// The error correction level of a QR code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QrErrorCorrection {
+    /// Recovers about 7% of the code.
+    #[default]
+    Low,
+    /// Recovers about 15% of the code.
+    Medium,
+    /// Recovers about 25% of the code.
+    Quartile,
+    /// Recovers about 30% of the code.
+    High,
+}
+
+/// How a UR fits into QR codes, as returned by [`qr_fit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrFit {
+    /// The UR fits in a single QR code of `version`.
+    Single { version: u8 },
+    /// The UR must be split into `parts` multi-part URs, encoded with a
+    /// maximum fragment length of `max_fragment_len` bytes, each of which
+    /// fits in a QR code of `version`.
+    MultiPart { parts: usize, max_fragment_len: usize, version: u8 },
+}
+
+/// Returns how `ur` fits into QR codes of at most `max_version` (1 to 40)
+/// with the error correction level `correction`, or `None` if not even a
+/// multi-part UR with the shortest fragments fits.
+///
+/// URs are written in upper case, so that the QR code can use alphanumeric
+/// mode. A multi-part UR uses the largest fragments that fit, so that it has
+/// as few parts as possible. Only the first `parts` parts are measured;
+/// fountain-coded parts after them have longer sequence numbers, and may
+/// need a few more characters.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{QrErrorCorrection, QrFit, qr_fit};
+/// # use bc_ur::prelude::*;
+/// let ur = UR::new("bytes", CBOR::to_byte_string(vec![0; 1000])).unwrap();
+/// assert_eq!(
+///     qr_fit(&ur, 40, QrErrorCorrection::Low),
+///     Some(QrFit::Single { version: 27 })
+/// );
+/// assert_eq!(
+///     qr_fit(&ur, 10, QrErrorCorrection::Medium),
+///     Some(QrFit::MultiPart { parts: 8, max_fragment_len: 143, version: 10 })
+/// );
+/// ```
+pub fn qr_fit(
+    ur: &UR,
+    max_version: u8,
+    correction: QrErrorCorrection,
+) -> Option<QrFit> {
+    let capacity = qr_capacity(max_version.min(40), correction)?;
+    let len = ur.qr_string().len();
+    if len <= capacity {
+        let version = qr_version(len, correction)?;
+        return Some(QrFit::Single { version });
+    }
+
+    // Find the largest fragment length whose parts all fit.
+    let message_len = ur.cbor().to_cbor_data().len();
+    let (mut low, mut high) = (0, message_len);
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if longest_part(ur, mid) <= capacity {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    if low == 0 {
+        return None;
+    }
+    let encoder = MultipartEncoder::new(ur, low).ok()?;
+    Some(QrFit::MultiPart {
+        parts: encoder.parts_count(),
+        max_fragment_len: low,
+        version: qr_version(longest_part(ur, low), correction)?,
+    })
+}
+
+/// Returns the length of the longest of the first parts of `ur` when split
+/// into fragments of at most `max_fragment_len` bytes.
+fn longest_part(ur: &UR, max_fragment_len: usize) -> usize {
+    let Ok(mut encoder) = MultipartEncoder::new(ur, max_fragment_len) else {
+        return usize::MAX;
+    };
+    (0..encoder.parts_count())
+        .map(|_| encoder.next_part().map_or(usize::MAX, |part| part.len()))
+        .max()
+        .unwrap_or(usize::MAX)
+}
+
+/// Returns the smallest QR code version that holds `len` alphanumeric
+/// characters.
+fn qr_version(len: usize, correction: QrErrorCorrection) -> Option<u8> {
+    (1..=40).find(|&version| {
+        qr_capacity(version, correction).is_some_and(|c| len <= c)
+    })
+}
+
+/// Returns the number of alphanumeric characters a QR code of `version`
+/// holds.
+fn qr_capacity(version: u8, correction: QrErrorCorrection) -> Option<usize> {
+    let index = usize::from(version).checked_sub(1)?;
+    let capacities = ALPHANUMERIC_CAPACITY.get(index)?;
+    Some(capacities[correction as usize])
+}
+
+/// Alphanumeric capacities of QR code versions 1 to 40, at error correction
+/// levels L, M, Q, and H.
+#[rustfmt::skip]
+const ALPHANUMERIC_CAPACITY: [[usize; 4]; 40] = [
+    [25, 20, 16, 10], [47, 38, 29, 20], [77, 61, 47, 35], [114, 90, 67, 50],
+    [154, 122, 87, 64], [195, 154, 108, 84], [224, 178, 125, 93],
+    [279, 221, 157, 122], [335, 262, 189, 143], [395, 311, 221, 174],
+    [468, 366, 259, 200], [535, 419, 296, 227], [619, 483, 352, 259],
+    [667, 528, 376, 283], [758, 600, 426, 321], [854, 656, 470, 365],
+    [938, 734, 531, 408], [1046, 816, 574, 452], [1153, 909, 644, 493],
+    [1249, 970, 702, 557], [1352, 1035, 742, 587], [1460, 1134, 823, 640],
+    [1588, 1248, 890, 672], [1704, 1326, 963, 744], [1853, 1451, 1041, 779],
+    [1990, 1542, 1094, 864], [2132, 1637, 1172, 910], [2223, 1732, 1263, 958],
+    [2369, 1839, 1322, 1016], [2520, 1994, 1429, 1080],
+    [2677, 2113, 1499, 1150], [2840, 2238, 1618, 1226],
+    [3009, 2369, 1700, 1307], [3183, 2506, 1787, 1394],
+    [3351, 2632, 1867, 1431], [3537, 2780, 1966, 1530],
+    [3729, 2894, 2071, 1591], [3927, 3054, 2181, 1658],
+    [4087, 3220, 2298, 1774], [4296, 3391, 2420, 1852],
+];
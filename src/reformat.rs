@@ -0,0 +1,336 @@
+use dcbor::prelude::*;
+
+use crate::{
+    DiagValue, ParseError, ParseResult, PathSegment, PrintOptions, SourceSpan,
+    format_diagnostic, parse_diag_value,
+};
+
+/// Returns the text of `src` edited to represent `edited`, changing as little
+/// of it as possible.
+///
+/// Every part of `edited` that is equal to the corresponding part of the item
+/// in `src` keeps its original text, along with the whitespace and comments
+/// around it. Array items are matched up by their longest common
+/// subsequence, and map entries by their keys. Items that are new or
+/// changed, other than arrays, maps, and tagged values that can be edited in
+/// place, are written on a single line with `options`. New array items and
+/// map entries are added with the separator already used between the items
+/// of their group, and new map entries are added at the end of the map.
+///
+/// Tools that update fixtures can use this so that each update is a
+/// reviewable diff of what changed, rather than a rewrite of the whole file.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor::prelude::*;
+/// # use dcbor_parse::{PrintOptions, reformat_edited};
+/// let src =
+///     "{\n    \"name\": \"Alice\", # the user\n    \"ids\": [1, 2, 3]\n}";
+/// let mut map = Map::new();
+/// map.insert("name", "Alice");
+/// map.insert("ids", vec![1, 3, 4]);
+/// map.insert("admin", true);
+/// let text = reformat_edited(src, &map.into(), &PrintOptions::default())
+///     .unwrap();
+/// assert_eq!(
+///     text,
+///     "{\n    \"name\": \"Alice\", # the user\n    \"ids\": [1, 3, 4],\n    \
+///      \"admin\": true\n}"
+/// );
+/// ```
+pub fn reformat_edited(
+    src: &str,
+    edited: &CBOR,
+    options: &PrintOptions,
+) -> ParseResult<String> {
+    let value = parse_diag_value(src)?;
+    let options = options.clone().flat(true);
+    let span = value.span();
+    let text = Reformatter { src, options: &options }.value(&value, edited);
+    Ok(format!("{}{text}{}", &src[..span.start], &src[span.end..]))
+}
+
+/// Returns the text of `src` with the item at `path` replaced by `new_item`,
+/// which is written in diagnostic notation, leaving the rest of the text,
+/// including its comments, as it was.
+///
+/// Only the text of the item in `new_item` is spliced in, without any
+/// whitespace or comments around it. An error in `new_item` has a span
+/// within `new_item`. If there is no item at `path`, the error is
+/// [`PathNotFound`](ParseError::PathNotFound), with the span of the last
+/// item the path led to. A path can't lead into an `!include`d document,
+/// whose text isn't in `src`.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor::prelude::*;
+/// # use dcbor_parse::{ParseError, PathSegment, replace_at};
+/// let src = "{\n  \"version\": 1, # bump me\n  \"ids\": [1, 2]\n}";
+/// let path = [PathSegment::Key(CBOR::from("version"))];
+/// assert_eq!(
+///     replace_at(src, &path, "2").unwrap(),
+///     "{\n  \"version\": 2, # bump me\n  \"ids\": [1, 2]\n}"
+/// );
+///
+/// let path = [PathSegment::Key(CBOR::from("ids")), PathSegment::Index(2)];
+/// let error = replace_at(src, &path, "3").unwrap_err();
+/// assert_eq!(error.to_string(), r#"No item at path ["ids"][2]"#);
+/// assert_eq!(&src[error.span().unwrap().range()], "[1, 2]");
+/// ```
+pub fn replace_at(
+    src: &str,
+    path: &[PathSegment],
+    new_item: &str,
+) -> ParseResult<String> {
+    let value = parse_diag_value(src)?;
+    let span = source_item_at(&value, path)?.span();
+    let replacement = parse_diag_value(new_item)?;
+    let text = &new_item[replacement.span().range()];
+    Ok(format!("{}{text}{}", &src[..span.start], &src[span.end..]))
+}
+
+//
+// === Private Functions ===
+//
+
+struct Reformatter<'a> {
+    src: &'a str,
+    options: &'a PrintOptions,
+}
+
+/// An item of a rebuilt array or map: the new text of the item at an index
+/// of the original, or the text of a new item.
+enum Piece {
+    Old(usize, String),
+    New(String),
+}
+
+impl Reformatter<'_> {
+    fn value(&self, value: &DiagValue, edited: &CBOR) -> String {
+        if value.to_cbor() == *edited {
+            return self.src[value.span().range()].to_string();
+        }
+        match (value, edited.as_case()) {
+            (DiagValue::Array { items, span }, CBORCase::Array(edited))
+                if !items.is_empty() && !edited.is_empty() =>
+            {
+                self.array(items, *span, edited)
+            }
+            (DiagValue::Map { entries, span }, CBORCase::Map(edited))
+                if !entries.is_empty() && !edited.is_empty() =>
+            {
+                self.map(entries, *span, edited)
+            }
+            (
+                DiagValue::Tagged { tag, item, span, .. },
+                CBORCase::Tagged(edited_tag, content),
+            ) if tag.value() == edited_tag.value() => {
+                let item_span = item.span();
+                format!(
+                    "{}{}{}",
+                    &self.src[span.start..item_span.start],
+                    self.value(item, content),
+                    &self.src[item_span.end..span.end]
+                )
+            }
+            _ => self.fresh(edited),
+        }
+    }
+
+    fn fresh(&self, cbor: &CBOR) -> String {
+        format_diagnostic(cbor, self.options)
+    }
+
+    fn array(
+        &self,
+        items: &[DiagValue],
+        span: SourceSpan,
+        edited: &[CBOR],
+    ) -> String {
+        let old: Vec<CBOR> = items.iter().map(DiagValue::to_cbor).collect();
+        let mut matches = common_subsequence(&old, edited);
+        matches.push((items.len(), edited.len()));
+        let mut pieces = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        for (match_i, match_j) in matches {
+            // Between two matches, the items that are left are edited in
+            // place in order, and the rest are removed or added.
+            while i < match_i && j < match_j {
+                let text = self.value(&items[i], &edited[j]);
+                pieces.push(Piece::Old(i, text));
+                i += 1;
+                j += 1;
+            }
+            for cbor in &edited[j..match_j] {
+                pieces.push(Piece::New(self.fresh(cbor)));
+            }
+            if match_i < items.len() {
+                let text = &self.src[items[match_i].span().range()];
+                pieces.push(Piece::Old(match_i, text.to_string()));
+            }
+            i = match_i + 1;
+            j = match_j + 1;
+        }
+        let spans: Vec<_> = items.iter().map(DiagValue::span).collect();
+        self.rebuild(span, &spans, pieces)
+    }
+
+    fn map(
+        &self,
+        entries: &[(DiagValue, DiagValue)],
+        span: SourceSpan,
+        edited: &Map,
+    ) -> String {
+        let mut pieces = Vec::new();
+        let mut kept = Vec::new();
+        for (index, (key, value)) in entries.iter().enumerate() {
+            let key_cbor = key.to_cbor();
+            if let Some(edited_value) =
+                edited.get::<CBOR, CBOR>(key_cbor.clone())
+            {
+                // The key is unchanged, so only the value can differ.
+                let key_text = &self.src[key.span().start..value.span().start];
+                let value_text = self.value(value, &edited_value);
+                let text = format!("{key_text}{value_text}");
+                pieces.push(Piece::Old(index, text));
+                kept.push(key_cbor);
+            }
+        }
+        for (key, value) in edited.iter() {
+            if !kept.contains(key) {
+                let (key, value) = (self.fresh(key), self.fresh(value));
+                pieces.push(Piece::New(format!("{key}: {value}")));
+            }
+        }
+        let spans: Vec<SourceSpan> = entries
+            .iter()
+            .map(|(key, value)| key.span().merge(value.span()))
+            .collect();
+        self.rebuild(span, &spans, pieces)
+    }
+
+    /// Rebuilds a non-empty group from the spans of its original items and
+    /// the pieces it now holds. The text before the first item and after the
+    /// last is kept, and so is the text after each original item that is
+    /// still followed by another, along with any comment on it.
+    fn rebuild(
+        &self,
+        span: SourceSpan,
+        spans: &[SourceSpan],
+        pieces: Vec<Piece>,
+    ) -> String {
+        let src = self.src;
+        let open = &src[span.start..spans[0].start];
+        let close = &src[spans[spans.len() - 1].end..span.end];
+        let separator = match spans {
+            [.., before, last] => {
+                new_item_separator(&src[before.end..last.start])
+            }
+            _ => ", ".to_string(),
+        };
+        let mut result = open.to_string();
+        let mut previous: Option<usize> = None;
+        for (position, piece) in pieces.into_iter().enumerate() {
+            if position > 0 {
+                match previous {
+                    Some(index) if index + 1 < spans.len() => {
+                        let after = spans[index].end..spans[index + 1].start;
+                        result.push_str(&src[after]);
+                    }
+                    _ => result.push_str(&separator),
+                }
+            }
+            let text = match piece {
+                Piece::Old(index, text) => {
+                    previous = Some(index);
+                    text
+                }
+                Piece::New(text) => {
+                    previous = None;
+                    text
+                }
+            };
+            result.push_str(&text);
+        }
+        result.push_str(close);
+        result
+    }
+}
+
+/// Returns the item at `path` within `value`, without following it into
+/// included documents.
+fn source_item_at<'a>(
+    value: &'a DiagValue,
+    path: &[PathSegment],
+) -> ParseResult<&'a DiagValue> {
+    let mut item = value;
+    for segment in path {
+        let child = match (segment, item) {
+            (PathSegment::Index(index), DiagValue::Array { items, .. }) => {
+                items.get(*index)
+            }
+            (PathSegment::Key(key), DiagValue::Map { entries, .. }) => entries
+                .iter()
+                .find(|(k, _)| k.to_cbor() == *key)
+                .map(|(_, value)| value),
+            (PathSegment::Content, DiagValue::Tagged { item, .. }) => {
+                Some(item.as_ref())
+            }
+            _ => None,
+        };
+        let Some(child) = child else {
+            let path = path.iter().map(ToString::to_string).collect();
+            return Err(ParseError::PathNotFound(path, item.span()));
+        };
+        item = child;
+    }
+    Ok(item)
+}
+
+/// Returns the separator for new items of a group, given the one between
+/// its last two items: a comma and a new line with the same indentation if
+/// the group is written on several lines, and `, ` otherwise. Any comments
+/// in the separator are left where they were.
+fn new_item_separator(between: &str) -> String {
+    match between.rsplit_once('\n') {
+        Some((line, indent))
+            if indent.bytes().all(|b| b == b' ' || b == b'\t') =>
+        {
+            let ending = if line.ends_with('\r') { "\r\n" } else { "\n" };
+            format!(",{ending}{indent}")
+        }
+        _ => ", ".to_string(),
+    }
+}
+
+/// Returns the pairs of indexes of a longest common subsequence of `a` and
+/// `b`, in ascending order.
+fn common_subsequence(a: &[CBOR], b: &[CBOR]) -> Vec<(usize, usize)> {
+    let (n, m) = (a.len(), b.len());
+    let mut lengths = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
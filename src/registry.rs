@@ -0,0 +1,34 @@
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+use dcbor::prelude::*;
+use known_values::KnownValuesStore;
+
+/// Runs `f` with the global tags registry, or returns `None` if the registry
+/// is unavailable.
+///
+/// The registry is unavailable if its initialization failed, or if a thread
+/// panicked while holding its lock. The registry reports both by panicking,
+/// which is caught here so parsing can return an error instead.
+pub(crate) fn with_tags_store<T>(
+    f: impl FnOnce(&TagsStore) -> T,
+) -> Option<T> {
+    catch_unwind(AssertUnwindSafe(|| {
+        let binding = dcbor::GLOBAL_TAGS.get();
+        binding.as_ref().map(f)
+    }))
+    .ok()
+    .flatten()
+}
+
+/// Runs `f` with the global known values registry, or returns `None` if the
+/// registry is unavailable, as with [`with_tags_store`].
+pub(crate) fn with_known_values<T>(
+    f: impl FnOnce(&KnownValuesStore) -> T,
+) -> Option<T> {
+    catch_unwind(AssertUnwindSafe(|| {
+        let binding = known_values::KNOWN_VALUES.get();
+        binding.as_ref().map(f)
+    }))
+    .ok()
+    .flatten()
+}
@@ -0,0 +1,126 @@
+use dcbor::{Simple, prelude::*};
+
+/// Returns Rust code that reconstructs a CBOR item using the `dcbor` API.
+///
+/// The code is a single expression that compiles where `dcbor::prelude::*`
+/// is in scope, so fixtures captured in diagnostic notation can be pasted
+/// into unit tests as constants. Arrays, maps, and tagged items are spread
+/// over several lines and indented by four spaces.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{parse_dcbor_item, to_rust_literal};
+/// # use dcbor::prelude::*;
+/// let cbor = parse_dcbor_item(r#"{"a": [1, -2], "b": h'0102'}"#).unwrap();
+/// let code = to_rust_literal(&cbor);
+/// assert_eq!(
+///     code,
+///     r#"{
+///     let mut map = Map::new();
+///     map.insert(CBOR::from("a"), CBOR::from(vec![
+///         CBOR::from(1u64),
+///         CBOR::from(-2i64),
+///     ]));
+///     map.insert(CBOR::from("b"), CBOR::to_byte_string_from_hex("0102"));
+///     CBOR::from(map)
+/// }"#
+/// );
+///
+/// let rebuilt = {
+///     let mut map = Map::new();
+///     map.insert(CBOR::from("a"), CBOR::from(vec![
+///         CBOR::from(1u64),
+///         CBOR::from(-2i64),
+///     ]));
+///     map.insert(CBOR::from("b"), CBOR::to_byte_string_from_hex("0102"));
+///     CBOR::from(map)
+/// };
+/// assert_eq!(rebuilt, cbor);
+/// ```
+pub fn to_rust_literal(cbor: &CBOR) -> String {
+    let mut result = String::new();
+    write_item(cbor, 0, &mut result);
+    result
+}
+
+//
+// === Private Functions ===
+//
+
+const INDENT: &str = "    ";
+
+fn write_item(cbor: &CBOR, level: usize, out: &mut String) {
+    let indent = INDENT.repeat(level);
+    match cbor.as_case() {
+        CBORCase::Unsigned(n) => out.push_str(&format!("CBOR::from({n}u64)")),
+        CBORCase::Negative(n) => match i64::try_from(*n) {
+            Ok(n) => out.push_str(&format!("CBOR::from({}i64)", -1 - n)),
+            Err(_) => out.push_str(&format!(
+                "CBOR::from(CBORCase::Negative({n}))"
+            )),
+        },
+        CBORCase::ByteString(bytes) => out.push_str(&format!(
+            "CBOR::to_byte_string_from_hex({:?})",
+            hex::encode(bytes)
+        )),
+        CBORCase::Text(text) => out.push_str(&format!("CBOR::from({text:?})")),
+        CBORCase::Array(items) if items.is_empty() => {
+            out.push_str("CBOR::from(Vec::<CBOR>::new())");
+        }
+        CBORCase::Array(items) => {
+            out.push_str("CBOR::from(vec![\n");
+            for item in items {
+                out.push_str(&indent);
+                out.push_str(INDENT);
+                write_item(item, level + 1, out);
+                out.push_str(",\n");
+            }
+            out.push_str(&indent);
+            out.push_str("])");
+        }
+        CBORCase::Map(map) if map.is_empty() => {
+            out.push_str("CBOR::from(Map::new())");
+        }
+        CBORCase::Map(map) => {
+            let inner = format!("{indent}{INDENT}");
+            out.push_str("{\n");
+            out.push_str(&format!("{inner}let mut map = Map::new();\n"));
+            for (key, value) in map.iter() {
+                out.push_str(&format!("{inner}map.insert("));
+                write_item(key, level + 1, out);
+                out.push_str(", ");
+                write_item(value, level + 1, out);
+                out.push_str(");\n");
+            }
+            out.push_str(&format!("{inner}CBOR::from(map)\n"));
+            out.push_str(&indent);
+            out.push('}');
+        }
+        CBORCase::Tagged(tag, item) => {
+            out.push_str(&format!("CBOR::to_tagged_value({}, ", tag.value()));
+            write_item(item, level, out);
+            out.push(')');
+        }
+        CBORCase::Simple(simple) => match simple {
+            Simple::False => out.push_str("CBOR::r#false()"),
+            Simple::True => out.push_str("CBOR::r#true()"),
+            Simple::Null => out.push_str("CBOR::null()"),
+            Simple::Float(f) => {
+                out.push_str(&format!("CBOR::from({})", float_literal(*f)))
+            }
+        },
+    }
+}
+
+fn float_literal(f: f64) -> String {
+    if f.is_nan() {
+        "f64::NAN".to_string()
+    } else if f == f64::INFINITY {
+        "f64::INFINITY".to_string()
+    } else if f == f64::NEG_INFINITY {
+        "f64::NEG_INFINITY".to_string()
+    } else {
+        format!("{f:?}f64")
+    }
+}
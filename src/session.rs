@@ -0,0 +1,339 @@
+use std::{fmt, sync::Arc};
+
+use dcbor::prelude::*;
+use known_values::KnownValuesStore;
+
+use crate::{
+    DiagValue, LiteralForm, ParseError, ParseOptions, SourceSpan, Warning,
+    WarningKind, parse_diag_value_with_options,
+    registry::{with_known_values, with_tags_store},
+};
+
+/// An error or warning about one of the documents in a [`Session`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionDiagnostic {
+    /// The document failed to parse.
+    Error { document: String, error: ParseError },
+    /// The document parsed, but has a problem.
+    Warning { document: String, warning: Warning },
+}
+
+impl SessionDiagnostic {
+    /// Returns the name of the document the diagnostic is about.
+    pub fn document(&self) -> &str {
+        match self {
+            SessionDiagnostic::Error { document, .. }
+            | SessionDiagnostic::Warning { document, .. } => document,
+        }
+    }
+
+    /// Returns `true` if the diagnostic is an error.
+    pub fn is_error(&self) -> bool {
+        matches!(self, SessionDiagnostic::Error { .. })
+    }
+}
+
+/// A set of named documents that are parsed and checked as a unit, such as a
+/// suite of fixtures.
+///
+/// Each document is parsed as it is added, with the options set by
+/// [`with_options`](Self::with_options). Errors and warnings are
+/// accumulated across all of the documents, so a tool can report every
+/// problem in the set at once rather than stopping at the first.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::Session;
+/// let mut session = Session::new();
+/// session.add("a.diag", "[1, 2, 3]");
+/// session.add("b.diag", "{1: 2, 1: 3}");
+/// session.add("c.diag", "'45'");
+///
+/// assert_eq!(session.get("a.diag").unwrap().diagnostic(), "[1, 2, 3]");
+/// assert!(session.get("b.diag").is_none());
+/// assert_eq!(session.error_count(), 1);
+/// assert_eq!(session.diagnostics().len(), 2);
+/// assert_eq!(
+///     session.report(),
+///     "b.diag: error: line 1: Duplicate map key\n\
+///      {1: 2, 1: 3}\n\
+///      \x20      ^\n\
+///      help: each key may appear only once in a map; keys that encode \
+///      identically, such as `1` and `1.0`, are duplicates\n\
+///      c.diag:1:1: warning: unregistered codepoint in reserved range: \
+///      '45' (unregistered, General 0-49)"
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    documents: Vec<Document>,
+    diagnostics: Vec<SessionDiagnostic>,
+    pinned: Option<Arc<RegistrySnapshot>>,
+    options: ParseOptions,
+}
+
+#[derive(Debug, Clone)]
+struct Document {
+    name: String,
+    src: String,
+    value: Option<DiagValue>,
+}
+
+impl Session {
+    /// Creates an empty session.
+    pub fn new() -> Self { Self::default() }
+
+    /// Parses every document added later with `options`, rather than the
+    /// default options.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor_parse::{ParseOptions, Session};
+    /// let options = ParseOptions::default().integer_map_keys_only(true);
+    /// let mut session = Session::new().with_options(options);
+    /// assert!(session.add("a.diag", "{1: 2}"));
+    /// assert!(!session.add("b.diag", r#"{"a": 2}"#));
+    /// ```
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Takes a snapshot of the global tags and known values registries, and
+    /// warns about each tag name, UR type, or known value name in a document
+    /// added later that the registries resolve differently than the
+    /// snapshot does, with a
+    /// [`RegistryChanged`](WarningKind::RegistryChanged) warning.
+    ///
+    /// Long-running tools can call this when they create the session, so
+    /// that a registration made while they run can't silently change what
+    /// the documents mean.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use dcbor::{Tag, TagsStore, with_tags_mut};
+    /// # use dcbor_parse::Session;
+    /// with_tags_mut!(|tags: &mut TagsStore| {
+    ///     tags.insert(Tag::new(80001, "point"));
+    /// });
+    /// let mut session = Session::new().pin_registries();
+    /// session.add("before.diag", "point([1, 2])");
+    /// with_tags_mut!(|tags: &mut TagsStore| {
+    ///     tags.insert(Tag::new(80002, "point"));
+    /// });
+    /// session.add("after.diag", "point([3, 4])");
+    ///
+    /// assert_eq!(session.diagnostics().len(), 1);
+    /// assert_eq!(
+    ///     session.report(),
+    ///     "after.diag:1:1: warning: 'point' resolves to 80002, not 80001 \
+    ///      as when the session began"
+    /// );
+    /// ```
+    pub fn pin_registries(mut self) -> Self {
+        self.pinned = Some(Arc::new(RegistrySnapshot {
+            tags: with_tags_store(TagsStore::clone),
+            known_values: with_known_values(KnownValuesStore::clone),
+        }));
+        self
+    }
+
+    /// Parses `src` as the document named `name`, and returns `true` if it
+    /// parsed successfully.
+    ///
+    /// A document that was already added with the same name is replaced,
+    /// along with its diagnostics.
+    pub fn add(
+        &mut self,
+        name: impl Into<String>,
+        src: impl Into<String>,
+    ) -> bool {
+        let name = name.into();
+        let src = src.into();
+        self.remove(&name);
+        let value = match parse_diag_value_with_options(&src, &self.options) {
+            Ok(value) => {
+                let mut warnings = Warning::collect(&value);
+                if let Some(pinned) = &self.pinned {
+                    pinned.check(&value, &mut warnings);
+                }
+                let warnings = warnings.into_iter().map(|warning| {
+                    SessionDiagnostic::Warning {
+                        document: name.clone(),
+                        warning,
+                    }
+                });
+                self.diagnostics.extend(warnings);
+                Some(value)
+            }
+            Err(error) => {
+                self.diagnostics.push(SessionDiagnostic::Error {
+                    document: name.clone(),
+                    error,
+                });
+                None
+            }
+        };
+        let parsed = value.is_some();
+        self.documents.push(Document { name, src, value });
+        parsed
+    }
+
+    /// Removes the document named `name`, and its diagnostics. Returns `true`
+    /// if there was such a document.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.documents.len();
+        self.documents.retain(|document| document.name != name);
+        self.diagnostics.retain(|diagnostic| diagnostic.document() != name);
+        self.documents.len() != len
+    }
+
+    /// Returns the names of the documents, in the order they were added.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.documents.iter().map(|document| document.name.as_str())
+    }
+
+    /// Returns the source of the document named `name`.
+    pub fn source(&self, name: &str) -> Option<&str> {
+        self.document(name).map(|document| document.src.as_str())
+    }
+
+    /// Returns the parsed value of the document named `name`, or `None` if
+    /// there is no such document or it failed to parse.
+    pub fn value(&self, name: &str) -> Option<&DiagValue> {
+        self.document(name)?.value.as_ref()
+    }
+
+    /// Returns the item parsed from the document named `name`, or `None` if
+    /// there is no such document or it failed to parse.
+    pub fn get(&self, name: &str) -> Option<CBOR> {
+        self.value(name).map(DiagValue::to_cbor)
+    }
+
+    /// Returns the errors and warnings for every document, in the order the
+    /// documents were added.
+    pub fn diagnostics(&self) -> &[SessionDiagnostic] { &self.diagnostics }
+
+    /// Returns the number of documents that failed to parse.
+    pub fn error_count(&self) -> usize {
+        self.diagnostics.iter().filter(|d| d.is_error()).count()
+    }
+
+    /// Returns every diagnostic formatted for display, one after another.
+    ///
+    /// Errors are shown with the line of the source where they occurred, as
+    /// [`ParseError::full_message`] formats them. Warnings are shown on a
+    /// single line starting with the document name, line, and column.
+    pub fn report(&self) -> String {
+        let mut lines = Vec::new();
+        for diagnostic in &self.diagnostics {
+            let name = diagnostic.document();
+            let src = self.source(name).unwrap_or_default();
+            match diagnostic {
+                SessionDiagnostic::Error { error, .. } => {
+                    let message = error.full_message(src);
+                    lines.push(format!("{name}: error: {message}"));
+                }
+                SessionDiagnostic::Warning { warning, .. } => {
+                    let (start, _) = warning.span.line_columns(src);
+                    lines.push(format!(
+                        "{name}:{}:{}: warning: {}",
+                        start.line, start.column, warning.kind
+                    ));
+                }
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn document(&self, name: &str) -> Option<&Document> {
+        self.documents.iter().find(|document| document.name == name)
+    }
+}
+
+//
+// === Private Functions ===
+//
+
+/// The registries as they were when a session was pinned.
+struct RegistrySnapshot {
+    tags: Option<TagsStore>,
+    known_values: Option<KnownValuesStore>,
+}
+
+impl fmt::Debug for RegistrySnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegistrySnapshot").finish_non_exhaustive()
+    }
+}
+
+impl RegistrySnapshot {
+    /// Adds a warning for each name in `value` that resolved to something
+    /// other than what the snapshot resolves it to.
+    fn check(&self, value: &DiagValue, warnings: &mut Vec<Warning>) {
+        match value {
+            DiagValue::Tagged { tag, tag_text, item, span } => {
+                if value.literal_form() == LiteralForm::NameTaggedValue {
+                    let end = span.start + tag_text.len();
+                    let span = SourceSpan::new(span.start, end);
+                    let pinned = self.tag_named(tag_text);
+                    compare(tag_text, pinned, tag.value(), span, warnings);
+                }
+                self.check(item, warnings);
+            }
+            DiagValue::Ur { ur, tag, span, .. } => {
+                let name = ur.ur_type_str();
+                let start = span.start + "ur:".len();
+                let span = SourceSpan::new(start, start + name.len());
+                let pinned = self.tag_named(name);
+                compare(name, pinned, tag.value(), span, warnings);
+            }
+            DiagValue::KnownValue { text, value: known_value, span }
+                if value.literal_form() == LiteralForm::KnownValueName =>
+            {
+                let name = &text[1..text.len() - 1];
+                let pinned = self
+                    .known_values
+                    .as_ref()
+                    .and_then(|store| store.known_value_named(name))
+                    .map(|known_value| known_value.value());
+                compare(name, pinned, known_value.value(), *span, warnings);
+            }
+            DiagValue::Array { items, .. } => {
+                items.iter().for_each(|item| self.check(item, warnings));
+            }
+            DiagValue::Map { entries, .. } => {
+                for (key, value) in entries {
+                    self.check(key, warnings);
+                    self.check(value, warnings);
+                }
+            }
+            DiagValue::Included { item, .. } => self.check(item, warnings),
+            _ => {}
+        }
+    }
+
+    /// Returns the value of the tag named `name` in the snapshot.
+    fn tag_named(&self, name: &str) -> Option<u64> {
+        let tag = self.tags.as_ref()?.tag_for_name(name)?;
+        Some(tag.value())
+    }
+}
+
+/// Adds a warning if `name` resolved to something other than `pinned`.
+fn compare(
+    name: &str,
+    pinned: Option<u64>,
+    resolved: u64,
+    span: SourceSpan,
+    warnings: &mut Vec<Warning>,
+) {
+    if pinned != Some(resolved) {
+        let name = name.to_string();
+        let kind = WarningKind::RegistryChanged { name, pinned, resolved };
+        warnings.push(Warning { kind, span });
+    }
+}
@@ -0,0 +1,105 @@
+/// A command shell whose quoting rules [`escape_for_single_arg`] follows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Shell {
+    /// `sh`, `bash`, `zsh`, and other POSIX shells.
+    #[default]
+    Posix,
+    /// PowerShell 7.3 and later, with `$PSNativeCommandArgumentPassing` set
+    /// to `Standard`, the default outside Windows.
+    PowerShell,
+    /// Windows PowerShell 5.1, and PowerShell before 7.3 or with
+    /// `$PSNativeCommandArgumentPassing` set to `Legacy`, which strip the
+    /// `"` characters from arguments passed to programs unless they are
+    /// escaped as `\"`.
+    WindowsPowerShell,
+}
+
+/// Returns `text` quoted so that `shell` passes it to a command as a single
+/// argument, exactly as written.
+///
+/// Text made only of characters that are never special to the shell, such as
+/// `42`, is returned unchanged. Anything else is wrapped in single quotes,
+/// inside which no shell expands variables or escapes. A single quote
+/// within the text is written as `'\''` for POSIX shells, and doubled for
+/// PowerShell, which also treats the typographic quotes `‘`, `’`, `‚`, and
+/// `‛` as single quotes. For Windows PowerShell, each `"` is also escaped as
+/// `\"`, with the backslashes before it doubled, as programs read their
+/// command lines.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{Shell, escape_for_single_arg};
+/// let diag = r#"{"name": 'isA', "cost": $5}"#;
+/// assert_eq!(
+///     escape_for_single_arg(diag, Shell::Posix),
+///     r#"'{"name": '\''isA'\'', "cost": $5}'"#
+/// );
+/// assert_eq!(
+///     escape_for_single_arg(diag, Shell::PowerShell),
+///     r#"'{"name": ''isA'', "cost": $5}'"#
+/// );
+/// assert_eq!(
+///     escape_for_single_arg(diag, Shell::WindowsPowerShell),
+///     r#"'{\"name\": ''isA'', \"cost\": $5}'"#
+/// );
+/// assert_eq!(escape_for_single_arg("42", Shell::Posix), "42");
+/// ```
+pub fn escape_for_single_arg(text: &str, shell: Shell) -> String {
+    let is_safe = match shell {
+        Shell::Posix => |c: char| {
+            c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c)
+        },
+        Shell::PowerShell | Shell::WindowsPowerShell => {
+            |c: char| c.is_ascii_alphanumeric() || "_./".contains(c)
+        }
+    };
+    if !text.is_empty() && text.chars().all(is_safe) {
+        return text.to_string();
+    }
+    let mut result = String::from("'");
+    // The number of backslashes just before `c`.
+    let mut backslashes = 0;
+    for c in text.chars() {
+        match (shell, c) {
+            (Shell::Posix, '\'') => result.push_str("'\\''"),
+            (
+                Shell::PowerShell | Shell::WindowsPowerShell,
+                '\'' | '‘' | '’' | '‚' | '‛',
+            ) => {
+                result.push(c);
+                result.push(c);
+            }
+            (Shell::WindowsPowerShell, '"') => {
+                result.push_str(&"\\".repeat(backslashes + 1));
+                result.push(c);
+            }
+            _ => result.push(c),
+        }
+        backslashes = if c == '\\' { backslashes + 1 } else { 0 };
+    }
+    // Windows PowerShell quotes an argument with whitespace in `"`, which
+    // the backslashes at its end would otherwise escape.
+    if shell == Shell::WindowsPowerShell && text.contains(char::is_whitespace)
+    {
+        result.push_str(&"\\".repeat(backslashes));
+    }
+    result.push('\'');
+    result
+}
+
+/// Returns diagnostic notation quoted as a single argument for a POSIX
+/// shell, so it can be pasted into a command line such as
+/// `envelope subject type cbor ...`.
+///
+/// This is [`escape_for_single_arg`] with [`Shell::Posix`].
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::shell_quote_diag;
+/// assert_eq!(shell_quote_diag(r#"["it's", 1]"#), r#"'["it'\''s", 1]'"#);
+/// ```
+pub fn shell_quote_diag(diag: &str) -> String {
+    escape_for_single_arg(diag, Shell::Posix)
+}
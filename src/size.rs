@@ -0,0 +1,72 @@
+use dcbor::prelude::*;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{ParseResult, parse_dcbor_item};
+
+/// Returns the length in bytes of the deterministic encoding of a CBOR item,
+/// without encoding it.
+///
+/// This is the length of `cbor.to_cbor_data()`, and is useful for checking
+/// items against a size budget, such as the capacity of a QR code or a UDP
+/// datagram, while authoring fixtures.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{encoded_size, parse_dcbor_item};
+/// let cbor = parse_dcbor_item(r#"{"key": [1, 1000, 1.5]}"#).unwrap();
+/// assert_eq!(encoded_size(&cbor), 13);
+/// assert_eq!(encoded_size(&cbor), cbor.to_cbor_data().len());
+/// ```
+pub fn encoded_size(cbor: &CBOR) -> usize {
+    match cbor.as_case() {
+        CBORCase::Unsigned(n) | CBORCase::Negative(n) => head_len(*n),
+        CBORCase::ByteString(bytes) => {
+            head_len(bytes.len() as u64) + bytes.len()
+        }
+        CBORCase::Text(text) => {
+            let len = text.nfc().map(char::len_utf8).sum::<usize>();
+            head_len(len as u64) + len
+        }
+        CBORCase::Array(items) => {
+            head_len(items.len() as u64)
+                + items.iter().map(encoded_size).sum::<usize>()
+        }
+        CBORCase::Map(map) => {
+            head_len(map.len() as u64)
+                + map
+                    .iter()
+                    .map(|(key, value)| encoded_size(key) + encoded_size(value))
+                    .sum::<usize>()
+        }
+        CBORCase::Tagged(tag, item) => {
+            head_len(tag.value()) + encoded_size(item)
+        }
+        CBORCase::Simple(simple) => simple.cbor_data().len(),
+    }
+}
+
+/// Parses a dCBOR item from a string input and returns its
+/// [`encoded_size`].
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::encoded_size_diag;
+/// assert_eq!(encoded_size_diag("h'0102'").unwrap(), 3);
+/// assert_eq!(encoded_size_diag("[1.0, 2.5]").unwrap(), 5);
+/// ```
+pub fn encoded_size_diag(src: &str) -> ParseResult<usize> {
+    Ok(encoded_size(&parse_dcbor_item(src)?))
+}
+
+/// Returns the length of the head of an item whose argument is `argument`.
+pub(crate) fn head_len(argument: u64) -> usize {
+    match argument {
+        0..=23 => 1,
+        24..=0xff => 2,
+        0x100..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
@@ -0,0 +1,122 @@
+use std::ops::Range;
+
+/// A range of byte offsets into the source text.
+///
+/// Used by errors, [`DiagValue`](crate::DiagValue) nodes, and reports to
+/// locate the source a value or problem came from.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{LineColumn, SourceSpan};
+/// let source = "[1,\n  22]";
+/// let span = SourceSpan::new(6, 8);
+/// assert_eq!(&source[span.range()], "22");
+/// assert_eq!(
+///     span.line_columns(source),
+///     (LineColumn { line: 2, column: 3 }, LineColumn { line: 2, column: 5 })
+/// );
+/// assert_eq!(span.merge(SourceSpan::new(0, 1)), 0..8);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SourceSpan {
+    /// The offset of the first byte of the span.
+    pub start: usize,
+    /// The offset one past the last byte of the span.
+    pub end: usize,
+}
+
+/// A position in the source text as seen by a person reading it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LineColumn {
+    /// The 1-based line number.
+    pub line: usize,
+    /// The 1-based column number, counted in characters.
+    pub column: usize,
+}
+
+impl SourceSpan {
+    pub fn new(start: usize, end: usize) -> Self { Self { start, end } }
+
+    /// Returns the length of the span in bytes.
+    pub fn len(&self) -> usize { self.end.saturating_sub(self.start) }
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Returns the span as a `Range`, suitable for slicing the source.
+    pub fn range(&self) -> Range<usize> { self.start..self.end }
+
+    /// Returns the smallest span that covers both `self` and `other`.
+    pub fn merge(self, other: SourceSpan) -> SourceSpan {
+        SourceSpan::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    /// Returns `true` if the byte at `offset` is within the span.
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+
+    /// Returns `true` if `other` lies entirely within the span.
+    pub fn contains_span(&self, other: SourceSpan) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// Returns the line and column of the start and end of the span within
+    /// `source`.
+    pub fn line_columns(&self, source: &str) -> (LineColumn, LineColumn) {
+        (line_column(source, self.start), line_column(source, self.end))
+    }
+}
+
+/// Returns the offset of the start of the line containing `offset`, and the
+/// 1-based number of that line.
+///
+/// Lines may end with `\n`, `\r\n`, or a lone `\r`, so sources written on
+/// any platform report the same positions.
+pub(crate) fn line_start(source: &str, offset: usize) -> (usize, usize) {
+    let bytes = source.as_bytes();
+    let mut line_number = 1;
+    let mut line_start = 0;
+    for (idx, &byte) in bytes.iter().enumerate().take(offset) {
+        let is_break = byte == b'\n'
+            || (byte == b'\r' && bytes.get(idx + 1) != Some(&b'\n'));
+        if is_break {
+            line_number += 1;
+            line_start = idx + 1;
+        }
+    }
+    (line_start, line_number)
+}
+
+/// Returns the offset of the end of the line starting at `line_start`,
+/// before its line break.
+pub(crate) fn line_end(source: &str, line_start: usize) -> usize {
+    source.as_bytes()[line_start..]
+        .iter()
+        .position(|&byte| byte == b'\n' || byte == b'\r')
+        .map_or(source.len(), |len| line_start + len)
+}
+
+fn line_column(source: &str, offset: usize) -> LineColumn {
+    let (start, line) = line_start(source, offset);
+    let offset = offset.min(source.len());
+    let column = source
+        .get(start..offset)
+        .map_or(offset - start, |prefix| prefix.chars().count())
+        + 1;
+    LineColumn { line, column }
+}
+
+impl From<Range<usize>> for SourceSpan {
+    fn from(range: Range<usize>) -> Self { Self::new(range.start, range.end) }
+}
+
+impl From<SourceSpan> for Range<usize> {
+    fn from(span: SourceSpan) -> Self { span.range() }
+}
+
+impl PartialEq<Range<usize>> for SourceSpan {
+    fn eq(&self, other: &Range<usize>) -> bool {
+        self.start == other.start && self.end == other.end
+    }
+}
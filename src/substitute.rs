@@ -0,0 +1,139 @@
+use std::ops::Range;
+
+use crate::{
+    DiagValue, EnvLookup, SourceSpan,
+    error::{Error, Result},
+};
+
+/// A source with every `${NAME}` replaced by the value of the variable, or
+/// with other replacements made, and the record of where each replacement
+/// was made.
+pub(crate) struct Substituted {
+    pub(crate) text: String,
+    /// The range of each `${NAME}` in the original source, and the range of
+    /// its value in the substituted text, in order.
+    regions: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl Substituted {
+    /// Replaces every `${NAME}` in `src`, where `NAME` is a letter or
+    /// underscore followed by letters, digits, and underscores, with the value
+    /// `lookup` returns for it. Any other `$` is left as it is.
+    pub(crate) fn new(src: &str, lookup: &EnvLookup) -> Result<Self> {
+        let mut text = String::new();
+        let mut regions = Vec::new();
+        let mut copied = 0;
+        let mut search = 0;
+        while let Some(found) = src[search..].find("${") {
+            let start = search + found;
+            let name_start = start + 2;
+            let name_len = src[name_start..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(src.len() - name_start);
+            let name = &src[name_start..name_start + name_len];
+            let end = name_start + name_len + 1;
+            let is_variable = src[name_start + name_len..].starts_with('}')
+                && name
+                    .starts_with(|c: char| c.is_ascii_alphabetic() || c == '_');
+            if !is_variable {
+                search = name_start;
+                continue;
+            }
+            let Some(value) = lookup(name) else {
+                let span = SourceSpan::new(name_start, name_start + name_len);
+                return Err(Error::UndefinedVariable(name.to_string(), span));
+            };
+            text.push_str(&src[copied..start]);
+            let value_start = text.len();
+            text.push_str(&value);
+            regions.push((start..end, value_start..text.len()));
+            copied = end;
+            search = end;
+        }
+        text.push_str(&src[copied..]);
+        Ok(Self { text, regions })
+    }
+
+    /// Returns a source made by replacing each of `regions`, ranges of the
+    /// original source in order, with the corresponding range of `text`.
+    pub(crate) fn from_regions(
+        text: String,
+        regions: Vec<(Range<usize>, Range<usize>)>,
+    ) -> Self {
+        Self { text, regions }
+    }
+
+    /// Maps a span of the substituted text back to the original source. A
+    /// span that starts or ends within a substituted value is widened to
+    /// cover the whole `${NAME}`.
+    pub(crate) fn original_span(&self, span: SourceSpan) -> SourceSpan {
+        SourceSpan::new(
+            self.original(span.start, false),
+            self.original(span.end, true),
+        )
+    }
+
+    fn original(&self, offset: usize, is_end: bool) -> usize {
+        let mut result = offset;
+        for (original, substituted) in &self.regions {
+            let within = if is_end {
+                substituted.start < offset && offset <= substituted.end
+            } else {
+                substituted.start <= offset && offset < substituted.end
+            };
+            if within {
+                return if is_end { original.end } else { original.start };
+            }
+            if offset < substituted.end {
+                break;
+            }
+            result = offset - substituted.end + original.end;
+        }
+        result
+    }
+
+    /// Maps the spans of a value parsed from the substituted text back to the
+    /// original source.
+    pub(crate) fn remap_value(&self, value: &mut DiagValue) {
+        match value {
+            DiagValue::Bool { span, .. }
+            | DiagValue::Null { span }
+            | DiagValue::Number { span, .. }
+            | DiagValue::Text { span, .. }
+            | DiagValue::ByteString { span, .. }
+            | DiagValue::Date { span, .. }
+            | DiagValue::DecimalFraction { span, .. }
+            | DiagValue::Rational { span, .. }
+            | DiagValue::KnownValue { span, .. }
+            | DiagValue::Ur { span, .. }
+            | DiagValue::RawTagged { span, .. }
+            | DiagValue::Included { span, .. } => {
+                *span = self.original_span(*span);
+            }
+            DiagValue::Tagged { item, span, .. } => {
+                self.remap_value(item);
+                *span = self.original_span(*span);
+            }
+            DiagValue::Array { items, span } => {
+                items.iter_mut().for_each(|item| self.remap_value(item));
+                *span = self.original_span(*span);
+            }
+            DiagValue::Map { entries, span } => {
+                for (key, value) in entries {
+                    self.remap_value(key);
+                    self.remap_value(value);
+                }
+                *span = self.original_span(*span);
+            }
+        }
+    }
+
+    /// Maps the span of an error in the substituted text back to the original
+    /// source.
+    pub(crate) fn remap_error(&self, mut error: Error) -> Error {
+        if let Some(span) = error.span_mut() {
+            *span = self.original_span(*span);
+        }
+        error
+    }
+}
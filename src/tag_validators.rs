@@ -0,0 +1,167 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use dcbor::prelude::*;
+
+/// Checks the content of an item with a particular tag, returning a message
+/// saying what is wrong with it if it's invalid.
+pub type TagValidator =
+    dyn Fn(&CBOR) -> std::result::Result<(), String> + Send + Sync;
+
+/// A registry of validators for the content of tagged items.
+///
+/// Pass a registry to
+/// [`ParseOptions::tag_validators`](crate::ParseOptions::tag_validators) to
+/// check the content of each tagged item, such as `37(h'...')`, as it is
+/// parsed. Content that fails validation is an
+/// [`InvalidTagContent`](crate::ParseError::InvalidTagContent) error with the
+/// span of the content. The payload of each `ur:` literal is checked by the
+/// validator for the tag of its UR type, and a failure has the span of the
+/// whole UR.
+///
+/// [`standard`](Self::standard) returns validators for these registered tags:
+///
+/// | Tag | Content                                    |
+/// | --- | ------------------------------------------ |
+/// | 0   | A text date/time string                    |
+/// | 1   | A number of seconds since the epoch        |
+/// | 2   | A byte string, the magnitude of a bignum   |
+/// | 3   | A byte string, the magnitude of a bignum   |
+/// | 24  | A byte string containing an encoded item   |
+/// | 32  | A text URI with a scheme                   |
+/// | 37  | A 16-byte byte string UUID                 |
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{
+/// #     ParseError, ParseOptions, TagValidators,
+/// #     parse_dcbor_item_with_options,
+/// # };
+/// let mut validators = TagValidators::standard();
+/// validators.insert(40_001, |item| match item.as_text() {
+///     Some(text) if text.len() <= 8 => Ok(()),
+///     _ => Err("expected text of at most 8 bytes".to_string()),
+/// });
+/// let options = ParseOptions::default().tag_validators(validators);
+/// let parse = |src| parse_dcbor_item_with_options(src, &options);
+///
+/// assert!(parse(r#"[32("https://example.com"), 40001("short")]"#).is_ok());
+/// assert_eq!(
+///     parse("[1, 37(h'0102')]"),
+///     Err(ParseError::InvalidTagContent(
+///         "37: expected a 16-byte byte string".to_string(),
+///         (7..14).into()
+///     ))
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct TagValidators {
+    validators: HashMap<TagValue, Arc<TagValidator>>,
+}
+
+impl TagValidators {
+    /// Creates an empty registry.
+    pub fn new() -> Self { Self::default() }
+
+    /// Creates a registry with validators for commonly used registered tags.
+    pub fn standard() -> Self {
+        let mut validators = Self::new();
+        validators.insert(0, |item| {
+            expect(item.is_text(), "expected a text date/time string")
+        });
+        validators.insert(1, |item| {
+            expect(is_number(item), "expected a number of seconds")
+        });
+        for tag in [2, 3] {
+            validators.insert(tag, |item| {
+                expect(item.is_byte_string(), "expected a byte string")
+            });
+        }
+        validators.insert(24, |item| {
+            let is_encoded_item = item
+                .as_byte_string()
+                .is_some_and(|data| CBOR::try_from_data(data).is_ok());
+            expect(is_encoded_item, "expected a byte string of encoded dCBOR")
+        });
+        validators.insert(32, |item| {
+            expect(item.as_text().is_some_and(is_uri), "expected a URI")
+        });
+        validators.insert(37, |item| {
+            let is_uuid = item.as_byte_string().is_some_and(|d| d.len() == 16);
+            expect(is_uuid, "expected a 16-byte byte string")
+        });
+        validators
+    }
+
+    /// Adds a validator for the content of items tagged with `tag`,
+    /// replacing any validator the registry already has for it.
+    pub fn insert(
+        &mut self,
+        tag: TagValue,
+        validator: impl Fn(&CBOR) -> std::result::Result<(), String>
+        + Send
+        + Sync
+        + 'static,
+    ) {
+        self.validators.insert(tag, Arc::new(validator));
+    }
+
+    /// Removes the validator for `tag`, and returns `true` if there was one.
+    pub fn remove(&mut self, tag: TagValue) -> bool {
+        self.validators.remove(&tag).is_some()
+    }
+
+    /// Returns `true` if the registry has a validator for `tag`.
+    pub fn contains(&self, tag: TagValue) -> bool {
+        self.validators.contains_key(&tag)
+    }
+
+    /// Checks `content` as the content of an item tagged with `tag`. Content
+    /// with a tag that has no validator is valid.
+    pub fn validate(
+        &self,
+        tag: TagValue,
+        content: &CBOR,
+    ) -> std::result::Result<(), String> {
+        match self.validators.get(&tag) {
+            Some(validator) => validator(content),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Debug for TagValidators {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tags: Vec<_> = self.validators.keys().collect();
+        tags.sort();
+        f.debug_struct("TagValidators").field("tags", &tags).finish()
+    }
+}
+
+//
+// === Private Functions ===
+//
+
+fn expect(is_valid: bool, message: &str) -> std::result::Result<(), String> {
+    if is_valid { Ok(()) } else { Err(message.to_string()) }
+}
+
+fn is_number(item: &CBOR) -> bool {
+    matches!(
+        item.as_case(),
+        CBORCase::Unsigned(_)
+            | CBORCase::Negative(_)
+            | CBORCase::Simple(dcbor::Simple::Float(_))
+    )
+}
+
+/// Returns `true` if `text` starts with a URI scheme, as in RFC 3986, and
+/// contains no whitespace.
+fn is_uri(text: &str) -> bool {
+    let Some((scheme, _)) = text.split_once(':') else { return false };
+    scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        && !text.contains(char::is_whitespace)
+}
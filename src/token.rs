@@ -1,13 +1,21 @@
+use std::ops::Range;
+
 use base64::Engine as _;
-use bc_ur::prelude::*;
+use bc_ur::{
+    bytewords::{Style, bytewords},
+    prelude::*,
+};
 use logos::Logos;
 
-use crate::error::{Error, Result};
+use crate::{
+    NumberValue, SourceSpan,
+    error::{Error, Result},
+};
 
 #[derive(Debug, Clone, Logos, PartialEq)]
 #[rustfmt::skip]
 #[logos(error = Error)]
-#[logos(skip r"(?:[ \t\r\n\f]|/[^/]*/|#[^\n]*)+")]
+#[logos(skip r"(?:[ \t\r\n\f]|/[^/]*/|#[^\r\n]*)+")]
 pub enum Token {
     #[token("false", |_| false)]
     #[token("true", |_| true)]
@@ -37,6 +45,19 @@ pub enum Token {
     #[token(",")]
     Comma,
 
+    /// Type assertion after an item, such as `:: bstr .size 32`, as the text
+    /// after the `::`, which the parser checks.
+    #[regex(r"::(?:[ \t]*[^ \t\r\n,:\]})>#/]+)*", |lex|
+        lex.slice()[2..].trim_start().to_string()
+    )]
+    TypeAssertion(String),
+
+    #[token("<<")]
+    EmbeddedOpen,
+
+    #[token(">>")]
+    EmbeddedClose,
+
     #[token("null")]
     Null,
 
@@ -49,17 +70,27 @@ pub enum Token {
     #[token("-Infinity")]
     NegInfinity,
 
-    /// Binary string in hex format.
-    #[regex(r"h'[0-9a-fA-F]*'", |lex| {
+    /// Binary string in hex format. The digits may be broken up by
+    /// whitespace and comments, as EDN allows.
+    #[regex(r"h'[^']*'", |lex| {
         let hex = lex.slice();
-        let raw_hex = &hex.as_bytes()[2..hex.len() - 1];
+        let body = &hex[2..hex.len() - 1];
+        let invalid = || Error::InvalidHexString(lex.span().into());
+        let comments = hex_comments(body).ok_or_else(invalid)?;
+        let mut raw_hex = Vec::with_capacity(body.len());
+        let mut offset = 0;
+        for comment in comments.iter().chain([&(body.len()..body.len())]) {
+            raw_hex.extend(
+                body[offset..comment.start]
+                    .bytes()
+                    .filter(|b| !b.is_ascii_whitespace()),
+            );
+            offset = comment.end;
+        }
         if !raw_hex.len().is_multiple_of(2) {
-            return Err(Error::InvalidHexString(lex.span()));
+            return Err(invalid());
         }
-        hex::decode(raw_hex)
-            .map_err(|_|
-                Error::InvalidHexString(lex.span())
-            )
+        hex::decode(raw_hex).map_err(|_| invalid())
     })]
     ByteStringHex(Result<Vec<u8>>),
 
@@ -70,7 +101,7 @@ pub enum Token {
         let s = &base64[4..base64.len() - 1];
         base64::engine::general_purpose::STANDARD
         .decode(s)
-        .map_err(|_| Error::InvalidBase64String(lex.span()))
+        .map_err(|_| Error::InvalidBase64String(lex.span().into()))
     })]
     ByteStringBase64(Result<Vec<u8>>),
 
@@ -81,16 +112,45 @@ pub enum Token {
         let s = &base64[4..base64.len() - 1];
         base64::engine::general_purpose::STANDARD
         .decode(s)
-        .map_err(|_| Error::InvalidBase64String(lex.span()))
+        .map_err(|_| Error::InvalidBase64String(lex.span().into()))
     })]
     ByteStringBase64(Result<Vec<u8>>),
 
+    /// Binary string in URL-safe base64 format, without padding.
+    #[regex(r"b64url'[^']*'", |lex| {
+        let base64 = lex.slice();
+        let s = &base64[7..base64.len() - 1];
+        base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(s)
+        .map_err(|_| Error::InvalidBase64String(lex.span().into()))
+    })]
+    ByteStringBase64Url(Result<Vec<u8>>),
+
+    /// Binary string in the base45 format of RFC 9285, as used to show the
+    /// payloads of QR codes.
+    #[regex(r"b45'[^']*'", |lex| {
+        let base45 = lex.slice();
+        decode_base45(&base45[4..base45.len() - 1])
+            .ok_or_else(|| Error::InvalidBase45String(lex.span().into()))
+    })]
+    ByteStringBase45(Result<Vec<u8>>),
+
+    /// Binary string in ByteWords, as written by UR tooling, with the
+    /// checksum removed.
+    #[regex(r"bw'[^']*'", |lex| {
+        let bytewords = lex.slice();
+        decode_bytewords(&bytewords[3..bytewords.len() - 1]).map_err(|e| {
+            Error::InvalidByteWords(e.to_string(), lex.span().into())
+        })
+    })]
+    ByteStringByteWords(Result<Vec<u8>>),
+
     /// ISO-8601 date literal (date-only or date-time).
     #[cfg(not(feature = "simplified-patterns"))]
     #[regex(r"\d{4}-\d{2}-\d{2}(?:T\d{2}:\d{2}:\d{2}(?:\.\d+)?(?:Z|[+-]\d{2}:\d{2})?)?", |lex| {
         let date_str = lex.slice();
         Date::from_string(date_str).map_err(|_| {
-            Error::InvalidDateString(date_str.to_string(), lex.span())
+            Error::InvalidDateString(date_str.to_string(), lex.span().into())
         })
     })]
     DateLiteral(Result<Date>),
@@ -100,20 +160,61 @@ pub enum Token {
     #[regex(r"\d{4}-\d{2}-\d{2}(?:T\d{2}:\d{2}:\d{2})?", |lex| {
         let date_str = lex.slice();
         Date::from_string(date_str).map_err(|_| {
-            Error::InvalidDateString(date_str.to_string(), lex.span())
+            Error::InvalidDateString(date_str.to_string(), lex.span().into())
         })
     })]
     DateLiteral(Result<Date>),
 
-    /// JavaScript-style number.
-    #[regex(r"-?(?:0|[1-9]\d*)(?:\.\d+)?(?:[eE][+-]?\d+)?", |lex|
-        lex.slice().parse::<f64>().unwrap()
+    /// Decimal fraction literal, such as `dec'2.75'`, as its exponent and
+    /// mantissa.
+    #[regex(r"dec'[^']*'", |lex| {
+        let slice = lex.slice();
+        let body = &slice[4..slice.len() - 1];
+        decimal_fraction(body).ok_or_else(|| {
+            Error::InvalidDecimalFraction(body.to_string(), lex.span().into())
+        })
+    })]
+    DecimalFraction(Result<(i64, NumberValue)>),
+
+    /// Rational number literal, such as `rat'1/3'`, as its numerator and
+    /// denominator.
+    #[regex(r"rat'[^']*'", |lex| {
+        let slice = lex.slice();
+        let body = &slice[4..slice.len() - 1];
+        rational(body).ok_or_else(|| {
+            Error::InvalidRational(body.to_string(), lex.span().into())
+        })
+    })]
+    Rational(Result<(NumberValue, NumberValue)>),
+
+    /// JavaScript-style number, or an integer in hex, octal, or binary with
+    /// a `0x`, `0o`, or `0b` prefix.
+    #[regex(r"-?(?:0|[1-9]\d*)(?:\.\d+)?(?:[eE][+-]?\d+)?|-?0(?:[xX][0-9a-fA-F]+|[oO][0-7]+|[bB][01]+)", |lex|
+        NumberValue::parse(lex.slice())
     )]
-    Number(f64),
+    Number(NumberValue),
 
-    /// JavaScript-style string.
+    /// A number followed by an EDN encoding indicator, such as `1.5_1` or
+    /// `0_0`, as the number and the character of the indicator. The parser
+    /// accepts these only with
+    /// [`ParseOptions::encoding_indicators`](crate::ParseOptions::encoding_indicators).
+    #[regex(r"(?:-?(?:0|[1-9]\d*)(?:\.\d+)?(?:[eE][+-]?\d+)?|-?0(?:[xX][0-9a-fA-F]+|[oO][0-7]+|[bB][01]+))_[0-3i]", |lex| {
+        let (number, indicator) = lex.slice().rsplit_once('_')?;
+        Some((NumberValue::parse(number)?, indicator.chars().next()?))
+    })]
+    IndicatedNumber((NumberValue, char)),
+
+    /// A spelling of a number that JSON doesn't allow, such as `+1`, `.5`,
+    /// `1.`, or `inf`. The parser accepts these only with lenient floats.
+    #[regex(r"(?i:[+-]?(?:nan|inf(?:inity)?))|[+-]?\.\d+(?:[eE][+-]?\d+)?|-?\d+\.(?:[eE][+-]?\d+)?|\+\d+(?:\.\d*)?(?:[eE][+-]?\d+)?", |lex|
+        lex.slice().parse::<f64>().ok()
+    )]
+    LenientNumber(f64),
+
+    /// JavaScript-style string. Any character may follow a backslash here,
+    /// and raw control characters are allowed; the parser checks both.
     #[cfg(not(feature = "simplified-patterns"))]
-    #[regex(r#""([^"\\\x00-\x1F]|\\(["\\bnfrt/]|u[a-fA-F0-9]{4}))*""#, |lex|
+    #[regex(r#""([^"\\]|\\[^\x00-\x1F])*""#, |lex|
         lex.slice().to_owned()
     )]
     String(String),
@@ -125,11 +226,13 @@ pub enum Token {
     )]
     String(String),
 
-    /// Integer followed immediately by an opening parenthesis.
-    #[regex(r#"0\(|[1-9][0-9]*\("#, |lex|
-        let span = (lex.span().start)..(lex.span().end - 1);
-        let stripped = lex.slice().strip_suffix('(').unwrap();
-        stripped.parse::<TagValue>().map_err(|_|
+    /// Integer followed immediately by an opening parenthesis. The integer
+    /// may be written in hex, octal, or binary with a `0x`, `0o`, or `0b`
+    /// prefix.
+    #[regex(r#"0\(|[1-9][0-9]*\(|0[xX][0-9a-fA-F]+\(|0[oO][0-7]+\(|0[bB][01]+\("#, |lex|
+        let span = SourceSpan::new(lex.span().start, lex.span().end - 1);
+        let stripped = &lex.slice()[..lex.slice().len() - 1];
+        parse_integer(stripped).map_err(|_|
                 Error::InvalidTagValue(stripped.to_string(), span)
             )
     )]
@@ -142,12 +245,26 @@ pub enum Token {
     )]
     TagName(String),
 
+    /// Raw tagged value keyword followed immediately by an opening
+    /// parenthesis.
+    #[token("raw(")]
+    Raw,
+
+    /// Include directive followed immediately by an opening parenthesis.
+    #[token("!include(")]
+    Include,
+
+    /// External byte string directive followed immediately by an opening
+    /// parenthesis.
+    #[token("!bytes(")]
+    ExternalBytes,
+
     /// Integer (same regex as TagValue) enclosed in single quotes.
-    #[regex(r#"'0'|'[1-9][0-9]*'"#, |lex|
-        let span = (lex.span().start + 1)..(lex.span().end - 1);
+    #[regex(r#"'0'|'[1-9][0-9]*'|'0[xX][0-9a-fA-F]+'|'0[oO][0-7]+'|'0[bB][01]+'"#, |lex|
+        let span = SourceSpan::new(lex.span().start + 1, lex.span().end - 1);
         let slice = lex.slice();
         let stripped = slice[1..slice.len() - 1].to_string();
-        stripped.parse::<TagValue>().map_err(|_|
+        parse_integer(&stripped).map_err(|_|
                 Error::InvalidKnownValue(stripped, span)
             )
     )]
@@ -168,8 +285,177 @@ pub enum Token {
         let s = lex.slice();
         let ur = UR::from_ur_string(s);
         ur.map_err(|e| {
-            Error::InvalidUr(e.to_string(), lex.span())
+            Error::InvalidUr(e.to_string(), lex.span().into())
         })
     )]
     UR(Result<UR>),
 }
+
+impl Token {
+    /// Returns the name of the variant, which is the name of its
+    /// [`TokenKind`](crate::TokenKind) in the [`grammar`](crate::grammar).
+    #[rustfmt::skip]
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Token::Bool(_) => "Bool",
+            Token::BraceOpen => "BraceOpen",
+            Token::BraceClose => "BraceClose",
+            Token::BracketOpen => "BracketOpen",
+            Token::BracketClose => "BracketClose",
+            Token::ParenthesisOpen => "ParenthesisOpen",
+            Token::ParenthesisClose => "ParenthesisClose",
+            Token::Colon => "Colon",
+            Token::Comma => "Comma",
+            Token::TypeAssertion(_) => "TypeAssertion",
+            Token::EmbeddedOpen => "EmbeddedOpen",
+            Token::EmbeddedClose => "EmbeddedClose",
+            Token::Null => "Null",
+            Token::NaN => "NaN",
+            Token::Infinity => "Infinity",
+            Token::NegInfinity => "NegInfinity",
+            Token::ByteStringHex(_) => "ByteStringHex",
+            Token::ByteStringBase64(_) => "ByteStringBase64",
+            Token::ByteStringBase64Url(_) => "ByteStringBase64Url",
+            Token::ByteStringBase45(_) => "ByteStringBase45",
+            Token::ByteStringByteWords(_) => "ByteStringByteWords",
+            Token::DateLiteral(_) => "DateLiteral",
+            Token::DecimalFraction(_) => "DecimalFraction",
+            Token::Rational(_) => "Rational",
+            Token::Number(_) => "Number",
+            Token::IndicatedNumber(_) => "IndicatedNumber",
+            Token::LenientNumber(_) => "LenientNumber",
+            Token::String(_) => "String",
+            Token::TagValue(_) => "TagValue",
+            Token::TagName(_) => "TagName",
+            Token::Raw => "Raw",
+            Token::Include => "Include",
+            Token::ExternalBytes => "ExternalBytes",
+            Token::KnownValueNumber(_) => "KnownValueNumber",
+            Token::KnownValueName(_) => "KnownValueName",
+            Token::Unit => "Unit",
+            Token::UR(_) => "UR",
+        }
+    }
+}
+
+/// Returns the ranges of the comments in the body of a hex byte string,
+/// written as `/.../` or from `#` to the end of the line, or `None` if a
+/// `/` comment isn't closed.
+pub(crate) fn hex_comments(s: &str) -> Option<Vec<Range<usize>>> {
+    let mut comments = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = s[offset..].find(['/', '#']).map(|i| offset + i) {
+        let end = if s[start..].starts_with('/') {
+            start + 1 + s[start + 1..].find('/')? + 1
+        } else {
+            s[start..].find('\n').map_or(s.len(), |i| start + i)
+        };
+        comments.push(start..end);
+        offset = end;
+    }
+    Some(comments)
+}
+
+/// Returns the exponent and mantissa of the decimal fraction that `s` writes
+/// as an optionally signed decimal number with an optional fraction and
+/// exponent, or `None` if it isn't one or its exponent is out of range.
+fn decimal_fraction(s: &str) -> Option<(i64, NumberValue)> {
+    let (number, exponent) = match s.split_once(['e', 'E']) {
+        Some((number, exponent)) => {
+            let digits = exponent.trim_start_matches(['+', '-']);
+            if digits.is_empty() || exponent.len() - digits.len() > 1 {
+                return None;
+            }
+            (number, exponent.parse::<i64>().ok()?)
+        }
+        None => (s, 0),
+    };
+    let (sign, number) = match number.strip_prefix('-') {
+        Some(number) => ("-", number),
+        None => ("", number),
+    };
+    let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if integer.is_empty()
+        || !is_digits(integer)
+        || !is_digits(fraction)
+        || number.ends_with('.')
+    {
+        return None;
+    }
+    let exponent = exponent.checked_sub(fraction.len().try_into().ok()?)?;
+    let mantissa = NumberValue::parse(&format!("{sign}{integer}{fraction}"))?;
+    Some((exponent, mantissa))
+}
+
+/// Returns the numerator and denominator of the rational number that `s`
+/// writes as `numerator/denominator`, with an optionally signed decimal
+/// numerator and an unsigned decimal denominator, or `None` if it isn't one
+/// or its denominator is zero.
+fn rational(s: &str) -> Option<(NumberValue, NumberValue)> {
+    let (numerator, denominator) = s.split_once('/')?;
+    let digits = numerator.strip_prefix('-').unwrap_or(numerator);
+    let is_digits =
+        |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if !is_digits(digits) || !is_digits(denominator) {
+        return None;
+    }
+    let denominator = NumberValue::parse(denominator)?;
+    if matches!(denominator, NumberValue::Unsigned(0)) {
+        return None;
+    }
+    Some((NumberValue::parse(numerator)?, denominator))
+}
+
+/// Returns the bytes that `s` encodes in base45, or `None` if it isn't valid
+/// base45: each group of three characters encodes two bytes, and a final
+/// group of two characters encodes one.
+fn decode_base45(s: &str) -> Option<Vec<u8>> {
+    const ALPHABET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+    let digits = s
+        .bytes()
+        .map(|c| ALPHABET.iter().position(|&a| a == c).map(|d| d as u32))
+        .collect::<Option<Vec<u32>>>()?;
+    let mut bytes = Vec::with_capacity(digits.len() / 3 * 2 + 1);
+    for group in digits.chunks(3) {
+        let value = group.iter().rev().fold(0, |value, d| value * 45 + d);
+        match group.len() {
+            3 => bytes.extend(u16::try_from(value).ok()?.to_be_bytes()),
+            2 => bytes.push(u8::try_from(value).ok()?),
+            _ => return None,
+        }
+    }
+    Some(bytes)
+}
+
+/// Returns the bytes that `s` encodes in ByteWords, without the checksum.
+/// The style is that of the separators: whitespace for the standard style,
+/// dashes for the URI style, and none for the minimal style. Letters may be
+/// in either case.
+fn decode_bytewords(
+    s: &str,
+) -> std::result::Result<Vec<u8>, bytewords::Error> {
+    let s = s.trim().to_ascii_lowercase();
+    if s.contains(char::is_whitespace) {
+        let words = s.split_whitespace().collect::<Vec<_>>().join(" ");
+        bytewords::decode(&words, Style::Standard)
+    } else if s.contains('-') {
+        bytewords::decode(&s, Style::Uri)
+    } else {
+        bytewords::decode(&s, Style::Minimal)
+    }
+}
+
+/// Parses an unsigned integer written in decimal, or in hex, octal, or
+/// binary with a `0x`, `0o`, or `0b` prefix.
+pub(crate) fn parse_integer(
+    s: &str,
+) -> std::result::Result<u64, std::num::ParseIntError> {
+    let radix = match s.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0o" | "0O") => 8,
+        Some("0b" | "0B") => 2,
+        _ => return s.parse(),
+    };
+    u64::from_str_radix(&s[2..], radix)
+}
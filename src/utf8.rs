@@ -0,0 +1,112 @@
+use dcbor::prelude::*;
+
+use crate::{
+    ParseResult, SourceSpan, Warning, WarningKind,
+    error::Error,
+    parse::Parser,
+    parse_dcbor_item,
+    substitute::Substituted,
+};
+
+/// Parses a dCBOR item from diagnostic notation in bytes.
+///
+/// Accepts the same input as [`parse_dcbor_item`] once the bytes are
+/// decoded. Bytes that aren't valid UTF-8 are an
+/// [`InvalidUtf8`](crate::ParseError::InvalidUtf8) error, with the span of
+/// the first invalid sequence. As with every entry point, a NUL character
+/// outside a comment is a
+/// [`NulCharacter`](crate::ParseError::NulCharacter) error.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{ParseError, parse_dcbor_item_from_bytes};
+/// let cbor = parse_dcbor_item_from_bytes(b"[1, \"a\"]").unwrap();
+/// assert_eq!(cbor.diagnostic_flat(), r#"[1, "a"]"#);
+/// assert_eq!(
+///     parse_dcbor_item_from_bytes(b"[1, \"a\xff\"]"),
+///     Err(ParseError::InvalidUtf8((6..7).into()))
+/// );
+/// assert_eq!(
+///     parse_dcbor_item_from_bytes(b"[1, \0]"),
+///     Err(ParseError::NulCharacter((4..5).into()))
+/// );
+/// ```
+pub fn parse_dcbor_item_from_bytes(src: &[u8]) -> ParseResult<CBOR> {
+    match std::str::from_utf8(src) {
+        Ok(src) => parse_dcbor_item(src),
+        Err(e) => {
+            let start = e.valid_up_to();
+            let end = e.error_len().map_or(src.len(), |len| start + len);
+            Err(Error::InvalidUtf8(SourceSpan::new(start, end)))
+        }
+    }
+}
+
+/// Parses a dCBOR item from diagnostic notation in bytes, replacing each
+/// sequence of bytes that isn't valid UTF-8, and each NUL character, with
+/// U+FFFD.
+///
+/// Each replacement is reported as an
+/// [`InvalidUtf8`](WarningKind::InvalidUtf8) or
+/// [`NulCharacter`](WarningKind::NulCharacter) warning, followed by the
+/// warnings returned by [`Warning::collect`] for the parsed value. Replaced
+/// bytes are usually inside strings, where U+FFFD is accepted; anywhere else
+/// it is an unrecognized token. The spans of warnings and errors refer to
+/// the bytes as they were given.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{WarningKind, parse_dcbor_item_from_bytes_lossy};
+/// let (cbor, warnings) =
+///     parse_dcbor_item_from_bytes_lossy(b"[\"a\xffb\", \"c\0\"]").unwrap();
+/// assert_eq!(cbor.diagnostic_flat(), "[\"a\u{fffd}b\", \"c\u{fffd}\"]");
+/// let kind = WarningKind::InvalidUtf8 { bytes: vec![0xff] };
+/// assert_eq!(warnings[0].kind, kind);
+/// assert_eq!(warnings[0].span, 3..4);
+/// assert_eq!(warnings[1].kind, WarningKind::NulCharacter);
+/// assert_eq!(warnings[1].span, 10..11);
+/// ```
+pub fn parse_dcbor_item_from_bytes_lossy(
+    src: &[u8],
+) -> ParseResult<(CBOR, Vec<Warning>)> {
+    let mut text = String::with_capacity(src.len());
+    let mut regions = Vec::new();
+    let mut warnings = Vec::new();
+    let mut offset = 0;
+    let mut replace = |text: &mut String, original, kind| {
+        let start = text.len();
+        text.push(char::REPLACEMENT_CHARACTER);
+        let span = SourceSpan::from(original);
+        regions.push((span.range(), start..text.len()));
+        warnings.push(Warning { kind, span });
+    };
+    for chunk in src.utf8_chunks() {
+        for (index, c) in chunk.valid().char_indices() {
+            if c == '\0' {
+                let start = offset + index;
+                replace(&mut text, start..start + 1, WarningKind::NulCharacter);
+            } else {
+                text.push(c);
+            }
+        }
+        offset += chunk.valid().len();
+        let invalid = chunk.invalid();
+        if !invalid.is_empty() {
+            let bytes = invalid.to_vec();
+            let kind = WarningKind::InvalidUtf8 { bytes };
+            replace(&mut text, offset..offset + invalid.len(), kind);
+            offset += invalid.len();
+        }
+    }
+
+    let substituted = Substituted::from_regions(text, regions);
+    let mut parser = Parser::new(&substituted.text);
+    let mut value = parser
+        .parse_all()
+        .map_err(|error| substituted.remap_error(error))?;
+    substituted.remap_value(&mut value);
+    warnings.extend(Warning::collect(&value));
+    Ok((value.to_cbor(), warnings))
+}
@@ -0,0 +1,327 @@
+use std::io::{self, BufRead, BufReader, Read};
+
+use dcbor::prelude::*;
+use thiserror::Error;
+use unicode_normalization::is_nfc;
+
+#[derive(Debug, Error)]
+#[rustfmt::skip]
+pub enum Error {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid hex at offset {0}")]
+    InvalidHex(u64),
+    #[error("Invalid dCBOR at byte {1}: {0}")]
+    Violation(dcbor::Error, u64),
+}
+
+impl Error {
+    /// Returns the offset of the error in the input, or `None` for I/O
+    /// errors.
+    ///
+    /// The offset of invalid hex is in the hex text, and the offset of a
+    /// violation is in the decoded bytes.
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Error::Io(_) => None,
+            Error::InvalidHex(offset) | Error::Violation(_, offset) => {
+                Some(*offset)
+            }
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The form of the input to [`validate_dcbor`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputFormat {
+    /// Binary dCBOR.
+    #[default]
+    Binary,
+    /// dCBOR in hex, which may be broken up by whitespace.
+    Hex,
+}
+
+/// Checks that the input is a sequence of zero or more items in valid
+/// dCBOR, and returns the number of items.
+///
+/// The input is read incrementally, so captures much larger than memory can
+/// be checked. Byte strings are never held in memory; text strings, which
+/// must be checked for normalization, and map keys, which must be compared
+/// with the previous key, are. The first violation is returned with its
+/// offset in the decoded bytes.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{InputFormat, ValidateError, validate_dcbor};
+/// let hex = "a2 01 02 03 04  82 f5 f6";
+/// assert_eq!(validate_dcbor(hex.as_bytes(), InputFormat::Hex).unwrap(), 2);
+///
+/// // The keys are out of order.
+/// let hex = "a2 03 04 01 02";
+/// let error = validate_dcbor(hex.as_bytes(), InputFormat::Hex).unwrap_err();
+/// assert!(matches!(
+///     error,
+///     ValidateError::Violation(dcbor::Error::MisorderedMapKey, 3)
+/// ));
+/// ```
+pub fn validate_dcbor(reader: impl Read, format: InputFormat) -> Result<u64> {
+    let mut validator = Validator {
+        reader: BufReader::new(reader),
+        format,
+        offset: 0,
+        text_offset: 0,
+        key: Vec::new(),
+        recording: 0,
+    };
+    let mut count = 0;
+    while let Some(first) = validator.next_byte()? {
+        validator.item(first)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+//
+// === Private Functions ===
+//
+
+/// An open array, map, or tagged item.
+enum Frame {
+    /// The number of items, or the tagged item, still to be read.
+    Items(u64),
+    Map {
+        entries: u64,
+        in_key: bool,
+        key_start: usize,
+        key_offset: u64,
+        previous: Option<Vec<u8>>,
+    },
+}
+
+struct Validator<R> {
+    reader: BufReader<R>,
+    format: InputFormat,
+    /// The offset in the decoded bytes.
+    offset: u64,
+    /// The offset in the input.
+    text_offset: u64,
+    /// The bytes of the map keys being read.
+    key: Vec<u8>,
+    /// The number of map keys being read.
+    recording: usize,
+}
+
+impl<R: Read> Validator<R> {
+    /// Reads an item whose first byte is `first`.
+    fn item(&mut self, first: u8) -> Result<()> {
+        let mut stack = Vec::new();
+        let mut first = Some(first);
+        loop {
+            if let Some(Frame::Map {
+                in_key: true, key_start, key_offset, ..
+            }) = stack.last_mut()
+            {
+                *key_start = self.key.len();
+                *key_offset = self.offset;
+                self.recording += 1;
+            }
+            let (start, header) = match first.take() {
+                Some(header) => (self.offset - 1, header),
+                None => (self.offset, self.byte()?),
+            };
+            let value = self.head(header, start)?;
+            match header >> 5 {
+                2 => {
+                    for _ in 0..value {
+                        self.byte()?;
+                    }
+                }
+                3 => {
+                    let mut bytes = Vec::new();
+                    for _ in 0..value {
+                        bytes.push(self.byte()?);
+                    }
+                    let text = std::str::from_utf8(&bytes).map_err(|e| {
+                        Error::Violation(dcbor::Error::InvalidString(e), start)
+                    })?;
+                    if !is_nfc(text) {
+                        return Err(Error::Violation(
+                            dcbor::Error::NonCanonicalString,
+                            start,
+                        ));
+                    }
+                }
+                4 if value > 0 => {
+                    stack.push(Frame::Items(value));
+                    continue;
+                }
+                5 if value > 0 => {
+                    stack.push(Frame::Map {
+                        entries: value,
+                        in_key: true,
+                        key_start: 0,
+                        key_offset: 0,
+                        previous: None,
+                    });
+                    continue;
+                }
+                6 => {
+                    stack.push(Frame::Items(1));
+                    continue;
+                }
+                _ => {}
+            }
+
+            // The item is complete, and may complete the items that contain
+            // it.
+            loop {
+                match stack.last_mut() {
+                    None => return Ok(()),
+                    Some(Frame::Items(remaining)) => {
+                        *remaining -= 1;
+                        if *remaining > 0 {
+                            break;
+                        }
+                    }
+                    Some(Frame::Map {
+                        entries,
+                        in_key,
+                        key_start,
+                        key_offset,
+                        previous,
+                    }) => {
+                        if *in_key {
+                            let key = self.key[*key_start..].to_vec();
+                            self.recording -= 1;
+                            if self.recording == 0 {
+                                self.key.clear();
+                            }
+                            if let Some(previous) = previous.as_ref() {
+                                let error = match key.cmp(previous) {
+                                    std::cmp::Ordering::Less => {
+                                        Some(dcbor::Error::MisorderedMapKey)
+                                    }
+                                    std::cmp::Ordering::Equal => {
+                                        Some(dcbor::Error::DuplicateMapKey)
+                                    }
+                                    std::cmp::Ordering::Greater => None,
+                                };
+                                if let Some(error) = error {
+                                    return Err(Error::Violation(
+                                        error,
+                                        *key_offset,
+                                    ));
+                                }
+                            }
+                            *previous = Some(key);
+                            *in_key = false;
+                            break;
+                        }
+                        *entries -= 1;
+                        if *entries > 0 {
+                            *in_key = true;
+                            break;
+                        }
+                    }
+                }
+                stack.pop();
+            }
+        }
+    }
+
+    /// Reads the rest of the head whose first byte is `header`, and returns
+    /// its argument.
+    fn head(&mut self, header: u8, start: u64) -> Result<u64> {
+        let info = header & 31;
+        let (len, minimum) = match info {
+            0..=23 => (0, 0),
+            24 => (1, 24),
+            25 => (2, 0x100),
+            26 => (4, 0x1_0000),
+            27 => (8, 0x1_0000_0000),
+            _ => {
+                return Err(Error::Violation(
+                    dcbor::Error::UnsupportedHeaderValue(info),
+                    start,
+                ));
+            }
+        };
+        let mut value = u64::from(info);
+        if len > 0 {
+            value = 0;
+            for _ in 0..len {
+                value = value << 8 | u64::from(self.byte()?);
+            }
+        }
+        if header >> 5 == 7 {
+            // Floats and simple values are checked by decoding them.
+            let mut data = vec![header];
+            data.extend(&value.to_be_bytes()[8 - len..]);
+            CBOR::try_from_data(data)
+                .map_err(|e| Error::Violation(e, start))?;
+        } else if value < minimum {
+            return Err(Error::Violation(
+                dcbor::Error::NonCanonicalNumeric,
+                start,
+            ));
+        }
+        Ok(value)
+    }
+
+    /// Reads the next byte, which must exist.
+    fn byte(&mut self) -> Result<u8> {
+        self.next_byte()?
+            .ok_or(Error::Violation(dcbor::Error::Underrun, self.offset))
+    }
+
+    /// Reads the next byte, or returns `None` at the end of the input.
+    fn next_byte(&mut self) -> Result<Option<u8>> {
+        let byte = match self.format {
+            InputFormat::Binary => match self.read()? {
+                Some(byte) => byte,
+                None => return Ok(None),
+            },
+            InputFormat::Hex => {
+                let Some(high) = self.hex_digit()? else {
+                    return Ok(None);
+                };
+                let Some(low) = self.hex_digit()? else {
+                    return Err(Error::InvalidHex(self.text_offset));
+                };
+                high << 4 | low
+            }
+        };
+        self.offset += 1;
+        if self.recording > 0 {
+            self.key.push(byte);
+        }
+        Ok(Some(byte))
+    }
+
+    /// Reads the next hex digit, skipping whitespace.
+    fn hex_digit(&mut self) -> Result<Option<u8>> {
+        while let Some(c) = self.read()? {
+            if c.is_ascii_whitespace() {
+                continue;
+            }
+            return match char::from(c).to_digit(16) {
+                Some(digit) => Ok(Some(digit as u8)),
+                None => Err(Error::InvalidHex(self.text_offset - 1)),
+            };
+        }
+        Ok(None)
+    }
+
+    /// Reads the next byte of the input.
+    fn read(&mut self) -> io::Result<Option<u8>> {
+        let Some(&byte) = self.reader.fill_buf()?.first() else {
+            return Ok(None);
+        };
+        self.reader.consume(1);
+        self.text_offset += 1;
+        Ok(Some(byte))
+    }
+}
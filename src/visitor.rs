@@ -0,0 +1,111 @@
+use crate::{DiagValue, ParseResult, SourceSpan, error::Error, parse::Parser};
+
+/// The kind of group whose opening delimiter the parser has read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GroupKind {
+    Array,
+    Map,
+    Tagged,
+    /// An embedded CBOR sequence, `<<item, ...>>`, which is a byte string.
+    Embedded,
+}
+
+/// What the parser does after calling a [`ParseVisitor`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum VisitAction {
+    /// Keep parsing.
+    #[default]
+    Continue,
+    /// Stop parsing, failing with
+    /// [`VisitorStopped`](crate::ParseError::VisitorStopped).
+    Stop,
+    /// Stop parsing, failing with
+    /// [`VisitorRejected`](crate::ParseError::VisitorRejected) and the given
+    /// reason.
+    Reject(String),
+}
+
+impl VisitAction {
+    pub(crate) fn into_result(self, span: SourceSpan) -> ParseResult<()> {
+        match self {
+            VisitAction::Continue => Ok(()),
+            VisitAction::Stop => Err(Error::VisitorStopped(span)),
+            VisitAction::Reject(reason) => {
+                Err(Error::VisitorRejected(reason, span))
+            }
+        }
+    }
+}
+
+/// Callbacks invoked by [`parse_diag_value_with_visitor`] as the source is
+/// parsed.
+///
+/// Useful for gathering statistics, rejecting disallowed content as soon as
+/// it is seen, and reporting progress through huge documents. Every method
+/// has a default implementation that continues parsing.
+pub trait ParseVisitor {
+    /// Called when the opening delimiter of an array, map, or tagged item at
+    /// `depth` has been read. `span` covers the delimiter, including the tag
+    /// of a tagged item.
+    fn begin_group(
+        &mut self,
+        kind: GroupKind,
+        span: SourceSpan,
+        depth: usize,
+    ) -> VisitAction {
+        let _ = (kind, span, depth);
+        VisitAction::Continue
+    }
+
+    /// Called when an item at `depth` is complete. The items of an array, the
+    /// keys and values of a map, and the content of a tagged item are visited
+    /// in source order, before the group that contains them. The top-level
+    /// item is at depth 0.
+    fn item(&mut self, item: &DiagValue, depth: usize) -> VisitAction {
+        let _ = (item, depth);
+        VisitAction::Continue
+    }
+}
+
+/// Parses a dCBOR item from a string input into a [`DiagValue`], calling
+/// `visitor` as each item is parsed.
+///
+/// Accepts the same input as [`parse_diag_value`](crate::parse_diag_value),
+/// and reports the same errors, as well as the errors the visitor asks for.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{
+/// #     DiagValue, ParseError, ParseVisitor, VisitAction,
+/// #     parse_diag_value_with_visitor,
+/// # };
+/// struct NoFloats;
+///
+/// impl ParseVisitor for NoFloats {
+///     fn item(&mut self, item: &DiagValue, _depth: usize) -> VisitAction {
+///         match item {
+///             DiagValue::Number { text, .. } if text.contains('.') => {
+///                 VisitAction::Reject("floats are not allowed".to_string())
+///             }
+///             _ => VisitAction::Continue,
+///         }
+///     }
+/// }
+///
+/// let error = parse_diag_value_with_visitor("[1, 2.5]", &mut NoFloats)
+///     .unwrap_err();
+/// assert_eq!(
+///     error,
+///     ParseError::VisitorRejected(
+///         "floats are not allowed".to_string(),
+///         (4..7).into()
+///     )
+/// );
+/// ```
+pub fn parse_diag_value_with_visitor(
+    src: &str,
+    visitor: &mut dyn ParseVisitor,
+) -> ParseResult<DiagValue> {
+    Parser::new(src).with_visitor(visitor).parse_all()
+}
@@ -0,0 +1,216 @@
+use std::fmt;
+
+use dcbor::prelude::*;
+
+use crate::{
+    DiagValue, NumberValue, ParseResult, SourceSpan, known_value_info,
+    parse::is_control_character, parse_diag_value,
+};
+
+/// The tag of a known value written as a tagged integer, such as `40000(1)`.
+const KNOWN_VALUE_TAG: TagValue = 40000;
+
+/// A problem in the source that doesn't prevent it from being parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WarningKind {
+    /// A known value in the reserved range that isn't in the known values
+    /// registry.
+    UnregisteredKnownValue { value: u64 },
+    /// A raw control character in a string, accepted by
+    /// [`ParseOptions::control_characters`](crate::ParseOptions::control_characters).
+    ControlCharacter { character: char },
+    /// A sequence of bytes that isn't valid UTF-8, replaced with U+FFFD by
+    /// [`parse_dcbor_item_from_bytes_lossy`](crate::parse_dcbor_item_from_bytes_lossy).
+    InvalidUtf8 { bytes: Vec<u8> },
+    /// A NUL character, replaced with U+FFFD by
+    /// [`parse_dcbor_item_from_bytes_lossy`](crate::parse_dcbor_item_from_bytes_lossy).
+    NulCharacter,
+    /// A `NaN` written with a negative sign, such as `-nan`, whose sign isn't
+    /// kept by the canonical NaN it is encoded as. Reported when NaNs are
+    /// parsed with [`NaNHandling::Warn`](crate::NaNHandling::Warn).
+    NaNSignLost,
+    /// A tag name, UR type, or known value name that the registries resolve
+    /// differently than when the [`Session`](crate::Session) was pinned
+    /// with [`pin_registries`](crate::Session::pin_registries), or that
+    /// wasn't registered then.
+    RegistryChanged {
+        name: String,
+        pinned: Option<u64>,
+        resolved: u64,
+    },
+}
+
+impl WarningKind {
+    /// Returns a stable identifier for the kind of this warning.
+    pub fn code(&self) -> &'static str {
+        match self {
+            WarningKind::UnregisteredKnownValue { .. } => {
+                "unregistered-known-value"
+            }
+            WarningKind::ControlCharacter { .. } => "control-character",
+            WarningKind::InvalidUtf8 { .. } => "invalid-utf8",
+            WarningKind::NulCharacter => "nul-character",
+            WarningKind::NaNSignLost => "nan-sign-lost",
+            WarningKind::RegistryChanged { .. } => "registry-changed",
+        }
+    }
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarningKind::UnregisteredKnownValue { value } => {
+                let info = known_value_info(*value);
+                write!(f, "unregistered codepoint in reserved range: {info}")
+            }
+            WarningKind::ControlCharacter { character } => {
+                let code = u32::from(*character);
+                write!(f, "raw control character U+{code:04X} in string")
+            }
+            WarningKind::InvalidUtf8 { bytes } => {
+                let bytes = hex::encode(bytes);
+                write!(f, "invalid UTF-8 h'{bytes}' replaced with U+FFFD")
+            }
+            WarningKind::NulCharacter => {
+                write!(f, "NUL character replaced with U+FFFD")
+            }
+            WarningKind::NaNSignLost => {
+                write!(f, "sign of NaN lost in canonical encoding")
+            }
+            WarningKind::RegistryChanged { name, pinned, resolved } => {
+                write!(f, "'{name}' resolves to {resolved}")?;
+                match pinned {
+                    Some(pinned) => {
+                        write!(f, ", not {pinned} as when the session began")
+                    }
+                    None => write!(
+                        f,
+                        ", but wasn't registered when the session began"
+                    ),
+                }
+            }
+        }
+    }
+}
+
+/// A [`WarningKind`] along with the span of the source it applies to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub span: SourceSpan,
+}
+
+impl Warning {
+    /// Returns the warnings for a parsed value.
+    pub fn collect(value: &DiagValue) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        visit(value, &mut warnings);
+        warnings
+    }
+}
+
+/// Parses a dCBOR item from a string input, and returns any warnings about
+/// the source along with it.
+///
+/// Accepts exactly the same input as
+/// [`parse_dcbor_item`](crate::parse_dcbor_item), and reports the same errors.
+///
+/// # Example
+///
+/// ```rust
+/// # use dcbor_parse::{WarningKind, parse_dcbor_item_with_warnings};
+/// let (_, warnings) = parse_dcbor_item_with_warnings("['1', '45']").unwrap();
+/// assert_eq!(warnings.len(), 1);
+/// assert_eq!(
+///     warnings[0].kind,
+///     WarningKind::UnregisteredKnownValue { value: 45 }
+/// );
+/// assert_eq!(warnings[0].span, 6..10);
+/// ```
+pub fn parse_dcbor_item_with_warnings(
+    src: &str,
+) -> ParseResult<(CBOR, Vec<Warning>)> {
+    let value = parse_diag_value(src)?;
+    let warnings = Warning::collect(&value);
+    Ok((value.to_cbor(), warnings))
+}
+
+//
+// === Private Functions ===
+//
+
+fn visit(value: &DiagValue, warnings: &mut Vec<Warning>) {
+    match value {
+        DiagValue::KnownValue { value, span, .. } => {
+            check_known_value(value.value(), *span, warnings);
+        }
+        DiagValue::Text { text, span, .. } => {
+            check_control_characters(text, *span, warnings);
+        }
+        DiagValue::Number { value: NumberValue::Float(f), span, .. }
+            if f.is_nan() && f.is_sign_negative() =>
+        {
+            let kind = WarningKind::NaNSignLost;
+            warnings.push(Warning { kind, span: *span });
+        }
+        DiagValue::Tagged { tag, item, span, .. } => {
+            if tag.value() == KNOWN_VALUE_TAG {
+                if let DiagValue::Number { value, .. } = item.as_ref() {
+                    if let Some(value) = value.to_u64() {
+                        check_known_value(value, *span, warnings);
+                    }
+                }
+            }
+            visit(item, warnings);
+        }
+        DiagValue::Array { items, .. } => {
+            items.iter().for_each(|item| visit(item, warnings));
+        }
+        DiagValue::Map { entries, .. } => {
+            for (key, value) in entries {
+                visit(key, warnings);
+                visit(value, warnings);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_known_value(
+    value: u64,
+    span: SourceSpan,
+    warnings: &mut Vec<Warning>,
+) {
+    let info = known_value_info(value);
+    if info.is_reserved() && !info.is_registered() {
+        warnings.push(Warning {
+            kind: WarningKind::UnregisteredKnownValue { value },
+            span,
+        });
+    }
+}
+
+/// Warns of each control character in the source `text` of a string at
+/// `span`. Each warning has the span of its character, unless the string
+/// came from a substituted variable, whose span is that of the variable.
+fn check_control_characters(
+    text: &str,
+    span: SourceSpan,
+    warnings: &mut Vec<Warning>,
+) {
+    let is_written = span.len() == text.len();
+    for (index, character) in text.char_indices() {
+        if !is_control_character(character) {
+            continue;
+        }
+        let span = if is_written {
+            SourceSpan::new(span.start + index, span.start + index + 1)
+        } else {
+            span
+        };
+        warnings.push(Warning {
+            kind: WarningKind::ControlCharacter { character },
+            span,
+        });
+    }
+}
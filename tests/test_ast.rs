@@ -0,0 +1,93 @@
+use dcbor_parse::{
+    AstNode, AstNodeKind, LiteralForm, grammar, parse_dcbor_ast,
+    parse_dcbor_item,
+};
+
+fn kinds(node: &AstNode) -> Vec<AstNodeKind> {
+    node.children.iter().map(|child| child.kind).collect()
+}
+
+#[test]
+fn test_ast_is_lossless() {
+    dcbor::register_tags();
+    let src = "  # leading\n{\"a\": [1, h'00' / inline /], \
+               'isA': date(2023-02-08)}  # trailing\n";
+    let ast = parse_dcbor_ast(src).unwrap();
+    assert_eq!(ast.to_cbor(), parse_dcbor_item(src).unwrap());
+
+    // Tokens and trivia tile the source.
+    let mut pieces: Vec<_> = ast
+        .tokens
+        .iter()
+        .map(|token| token.span)
+        .chain(ast.trivia.iter().copied())
+        .collect();
+    pieces.sort_by_key(|span| span.start);
+    let rebuilt: String =
+        pieces.iter().map(|span| &src[span.range()]).collect();
+    assert_eq!(rebuilt, src);
+    assert!(pieces.windows(2).all(|w| w[0].end == w[1].start));
+
+    // Every token kind is named as in the grammar.
+    let grammar = grammar();
+    assert!(ast.tokens.iter().all(|t| grammar.token(t.kind).is_some()));
+    assert_eq!(ast.root.tokens, 0..ast.tokens.len());
+}
+
+#[test]
+fn test_ast_nodes() {
+    dcbor::register_tags();
+    let src = r#"{"a": [1, -2.5], 'isA': date(1)}"#;
+    let ast = parse_dcbor_ast(src).unwrap();
+    assert_eq!(ast.root.kind, AstNodeKind::Item(LiteralForm::Map));
+    assert_eq!(kinds(&ast.root), [AstNodeKind::Entry, AstNodeKind::Entry]);
+
+    let entry = &ast.root.children[0];
+    assert_eq!(&src[entry.span.range()], r#""a": [1, -2.5]"#);
+    let tokens: Vec<_> =
+        ast.tokens[entry.tokens.clone()].iter().map(|t| t.kind).collect();
+    assert_eq!(
+        tokens,
+        [
+            "String",
+            "Colon",
+            "BracketOpen",
+            "Number",
+            "Comma",
+            "Number",
+            "BracketClose"
+        ]
+    );
+    let array = &entry.children[1];
+    assert_eq!(
+        kinds(array),
+        [
+            AstNodeKind::Item(LiteralForm::Integer),
+            AstNodeKind::Item(LiteralForm::Float)
+        ]
+    );
+
+    let entry = &ast.root.children[1];
+    assert_eq!(
+        kinds(entry),
+        [
+            AstNodeKind::Item(LiteralForm::KnownValueName),
+            AstNodeKind::Item(LiteralForm::NameTaggedValue)
+        ]
+    );
+    let tagged = &entry.children[1];
+    assert_eq!(&src[tagged.span.range()], "date(1)");
+    assert_eq!(&src[tagged.children[0].span.range()], "1");
+
+    // Offsets resolve to the innermost node.
+    let path = ast.nodes_at(src.find("-2.5").unwrap() + 1);
+    assert_eq!(path.len(), 4);
+    assert_eq!(path[3].kind, AstNodeKind::Item(LiteralForm::Float));
+    assert!(ast.nodes_at(src.len()).is_empty());
+}
+
+#[test]
+fn test_ast_errors() {
+    assert!(parse_dcbor_ast("[1, 2").is_err());
+    assert!(parse_dcbor_ast("1 2").is_err());
+}
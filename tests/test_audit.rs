@@ -0,0 +1,84 @@
+use dcbor_parse::{
+    AuditTrail, LiteralForm, ParseOptions, parse_dcbor_item,
+    parse_dcbor_item_with_audit, parse_diag_value_with_options,
+};
+
+#[test]
+fn test_audit_trail_maps_leaves_to_bytes() {
+    dcbor::register_tags();
+    let src =
+        r#"{"z": [NaN, '1', 'isA', ''], "a": 1(2), 2023-02-08: b64'AA=='}"#;
+    let (cbor, trail) = parse_dcbor_item_with_audit(src).unwrap();
+    let data = cbor.to_cbor_data();
+    let forms: Vec<_> = trail.entries.iter().map(|entry| entry.form).collect();
+    assert_eq!(
+        forms,
+        [
+            LiteralForm::Text,
+            LiteralForm::FloatKeyword,
+            LiteralForm::KnownValueNumber,
+            LiteralForm::KnownValueName,
+            LiteralForm::Unit,
+            LiteralForm::Text,
+            LiteralForm::Integer,
+            LiteralForm::Date,
+            LiteralForm::Base64ByteString,
+        ]
+    );
+    // Each leaf's bytes are the encoding of the literal it came from.
+    for entry in &trail.entries {
+        let leaf = parse_dcbor_item(&src[entry.span.range()]).unwrap();
+        assert_eq!(data[entry.bytes.clone()], leaf.to_cbor_data());
+    }
+    // The integer inside `1(...)` is encoded after the tag's head.
+    assert_eq!(trail.entries[6].bytes, 4..5);
+    assert_eq!(data[3], 0xc1);
+}
+
+#[test]
+fn test_audit_trail_serialization() {
+    let (_, trail) = parse_dcbor_item_with_audit("[true, h'01']").unwrap();
+    assert_eq!(
+        trail.to_cbor().diagnostic_flat(),
+        r#"[{"form": "boolean", "span": [1, 5], "bytes": [1, 2]}, "#
+            .to_owned()
+            + r#"{"form": "hex byte string", "span": [7, 12], "#
+            + r#""bytes": [2, 4]}]"#
+    );
+    assert_eq!(
+        trail.to_json(),
+        r#"[{"span": [1, 5], "form": "boolean", "bytes": [1, 2]}, "#
+            .to_owned()
+            + r#"{"span": [7, 12], "form": "hex byte string", "#
+            + r#""bytes": [2, 4]}]"#
+    );
+}
+
+#[test]
+fn test_audit_trail_of_included_documents() {
+    let options = ParseOptions::default().include_loader(|name| match name {
+        "pair" => Ok("[1, 2]".to_string()),
+        _ => Err("not found".to_string()),
+    });
+    let src = r#"[0, !include("pair"), 3]"#;
+    let value = parse_diag_value_with_options(src, &options).unwrap();
+    let trail = AuditTrail::collect(&value);
+    let entries: Vec<_> = trail
+        .entries
+        .iter()
+        .map(|entry| {
+            (entry.span.range(), entry.bytes.clone(), entry.document.clone())
+        })
+        .collect();
+    let pair = Some("pair".to_string());
+    assert_eq!(
+        entries,
+        [
+            (1..2, 1..2, None),
+            (1..2, 3..4, pair.clone()),
+            (4..5, 4..5, pair),
+            (22..23, 5..6, None),
+        ]
+    );
+    assert!(trail.to_json().contains(r#""document": "pair""#));
+}
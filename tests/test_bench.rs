@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use dcbor_parse::bench::{PhaseTiming, bench_corpus};
+
+#[test]
+fn test_bench_corpus() {
+    let dir = std::env::temp_dir().join("dcbor-parse-test-bench");
+    std::fs::create_dir_all(&dir).unwrap();
+    let good = dir.join("good.diag");
+    let bad = dir.join("bad.diag");
+    std::fs::write(&good, "[1, 2, 3]").unwrap();
+    std::fs::write(&bad, "[1, 2").unwrap();
+
+    let report = bench_corpus([&good, &bad]).unwrap();
+    assert_eq!((report.files, report.failures), (2, 1));
+    assert_eq!(report.lex.bytes, 14);
+    assert_eq!(report.parse.bytes, 9);
+    assert_eq!(report.print.bytes, 9);
+
+    assert!(bench_corpus([dir.join("missing.diag")]).is_err());
+}
+
+#[test]
+fn test_phase_timing_throughput() {
+    let timing =
+        PhaseTiming { duration: Duration::from_millis(500), bytes: 1_000_000 };
+    assert_eq!(timing.bytes_per_second(), 2_000_000.0);
+    assert_eq!(timing.to_string(), "1000000 bytes in 500ms (2.00 MB/s)");
+    let instant = PhaseTiming { duration: Duration::ZERO, bytes: 1 };
+    assert_eq!(instant.bytes_per_second(), 0.0);
+}
@@ -0,0 +1,60 @@
+use dcbor_parse::{
+    Budget, ParseError, parse_diag_value, parse_diag_value_budgeted,
+};
+
+fn items(max: usize) -> Budget {
+    Budget { max_items: Some(max), ..Default::default() }
+}
+
+fn bytes(max: usize) -> Budget {
+    Budget { max_bytes: Some(max), ..Default::default() }
+}
+
+fn diagnostic(src: &str, budget: Budget) -> (String, Option<usize>) {
+    let result = parse_diag_value_budgeted(src, budget).unwrap();
+    (
+        result.value.to_cbor().diagnostic_flat(),
+        result.truncated.map(|t| t.offset),
+    )
+}
+
+#[test]
+fn test_budget_not_spent() {
+    let src = r#"{"a": [1, 2], "b": 3(4)}"#;
+    let result = parse_diag_value_budgeted(src, Budget::default()).unwrap();
+    assert_eq!(result.value, parse_diag_value(src).unwrap());
+    assert_eq!(result.truncated, None);
+    assert_eq!(diagnostic(src, items(100)).1, None);
+}
+
+#[test]
+fn test_item_budget() {
+    let src = r#"{"a": [1, 2], "b": 3(4), "c": 5}"#;
+    assert_eq!(diagnostic(src, items(1)), (r#"{}"#.to_string(), Some(4)));
+    assert_eq!(
+        diagnostic(src, items(3)),
+        (r#"{"a": [1, 2]}"#.to_string(), Some(11))
+    );
+    assert_eq!(
+        diagnostic(src, items(7)),
+        (r#"{"a": [1, 2], "b": 3(4)}"#.to_string(), Some(23))
+    );
+    assert_eq!(diagnostic("[1, 2] garbage", items(2)).0, "[1, 2]");
+    assert_eq!(diagnostic("7", items(0)), ("7".to_string(), Some(1)));
+}
+
+#[test]
+fn test_byte_budget() {
+    let src = "[h'00010203', h'04050607', h'08090a0b']";
+    assert_eq!(
+        diagnostic(src, bytes(10)),
+        ("[h'00010203', h'04050607']".to_string(), Some(25))
+    );
+}
+
+#[test]
+fn test_errors_before_budget_is_spent() {
+    let error = parse_diag_value_budgeted("[1, @, 3]", items(3)).unwrap_err();
+    assert!(matches!(error, ParseError::UnrecognizedToken(_)));
+    assert!(parse_diag_value_budgeted("[1, 2, @]", items(2)).is_ok());
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use dcbor::prelude::*;
+use dcbor_parse::{
+    CacheStore, ComposeError, ParseCache, ParseError, fingerprint,
+};
+
+#[test]
+fn test_cache_hits_and_misses() {
+    let mut cache = ParseCache::new();
+    let a = cache.parse_dcbor_item("[1, 2]").unwrap();
+    let b = cache.parse_dcbor_item("[1, 2]").unwrap();
+    let c = cache.parse_dcbor_item("[1,2]").unwrap();
+    assert_eq!(a, b);
+    assert_eq!(a, c);
+    assert_eq!((cache.hits(), cache.misses()), (1, 2));
+    assert_eq!(cache.store().len(), 2);
+}
+
+#[test]
+fn test_cache_errors_not_stored() {
+    let mut cache = ParseCache::new();
+    for _ in 0..2 {
+        assert!(matches!(
+            cache.parse_dcbor_item("[1,"),
+            Err(ParseError::UnexpectedEndOfInput)
+        ));
+    }
+    assert_eq!((cache.hits(), cache.misses()), (0, 2));
+    assert!(cache.store().is_empty());
+}
+
+#[test]
+fn test_cache_compose_map() {
+    let mut cache = ParseCache::new();
+    let cbor = cache.compose_dcbor_map(&["1", "true", "2", "true"]).unwrap();
+    assert_eq!(cbor.diagnostic(), "{1: true, 2: true}");
+    assert_eq!(cache.hits(), 1);
+    assert_eq!(
+        cache.compose_dcbor_map(&["1", "2", "1", "3"]),
+        Err(ComposeError::DuplicateMapKey(0, 2))
+    );
+}
+
+/// A store that records the fingerprint of each item it is given.
+#[derive(Default)]
+struct RecordingStore {
+    items: HashMap<[u8; 32], CBOR>,
+    fingerprints: Vec<[u8; 32]>,
+}
+
+impl CacheStore for RecordingStore {
+    fn get(&self, key: &[u8; 32]) -> Option<CBOR> {
+        self.items.get(key).cloned()
+    }
+
+    fn insert(&mut self, key: [u8; 32], item: CBOR) {
+        self.fingerprints.push(fingerprint(&item));
+        self.items.insert(key, item);
+    }
+}
+
+#[test]
+fn test_cache_custom_store() {
+    let mut cache = ParseCache::with_store(RecordingStore::default());
+    cache.compose_dcbor_array(&["1", "1", "1.0"]).unwrap();
+    assert_eq!(cache.hits(), 1);
+    let store = cache.into_store();
+    let one = fingerprint(&CBOR::from(1));
+    assert_eq!(store.fingerprints, [one, one]);
+}
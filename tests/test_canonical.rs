@@ -0,0 +1,103 @@
+use dcbor_parse::{
+    Canonicalization, CanonicalizationReport, MemoryEstimate, ParseError,
+    SourceSpan, parse_dcbor_item, parse_dcbor_item_with_report,
+    parse_diag_value, sorted_key_preview,
+};
+
+fn kinds(src: &str) -> Vec<Canonicalization> {
+    let (_, report) = parse_dcbor_item_with_report(src).unwrap();
+    report.notes.into_iter().map(|note| note.kind).collect()
+}
+
+#[test]
+fn test_canonical_source_has_empty_report() {
+    let src = r#"{1: [2, -3, "text", h'00'], "a": 1.1}"#;
+    let (_, report) = parse_dcbor_item_with_report(src).unwrap();
+    assert!(report.is_empty(), "{:?}", report);
+}
+
+#[test]
+fn test_numeric_canonicalizations() {
+    assert_eq!(kinds("1.0"), [Canonicalization::FloatReducedToInteger]);
+    assert_eq!(kinds("-1e2"), [Canonicalization::FloatReducedToInteger]);
+    assert_eq!(kinds("1.5"), [Canonicalization::FloatNarrowed { bits: 16 }]);
+    assert_eq!(
+        kinds("100000.5"),
+        [Canonicalization::FloatNarrowed { bits: 32 }]
+    );
+    assert_eq!(kinds("1.1"), []);
+    assert_eq!(kinds("NaN"), [Canonicalization::NaNCanonicalized]);
+    assert_eq!(
+        kinds("Infinity"),
+        [Canonicalization::FloatNarrowed { bits: 16 }]
+    );
+    assert_eq!(
+        kinds("100000000000000000000"),
+        [Canonicalization::IntegerEncodedAsBignum]
+    );
+}
+
+#[test]
+fn test_other_canonicalizations() {
+    dcbor::register_tags();
+    assert_eq!(kinds("2023-02-08"), [Canonicalization::DateAsEpochTime]);
+    // "e" followed by a combining acute accent normalizes to "é".
+    assert_eq!(kinds("\"e\u{301}\""), [Canonicalization::TextNormalized]);
+    assert_eq!(kinds("\"\u{e9}\""), []);
+}
+
+#[test]
+fn test_map_reordering_spans() {
+    let src = r#"[{"a": 1, "b": 2}, {"bb": 1, "c": 2}]"#;
+    let report = CanonicalizationReport::new(&parse_diag_value(src).unwrap());
+    assert_eq!(report.notes.len(), 1);
+    let note = &report.notes[0];
+    assert_eq!(note.kind, Canonicalization::MapKeysReordered);
+    assert_eq!(&src[note.span.range()], r#"{"bb": 1, "c": 2}"#);
+    assert_eq!(
+        note.kind.to_string(),
+        "map entries reordered by encoded key"
+    );
+}
+
+#[test]
+fn test_sorted_key_preview() {
+    let src = r#"{"bb": 1, "a": 2, 3: 3, -1: 4, [1]: 5}"#;
+    let keys = sorted_key_preview(src).unwrap();
+    let texts: Vec<_> =
+        keys.iter().map(|k| &src[k.key.span().range()]).collect();
+    assert_eq!(texts, ["3", "-1", r#""a""#, r#""bb""#, "[1]"]);
+    let indexes: Vec<_> = keys.iter().map(|k| k.source_index).collect();
+    assert_eq!(indexes, [2, 3, 1, 0, 4]);
+    assert_eq!(&src[keys[0].value_span.range()], "3");
+
+    let cbor = parse_dcbor_item(src).unwrap();
+    let map = cbor.try_into_map().unwrap();
+    let encoded: Vec<_> = map.iter().map(|(k, _)| k.diagnostic()).collect();
+    assert_eq!(encoded, ["3", "-1", r#""a""#, r#""bb""#, "[1]"]);
+}
+
+#[test]
+fn test_sorted_key_preview_requires_map() {
+    let error = sorted_key_preview(" [1, 2]").unwrap_err();
+    assert_eq!(error, ParseError::ExpectedMap(SourceSpan::new(1, 7)));
+    assert!(sorted_key_preview("{}").unwrap().is_empty());
+}
+
+#[test]
+fn test_memory_estimate() {
+    let slot = size_of::<dcbor::CBOR>();
+    let src = r#"{"name": "Alice", "keys": [h'00010203', 1(2)], "n": 1.5}"#;
+    let (cbor, report) = parse_dcbor_item_with_report(src).unwrap();
+    let memory = report.memory.unwrap();
+    assert_eq!(memory.strings, 4 + 5 + 4 + 1);
+    assert_eq!(memory.byte_strings, 4);
+    assert_eq!(memory.containers, (3 * 2 + 2 + 1) * slot);
+    assert_eq!(memory.total(), 18 + 9 * slot);
+    assert_eq!(memory, MemoryEstimate::new(&cbor));
+
+    let (_, report) = parse_dcbor_item_with_report("42").unwrap();
+    assert_eq!(report.memory.unwrap().total(), 0);
+    let value = parse_diag_value("[1]").unwrap();
+    assert_eq!(CanonicalizationReport::new(&value).memory, None);
+}
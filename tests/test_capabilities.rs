@@ -0,0 +1,46 @@
+use dcbor_parse::{LiteralForm, parse_dcbor_item, syntax_capabilities};
+
+#[test]
+fn test_every_literal_form_example_parses() {
+    dcbor::register_tags();
+    let capabilities = syntax_capabilities();
+    for form in &capabilities.literal_forms {
+        assert!(
+            parse_dcbor_item(form.example()).is_ok(),
+            "{} example failed to parse: {}",
+            form.name(),
+            form.example()
+        );
+    }
+}
+
+#[test]
+fn test_byte_string_prefixes_parse() {
+    let capabilities = syntax_capabilities();
+    for prefix in &capabilities.byte_string_prefixes {
+        // Valid hex, base64, base64url, and base45, and the ByteWords of
+        // an empty byte string.
+        let body = if *prefix == "bw" { "aeaeaeae" } else { "00000000" };
+        let src = format!("{prefix}'{body}'");
+        assert!(parse_dcbor_item(&src).is_ok(), "{src}");
+    }
+    assert!(capabilities.supports(LiteralForm::HexByteString));
+    assert!(capabilities.supports(LiteralForm::Base64ByteString));
+}
+
+#[test]
+fn test_feature_dependent_capabilities() {
+    let capabilities = syntax_capabilities();
+    let full = cfg!(not(feature = "simplified-patterns"));
+    assert_eq!(capabilities.string_escapes, full);
+    assert_eq!(capabilities.date_fractional_seconds, full);
+    assert_eq!(capabilities.date_time_zones, full);
+    assert_eq!(
+        parse_dcbor_item("2023-02-08T15:30:45.123Z").is_ok(),
+        capabilities.date_fractional_seconds
+    );
+    assert_eq!(
+        parse_dcbor_item(r#""a\"b""#).is_ok(),
+        capabilities.string_escapes
+    );
+}
@@ -0,0 +1,54 @@
+use dcbor::prelude::*;
+use dcbor_parse::{infer_cddl, parse_dcbor_item};
+
+fn infer(examples: &[&str]) -> String {
+    let examples: Vec<CBOR> = examples
+        .iter()
+        .map(|src| parse_dcbor_item(src).unwrap())
+        .collect();
+    infer_cddl(&examples)
+}
+
+#[test]
+fn test_infer_cddl_primitives() {
+    assert_eq!(infer(&[]), "root = any");
+    assert_eq!(infer(&["1", "2"]), "root = uint");
+    assert_eq!(infer(&["1", "-1", "2"]), "root = int");
+    assert_eq!(infer(&["1.5", "true", "null"]), "root = float / bool / null");
+    assert_eq!(infer(&[r#""a""#, "h'00'"]), "root = tstr / bstr");
+}
+
+#[test]
+fn test_infer_cddl_arrays_and_tags() {
+    assert_eq!(infer(&["[]"]), "root = []");
+    assert_eq!(infer(&["[1, \"a\"]", "[]"]), "root = [* uint / tstr]");
+    assert_eq!(infer(&["[[1], [-1]]"]), "root = [* [* int]]");
+    assert_eq!(
+        infer(&["1(1675854714)", "1(\"x\")", "2(h'00')"]),
+        "root = #6.1(uint / tstr) / #6.2(bstr)"
+    );
+}
+
+#[test]
+fn test_infer_cddl_maps() {
+    assert_eq!(infer(&["{}"]), "root = {}");
+    assert_eq!(infer(&["{1: \"a\", 2: \"b\"}"]), "root = {* uint => tstr}");
+    let expected = r#"root = {
+  "a": {
+    "b": uint,
+    ? "c": [* bool],
+  },
+} / {* int => tstr}"#;
+    assert_eq!(
+        infer(&[
+            r#"{"a": {"b": 1}}"#,
+            r#"{"a": {"b": 2, "c": [true]}}"#,
+            r#"{1: "x", -1: "y"}"#,
+        ]),
+        expected
+    );
+    assert_eq!(
+        infer(&[r#"{"a": 1}"#, "{}"]),
+        "root = {\n  ? \"a\": uint,\n}"
+    );
+}
@@ -0,0 +1,79 @@
+use dcbor::prelude::*;
+use dcbor_parse::{
+    Comment, ParseError, parse_dcbor_item_partial,
+    parse_dcbor_item_partial_with_comments,
+};
+
+fn texts(comments: &[Comment]) -> Vec<&str> {
+    comments.iter().map(|comment| comment.text.as_str()).collect()
+}
+
+#[test]
+fn test_leading_and_trailing_comments() {
+    let src = "/ first / # second\n  /third/\n[1, / inner / 2] # after\n";
+    let (commented, used) =
+        parse_dcbor_item_partial_with_comments(src).unwrap();
+    assert_eq!(commented.item, CBOR::from(vec![1, 2]));
+    assert_eq!(texts(&commented.leading), ["first", "second", "third"]);
+    assert_eq!(texts(&commented.trailing), ["after"]);
+    assert_eq!(used, src.len());
+
+    let leading = &commented.leading;
+    assert_eq!(&src[leading[0].span.range()], "/ first /");
+    assert_eq!(&src[leading[1].span.range()], "# second");
+    assert_eq!(&src[leading[2].span.range()], "/third/");
+}
+
+#[test]
+fn test_comments_in_a_sequence_of_items() {
+    let src = indoc::indoc! {"
+        # Case 1: an empty array
+        []   # always valid
+
+        # Case 2: a map
+        / with one entry /
+        {1: 2}
+        3 / same line /
+    "};
+    let mut rest = src;
+    let mut cases = Vec::new();
+    while !rest.trim().is_empty() {
+        let (commented, used) =
+            parse_dcbor_item_partial_with_comments(rest).unwrap();
+        cases.push((
+            commented.item.diagnostic_flat(),
+            texts(&commented.leading).join("|"),
+            texts(&commented.trailing).join("|"),
+        ));
+        rest = &rest[used..];
+    }
+    let case = |item: &str, leading: &str, trailing: &str| {
+        (item.to_string(), leading.to_string(), trailing.to_string())
+    };
+    assert_eq!(
+        cases,
+        [
+            case("[]", "Case 1: an empty array", "always valid"),
+            case("{1: 2}", "Case 2: a map|with one entry", ""),
+            case("3", "", "same line"),
+        ]
+    );
+}
+
+#[test]
+fn test_used_matches_partial_without_later_comments() {
+    for src in ["true )", "false  # comment\n", "1 2", "[1]   \n\n"] {
+        let (_, used) = parse_dcbor_item_partial_with_comments(src).unwrap();
+        let (_, expected) = parse_dcbor_item_partial(src).unwrap();
+        assert_eq!(used, expected, "{src:?}");
+    }
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(
+        parse_dcbor_item_partial_with_comments("# only a comment"),
+        Err(ParseError::EmptyInput)
+    );
+    assert!(parse_dcbor_item_partial_with_comments("/ a / [1,").is_err());
+}
@@ -89,7 +89,16 @@ fn test_compose_map() {
     // Error: Duplicate keys should throw an error
     let array = vec!["1", "2", "1", "3"];
     let err = compose_dcbor_map(&array).unwrap_err();
-    assert!(matches!(err, ComposeError::DuplicateMapKey));
+    assert!(matches!(err, ComposeError::DuplicateMapKey(0, 2)));
+
+    // Error: Keys written differently that are the same item are duplicates
+    let array = vec!["[1.0]", "2", "3", "4", "[1]", "5"];
+    let err = compose_dcbor_map(&array).unwrap_err();
+    assert_eq!(err, ComposeError::DuplicateMapKey(0, 4));
+    assert_eq!(err.to_string(), "Duplicate map key at indices 0 and 4");
+    let array = vec!["-0.0", "2", "0", "3"];
+    let err = compose_dcbor_map(&array).unwrap_err();
+    assert_eq!(err, ComposeError::DuplicateMapKey(0, 2));
 
     // Error: Odd number of items in map
     let array = vec!["1", "2", "3"];
@@ -104,3 +113,88 @@ fn test_compose_map() {
         ComposeError::ParseError(ParseError::EmptyInput)
     ));
 }
+
+#[test]
+fn test_compose_spanned() {
+    let array = vec!["[1]", "true", r#""x""#];
+    let (cbor, nodes) = compose_dcbor_array_spanned(&array).unwrap();
+    for (fragment, node) in array.iter().zip(&nodes) {
+        let expected = parse_dcbor_item(fragment).unwrap();
+        assert_eq!(node.locate(&cbor), Some(expected));
+    }
+    assert_eq!(ComposedNode::ArrayItem(3).locate(&cbor), None);
+    assert_eq!(ComposedNode::MapKey(0).locate(&cbor), None);
+
+    let array = vec![r#""zz""#, "1", "10", "2", r#""a""#, "3"];
+    let (cbor, nodes) = compose_dcbor_map_spanned(&array).unwrap();
+    assert_eq!(
+        nodes,
+        [
+            ComposedNode::MapKey(2),
+            ComposedNode::MapValue(2),
+            ComposedNode::MapKey(0),
+            ComposedNode::MapValue(0),
+            ComposedNode::MapKey(1),
+            ComposedNode::MapValue(1),
+        ]
+    );
+    for (fragment, node) in array.iter().zip(&nodes) {
+        let expected = parse_dcbor_item(fragment).unwrap();
+        assert_eq!(node.locate(&cbor), Some(expected));
+    }
+
+    let err = compose_dcbor_map_spanned(&["1", "2", "1", "3"]).unwrap_err();
+    assert!(matches!(err, ComposeError::DuplicateMapKey(0, 2)));
+    // A fragment that fails to parse is reported with its index.
+    assert_eq!(
+        compose_dcbor_array_spanned(&["1", "[", "2"]),
+        Err(ComposeError::InvalidFragment(1, ParseError::UnexpectedEndOfInput))
+    );
+    assert_eq!(
+        compose_dcbor_map_spanned(&["1", "2", "3", ""]),
+        Err(ComposeError::InvalidFragment(3, ParseError::EmptyInput))
+    );
+}
+
+#[test]
+fn test_compose_multipart_ur() {
+    use bc_ur::prelude::*;
+
+    let src = r#"{"data": h'000102030405060708090a0b0c0d0e0f', "n": 42}"#;
+    let expected = UR::new("test", parse_dcbor_item(src).unwrap()).unwrap();
+    let parts = compose_multipart_ur("test", src, 8, None).unwrap();
+    assert_eq!(parts.len(), 4);
+    for (i, part) in parts.iter().enumerate() {
+        assert!(part.starts_with(&format!("ur:test/{}-4/", i + 1)));
+    }
+    let mut decoder = MultipartDecoder::new();
+    for part in &parts {
+        decoder.receive(part).unwrap();
+    }
+    assert_eq!(decoder.message().unwrap(), Some(expected.clone()));
+
+    // The extra parts let a receiver recover from missing ones.
+    let parts = compose_multipart_ur("test", src, 8, Some(40)).unwrap();
+    assert_eq!(parts.len(), 40);
+    let mut decoder = MultipartDecoder::new();
+    for part in parts.iter().skip(1) {
+        if decoder.is_complete() {
+            break;
+        }
+        decoder.receive(part).unwrap();
+    }
+    assert_eq!(decoder.message().unwrap(), Some(expected));
+
+    assert!(matches!(
+        compose_multipart_ur("Not A Type", src, 10, None),
+        Err(ComposeError::InvalidUR(_))
+    ));
+    assert!(matches!(
+        compose_multipart_ur("test", src, 0, None),
+        Err(ComposeError::InvalidUR(_))
+    ));
+    assert!(matches!(
+        compose_multipart_ur("test", "[1,", 10, None),
+        Err(ComposeError::ParseError(ParseError::UnexpectedEndOfInput))
+    ));
+}
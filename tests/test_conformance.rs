@@ -0,0 +1,123 @@
+use std::io;
+
+use dcbor_parse::conformance::{
+    Expected, TestFailure, parse_test_cases, parse_test_file, run_test_cases,
+};
+
+#[test]
+fn test_conformance_cases() {
+    let path =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/conformance/basic.cases");
+    let cases = parse_test_file(path).unwrap();
+    assert!(cases.len() >= 24);
+    let report = run_test_cases(&cases);
+    assert!(report.is_success(), "{report}");
+    assert_eq!(report.passed, cases.len());
+}
+
+#[test]
+fn test_parse_test_cases() {
+    let text = [
+        "## name: first",
+        "## options: tag-validators, validate-envelopes",
+        "## hex: D8 25 50",
+        "",
+        "37(",
+        "  h'00000000000000000000000000000000'",
+        ")",
+        "## name: empty",
+        "## error: empty-input",
+        "## name: last",
+        "## description: anything",
+        "## error: extra-data",
+        "1 2",
+    ]
+    .join("\n");
+    let cases = parse_test_cases(&text).unwrap();
+    assert_eq!(cases.len(), 3);
+
+    assert_eq!(cases[0].name, "first");
+    assert_eq!(cases[0].line, 1);
+    assert_eq!(cases[0].options, ["tag-validators", "validate-envelopes"]);
+    assert_eq!(cases[0].expected, Expected::Hex("d82550".to_string()));
+    assert_eq!(
+        cases[0].source,
+        "37(\n  h'00000000000000000000000000000000'\n)"
+    );
+    assert_eq!(
+        cases[0].run(),
+        Err("expected d82550, got \
+             d8255000000000000000000000000000000000 \
+             (37(h'00000000000000000000000000000000'))"
+            .to_string())
+    );
+
+    assert_eq!(cases[1].source, "");
+    assert_eq!(cases[1].run(), Ok(()));
+
+    assert_eq!(cases[2].line, 10);
+    assert_eq!(cases[2].expected, Expected::Error("extra-data".to_string()));
+    assert_eq!(cases[2].run(), Ok(()));
+
+    let mut case = cases[2].clone();
+    case.options.push("fast".to_string());
+    assert_eq!(case.parse_options().unwrap_err(), "unknown option `fast`");
+    assert_eq!(case.run(), Err("unknown option `fast`".to_string()));
+}
+
+#[test]
+fn test_report_failures() {
+    let text = "## name: wrong\n## error: expected-comma\n[1, 2]";
+    let cases = parse_test_cases(text).unwrap();
+    let report = run_test_cases(&cases);
+    assert!(!report.is_success());
+    assert_eq!(
+        report.failures,
+        [TestFailure {
+            name: "wrong".to_string(),
+            line: 1,
+            message: "expected error expected-comma, got 820102 ([1, 2])"
+                .to_string(),
+        }]
+    );
+    assert_eq!(
+        report.to_string(),
+        "wrong (line 1): expected error expected-comma, got 820102 ([1, 2])\n\
+         0 passed, 1 failed"
+    );
+}
+
+#[test]
+fn test_invalid_test_files() {
+    let error = |text: &str| {
+        let error = parse_test_cases(text).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        error.to_string()
+    };
+    assert_eq!(
+        error("## name: a\n## hex: 01\n## error: extra-data\n1"),
+        "line 1: the case must have either `## hex:` or `## error:`"
+    );
+    assert_eq!(
+        error("## name: a\n1"),
+        "line 1: the case must have either `## hex:` or `## error:`"
+    );
+    assert_eq!(error("## name: a\n## hex: 0"), "line 2: invalid hex `0`");
+    assert_eq!(
+        error("## name: a\n## expect: 01"),
+        "line 2: unknown key `expect`"
+    );
+    assert_eq!(
+        error("## name: a\n## options: fast\n## hex: 01"),
+        "line 2: unknown option `fast`"
+    );
+    assert_eq!(
+        error("## name: a\n## options: spaced-tags, lenient\n## hex: 01"),
+        "line 2: the profile `lenient` must be the first option"
+    );
+    assert_eq!(
+        error("## name: a\n## hex 01"),
+        "line 2: expected `## key: value`, found `## hex 01`"
+    );
+    assert!(parse_test_file("/nonexistent/file.cases").is_err());
+}
@@ -0,0 +1,72 @@
+use dcbor_parse::{CorpusReport, corpus_report, parse_dcbor_item};
+
+fn report(srcs: &[&str]) -> CorpusReport {
+    let items: Vec<_> =
+        srcs.iter().map(|src| parse_dcbor_item(src).unwrap()).collect();
+    corpus_report(&items)
+}
+
+#[test]
+fn test_corpus_empty() {
+    let report = report(&[]);
+    assert_eq!(report, CorpusReport::default());
+    assert_eq!(
+        report.to_json(),
+        r#"{"items": 0, "types": {}, "depths": {}, "text_lengths": {}, "#
+            .to_string()
+            + r#""byte_string_lengths": {}, "tags": {}}"#
+    );
+}
+
+#[test]
+fn test_corpus_types_and_depths() {
+    let report = report(&[
+        "1",
+        "-1",
+        "[true, false, null, 1.5]",
+        "{1: [[2]]}",
+        "'isA'",
+    ]);
+    assert_eq!(report.items, 5);
+    let types: Vec<_> = report.types.iter().map(|(k, v)| (*k, *v)).collect();
+    assert_eq!(
+        types,
+        [
+            ("array", 3),
+            ("bool", 2),
+            ("float", 1),
+            ("map", 1),
+            ("nint", 1),
+            ("null", 1),
+            ("tag", 1),
+            ("uint", 4),
+        ]
+    );
+    let depths: Vec<_> = report.depths.into_iter().collect();
+    assert_eq!(depths, [(0, 2), (1, 2), (3, 1)]);
+    assert_eq!(report.tags.into_iter().collect::<Vec<_>>(), [(40000, 1)]);
+}
+
+#[test]
+fn test_corpus_length_buckets() {
+    let report = report(&[
+        r#"["", "a", "abc", "abcd", "abcdefg", "abcdefgh"]"#,
+        "[h'', h'00', h'0001020304050607080910111213141516']",
+    ]);
+    let text: Vec<_> = report.text_lengths.into_iter().collect();
+    assert_eq!(text, [(0, 1), (1, 1), (2, 1), (4, 2), (8, 1)]);
+    let bytes: Vec<_> = report.byte_string_lengths.into_iter().collect();
+    assert_eq!(bytes, [(0, 1), (1, 1), (16, 1)]);
+}
+
+#[test]
+fn test_corpus_to_cbor() {
+    let report = report(&[r#"1("a")"#, "1(2)"]);
+    assert_eq!(
+        report.to_cbor().diagnostic_flat(),
+        r#"{"tags": {1: 2}, "items": 2, "types": {"tag": 2, "tstr": 1, "#
+            .to_string()
+            + r#""uint": 1}, "depths": {1: 2}, "text_lengths": {1: 1}, "#
+            + r#""byte_string_lengths": {}}"#
+    );
+}
@@ -0,0 +1,29 @@
+use dcbor_parse::{dedupe_items, parse_dcbor_item};
+
+fn dedupe(srcs: &[&str]) -> dcbor_parse::DedupeReport {
+    dedupe_items(srcs.iter().map(|src| parse_dcbor_item(src).unwrap()))
+}
+
+#[test]
+fn test_dedupe_empty() {
+    let report = dedupe(&[]);
+    assert!(report.unique.is_empty());
+    assert!(report.duplicates().is_empty());
+    assert_eq!(report.redundant_count(), 0);
+}
+
+#[test]
+fn test_dedupe_equivalent_notations() {
+    let report = dedupe(&[
+        "h'00ff'",
+        "2023-02-08",
+        "b64'AP8='",
+        "1(1675814400)",
+        "[1, 2]",
+        "[2, 1]",
+        "h'00FF'",
+    ]);
+    assert_eq!(report.unique_index, [0, 1, 0, 1, 2, 3, 0]);
+    assert_eq!(report.duplicates(), [vec![0, 2, 6], vec![1, 3]]);
+    assert_eq!(report.redundant_count(), 3);
+}
@@ -0,0 +1,174 @@
+use dcbor::prelude::*;
+use dcbor_parse::{
+    ByteStringEncoding, DiagValue, NumberEncoding, NumberKind, ParseError,
+    parse_dcbor_item, parse_diag_value,
+};
+
+#[test]
+fn test_diag_value_preserves_literal_text() {
+    let src = "[1.0, 1e2, h'00FF', b64'AP8=', 2023-02-08, 'isA', Unit]";
+    let value = parse_diag_value(src).unwrap();
+    let DiagValue::Array { items, span } = &value else {
+        panic!("expected an array, got {:?}", value);
+    };
+    assert_eq!(*span, 0..src.len());
+    let texts: Vec<_> = items.iter().map(|item| item.text().unwrap()).collect();
+    assert_eq!(
+        texts,
+        ["1.0", "1e2", "h'00FF'", "b64'AP8='", "2023-02-08", "'isA'", "Unit"]
+    );
+    assert!(matches!(
+        items[2],
+        DiagValue::ByteString { encoding: ByteStringEncoding::Hex, .. }
+    ));
+    assert!(matches!(
+        items[3],
+        DiagValue::ByteString { encoding: ByteStringEncoding::Base64, .. }
+    ));
+    for item in items {
+        assert_eq!(&src[item.span().range()], item.text().unwrap());
+    }
+}
+
+#[test]
+fn test_diag_value_converts_to_cbor() {
+    dcbor::register_tags();
+    let sources = [
+        "true",
+        "null",
+        "-2.5",
+        r#""hello""#,
+        "1234(h'010203')",
+        "date(1700000000)",
+        "ur:date/cyisdadmlasgtapttl",
+        r#"{"b": [1, 2], "a": {3: 4}}"#,
+    ];
+    for src in sources {
+        let value = parse_diag_value(src).unwrap();
+        assert_eq!(value.to_cbor(), parse_dcbor_item(src).unwrap());
+        assert_eq!(CBOR::from(value), parse_dcbor_item(src).unwrap());
+    }
+}
+
+#[test]
+fn test_diag_value_map_source_order() {
+    let src = r#"{"b": 1, "a": 2}"#;
+    let value = parse_diag_value(src).unwrap();
+    let DiagValue::Map { entries, .. } = &value else {
+        panic!("expected a map, got {:?}", value);
+    };
+    assert_eq!(entries[0].0.text(), Some(r#""b""#));
+    assert_eq!(entries[1].0.text(), Some(r#""a""#));
+    assert_eq!(value.to_cbor().diagnostic(), r#"{"a": 2, "b": 1}"#);
+}
+
+#[test]
+fn test_diag_value_tagged_spans() {
+    dcbor::register_tags();
+    let src = "[date( 1 ), 1234(2)]";
+    let DiagValue::Array { items, .. } = parse_diag_value(src).unwrap() else {
+        panic!("expected an array");
+    };
+    let DiagValue::Tagged { tag_text, item, span, .. } = &items[0] else {
+        panic!("expected a tagged value");
+    };
+    assert_eq!(tag_text, "date");
+    assert_eq!(&src[span.range()], "date( 1 )");
+    assert_eq!(&src[item.span().range()], "1");
+    let DiagValue::Tagged { tag_text, span, .. } = &items[1] else {
+        panic!("expected a tagged value");
+    };
+    assert_eq!(tag_text, "1234");
+    assert_eq!(&src[span.range()], "1234(2)");
+}
+
+#[test]
+fn test_array_item_errors() {
+    // Errors embedded in tokens are reported the same way inside arrays as at
+    // the top level.
+    assert!(matches!(
+        parse_diag_value("[h'01020']"),
+        Err(ParseError::InvalidHexString(_))
+    ));
+    assert!(matches!(
+        parse_diag_value("[2023-13-01]"),
+        Err(ParseError::InvalidDateString(_, _))
+    ));
+}
+
+#[test]
+fn test_source_span() {
+    use dcbor_parse::{LineColumn, SourceSpan};
+
+    let src = "{\n  \"é\": [1,\n    22]\n}";
+    let value = parse_diag_value(src).unwrap();
+    let DiagValue::Map { entries, .. } = &value else {
+        panic!("expected a map");
+    };
+    let (key, array) = &entries[0];
+    let DiagValue::Array { items, .. } = array else {
+        panic!("expected an array");
+    };
+    let span = items[1].span();
+    assert_eq!(&src[span.range()], "22");
+    assert_eq!(
+        span.line_columns(src),
+        (LineColumn { line: 3, column: 5 }, LineColumn { line: 3, column: 7 })
+    );
+    // Columns count characters, not bytes.
+    assert_eq!(
+        array.span().line_columns(src).0,
+        LineColumn { line: 2, column: 8 }
+    );
+    assert!(value.span().contains_span(key.span()));
+    assert!(array.span().contains(span.start));
+    assert!(!array.span().contains(array.span().end));
+    assert_eq!(key.span().merge(span), SourceSpan::new(4, span.end));
+    assert_eq!(std::ops::Range::from(span), span.range());
+}
+
+#[test]
+fn test_number_kind_and_encoding() {
+    use NumberEncoding::*;
+    let src = "[0, -1, 0x1F, -0x10, 1.0, -2e0, 1.5, 1e300, NaN, -Infinity]";
+    let value = parse_diag_value(src).unwrap();
+    let DiagValue::Array { items, .. } = &value else { panic!() };
+    let numbers: Vec<_> = items
+        .iter()
+        .map(|item| {
+            (item.number_kind().unwrap(), item.number_encoding().unwrap())
+        })
+        .collect();
+    assert_eq!(
+        numbers,
+        [
+            (NumberKind::Integer, Unsigned),
+            (NumberKind::Integer, Negative),
+            (NumberKind::HexInteger, Unsigned),
+            (NumberKind::HexInteger, Negative),
+            (NumberKind::Float, Unsigned),
+            (NumberKind::Float, Negative),
+            (NumberKind::Float, Float),
+            (NumberKind::Float, Float),
+            (NumberKind::Float, Float),
+            (NumberKind::Float, Float),
+        ]
+    );
+    let kinds: Vec<_> = ["0x1f", "0o17", "-0O17", "0b1010", "15"]
+        .iter()
+        .map(|src| parse_diag_value(src).unwrap().number_kind().unwrap())
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            NumberKind::HexInteger,
+            NumberKind::OctalInteger,
+            NumberKind::OctalInteger,
+            NumberKind::BinaryInteger,
+            NumberKind::Integer,
+        ]
+    );
+    assert_eq!(Float.major_type(), 7);
+    assert_eq!(value.number_kind(), None);
+    assert_eq!(value.number_encoding(), None);
+}
@@ -0,0 +1,46 @@
+use dcbor::prelude::*;
+use dcbor_parse::{ParseError, parse_documented_items};
+
+#[test]
+fn test_metadata_per_item() {
+    let src = indoc::indoc! {"
+        ## name: nested
+        ## tags:  a, b
+        ##   description  : arrays: inside arrays
+        [[1], [2]]   ## name: trailing metadata is ignored
+
+        / ## name: block comments are ignored /
+        ## no colon here
+        2
+        ## name: last
+        ## name: repeated
+        h'00'
+    "};
+    let items = parse_documented_items(src).unwrap();
+    assert_eq!(items.len(), 3);
+
+    assert_eq!(items[0].item.diagnostic_flat(), "[[1], [2]]");
+    assert_eq!(items[0].get("name"), Some("nested"));
+    assert_eq!(items[0].get("tags"), Some("a, b"));
+    assert_eq!(items[0].get("description"), Some("arrays: inside arrays"));
+    assert_eq!(items[0].get("missing"), None);
+
+    assert_eq!(items[1].item, CBOR::from(2));
+    assert!(items[1].metadata.is_empty());
+
+    assert_eq!(items[2].get("name"), Some("last"));
+    assert_eq!(items[2].metadata.len(), 2);
+}
+
+#[test]
+fn test_empty_and_invalid_sources() {
+    assert!(parse_documented_items("").unwrap().is_empty());
+    assert!(parse_documented_items("## name: orphan\n").unwrap().is_empty());
+
+    // Error offsets are relative to the whole source.
+    let src = "## name: a\n1\n## name: b\n[1 2]";
+    assert_eq!(
+        parse_documented_items(src),
+        Err(ParseError::ExpectedComma((27..28).into()))
+    );
+}
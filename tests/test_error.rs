@@ -0,0 +1,302 @@
+use dcbor_parse::{
+    EnglishMessages, MessageRenderer, ParseError, SourceSpan, Token,
+    parse_dcbor_item,
+};
+
+fn sample_errors() -> Vec<(&'static str, ParseError)> {
+    dcbor::register_tags();
+    [
+        "",
+        "[1, 2",
+        "1 1",
+        "(",
+        "q",
+        "[1 2 3]",
+        "{1: 2, 3}",
+        "1([1, 2, 3]",
+        "{1: 2, 3: 4",
+        "{1: 2, 3:}",
+        "20000000000000000000(1)",
+        "foobar(1)",
+        "h'01020'",
+        "b64'AQIDBAUGBwgJCg'",
+        "ur:foobar/cyisdadmlasgtapttl",
+        "ur:date/cyisdadmlasgtapttx",
+        "'20000000000000000000'",
+        "'foobar'",
+        "2023-13-01",
+        "{1: 2, 1: 3}",
+    ]
+    .into_iter()
+    .map(|src| (src, parse_dcbor_item(src).unwrap_err()))
+    .collect()
+}
+
+/// Returns one instance of every error. The match doesn't compile when a
+/// variant is added, so the variant must be added here as well.
+fn every_error() -> Vec<ParseError> {
+    let span = SourceSpan::new(0, 1);
+    let text = || "x".to_string();
+    let errors = vec![
+        ParseError::EmptyInput,
+        ParseError::UnexpectedEndOfInput,
+        ParseError::ExtraData(span),
+        ParseError::UnexpectedToken(Box::new(Token::Comma), span),
+        ParseError::UnrecognizedToken(span),
+        ParseError::ExpectedComma(span),
+        ParseError::ExpectedColon(span),
+        ParseError::UnmatchedParentheses(span),
+        ParseError::UnmatchedBraces(span),
+        ParseError::ExpectedMapKey(span),
+        ParseError::InvalidTagValue(text(), span),
+        ParseError::UnknownTagName(text(), span),
+        ParseError::SpaceBeforeParenthesis(span),
+        ParseError::InvalidHexString(span),
+        ParseError::InvalidBase64String(span),
+        ParseError::InvalidBase45String(span),
+        ParseError::InvalidByteWords(text(), span),
+        ParseError::InvalidEscapeSequence(text(), span),
+        ParseError::InvalidUnicodeEscape(text(), span),
+        ParseError::ControlCharacter(text(), span),
+        ParseError::TokenTooLong(7, span),
+        ParseError::NestingTooDeep(7, span),
+        ParseError::InvalidUtf8(span),
+        ParseError::NulCharacter(span),
+        ParseError::UnknownUrType(text(), span),
+        ParseError::InvalidUr(text(), span),
+        ParseError::InvalidKnownValue(text(), span),
+        ParseError::UnknownKnownValueName(text(), span),
+        ParseError::InvalidDateString(text(), span),
+        ParseError::DuplicateMapKey(span),
+        ParseError::NonIntegerMapKey(text(), span),
+        ParseError::ExpectedMap(span),
+        ParseError::VisitorStopped(span),
+        ParseError::VisitorRejected(text(), span),
+        ParseError::IncludeNotEnabled(span),
+        ParseError::IncludeFailed(text(), span),
+        ParseError::UndefinedVariable(text(), span),
+        ParseError::DisallowedKnownValue(text(), span),
+        ParseError::InvalidTagContent(text(), span),
+        ParseError::ExternalBytesNotEnabled(span),
+        ParseError::ExternalBytesFailed(text(), span),
+        ParseError::TagsRegistryUnavailable(span),
+        ParseError::KnownValuesRegistryUnavailable(span),
+        ParseError::SyntaxDisabled(text(), span),
+        ParseError::PathNotFound(text(), span),
+        ParseError::InvalidKnownValueRange(text(), span),
+        ParseError::InvalidDecimalFraction(text(), span),
+        ParseError::InvalidRational(text(), span),
+        ParseError::EncodingIndicatorMismatch(text(), span),
+        ParseError::InvalidRawContent(text(), span),
+        ParseError::InvalidTypeAssertion(text(), span),
+        ParseError::TypeMismatch(text(), span),
+    ];
+    for (expected, error) in errors.iter().enumerate() {
+        let index = match error {
+            ParseError::EmptyInput => 0,
+            ParseError::UnexpectedEndOfInput => 1,
+            ParseError::ExtraData(..) => 2,
+            ParseError::UnexpectedToken(..) => 3,
+            ParseError::UnrecognizedToken(..) => 4,
+            ParseError::ExpectedComma(..) => 5,
+            ParseError::ExpectedColon(..) => 6,
+            ParseError::UnmatchedParentheses(..) => 7,
+            ParseError::UnmatchedBraces(..) => 8,
+            ParseError::ExpectedMapKey(..) => 9,
+            ParseError::InvalidTagValue(..) => 10,
+            ParseError::UnknownTagName(..) => 11,
+            ParseError::SpaceBeforeParenthesis(..) => 12,
+            ParseError::InvalidHexString(..) => 13,
+            ParseError::InvalidBase64String(..) => 14,
+            ParseError::InvalidBase45String(..) => 15,
+            ParseError::InvalidByteWords(..) => 16,
+            ParseError::InvalidEscapeSequence(..) => 17,
+            ParseError::InvalidUnicodeEscape(..) => 18,
+            ParseError::ControlCharacter(..) => 19,
+            ParseError::TokenTooLong(..) => 20,
+            ParseError::NestingTooDeep(..) => 21,
+            ParseError::InvalidUtf8(..) => 22,
+            ParseError::NulCharacter(..) => 23,
+            ParseError::UnknownUrType(..) => 24,
+            ParseError::InvalidUr(..) => 25,
+            ParseError::InvalidKnownValue(..) => 26,
+            ParseError::UnknownKnownValueName(..) => 27,
+            ParseError::InvalidDateString(..) => 28,
+            ParseError::DuplicateMapKey(..) => 29,
+            ParseError::NonIntegerMapKey(..) => 30,
+            ParseError::ExpectedMap(..) => 31,
+            ParseError::VisitorStopped(..) => 32,
+            ParseError::VisitorRejected(..) => 33,
+            ParseError::IncludeNotEnabled(..) => 34,
+            ParseError::IncludeFailed(..) => 35,
+            ParseError::UndefinedVariable(..) => 36,
+            ParseError::DisallowedKnownValue(..) => 37,
+            ParseError::InvalidTagContent(..) => 38,
+            ParseError::ExternalBytesNotEnabled(..) => 39,
+            ParseError::ExternalBytesFailed(..) => 40,
+            ParseError::TagsRegistryUnavailable(..) => 41,
+            ParseError::KnownValuesRegistryUnavailable(..) => 42,
+            ParseError::SyntaxDisabled(..) => 43,
+            ParseError::PathNotFound(..) => 44,
+            ParseError::InvalidKnownValueRange(..) => 45,
+            ParseError::InvalidDecimalFraction(..) => 46,
+            ParseError::InvalidRational(..) => 47,
+            ParseError::EncodingIndicatorMismatch(..) => 48,
+            ParseError::InvalidRawContent(..) => 49,
+            ParseError::InvalidTypeAssertion(..) => 50,
+            ParseError::TypeMismatch(..) => 51,
+        };
+        assert_eq!(index, expected, "{error:?}");
+    }
+    // The number of arms of the match.
+    assert_eq!(errors.len(), 52);
+    errors
+}
+
+#[test]
+fn test_english_messages_match_display() {
+    let errors = sample_errors();
+    let mut codes: Vec<_> = errors.iter().map(|(_, e)| e.code()).collect();
+    codes.sort();
+    codes.dedup();
+    assert_eq!(codes.len(), errors.len(), "every sample has a distinct code");
+    for (src, error) in &errors {
+        assert_eq!(
+            EnglishMessages.render(error.code(), &error.params()),
+            error.to_string()
+        );
+        assert_eq!(
+            error.full_message(src),
+            error.full_message_with(src, &EnglishMessages)
+        );
+    }
+}
+
+#[test]
+fn test_english_messages_cover_every_error() {
+    for error in every_error() {
+        assert_eq!(
+            EnglishMessages.render(error.code(), &error.params()),
+            error.to_string(),
+            "{error:?}"
+        );
+    }
+}
+
+#[test]
+fn test_custom_renderer() {
+    struct Codes;
+
+    impl MessageRenderer for Codes {
+        fn render(&self, code: &str, params: &[String]) -> String {
+            format!("{code}({})", params.join(", "))
+        }
+
+        fn render_help(&self, code: &str) -> Option<String> {
+            Some(format!("see the documentation for `{code}`"))
+        }
+    }
+
+    let src = "foobar(1)";
+    let error = parse_dcbor_item(src).unwrap_err();
+    assert!(matches!(error, ParseError::UnknownTagName(_, _)));
+    assert_eq!(
+        error.full_message_with(src, &Codes),
+        "line 1: unknown-tag-name(foobar)\nfoobar(1)\n^^^^^^\n\
+         help: see the documentation for `unknown-tag-name`"
+    );
+}
+
+#[test]
+fn test_help() {
+    for (src, error) in sample_errors() {
+        let full_message = error.full_message(src);
+        match error.help() {
+            Some(help) => {
+                assert!(full_message.ends_with(&format!("\nhelp: {help}")))
+            }
+            None => assert!(!full_message.contains("help:")),
+        }
+    }
+    let error = parse_dcbor_item("{1: 2, 1.0: 3}").unwrap_err();
+    assert!(error.help().unwrap().contains("`1` and `1.0`"));
+    let error = parse_dcbor_item("(").unwrap_err();
+    assert_eq!(error.help(), None);
+}
+
+#[test]
+fn test_line_breaks() {
+    use dcbor_parse::LineColumn;
+
+    // Windows line endings.
+    let src = "[1,\r\n 2\r\n 3]";
+    let error = parse_dcbor_item(src).unwrap_err();
+    let ParseError::ExpectedComma(span) = error else {
+        panic!("expected ExpectedComma");
+    };
+    assert_eq!(span.line_columns(src).0, LineColumn { line: 3, column: 2 });
+    assert!(
+        error
+            .full_message(src)
+            .starts_with("line 3: Expected comma\n 3]\n ^\n")
+    );
+
+    // Classic Mac OS line endings, including one ending a comment.
+    let src = "[1, # one\r 2,\r 3 4]";
+    let error = parse_dcbor_item(src).unwrap_err();
+    let ParseError::ExpectedComma(span) = error else {
+        panic!("expected ExpectedComma");
+    };
+    assert_eq!(span.line_columns(src).0, LineColumn { line: 3, column: 4 });
+    assert!(
+        error
+            .full_message(src)
+            .starts_with("line 3: Expected comma\n 3 4]\n   ^\n")
+    );
+    assert_eq!(
+        parse_dcbor_item("[1, # one\r 2]").unwrap().diagnostic(),
+        "[1, 2]"
+    );
+
+    // An error spanning several lines is underlined to the end of the first.
+    let src = "[1,\r\n 2]";
+    let error = dcbor_parse::sorted_key_preview(src).unwrap_err();
+    assert_eq!(error, ParseError::ExpectedMap((0..8).into()));
+    assert!(
+        error
+            .full_message(src)
+            .starts_with("line 1: Expected map\n[1,\n^^^\n")
+    );
+}
+
+#[test]
+fn test_caret_alignment() {
+    // Tabs are expanded to the next tab stop.
+    let src = "[1,\t2\t\t3]";
+    let error = parse_dcbor_item(src).unwrap_err();
+    assert!(error.full_message(src).starts_with(
+        "line 1: Expected comma\n[1, 2       3]\n            ^\n"
+    ));
+
+    struct WideTabs;
+
+    impl MessageRenderer for WideTabs {
+        fn render(&self, code: &str, params: &[String]) -> String {
+            EnglishMessages.render(code, params)
+        }
+
+        fn tab_width(&self) -> usize { 8 }
+    }
+
+    assert!(error.full_message_with(src, &WideTabs).starts_with(
+        "line 1: Expected comma\n[1,     2               3]\n\
+         \x20                       ^\n"
+    ));
+
+    // Columns count characters, not bytes.
+    let src = "[\"é\" \"ü\"]";
+    let error = parse_dcbor_item(src).unwrap_err();
+    assert!(error.full_message(src).starts_with(
+        "line 1: Expected comma\n[\"é\" \"ü\"]\n     ^^^\n"
+    ));
+}
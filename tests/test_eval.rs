@@ -0,0 +1,18 @@
+use dcbor::prelude::*;
+use dcbor_parse::{ParseError, WarningKind, evaluate};
+
+#[test]
+fn test_evaluate() {
+    let result = evaluate(r#"{"b": h'0102', "a": [1.5, '999']}"#).unwrap();
+    assert_eq!(CBOR::try_from_hex(&result.hex).unwrap(), result.cbor);
+    assert_eq!(result.diagnostic, result.cbor.diagnostic());
+    assert_eq!(result.hex, hex::encode(result.cbor.to_cbor_data()));
+    assert_eq!(result.size, result.hex.len() / 2);
+    let kinds: Vec<_> = result.warnings.iter().map(|w| &w.kind).collect();
+    assert_eq!(kinds, [&WarningKind::UnregisteredKnownValue { value: 999 }]);
+}
+
+#[test]
+fn test_evaluate_error() {
+    assert_eq!(evaluate(""), Err(ParseError::EmptyInput));
+}
@@ -0,0 +1,41 @@
+use dcbor::prelude::*;
+use dcbor_parse::{
+    ParseError, checksum, checksum_diag, fingerprint, fingerprint_diag,
+};
+
+#[test]
+fn test_fingerprint_of_encoding() {
+    let cbor = CBOR::from("hello");
+    let expected = bc_crypto::sha256(cbor.to_cbor_data());
+    assert_eq!(fingerprint(&cbor), expected);
+    assert_eq!(fingerprint_diag(r#""hello""#).unwrap(), expected);
+}
+
+#[test]
+fn test_fingerprint_distinguishes_items() {
+    let a = fingerprint_diag("[1, 2]").unwrap();
+    let b = fingerprint_diag("[2, 1]").unwrap();
+    assert_ne!(a, b);
+    assert_eq!(a, fingerprint_diag("[1, 2.0]").unwrap());
+}
+
+#[test]
+fn test_fingerprint_diag_error() {
+    assert!(matches!(
+        fingerprint_diag("[1, 2"),
+        Err(ParseError::UnexpectedEndOfInput)
+    ));
+}
+
+#[test]
+fn test_checksum_of_encoding() {
+    let cbor = CBOR::from("hello");
+    let data = cbor.to_cbor_data();
+    let expected = checksum(&cbor);
+    assert_eq!(expected.crc32, bc_crypto::hash::crc32(&data));
+    assert_eq!(expected.sha256, fingerprint(&cbor));
+    // Reformatting the text doesn't change the checksum.
+    assert_eq!(checksum_diag(r#"  "hello" / greeting /"#).unwrap(), expected);
+    assert_ne!(checksum_diag(r#""hello!""#).unwrap(), expected);
+    assert!(matches!(checksum_diag(""), Err(ParseError::EmptyInput)));
+}
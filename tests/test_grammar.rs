@@ -0,0 +1,34 @@
+use dcbor_parse::{Token, grammar};
+use logos::Logos;
+
+#[test]
+fn test_token_examples_lex_as_named_token() {
+    for kind in grammar().tokens {
+        let tokens: Vec<_> = Token::lexer(kind.example).collect();
+        assert_eq!(tokens.len(), 1, "{}: {:?}", kind.name, tokens);
+        let token = tokens[0].as_ref().unwrap();
+        let debug = format!("{:?}", token);
+        let variant = debug.split('(').next().unwrap();
+        assert_eq!(variant, kind.name, "{}", kind.example);
+    }
+}
+
+#[test]
+fn test_rules_reference_known_tokens_and_rules() {
+    let grammar = grammar();
+    for rule in &grammar.rules {
+        for symbol in rule
+            .definition
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|s| !s.is_empty())
+        {
+            assert!(
+                grammar.token(symbol).is_some()
+                    || grammar.rules.iter().any(|r| r.name == symbol),
+                "{} refers to unknown symbol {}",
+                rule.name,
+                symbol
+            );
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use dcbor_parse::{ParseError, SourceSpan, lint_reader};
+
+#[test]
+fn test_lint_reader_documents() {
+    let stream = "[1, 2]\n---\n\n---\n{1: 2,\n 1: 3}\n---  \n['46']\n---\n";
+    let diagnostics: Vec<_> = lint_reader(stream.as_bytes()).collect();
+    let indexes: Vec<_> = diagnostics.iter().map(|d| d.index).collect();
+    assert_eq!(indexes, [0, 1, 2]);
+    let lines: Vec<_> = diagnostics.iter().map(|d| d.line).collect();
+    assert_eq!(lines, [1, 5, 8]);
+    assert!(diagnostics[0].is_clean());
+    assert_eq!(diagnostics[1].source, "{1: 2,\n 1: 3}\n");
+    assert!(matches!(
+        diagnostics[1].error,
+        Some(ParseError::DuplicateMapKey(_))
+    ));
+    assert_eq!(diagnostics[1].report(), "6:2: error: Duplicate map key");
+    assert!(diagnostics[2].error.is_none());
+    assert_eq!(diagnostics[2].warnings.len(), 1);
+    assert!(diagnostics[2].report().starts_with("8:2: warning: "));
+}
+
+#[test]
+fn test_lint_reader_incremental() {
+    // The first document is reported before the rest of the stream is read.
+    let stream = "[1]\n---\n[2\n";
+    let mut diagnostics = lint_reader(stream.as_bytes());
+    assert!(diagnostics.next().unwrap().is_clean());
+    let second = diagnostics.next().unwrap();
+    assert_eq!(second.error, Some(ParseError::UnexpectedEndOfInput));
+    assert_eq!(second.report(), "4:1: error: Unexpected end of input");
+    assert!(diagnostics.next().is_none());
+}
+
+#[test]
+fn test_lint_reader_invalid_utf8() {
+    let stream = b"\"a\xffb\"\n---\n1\n";
+    let diagnostics: Vec<_> = lint_reader(&stream[..]).collect();
+    assert_eq!(
+        diagnostics[0].error,
+        Some(ParseError::InvalidUtf8(SourceSpan::new(2, 3)))
+    );
+    assert_eq!(diagnostics[0].source, "\"a\u{fffd}b\"\n");
+    assert!(diagnostics[1].is_clean());
+}
@@ -0,0 +1,40 @@
+use dcbor_parse::{
+    ParseError, SourceSpan, normalize_hex_case, normalize_string_escapes,
+    parse_dcbor_item,
+};
+
+#[test]
+fn test_normalize_hex_case() {
+    let src = "# H'FF' in a comment\n{\"H'AB'\": [h'DEADbeef', h'']}";
+    let normalized = normalize_hex_case(src).unwrap();
+    assert_eq!(
+        normalized,
+        "# H'FF' in a comment\n{\"H'AB'\": [h'deadbeef', h'']}"
+    );
+    assert_eq!(
+        parse_dcbor_item(&normalized).unwrap(),
+        parse_dcbor_item(src).unwrap()
+    );
+    assert_eq!(normalize_hex_case(&normalized).unwrap(), normalized);
+    assert_eq!(
+        normalize_hex_case("h'AB /KEEP/ CD # AND THIS\n'").unwrap(),
+        "h'ab /KEEP/ cd # AND THIS\n'"
+    );
+    assert_eq!(
+        normalize_hex_case("h'ABC'"),
+        Err(ParseError::InvalidHexString(SourceSpan::new(0, 6)))
+    );
+}
+
+#[test]
+fn test_normalize_string_escapes() {
+    let src = "{\"k\u{1}\": \"tab\there\r\n\", 1: \"\\u0041\\/\"}";
+    let normalized = normalize_string_escapes(src).unwrap();
+    assert_eq!(
+        normalized,
+        "{\"k\\u0001\": \"tab\\there\\r\\n\", 1: \"\\u0041\\/\"}"
+    );
+    assert!(parse_dcbor_item(src).is_err());
+    assert!(parse_dcbor_item(&normalized).is_ok());
+    assert_eq!(normalize_string_escapes(&normalized).unwrap(), normalized);
+}
@@ -0,0 +1,829 @@
+use dcbor::prelude::*;
+use dcbor_parse::{
+    ControlCharacters, DiagValue, DuplicateKeys, EncodingIndicators,
+    NaNHandling, ParseError, ParseOptions, PathSegment, PrintOptions,
+    SourceSpan, Warning, WarningKind,
+    external_byte_strings, format_diag_value, format_diagnostic,
+    parse_dcbor_item, parse_dcbor_item_with_options,
+    parse_diag_value_with_options,
+};
+
+fn fragments() -> ParseOptions {
+    ParseOptions::default().include_loader(|name| match name {
+        "pair" => Ok("[1, 2]".to_string()),
+        "nested" => Ok(r#"{"pair": !include("pair")}"#.to_string()),
+        "broken" => Ok("[1,\n 2 3]".to_string()),
+        "loop-a" => Ok(r#"[!include("loop-b")]"#.to_string()),
+        "loop-b" => Ok(r#"[!include("loop-a")]"#.to_string()),
+        _ => Err("not found".to_string()),
+    })
+}
+
+#[test]
+fn test_include_splices_item() {
+    let src = r#"[0, !include("nested")]"#;
+    let value = parse_diag_value_with_options(src, &fragments()).unwrap();
+    assert_eq!(
+        value.to_cbor().diagnostic_flat(),
+        r#"[0, {"pair": [1, 2]}]"#
+    );
+    let DiagValue::Array { items, .. } = &value else { panic!() };
+    let DiagValue::Included { name, item, span } = &items[1] else { panic!() };
+    assert_eq!(name, "nested");
+    assert_eq!(&src[span.range()], r#"!include("nested")"#);
+    assert_eq!(item.span(), 0..26);
+
+    // Paths pass through included items.
+    let path = [
+        PathSegment::Index(1),
+        PathSegment::Key("pair".into()),
+        PathSegment::Index(0),
+    ];
+    assert_eq!(value.at(&path).unwrap().text(), Some("1"));
+}
+
+#[test]
+fn test_include_errors() {
+    let options = fragments();
+    let parse = |src| parse_dcbor_item_with_options(src, &options);
+    assert_eq!(
+        parse(r#"!include("missing")"#),
+        Err(ParseError::IncludeFailed(
+            "'missing': not found".to_string(),
+            (0..19).into()
+        ))
+    );
+    assert_eq!(
+        parse(r#"[!include("broken")]"#),
+        Err(ParseError::IncludeFailed(
+            "'broken': 2:4: Expected comma".to_string(),
+            (1..19).into()
+        ))
+    );
+    let Err(ParseError::IncludeFailed(message, _)) =
+        parse(r#"!include("loop-a")"#)
+    else {
+        panic!("expected IncludeFailed");
+    };
+    assert_eq!(
+        message,
+        "'loop-a': 1:2: Include failed: 'loop-b': 1:2: Include failed: \
+         'loop-a': the document includes itself"
+    );
+    assert!(matches!(
+        parse("!include(1)"),
+        Err(ParseError::UnexpectedToken(_, span)) if span == (9..10)
+    ));
+    assert_eq!(
+        parse(r#"!include("pair""#),
+        Err(ParseError::UnmatchedParentheses((15..15).into()))
+    );
+}
+
+#[test]
+fn test_include_dir() {
+    let dir = std::env::temp_dir().join("dcbor-parse-test-include");
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("sub/item.diag"), "h'0102'").unwrap();
+    std::fs::write(dir.join("main.diag"), r#"[!include("sub/item.diag")]"#)
+        .unwrap();
+    let options = ParseOptions::default().include_dir(&dir);
+
+    let cbor =
+        parse_dcbor_item_with_options(r#"!include("main.diag")"#, &options)
+            .unwrap();
+    assert_eq!(cbor.diagnostic_flat(), "[h'0102']");
+
+    for name in ["../secret", "/etc/passwd", "sub/../main.diag"] {
+        let src = format!("!include({name:?})");
+        let Err(ParseError::IncludeFailed(message, _)) =
+            parse_dcbor_item_with_options(&src, &options)
+        else {
+            panic!("expected IncludeFailed for {name}");
+        };
+        assert!(message.ends_with("is outside the include directory"));
+    }
+
+    // A symbolic link can't lead out of the directory either.
+    #[cfg(unix)]
+    {
+        let outside = std::env::temp_dir().join("dcbor-parse-test-outside");
+        std::fs::write(&outside, "1").unwrap();
+        let link = dir.join("link.diag");
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink(&outside, &link).unwrap();
+        let Err(ParseError::IncludeFailed(message, _)) =
+            parse_dcbor_item_with_options(r#"!include("link.diag")"#, &options)
+        else {
+            panic!("expected IncludeFailed for a link");
+        };
+        assert!(message.ends_with("is outside the include directory"));
+
+        let inner = dir.join("inner.diag");
+        let _ = std::fs::remove_file(&inner);
+        std::os::unix::fs::symlink("main.diag", &inner).unwrap();
+        let cbor =
+            parse_dcbor_item_with_options(r#"!include("inner.diag")"#, &options)
+                .unwrap();
+        assert_eq!(cbor.diagnostic_flat(), "[h'0102']");
+    }
+}
+
+fn variables() -> ParseOptions {
+    ParseOptions::default().env_lookup(|name| match name {
+        "NAME" => Some("Alice".to_string()),
+        "PAIR" => Some("[1, 2]".to_string()),
+        "EMPTY" => Some(String::new()),
+        _ => None,
+    })
+}
+
+#[test]
+fn test_env_substitution() {
+    let options = variables();
+    let parse = |src| parse_diag_value_with_options(src, &options);
+
+    let value = parse(r#"{"name": "${NAME}", "pair": ${PAIR}}"#).unwrap();
+    assert_eq!(
+        value.to_cbor().diagnostic_flat(),
+        r#"{"name": "Alice", "pair": [1, 2]}"#
+    );
+
+    // Spans refer to the source as written.
+    let value = parse(r#"["${NAME}", ${PAIR}, ${EMPTY}3]"#).unwrap();
+    assert_eq!(value.span(), 0..31);
+    let DiagValue::Array { items, .. } = &value else { panic!() };
+    assert_eq!(items[0].span(), 1..10);
+    assert_eq!(items[1].span(), 12..19);
+    let DiagValue::Array { items: pair, .. } = &items[1] else { panic!() };
+    assert_eq!(pair[0].span(), 12..19);
+    assert_eq!(items[2].span(), 29..30);
+
+    // Anything other than `${NAME}` is left alone.
+    assert_eq!(
+        parse(r#""$NAME ${} ${1} ${NAME""#).unwrap().to_cbor(),
+        dcbor::CBOR::from("$NAME ${} ${1} ${NAME")
+    );
+}
+
+#[test]
+fn test_env_substitution_errors() {
+    let options = variables();
+    let parse = |src| parse_dcbor_item_with_options(src, &options);
+    assert_eq!(
+        parse("[${NAME}, ${MISSING}]"),
+        Err(ParseError::UndefinedVariable(
+            "MISSING".to_string(),
+            (12..19).into()
+        ))
+    );
+    let src = "[${PAIR} ${PAIR}]";
+    let error = parse(src).unwrap_err();
+    assert_eq!(error, ParseError::ExpectedComma((9..16).into()));
+    assert_eq!(
+        error.full_message(src),
+        "line 1: Expected comma\n[${PAIR} ${PAIR}]\n         ^^^^^^^\n\
+         help: separate array items and map entries with commas"
+    );
+
+    // Without a lookup, `${NAME}` is parsed as written.
+    let error =
+        parse_dcbor_item_with_options("${NAME}", &ParseOptions::new());
+    assert!(error.is_err());
+}
+
+#[test]
+fn test_known_value_policy() {
+    let options = ParseOptions::default()
+        .registered_known_values_only(true)
+        .reserve_known_values(800..=999)
+        .allow_known_values(850..=850)
+        .allow_known_values(1000..=u64::MAX);
+    let parse = |src| parse_dcbor_item_with_options(src, &options);
+
+    assert!(parse("['isA', '1', '', '850', '1000', 40000(2)]").is_ok());
+    assert_eq!(
+        parse("[1, '45']"),
+        Err(ParseError::DisallowedKnownValue(
+            "'45' (unregistered, General 0-49)".to_string(),
+            (4..8).into()
+        ))
+    );
+    assert_eq!(
+        parse("40000(900)"),
+        Err(ParseError::DisallowedKnownValue(
+            "'900' (unregistered, Reserved 800-999)".to_string(),
+            (0..10).into()
+        ))
+    );
+
+    // Reserved ranges apply to registered known values too.
+    let options = ParseOptions::default().reserve_known_values(0..=9);
+    let error = parse_dcbor_item_with_options("'isA'", &options).unwrap_err();
+    assert_eq!(
+        error.full_message("'isA'"),
+        "line 1: Known value 'isA' (1, General 0-49) is not allowed\n\
+         'isA'\n\
+         ^^^^^\n\
+         help: use a registered known value, or allow the codepoint with \
+         `ParseOptions::allow_known_values`"
+    );
+
+    // The default options accept any known value.
+    let src = "['45', '900', 40000(45)]";
+    assert!(parse_dcbor_item_with_options(src, &ParseOptions::new()).is_ok());
+}
+
+#[test]
+fn test_validate_envelopes() {
+    let options = ParseOptions::default().validate_envelopes(true);
+    let parse = |src: &str| parse_dcbor_item_with_options(src, &options);
+    let digest = format!("h'{}'", "00".repeat(32));
+    let valid = [
+        r#"200(201("Alice"))"#.to_string(),
+        "200(1)".to_string(),
+        format!("200({digest})"),
+        r#"200(200(201("wrapped")))"#.to_string(),
+        r#"200({1: 201("Person")})"#.to_string(),
+        format!(r#"200([201("Alice"), {{2: 201("Bob")}}, {digest}])"#),
+        "200([1, 40002([h'00', h'00', h'00'])])".to_string(),
+        "200(40003([h'00', h'00']))".to_string(),
+    ];
+    for src in &valid {
+        assert!(parse(src).is_ok(), "{src}");
+    }
+
+    let error = |src: &str| match parse(src) {
+        Err(ParseError::InvalidTagContent(message, span)) => {
+            format!("{message} at {}", &src[span.range()])
+        }
+        result => panic!("expected InvalidTagContent, got {result:?}"),
+    };
+    assert_eq!(
+        error(r#"[200("Alice")]"#),
+        "200: expected a leaf, node, assertion, known value, or wrapped, \
+         elided, encrypted, or compressed envelope at \"Alice\""
+    );
+    assert_eq!(
+        error("200(h'0102')"),
+        "200: an elided envelope must be a 32-byte digest at h'0102'"
+    );
+    assert_eq!(
+        error("200({1: 201(2), 3: 201(4)})"),
+        "200: an assertion must be a map with exactly one entry \
+         at {1: 201(2), 3: 201(4)}"
+    );
+    assert_eq!(
+        error("200([1, 2])"),
+        "200: each assertion of a node must be an assertion, or an elided, \
+         encrypted, or compressed assertion at [1, 2]"
+    );
+    assert_eq!(
+        error("200([201(1), {1: 5(2)}])"),
+        "200: unexpected tag 5 in an envelope at [201(1), {1: 5(2)}]"
+    );
+    assert_eq!(
+        error("200(40002(1))"),
+        "200: an encrypted envelope must be an array at 40002(1)"
+    );
+
+    // Without the option, envelopes aren't checked.
+    let unchecked =
+        parse_dcbor_item_with_options("200([])", &ParseOptions::new());
+    assert!(unchecked.is_ok());
+}
+
+#[test]
+fn test_validate_envelope_urs() {
+    use bc_ur::prelude::*;
+    dcbor::with_tags_mut!(|tags: &mut dcbor::TagsStore| {
+        tags.insert(dcbor::Tag::new(200, "envelope"));
+        tags.insert(dcbor::Tag::new(201, "leaf"));
+    });
+    let options = ParseOptions::default().validate_envelopes(true);
+    let parse = |src: &str| parse_dcbor_item_with_options(src, &options);
+
+    let content = parse_dcbor_item_with_options(
+        r#"[201("Alice"), {1: 201("Person")}]"#,
+        &ParseOptions::new(),
+    )
+    .unwrap();
+    let valid = UR::new("envelope", content).unwrap().string();
+    assert!(parse(&valid).is_ok());
+    assert!(parse(r#"envelope(leaf("Alice"))"#).is_ok());
+
+    let invalid = UR::new("envelope", dcbor::CBOR::from("Alice"))
+        .unwrap()
+        .string();
+    let src = format!("{{1: {invalid}}}");
+    let Err(ParseError::InvalidTagContent(_, span)) = parse(&src) else {
+        panic!("expected InvalidTagContent");
+    };
+    assert_eq!(&src[span.range()], invalid);
+}
+
+#[test]
+fn test_external_byte_strings() {
+    let cbor = CBOR::from(vec![
+        CBOR::to_byte_string([1, 2]),
+        CBOR::to_byte_string(vec![7; 64]),
+    ]);
+    let print_options = PrintOptions::default()
+        .flat(true)
+        .externalize_byte_strings(Some(2));
+    let diag = format_diagnostic(&cbor, &print_options);
+    let blobs = external_byte_strings(&cbor, 2);
+    assert_eq!(blobs.len(), 1);
+    let (digest, bytes) = &blobs[0];
+    assert_eq!(diag, format!(r#"[h'0102', !bytes("{digest}")]"#));
+
+    let dir = std::env::temp_dir().join("dcbor-parse-test-bytes");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(digest), bytes).unwrap();
+    let options = ParseOptions::default().byte_string_dir(&dir);
+    let value = parse_diag_value_with_options(&diag, &options).unwrap();
+    assert_eq!(value.to_cbor(), cbor);
+    // The directive is kept when the parsed value is written back.
+    assert_eq!(
+        format_diag_value(&value, &PrintOptions::default().flat(true)),
+        diag
+    );
+
+    let parse = |src: &str| parse_dcbor_item_with_options(src, &options);
+    assert_eq!(
+        parse_dcbor_item_with_options(&diag, &ParseOptions::default()),
+        Err(ParseError::ExternalBytesNotEnabled((10..84).into()))
+    );
+    std::fs::write(dir.join("00"), [1]).unwrap();
+    assert_eq!(
+        parse(r#"!bytes("00")"#),
+        Err(ParseError::ExternalBytesFailed(
+            "'00': the bytes don't match the digest".to_string(),
+            (0..12).into()
+        ))
+    );
+    let Err(ParseError::ExternalBytesFailed(message, _)) =
+        parse(r#"!bytes("../secret")"#)
+    else {
+        panic!("expected ExternalBytesFailed");
+    };
+    assert_eq!(message, "'../secret': '../secret' is not a hex digest");
+}
+
+#[test]
+fn test_control_character_policy() {
+    let src = "[\"log:\t\x1b[31mred\x1b[0m\", \"ok\\t\"]";
+    let parse = |policy| {
+        let options = ParseOptions::default().control_characters(policy);
+        parse_diag_value_with_options(src, &options)
+    };
+
+    assert_eq!(
+        parse(ControlCharacters::Reject),
+        Err(ParseError::ControlCharacter("U+0009".to_string(), (6..7).into()))
+    );
+    // They are rejected by default.
+    assert_eq!(
+        parse_dcbor_item_with_options(src, &ParseOptions::default()),
+        Err(ParseError::ControlCharacter("U+0009".to_string(), (6..7).into()))
+    );
+
+    let value = parse(ControlCharacters::Escape).unwrap();
+    assert_eq!(
+        value.to_cbor().diagnostic_flat(),
+        r#"["log:\t\u001b[31mred\u001b[0m", "ok\t"]"#
+    );
+    let value = parse(ControlCharacters::Verbatim).unwrap();
+    let CBORCase::Array(items) = value.to_cbor().into_case() else {
+        panic!("expected an array");
+    };
+    assert_eq!(
+        items[0].clone().try_into_text().unwrap(),
+        "log:\t\x1b[31mred\x1b[0m"
+    );
+
+    let warnings: Vec<_> = Warning::collect(&value)
+        .into_iter()
+        .map(|w| (w.kind, w.span.range()))
+        .collect();
+    assert_eq!(
+        warnings,
+        [
+            (WarningKind::ControlCharacter { character: '\t' }, 6..7),
+            (WarningKind::ControlCharacter { character: '\x1b' }, 7..8),
+            (WarningKind::ControlCharacter { character: '\x1b' }, 15..16),
+        ]
+    );
+    assert_eq!(warnings[1].0.code(), "control-character");
+    assert_eq!(
+        warnings[1].0.to_string(),
+        "raw control character U+001B in string"
+    );
+
+    // A control character can't follow a backslash under any policy.
+    assert!(parse_diag_value_with_options(
+        "\"a\\\tb\"",
+        &ParseOptions::default().control_characters(ControlCharacters::Escape)
+    )
+    .is_err());
+}
+
+#[test]
+fn test_max_token_len() {
+    let options = ParseOptions::default().max_token_len(5);
+    let parse = |src: &str| parse_dcbor_item_with_options(src, &options);
+    assert!(parse(r#"["abc", 12345, h'01']"#).is_ok());
+    assert_eq!(
+        parse(r#"["abcd"]"#),
+        Err(ParseError::TokenTooLong(5, (1..7).into()))
+    );
+    assert_eq!(
+        parse("[1, 123456]"),
+        Err(ParseError::TokenTooLong(5, (4..10).into()))
+    );
+
+    // An unterminated string is reported at its opening quote, without
+    // being scanned to the end of the source.
+    let src = format!("[1, \"{}]", "x".repeat(10_000));
+    assert_eq!(parse(&src), Err(ParseError::TokenTooLong(5, (4..10).into())));
+    let src = format!("[1, /c/ b64'{}]", "A".repeat(10_000));
+    assert_eq!(
+        parse(&src),
+        Err(ParseError::TokenTooLong(5, (8..14).into()))
+    );
+    // An escaped quote doesn't close a string, and a short token that is
+    // unterminated is reported by the lexer.
+    assert_eq!(
+        parse(r#"["\"\"\""]"#),
+        Err(ParseError::TokenTooLong(5, (1..7).into()))
+    );
+    assert_eq!(parse(r#"["ab"#).unwrap_err().code(), "unrecognized-token");
+    assert_eq!(
+        parse_dcbor_item_with_options(&src, &ParseOptions::default())
+            .unwrap_err()
+            .code(),
+        "unrecognized-token"
+    );
+}
+
+#[test]
+fn test_integer_map_keys_only() {
+    let options = ParseOptions::default().integer_map_keys_only(true);
+    let parse = |src: &str| parse_dcbor_item_with_options(src, &options);
+    assert!(parse("{1: 2, -3: [{4: 5}], 6.0: 7}").is_ok());
+    let error = |type_name: &str, span: std::ops::Range<usize>| {
+        Err(ParseError::NonIntegerMapKey(type_name.to_string(), span.into()))
+    };
+    assert_eq!(parse("{1.5: 1}"), error("float", 1..4));
+    assert_eq!(parse("{1: 1, h'01': 2}"), error("byte string", 7..12));
+    assert_eq!(parse("{[1]: 1}"), error("array", 1..4));
+    assert_eq!(parse("{1: {'isA': 2}}"), error("known value", 5..10));
+    assert_eq!(parse("{2023-02-08: 1}"), error("date", 1..11));
+    assert_eq!(parse("{18446744073709551616: 1}"), error("bignum", 1..21));
+    assert_eq!(
+        parse("{-18446744073709551617: 1}"),
+        error("bignum", 1..22)
+    );
+    assert!(parse("{-18446744073709551616: 1}").is_ok());
+    // Without the option, any key is accepted.
+    assert!(
+        parse_dcbor_item_with_options("{1.5: 1}", &ParseOptions::default())
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_cose() {
+    let options = ParseOptions::default().cose(true);
+    let parse = |src: &str| parse_dcbor_item_with_options(src, &options);
+    let valid = [
+        "COSE_Sign1([h'a10126', {}, null, h'00'])",
+        "COSE_Mac0([h'', {1: 5}, h'00', h'00'])",
+        "COSE_Encrypt0([h'', {}, h'00'])",
+        "COSE_Sign([h'', {}, h'00', [[h'', {}, h'00'], [h'', {}, h'01']]])",
+        "COSE_Mac([h'', {}, h'00', h'00', [[h'', {}, null]]])",
+        "COSE_Encrypt([h'', {}, h'00', [[h'', {}, h'00', [[h'', {}, null]]]]])",
+        "CWT(COSE_Mac0([h'', {}, h'00', h'00']))",
+        "18([h'', {}, h'00', h'00'])",
+    ];
+    for src in valid {
+        assert!(parse(src).is_ok(), "{src}");
+    }
+
+    let error = |src: &str| match parse(src) {
+        Err(ParseError::InvalidTagContent(message, span)) => {
+            format!("{message} at {}", &src[span.range()])
+        }
+        result => panic!("expected InvalidTagContent, got {result:?}"),
+    };
+    assert_eq!(
+        error("[COSE_Sign1([h'', {}, h'00'])]"),
+        "18: COSE_Sign1 must have 4 elements, not 3 at [h'', {}, h'00']"
+    );
+    assert_eq!(
+        error("COSE_Encrypt0({})"),
+        "16: COSE_Encrypt0 must be an array at {}"
+    );
+    assert_eq!(
+        error("COSE_Mac0([{}, {}, h'00', h'00'])"),
+        "17: the protected of COSE_Mac0 must be a byte string at \
+         [{}, {}, h'00', h'00']"
+    );
+    assert_eq!(
+        error("98([h'', {}, h'00', []])"),
+        "98: the signatures of COSE_Sign must not be empty at \
+         [h'', {}, h'00', []]"
+    );
+    assert_eq!(
+        error("COSE_Sign([h'', {}, h'00', [[h'', h'00']]])"),
+        "98: COSE_Signature must have 3 elements, not 2 at \
+         [h'', {}, h'00', [[h'', h'00']]]"
+    );
+    assert_eq!(
+        error("CWT([h'', {}, h'00'])"),
+        "61: a CWT must be a tagged COSE message at [h'', {}, h'00']"
+    );
+
+    // Without the option, the names are unknown and the tags unchecked.
+    let options = ParseOptions::default();
+    assert_eq!(
+        parse_dcbor_item_with_options("COSE_Sign1([])", &options),
+        Err(ParseError::UnknownTagName(
+            "COSE_Sign1".to_string(),
+            (0..10).into()
+        ))
+    );
+    assert!(parse_dcbor_item_with_options("18([])", &options).is_ok());
+}
+
+#[test]
+fn test_spaced_tags() {
+    dcbor::register_tags();
+    let spacing = |start, end| {
+        Err(ParseError::SpaceBeforeParenthesis(SourceSpan::new(start, end)))
+    };
+    let strict = ParseOptions::default();
+    let parse = |src| parse_dcbor_item_with_options(src, &strict);
+    assert_eq!(parse("date (1)"), spacing(4, 5));
+    assert_eq!(parse("[1, 0x10\t\n(2)]"), spacing(8, 10));
+    assert_eq!(parse("{1:\n  42 (\"a\")}"), spacing(8, 9));
+    assert_eq!(
+        parse("date (1)").unwrap_err().help(),
+        Some(
+            "remove the whitespace, as in `date(1)`, or enable \
+             `ParseOptions::spaced_tags`"
+        )
+    );
+    // Whitespace isn't allowed after the parenthesis.
+    assert!(matches!(parse("1.5 (1)"), Err(ParseError::ExtraData(_))));
+
+    let options = ParseOptions::default().spaced_tags(true);
+    let value = parse_diag_value_with_options("[0x10 (1), date  (2)]", &options)
+        .unwrap();
+    assert_eq!(value.to_cbor().diagnostic_flat(), "[16(1), 1(2)]");
+    let DiagValue::Array { items, .. } = &value else { panic!() };
+    let DiagValue::Tagged { tag_text, span, .. } = &items[1] else { panic!() };
+    assert_eq!((tag_text.as_str(), span.range()), ("date", 11..20));
+    assert_eq!(
+        parse_dcbor_item_with_options("nosuchtag (1)", &options),
+        Err(ParseError::UnknownTagName(
+            "nosuchtag".to_string(),
+            (0..9).into()
+        ))
+    );
+    assert!(matches!(
+        parse_dcbor_item_with_options("99999999999999999999 (1)", &options),
+        Err(ParseError::InvalidTagValue(_, _))
+    ));
+}
+
+#[test]
+fn test_duplicate_keys() {
+    let src = r#"{1: "a", 2: "b", 1.0: "c", 1: "d"}"#;
+    let parse = |policy| {
+        let options = ParseOptions::default().duplicate_keys(policy);
+        parse_diag_value_with_options(src, &options)
+    };
+    assert_eq!(
+        parse(DuplicateKeys::Reject),
+        Err(ParseError::DuplicateMapKey((17..20).into()))
+    );
+    let first = parse(DuplicateKeys::KeepFirst).unwrap();
+    assert_eq!(first.to_cbor().diagnostic_flat(), r#"{1: "a", 2: "b"}"#);
+    let last = parse(DuplicateKeys::KeepLast).unwrap();
+    assert_eq!(last.to_cbor().diagnostic_flat(), r#"{1: "d", 2: "b"}"#);
+    // The last entry replaces the first in its place, and its span is the
+    // one recorded.
+    let DiagValue::Map { entries, .. } = &last else {
+        panic!("expected a map");
+    };
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].0.span(), 27..28);
+    assert_eq!(entries[0].1.span(), 30..33);
+}
+
+#[test]
+fn test_disabled_syntax() {
+    let options = ParseOptions::default()
+        .date_literals(false)
+        .known_value_literals(false);
+    let parse = |src| parse_dcbor_item_with_options(src, &options);
+    assert!(parse("[1(1675814400), 40000(1)]").is_ok());
+    let disabled = |syntax: &str, span: std::ops::Range<usize>| {
+        Err(ParseError::SyntaxDisabled(syntax.into(), span.into()))
+    };
+    assert_eq!(parse("[2023-02-08]"), disabled("Date literals", 1..11));
+    for (src, span) in [("'isA'", 0..5), ("'1'", 0..3), ("Unit", 0..4)] {
+        assert_eq!(parse(src), disabled("Known value literals", span));
+    }
+    let error = parse("Unit").unwrap_err();
+    assert_eq!(error.to_string(), "Known value literals are disabled");
+    assert_eq!(error.code(), "syntax-disabled");
+    assert!(error.help().is_some());
+}
+
+#[test]
+fn test_lenient_floats() {
+    for src in ["+1", ".5", "1.", "-.5e1", "inf", "-INFINITY", "nan"] {
+        assert_eq!(
+            parse_dcbor_item(src),
+            Err(ParseError::SyntaxDisabled(
+                "Lenient floats".into(),
+                (0..src.len()).into()
+            ))
+        );
+    }
+    let options = ParseOptions::default().lenient_floats(true);
+    let parse = |src| parse_dcbor_item_with_options(src, &options).unwrap();
+    assert_eq!(parse("+1"), CBOR::from(1));
+    assert_eq!(parse("-.5e1"), CBOR::from(-5));
+    assert_eq!(parse("+infinity"), CBOR::from(f64::INFINITY));
+    assert_eq!(parse("[-inf, Nan]").diagnostic_flat(), "[-Infinity, NaN]");
+    // The float keywords of other diagnostic notation tools.
+    assert_eq!(
+        parse("[inf, +Infinity, -inf, nan, -INFINITY, +NAN]").diagnostic_flat(),
+        "[Infinity, Infinity, -Infinity, NaN, -Infinity, NaN]"
+    );
+    assert_eq!(
+        parse_dcbor_item("+Infinity"),
+        Err(ParseError::SyntaxDisabled(
+            "Lenient floats".into(),
+            (0..9).into()
+        ))
+    );
+    // The standard spellings are unchanged.
+    assert_eq!(
+        parse("[1.5, -Infinity, NaN]").diagnostic_flat(),
+        "[1.5, -Infinity, NaN]"
+    );
+}
+
+#[test]
+fn test_nan_handling() {
+    let parse = |src, policy| {
+        let options =
+            ParseOptions::default().lenient_floats(true).nan_handling(policy);
+        parse_diag_value_with_options(src, &options)
+    };
+    let src = "[NaN, -nan, +NAN, -Infinity]";
+    for policy in [NaNHandling::Canonicalize, NaNHandling::Warn] {
+        let value = parse(src, policy).unwrap();
+        let data = value.to_cbor().to_cbor_data();
+        assert_eq!(hex::encode(data), "84f97e00f97e00f97e00f9fc00");
+    }
+    let value = parse(src, NaNHandling::Canonicalize).unwrap();
+    assert!(Warning::collect(&value).is_empty());
+
+    let value = parse(src, NaNHandling::Warn).unwrap();
+    let warnings: Vec<_> = Warning::collect(&value)
+        .into_iter()
+        .map(|w| (w.kind, w.span.range()))
+        .collect();
+    assert_eq!(warnings, [(WarningKind::NaNSignLost, 6..10)]);
+    assert_eq!(warnings[0].0.code(), "nan-sign-lost");
+
+    assert_eq!(
+        parse(src, NaNHandling::Reject),
+        Err(ParseError::SyntaxDisabled("NaN values".into(), (1..4).into()))
+    );
+    let value = parse("[1.5, -Infinity]", NaNHandling::Reject).unwrap();
+    assert_eq!(value.to_cbor().diagnostic_flat(), "[1.5, -Infinity]");
+}
+
+#[test]
+fn test_encoding_indicators() {
+    let parse = |src, policy| {
+        let options = ParseOptions::default().encoding_indicators(policy);
+        parse_diag_value_with_options(src, &options)
+    };
+    let src = "[0_i, 24_0, 1000_1, -70000_2, 0x100000000_3, 1.5_1, 1e300_3]";
+    assert_eq!(
+        parse(src, EncodingIndicators::Reject),
+        Err(ParseError::SyntaxDisabled(
+            "Encoding indicators".into(),
+            (1..4).into()
+        ))
+    );
+    for policy in [EncodingIndicators::Ignore, EncodingIndicators::Validate] {
+        let value = parse(src, policy).unwrap();
+        assert_eq!(
+            value.to_cbor().diagnostic_flat(),
+            "[0, 24, 1000, -70000, 4294967296, 1.5, 1e300]"
+        );
+    }
+    let value = parse("1_3", EncodingIndicators::Ignore).unwrap();
+    assert_eq!(value.text(), Some("1_3"));
+
+    for (src, span) in [("1_0", 0..3), ("[1.0_1]", 1..6), ("1.5_3", 0..5)] {
+        let indicator = &src[span.end - 2..span.end];
+        assert_eq!(
+            parse(src, EncodingIndicators::Validate),
+            Err(ParseError::EncodingIndicatorMismatch(
+                indicator.into(),
+                span.into()
+            )),
+            "{src}"
+        );
+    }
+    // An indicator must follow the number directly.
+    assert!(parse("1 _1", EncodingIndicators::Ignore).is_err());
+    assert!(parse("1_4", EncodingIndicators::Ignore).is_err());
+}
+
+#[test]
+fn test_profiles() {
+    let strict = ParseOptions::strict();
+    assert!(parse_dcbor_item_with_options("'isA'", &strict).is_ok());
+    assert!(matches!(
+        parse_dcbor_item_with_options("'100000'", &strict),
+        Err(ParseError::DisallowedKnownValue(_, _))
+    ));
+    assert!(matches!(
+        parse_dcbor_item_with_options("37(h'00')", &strict),
+        Err(ParseError::InvalidTagContent(_, _))
+    ));
+
+    let lenient = ParseOptions::lenient();
+    let src = "{\"a\": +1, \"a\": 1234 (\"tab\there\")}";
+    let cbor = parse_dcbor_item_with_options(src, &lenient).unwrap();
+    assert_eq!(cbor.diagnostic_flat(), r#"{"a": 1234("tab\there")}"#);
+    assert!(parse_dcbor_item(src).is_err());
+}
+
+#[test]
+fn test_data_only() {
+    dcbor::register_tags();
+    let options = fragments()
+        .env_lookup(|_| Some("1".to_string()))
+        .data_only(true);
+    let parse = |src| parse_dcbor_item_with_options(src, &options);
+    let src = r#"[1, -2.5, "a", h'00', b64'AQI=', true, null, 1(1), {1: []}]"#;
+    assert_eq!(parse(src).unwrap(), parse_dcbor_item(src).unwrap());
+    let disabled = |syntax: &str, span: std::ops::Range<usize>| {
+        Err(ParseError::SyntaxDisabled(syntax.into(), span.into()))
+    };
+    assert_eq!(parse("2023-02-08"), disabled("Date literals", 0..10));
+    assert_eq!(parse("[Unit]"), disabled("Known value literals", 1..5));
+    assert_eq!(parse("date(1)"), disabled("Tag names", 0..4));
+    assert_eq!(
+        parse("ur:date/cyisdadmlasgtapttl"),
+        disabled("URs", 0..26)
+    );
+    assert_eq!(parse(r#"!include("pair")"#), disabled("Directives", 0..9));
+    // Variables aren't substituted.
+    assert!(parse("${ANY}").is_err());
+    // Tagged numbers aren't known values.
+    let options = options.reserve_known_values(0..=10);
+    let cbor = parse_dcbor_item_with_options("40000(1)", &options).unwrap();
+    assert_eq!(cbor.diagnostic_flat(), "40000(1)");
+}
+
+#[test]
+fn test_max_depth() {
+    // Deeply nested input is an error, not a stack overflow.
+    for open in ["[", "{1: ", "1(", "<<"] {
+        let src = open.repeat(100_000);
+        let error = parse_dcbor_item(&src).unwrap_err();
+        assert_eq!(error.code(), "nesting-too-deep", "{open}");
+    }
+    let src = "[".repeat(300);
+    assert_eq!(
+        parse_dcbor_item(&src),
+        Err(ParseError::NestingTooDeep(256, (256..257).into()))
+    );
+    let src = format!("{}{}", "[".repeat(256), "]".repeat(256));
+    assert!(parse_dcbor_item(&src).is_ok());
+
+    let options = ParseOptions::default()
+        .max_depth(3)
+        .include_loader(|_| Ok("[[1]]".to_string()));
+    let parse = |src: &str| parse_dcbor_item_with_options(src, &options);
+    assert!(parse("[[[1]]]").is_ok());
+    assert!(parse("[!include(\"a\")]").is_ok());
+    // The groups of an included document count the levels they are
+    // included in.
+    assert_eq!(
+        parse("[[!include(\"a\")]]").unwrap_err().code(),
+        "include-failed"
+    );
+}
@@ -1,8 +1,17 @@
 use std::collections::HashMap;
 
 use base64::Engine as _;
-use bc_ur::prelude::*;
-use dcbor_parse::{ParseError, parse_dcbor_item, parse_dcbor_item_partial};
+use bc_ur::{
+    bytewords::{self, Style},
+    prelude::*,
+};
+use dcbor_parse::{
+    DiagValue, EndReason, LiteralForm, NumberEncoding, NumberKind,
+    NumberValue, ParseError, PrintOptions, SourceSpan, format_diag_value,
+    parse_dcbor_entry, parse_dcbor_implicit_array, parse_dcbor_item,
+    parse_dcbor_item_partial, parse_dcbor_item_partial_detailed,
+    parse_dcbor_sequence, parse_diag_entry, parse_diag_value,
+};
 use indoc::indoc;
 
 fn roundtrip<T: Into<CBOR>>(value: T) {
@@ -62,6 +71,359 @@ fn test_byte_string() {
     assert_eq!(cbor3, cbor);
 }
 
+#[test]
+fn test_hex_byte_strings_with_whitespace_and_comments() {
+    let src = indoc! {"
+        h'0102 0304 /checksum/ 0506
+          07 # the last byte
+        '
+    "};
+    let cbor = parse_dcbor_item(src).unwrap();
+    assert_eq!(cbor, CBOR::to_byte_string([1, 2, 3, 4, 5, 6, 7]));
+    // A comment may come between the digits of a byte.
+    assert_eq!(
+        parse_dcbor_item("h'0/high/1/low/'").unwrap(),
+        CBOR::to_byte_string([1])
+    );
+    let value = parse_diag_value("h'00 /zero/'").unwrap();
+    assert_eq!(value.text(), Some("h'00 /zero/'"));
+
+    for src in ["h'01 0'", "h'01 /unclosed'", "h'01 0g'", "h'0x01'"] {
+        assert_eq!(
+            parse_dcbor_item(src),
+            Err(ParseError::InvalidHexString((0..src.len()).into())),
+            "{src}"
+        );
+    }
+}
+
+#[test]
+fn test_concatenated_byte_strings() {
+    let src = indoc! {"
+        [
+            h'0102'
+            h'0304' # the middle
+            b64'BQY=' b64url'Bw' h'',
+            h'08'
+        ]
+    "};
+    let value = parse_diag_value(src).unwrap();
+    assert_eq!(
+        value.to_cbor(),
+        CBOR::from(vec![
+            CBOR::to_byte_string([1, 2, 3, 4, 5, 6, 7]),
+            CBOR::to_byte_string([8]),
+        ])
+    );
+    let DiagValue::Array { items, .. } = &value else { panic!() };
+    assert_eq!(items[0].span(), SourceSpan::new(6, 67));
+    assert_eq!(items[0].literal_form(), LiteralForm::HexByteString);
+    assert_eq!(
+        items[0].text(),
+        Some("h'0102'\n    h'0304' # the middle\n    b64'BQY=' b64url'Bw' h''")
+    );
+
+    // In a sequence, adjacent byte strings are a single item.
+    assert_eq!(
+        parse_dcbor_sequence("h'01' h'02' 3 h'04'").unwrap(),
+        [
+            CBOR::to_byte_string([1, 2]),
+            CBOR::from(3),
+            CBOR::to_byte_string([4])
+        ]
+    );
+    assert_eq!(
+        parse_dcbor_item("raw(24, h'8201' h'02')").unwrap(),
+        parse_dcbor_item("24([1, 2])").unwrap()
+    );
+    assert_eq!(
+        parse_dcbor_item("h'01' h'0'"),
+        Err(ParseError::InvalidHexString((6..10).into()))
+    );
+    // A duplicate key is reported at all of its segments.
+    assert_eq!(
+        parse_dcbor_item("{h'00' h'01': 1, h'00' h'01': 2}"),
+        Err(ParseError::DuplicateMapKey((17..28).into()))
+    );
+}
+
+#[test]
+fn test_base64url_byte_strings() {
+    let cbor = parse_dcbor_item("b64url'AQIDBAUGBwgJCg'").unwrap();
+    assert_eq!(cbor, CBOR::to_byte_string((1..=10).collect::<Vec<u8>>()));
+    // The URL-safe alphabet uses `-` and `_` in place of `+` and `/`.
+    let cbor = parse_dcbor_item("[b64url'-_8', b64url'']").unwrap();
+    assert_eq!(cbor.diagnostic_flat(), "[h'fbff', h'']");
+    let value = parse_diag_value("b64url'AP8'").unwrap();
+    assert_eq!(value.literal_form(), LiteralForm::Base64UrlByteString);
+    assert_eq!(value.text(), Some("b64url'AP8'"));
+
+    for src in ["b64url'AP8='", "b64url'+/8'", "b64url'A'", "b64url'AP9'"] {
+        assert_eq!(
+            parse_dcbor_item(src),
+            Err(ParseError::InvalidBase64String((0..src.len()).into())),
+            "{src}"
+        );
+    }
+}
+
+#[test]
+fn test_base45_byte_strings() {
+    // The examples of RFC 9285.
+    for (src, bytes) in [
+        ("b45'BB8'", "AB"),
+        ("b45'%69 VD92EX0'", "Hello!!"),
+        ("b45'UJCLQE7W581'", "base-45"),
+        ("b45'QED8WEX0'", "ietf!"),
+        ("b45''", ""),
+    ] {
+        let cbor = parse_dcbor_item(src).unwrap();
+        assert_eq!(cbor, CBOR::to_byte_string(bytes), "{src}");
+    }
+    let value = parse_diag_value("[b45'BB8']").unwrap();
+    let DiagValue::Array { items, .. } = &value else { panic!() };
+    assert_eq!(items[0].literal_form(), LiteralForm::Base45ByteString);
+    assert_eq!(items[0].text(), Some("b45'BB8'"));
+
+    // Lowercase isn't in the alphabet, a single character left over is
+    // invalid, and `:::` and `GGW` encode values that don't fit in two
+    // bytes.
+    for src in ["b45'bb8'", "b45'BB8A'", "b45':::'", "b45'GGW'"] {
+        assert_eq!(
+            parse_dcbor_item(src),
+            Err(ParseError::InvalidBase45String((0..src.len()).into())),
+            "{src}"
+        );
+    }
+}
+
+#[test]
+fn test_bytewords_byte_strings() {
+    for src in [
+        "bw'able tied also webs lung'",
+        "bw'able-tied-also-webs-lung'",
+        "bw'aetdaowslg'",
+        "bw'AETDAOWSLG'",
+        "bw'\n    able tied\n    also webs lung\n'",
+    ] {
+        let cbor = parse_dcbor_item(src).unwrap();
+        assert_eq!(cbor, CBOR::to_byte_string([0]), "{src}");
+    }
+    let bytes: Vec<u8> = (0..=255).collect();
+    for style in [Style::Standard, Style::Uri, Style::Minimal] {
+        let src = format!("bw'{}'", bytewords::encode(&bytes, style));
+        let cbor = parse_dcbor_item(&src).unwrap();
+        assert_eq!(cbor, CBOR::to_byte_string(bytes.clone()));
+    }
+    let value = parse_diag_value("bw'aetdaowslg'").unwrap();
+    assert_eq!(value.literal_form(), LiteralForm::ByteWordsByteString);
+
+    for (src, message) in [
+        ("bw'able tied also webs limp'", "invalid checksum"),
+        ("bw'able tied also webs'", "invalid checksum"),
+        ("bw'able tide also webs lung'", "invalid word"),
+        ("bw'aetdaowsl'", "invalid length"),
+    ] {
+        assert_eq!(
+            parse_dcbor_item(src),
+            Err(ParseError::InvalidByteWords(
+                message.into(),
+                (0..src.len()).into()
+            )),
+            "{src}"
+        );
+    }
+}
+
+#[test]
+fn test_type_assertions() {
+    let src = indoc! {r#"
+        {
+            "id": 42 :: uint .size 1,
+            "key": h'00112233' :: bstr .size 4, # four bytes
+            "tags": ["a" :: tstr, 1.5 :: float] :: array,
+            -1 :: int: null :: null
+        } :: map
+    "#};
+    let cbor = parse_dcbor_item(src).unwrap();
+    assert_eq!(
+        cbor.diagnostic_flat(),
+        r#"{-1: null, "id": 42, "key": h'00112233', "tags": ["a", 1.5]}"#
+    );
+    // The assertion isn't part of the item or its span.
+    let value = parse_diag_value("1(2) :: any").unwrap();
+    assert_eq!(value.span(), SourceSpan::new(0, 4));
+    let cbor = parse_dcbor_item("<<1::number>>").unwrap();
+    assert_eq!(cbor, CBOR::to_byte_string([1]));
+    // Comments may come between an item and its assertion.
+    let cbor = parse_dcbor_item("[42 /c/ :: uint, 1 # c\n:: int]").unwrap();
+    assert_eq!(cbor.diagnostic_flat(), "[42, 1]");
+    assert_eq!(
+        parse_dcbor_item("42 /c/ :: tstr"),
+        Err(ParseError::TypeMismatch("tstr".into(), (0..2).into()))
+    );
+
+    assert_eq!(
+        parse_dcbor_item("[1, 256 :: uint .size 1]"),
+        Err(ParseError::TypeMismatch("uint .size 1".into(), (4..7).into()))
+    );
+    assert_eq!(
+        parse_dcbor_item(r#""é" :: tstr .size 1"#),
+        Err(ParseError::TypeMismatch("tstr .size 1".into(), (0..4).into()))
+    );
+    // Integral floats are reduced to integers before they are checked.
+    assert_eq!(
+        parse_dcbor_item("2.0 :: float"),
+        Err(ParseError::TypeMismatch("float".into(), (0..3).into()))
+    );
+    for (src, text, span) in [
+        ("1 :: integer", "integer", 2..12),
+        ("1 :: int .size 1", "int .size 1", 2..16),
+        ("h'' :: bstr .size", "bstr .size", 4..17),
+        ("[1 ::, 2]", "", 3..5),
+    ] {
+        assert_eq!(
+            parse_dcbor_item(src),
+            Err(ParseError::InvalidTypeAssertion(text.into(), span.into())),
+            "{src}"
+        );
+    }
+    // A duplicate key is reported at the key, not at its assertion.
+    let src = r#"{"a": 1, "a" :: tstr: 2}"#;
+    let error = parse_dcbor_item(src).unwrap_err();
+    assert_eq!(error, ParseError::DuplicateMapKey((9..12).into()));
+    let caret = format!("{src}\n         ^^^\n");
+    assert!(error.full_message(src).contains(&caret));
+}
+
+#[test]
+fn test_large_integers() {
+    roundtrip(u64::MAX);
+    roundtrip(i64::MIN);
+    roundtrip((1u64 << 53) + 1);
+    let cbor = parse_dcbor_item("18446744073709551615").unwrap();
+    assert_eq!(cbor.to_cbor_data(), hex::decode("1bffffffffffffffff").unwrap());
+    let cbor = parse_dcbor_item("-9223372036854775808").unwrap();
+    assert_eq!(cbor.to_cbor_data(), hex::decode("3b7fffffffffffffff").unwrap());
+    // Integral floats are still reduced to integers.
+    assert_eq!(parse_dcbor_item("-0").unwrap(), CBOR::from(0));
+    assert_eq!(parse_dcbor_item("1e3").unwrap(), CBOR::from(1000));
+}
+
+#[test]
+fn test_bignums() {
+    let data =
+        |src: &str| hex::encode(parse_dcbor_item(src).unwrap().to_cbor_data());
+    assert_eq!(data("18446744073709551616"), "c249010000000000000000");
+    assert_eq!(
+        data("340282366920938463463374607431768211455"),
+        "c250ffffffffffffffffffffffffffffffff"
+    );
+    // Negative integers down to -2^64 fit in a CBOR integer.
+    assert_eq!(data("-9223372036854775809"), "3b8000000000000000");
+    assert_eq!(data("-18446744073709551616"), "3bffffffffffffffff");
+    assert_eq!(data("-18446744073709551617"), "c349010000000000000000");
+    let value = parse_diag_value("[18446744073709551616]").unwrap();
+    let DiagValue::Array { items, .. } = &value else { panic!() };
+    assert_eq!(
+        items[0],
+        DiagValue::Number {
+            text: "18446744073709551616".to_string(),
+            value: NumberValue::BigUnsigned(vec![1, 0, 0, 0, 0, 0, 0, 0, 0]),
+            span: SourceSpan::new(1, 21),
+        }
+    );
+    assert_eq!(items[0].number_kind(), Some(NumberKind::Integer));
+    assert_eq!(items[0].number_encoding(), Some(NumberEncoding::Bignum));
+}
+
+#[test]
+fn test_decimal_fractions() {
+    let parse = |src| parse_dcbor_item(src).unwrap().diagnostic_flat();
+    assert_eq!(parse("dec'2.75'"), "4([-2, 275])");
+    assert_eq!(parse("dec'-1.5e-3'"), "4([-4, -15])");
+    assert_eq!(parse("dec'2.50'"), "4([-2, 250])");
+    assert_eq!(parse("dec'1E+3'"), "4([3, 1])");
+    assert_eq!(parse("dec'-0.0'"), "4([-1, 0])");
+    assert_eq!(
+        parse("dec'1844674407370955161.6'"),
+        "4([-1, 2(h'010000000000000000')])"
+    );
+    assert_eq!(
+        parse_dcbor_item("[dec'2.75']").unwrap(),
+        parse_dcbor_item("[4([-2, 275])]").unwrap()
+    );
+
+    let value = parse_diag_value("dec'0.10'").unwrap();
+    assert_eq!(
+        value,
+        DiagValue::DecimalFraction {
+            text: "dec'0.10'".to_string(),
+            exponent: -2,
+            mantissa: NumberValue::Unsigned(10),
+            span: SourceSpan::new(0, 9),
+        }
+    );
+    let options = PrintOptions::default();
+    assert_eq!(format_diag_value(&value, &options), "dec'0.10'");
+
+    for body in ["", "1.", ".5", "+1", "1e", "1e+-2", "0x10", "1 "] {
+        let src = format!("[dec'{body}']");
+        assert_eq!(
+            parse_dcbor_item(&src),
+            Err(ParseError::InvalidDecimalFraction(
+                body.to_string(),
+                SourceSpan::new(1, src.len() - 1)
+            )),
+            "{src}"
+        );
+    }
+    assert!(parse_dcbor_item("dec'1e9223372036854775808'").is_err());
+    assert!(parse_dcbor_item("dec'1.5e-9223372036854775808'").is_err());
+}
+
+#[test]
+fn test_rationals() {
+    let parse = |src| parse_dcbor_item(src).unwrap().diagnostic_flat();
+    assert_eq!(parse("rat'1/3'"), "30([1, 3])");
+    assert_eq!(parse("rat'-22/7'"), "30([-22, 7])");
+    assert_eq!(parse("rat'2/6'"), "30([2, 6])");
+    assert_eq!(parse("rat'0/1'"), "30([0, 1])");
+    assert_eq!(
+        parse("rat'1/18446744073709551616'"),
+        "30([1, 2(h'010000000000000000')])"
+    );
+    assert_eq!(
+        parse_dcbor_item("[rat'1/3']").unwrap(),
+        parse_dcbor_item("[30([1, 3])]").unwrap()
+    );
+
+    let value = parse_diag_value("rat'-1/03'").unwrap();
+    assert_eq!(
+        value,
+        DiagValue::Rational {
+            text: "rat'-1/03'".to_string(),
+            numerator: NumberValue::Negative(-1),
+            denominator: NumberValue::Unsigned(3),
+            span: SourceSpan::new(0, 10),
+        }
+    );
+    let options = PrintOptions::default();
+    assert_eq!(format_diag_value(&value, &options), "rat'-1/03'");
+
+    for body in ["", "1", "1/0", "1/-3", "+1/3", "1.5/2", "0x1/2", "1/2/3"] {
+        let src = format!("[rat'{body}']");
+        assert_eq!(
+            parse_dcbor_item(&src),
+            Err(ParseError::InvalidRational(
+                body.to_string(),
+                SourceSpan::new(1, src.len() - 1)
+            )),
+            "{src}"
+        );
+    }
+}
+
 #[test]
 fn test_nan() {
     // NaN is a special case because it doesn't equal itself
@@ -175,6 +537,55 @@ fn test_known_value() {
     assert_eq!(cbor4, cbor);
 }
 
+#[test]
+fn test_alternate_bases() {
+    let parse = |src| parse_dcbor_item(src).unwrap().diagnostic_flat();
+    assert_eq!(parse("0x10001(1)"), "65537(1)");
+    assert_eq!(parse("0X4D2(\"a\")"), r#"1234("a")"#);
+    assert_eq!(parse("[0o777(1), 0b101(2)]"), "[511(1), 5(2)]");
+    assert_eq!(parse("'0x64'"), "40000(100)");
+    assert_eq!(parse("['0b1', '0o0']"), "[40000(1), 40000(0)]");
+
+    let value = parse_diag_value("0x4d2(1)").unwrap();
+    let DiagValue::Tagged { tag, tag_text, .. } = value else { panic!() };
+    assert_eq!((tag.value(), tag_text.as_str()), (1234, "0x4d2"));
+
+    assert_eq!(
+        parse_dcbor_item("0x10000000000000000(1)"),
+        Err(ParseError::InvalidTagValue(
+            "0x10000000000000000".to_string(),
+            (0..19).into()
+        ))
+    );
+    assert_eq!(
+        parse_dcbor_item("'0x10000000000000000'"),
+        Err(ParseError::InvalidKnownValue(
+            "0x10000000000000000".to_string(),
+            (1..20).into()
+        ))
+    );
+    // Integers may be written in these bases anywhere.
+    assert_eq!(parse("[0x1F, 0o17, 0b1010, -0X10]"), "[31, 15, 10, -16]");
+    assert_eq!(
+        parse("{0x01: 0xffffffffffffffff}"),
+        "{1: 18446744073709551615}"
+    );
+    assert_eq!(parse("-0x8000000000000000"), "-9223372036854775808");
+    assert_eq!(parse("-0x0"), "0");
+    assert_eq!(
+        parse_dcbor_item("0x10000000000000000").unwrap(),
+        parse_dcbor_item("18446744073709551616").unwrap()
+    );
+    assert_eq!(
+        parse_dcbor_item("0x1E").unwrap().to_cbor_data(),
+        parse_dcbor_item("30").unwrap().to_cbor_data()
+    );
+    assert_eq!(
+        parse_dcbor_item("0x1g"),
+        Err(ParseError::ExtraData(SourceSpan::new(3, 4)))
+    );
+}
+
 #[test]
 fn test_unit_known_value() {
     let v = known_values::UNIT;
@@ -238,6 +649,9 @@ fn test_errors() {
         matches!(e, ParseError::UnknownTagName(_, _))
     });
     check_error("h'01020'", |e| matches!(e, ParseError::InvalidHexString(_)));
+    check_error(r#""\q""#, |e| {
+        matches!(e, ParseError::InvalidEscapeSequence(_, _))
+    });
     check_error("b64'AQIDBAUGBwgJCg'", |e| {
         matches!(e, ParseError::InvalidBase64String(_))
     });
@@ -318,6 +732,60 @@ fn test_parse_partial_trailing_ws() {
     assert_eq!(used, src.len());
 }
 
+#[test]
+fn test_parse_partial_end_reason() {
+    let ended_with = |src| {
+        let partial = parse_dcbor_item_partial_detailed(src).unwrap();
+        (partial.consumed, partial.trailing_span.range(), partial.ended_with)
+    };
+    assert_eq!(ended_with("1"), (1, 1..1, EndReason::EndOfInput));
+    assert_eq!(ended_with("1 \n"), (3, 1..3, EndReason::EndOfInput));
+    assert_eq!(ended_with("1 / a /"), (7, 1..7, EndReason::TrailingComment));
+    assert_eq!(ended_with("1 # a\n"), (6, 1..6, EndReason::TrailingComment));
+    assert_eq!(ended_with("1 # a\n2"), (6, 1..6, EndReason::Token));
+    assert_eq!(ended_with("[1]]"), (3, 3..3, EndReason::Token));
+    // An unterminated comment isn't a comment.
+    assert_eq!(ended_with("1 / a"), (1, 1..1, EndReason::Token));
+    assert_eq!(
+        parse_dcbor_item_partial_detailed("# only a comment"),
+        Err(ParseError::EmptyInput)
+    );
+}
+
+#[test]
+fn test_parse_entry() {
+    let (key, value) = parse_dcbor_entry(r#"  "a" : {1: [2]}  "#).unwrap();
+    assert_eq!(key, CBOR::from("a"));
+    assert_eq!(value.diagnostic_flat(), "{1: [2]}");
+
+    let src = "1(2): 'isA' # comment";
+    let (key, value) = parse_diag_entry(src).unwrap();
+    assert_eq!(&src[key.span().range()], "1(2)");
+    assert_eq!(&src[value.span().range()], "'isA'");
+
+    assert_eq!(parse_dcbor_entry(""), Err(ParseError::EmptyInput));
+    assert_eq!(
+        parse_dcbor_entry("1"),
+        Err(ParseError::ExpectedColon((1..1).into()))
+    );
+    assert_eq!(
+        parse_dcbor_entry("1:"),
+        Err(ParseError::UnexpectedEndOfInput)
+    );
+    assert_eq!(
+        parse_dcbor_entry("1: 2,"),
+        Err(ParseError::ExtraData((4..5).into()))
+    );
+    assert_eq!(
+        parse_dcbor_entry("1: 2, 3: 4"),
+        Err(ParseError::ExtraData((4..5).into()))
+    );
+    assert!(matches!(
+        parse_dcbor_entry("{1: 2}"),
+        Err(ParseError::ExpectedColon(_))
+    ));
+}
+
 #[test]
 fn test_date_literals() {
     dcbor::register_tags();
@@ -529,3 +997,244 @@ fn test_duplicate_key_error_location() {
         e => panic!("Expected DuplicateMapKey error, got: {:?}", e),
     }
 }
+
+#[test]
+fn test_invalid_escape_sequence_location() {
+    let cases = [
+        (r#""abc\qdef""#, r"\q", 4..6),
+        (r#"["ok", "a\\b\x"]"#, r"\x", 12..14),
+        (r#""\u12G4""#, r"\u12", 1..5),
+        (r#""\u""#, r"\u", 1..3),
+        (r#""caf\é""#, r"\é", 4..7),
+    ];
+    for (source, escape, range) in cases {
+        let error = parse_dcbor_item(source).unwrap_err();
+        let ParseError::InvalidEscapeSequence(text, span) = &error else {
+            panic!("Expected InvalidEscapeSequence for `{source}`: {error:?}");
+        };
+        assert_eq!(text, escape);
+        assert_eq!(span.range(), range);
+        assert_eq!(&source[span.range()], escape);
+    }
+
+    // Valid escapes are kept as written.
+    let source = r#""\"\\\/\b\f\n\r\t\u00e9""#;
+    assert_eq!(
+        parse_dcbor_item(source).unwrap(),
+        CBOR::from(&source[1..source.len() - 1])
+    );
+    assert_eq!(
+        parse_dcbor_item(r#""\q""#).unwrap_err().to_string(),
+        r"Invalid escape sequence '\q'"
+    );
+}
+
+#[test]
+fn test_invalid_unicode_escape() {
+    let cases = [
+        (r#""\ud800""#, r"\ud800", 1..7),
+        (r#""a\uDBFFb""#, r"\uDBFF", 2..8),
+        (r#""\ud83cA""#, r"\ud83c", 1..7),
+        (r#""\ud83c🌎""#, r"\ud83c", 1..7),
+        (r#"["x", "\udc00"]"#, r"\udc00", 7..13),
+        (r#""\ud83c\udf0e\udf0e""#, r"\udf0e", 13..19),
+    ];
+    for (source, escape, range) in cases {
+        let error = parse_dcbor_item(source).unwrap_err();
+        let ParseError::InvalidUnicodeEscape(text, span) = &error else {
+            panic!("Expected InvalidUnicodeEscape for `{source}`: {error:?}");
+        };
+        assert_eq!(text, escape);
+        assert_eq!(span.range(), range);
+    }
+
+    // Surrogate pairs, and escapes that aren't surrogates, are valid.
+    for source in [
+        r#""\ud83c\udf0e""#,
+        r#""\uD83C\uDF0E!""#,
+        r#""\uffff\u0041""#,
+    ] {
+        assert_eq!(
+            parse_dcbor_item(source).unwrap(),
+            CBOR::from(&source[1..source.len() - 1])
+        );
+    }
+    // An escaped backslash before `u` isn't an escape.
+    assert!(parse_dcbor_item(r#""\\ud800""#).is_ok());
+}
+
+#[test]
+fn test_tag_chains() {
+    dcbor::register_tags();
+    let value = parse_diag_value("date(1(date(2)))").unwrap();
+    assert_eq!(value.to_cbor().diagnostic_flat(), "1(1(1(2)))");
+    let DiagValue::Tagged { item, .. } = &value else {
+        panic!("expected a tagged value");
+    };
+    assert_eq!(item.span().range(), 5..15);
+
+    // Each name in a chain is resolved on its own, and an unknown name is
+    // reported before any error within its content.
+    assert_eq!(
+        parse_dcbor_item("date(nosuch(1))"),
+        Err(ParseError::UnknownTagName("nosuch".into(), (5..11).into()))
+    );
+    assert_eq!(
+        parse_dcbor_item("nosuch(date(q))"),
+        Err(ParseError::UnknownTagName("nosuch".into(), (0..6).into()))
+    );
+
+    // A parenthesis left open is reported at the tag that opened it.
+    assert_eq!(
+        parse_dcbor_item("1(date(3)"),
+        Err(ParseError::UnmatchedParentheses((0..2).into()))
+    );
+    assert_eq!(
+        parse_dcbor_item("1(date(3"),
+        Err(ParseError::UnmatchedParentheses((2..7).into()))
+    );
+    assert_eq!(
+        parse_dcbor_item("1(date(3) 4)"),
+        Err(ParseError::UnmatchedParentheses((10..11).into()))
+    );
+}
+
+#[test]
+fn test_sequence() {
+    let src = indoc! {r#"
+        # A log of three items.
+        {"event": "start"}
+        /* between */ [1, 2] 3
+    "#};
+    let items = parse_dcbor_sequence(src).unwrap();
+    assert_eq!(
+        items,
+        [
+            parse_dcbor_item(r#"{"event": "start"}"#).unwrap(),
+            parse_dcbor_item("[1, 2]").unwrap(),
+            CBOR::from(3),
+        ]
+    );
+    assert_eq!(parse_dcbor_sequence("").unwrap(), []);
+
+    // Errors are reported at their offset in the whole source.
+    assert_eq!(
+        parse_dcbor_sequence("1 [2 3]"),
+        Err(ParseError::ExpectedComma((5..6).into()))
+    );
+    assert_eq!(
+        parse_dcbor_sequence("1, 2"),
+        Err(ParseError::UnexpectedToken(
+            Box::new(dcbor_parse::Token::Comma),
+            (1..2).into()
+        ))
+    );
+}
+
+#[test]
+fn test_implicit_array() {
+    let src = indoc! {r#"
+        # One item per line, as a CLI writes them.
+        {"id": 1}
+        {"id": 2} # same shape
+        [3]
+    "#};
+    assert_eq!(
+        parse_dcbor_implicit_array(src).unwrap(),
+        parse_dcbor_item(r#"[{"id": 1}, {"id": 2}, [3]]"#).unwrap()
+    );
+    assert_eq!(parse_dcbor_implicit_array(" ").unwrap().diagnostic(), "[]");
+    assert_eq!(
+        parse_dcbor_implicit_array("1 [2 3]"),
+        Err(ParseError::ExpectedComma((5..6).into()))
+    );
+}
+
+#[test]
+fn test_raw_tagged_values() {
+    let cbor = parse_dcbor_item("[raw(1234, h'820102'), raw(0x4d2, b64'9Q==')]")
+        .unwrap();
+    assert_eq!(cbor.diagnostic_flat(), "[1234([1, 2]), 1234(true)]");
+
+    let src = r#"{"payload": raw(40000, h'1904d2')}"#;
+    let value = parse_diag_value(src).unwrap();
+    let DiagValue::Map { entries, .. } = &value else {
+        panic!("expected a map");
+    };
+    let raw = &entries[0].1;
+    assert_eq!(raw.text(), Some("raw(40000, h'1904d2')"));
+    assert_eq!(raw.span(), 12..33);
+    assert_eq!(raw.to_cbor(), CBOR::to_tagged_value(40000, 1234));
+    let options = PrintOptions::default().flat(true);
+    assert_eq!(format_diag_value(&value, &options), src);
+
+    // The content must be a single item in its deterministic encoding.
+    for (src, span) in [
+        ("raw(1, h'1801')", 7..14),
+        ("raw(1, h'0102')", 7..14),
+        ("raw(1, h'')", 7..10),
+        ("raw(1, h'f93c00')", 7..16),
+    ] {
+        assert!(
+            matches!(
+                parse_dcbor_item(src),
+                Err(ParseError::InvalidRawContent(_, s)) if s.range() == span
+            ),
+            "{src}"
+        );
+    }
+    assert_eq!(
+        parse_dcbor_item("raw(1, 2)"),
+        Err(ParseError::UnexpectedToken(
+            Box::new(dcbor_parse::Token::Number(NumberValue::Unsigned(2))),
+            (7..8).into()
+        ))
+    );
+    assert_eq!(
+        parse_dcbor_item("raw(-1, h'01')"),
+        Err(ParseError::UnexpectedToken(
+            Box::new(dcbor_parse::Token::Number(NumberValue::Negative(-1))),
+            (4..6).into()
+        ))
+    );
+    assert_eq!(
+        parse_dcbor_item("raw(1 h'01')"),
+        Err(ParseError::ExpectedComma((6..11).into()))
+    );
+    assert_eq!(
+        parse_dcbor_item("raw(1, h'01'"),
+        Err(ParseError::UnmatchedParentheses((12..12).into()))
+    );
+}
+
+#[test]
+fn test_embedded_cbor() {
+    let cbor = parse_dcbor_item("<<1, [2]>>").unwrap();
+    assert_eq!(cbor, CBOR::to_byte_string([0x01, 0x81, 0x02]));
+    assert_eq!(parse_dcbor_item("<<>>").unwrap(), CBOR::to_byte_string([]));
+    let nested = parse_dcbor_item(r#"<< {"a": <<h'00'>>} >>"#).unwrap();
+    let inner = CBOR::to_byte_string([0x41, 0x00]);
+    let mut map = Map::new();
+    map.insert("a", inner);
+    assert_eq!(nested, CBOR::to_byte_string(CBOR::from(map).to_cbor_data()));
+
+    let value = parse_diag_value("[<<1>> / one /]").unwrap();
+    let DiagValue::Array { items, .. } = &value else {
+        panic!("expected an array");
+    };
+    assert_eq!(items[0].text(), Some("<<1>>"));
+    assert_eq!(items[0].span(), 1..6);
+
+    assert_eq!(
+        parse_dcbor_item("<<1 2>>"),
+        Err(ParseError::ExpectedComma((4..5).into()))
+    );
+    assert!(matches!(
+        parse_dcbor_item("<<1,>>"),
+        Err(ParseError::UnexpectedToken(_, span)) if span == (4..6)
+    ));
+    assert_eq!(
+        parse_dcbor_item("<<1"),
+        Err(ParseError::UnexpectedEndOfInput)
+    );
+}
@@ -0,0 +1,86 @@
+use dcbor::prelude::*;
+use dcbor_parse::{
+    DiagValue, NumberValue, ParseError, PathSegment, extract_first,
+    parse_diag_value,
+};
+
+fn key(s: &str) -> PathSegment { PathSegment::Key(CBOR::from(s)) }
+
+#[test]
+fn test_extract_first_matches_at() {
+    dcbor::register_tags();
+    let src = r#"{
+        "a": [1, {"b": date(2)}, [3]],
+        [1]: {"c": 4},
+        "z": 1(2(3))
+    }"#;
+    let value = parse_diag_value(src).unwrap();
+    let paths = [
+        vec![],
+        vec![key("a")],
+        vec![key("a"), PathSegment::Index(1), key("b")],
+        vec![key("a"), PathSegment::Index(1), key("b"), PathSegment::Content],
+        vec![key("a"), PathSegment::Index(2), PathSegment::Index(0)],
+        vec![PathSegment::Key(CBOR::from(vec![1])), key("c")],
+        vec![key("z"), PathSegment::Content, PathSegment::Content],
+        vec![key("missing")],
+        vec![key("a"), PathSegment::Index(3)],
+        vec![key("a"), PathSegment::Content],
+        vec![PathSegment::Index(0)],
+    ];
+    for path in paths {
+        let extracted = extract_first(src, &path).unwrap();
+        assert_eq!(extracted.as_ref(), value.at(&path), "{:?}", path);
+    }
+}
+
+#[test]
+fn test_extract_first_stops_early() {
+    let src = "[[1, 2], 3, @@@ not valid";
+    let path = [PathSegment::Index(0), PathSegment::Index(1)];
+    let value = extract_first(src, &path).unwrap().unwrap();
+    assert!(matches!(
+        value,
+        DiagValue::Number { value: NumberValue::Unsigned(2), .. }
+    ));
+
+    let error = extract_first(src, &[PathSegment::Index(5)]).unwrap_err();
+    assert_eq!(error.code(), "unrecognized-token");
+    assert!(matches!(
+        extract_first("[1, @", &[PathSegment::Index(0)]),
+        Ok(Some(_))
+    ));
+    assert!(matches!(
+        extract_first("[@, 1]", &[PathSegment::Index(1)]),
+        Err(ParseError::UnrecognizedToken(_))
+    ));
+}
+
+#[test]
+fn test_path_segment_display() {
+    let path = [key("a"), PathSegment::Index(2), PathSegment::Content];
+    let text: String = path.iter().map(ToString::to_string).collect();
+    assert_eq!(text, r#"["a"][2]()"#);
+}
+
+#[test]
+fn test_encoded_range() {
+    let value =
+        parse_diag_value(r#"[{"z": 1, "y": 24(h'00')}, 1("x"), 3]"#).unwrap();
+    let data = value.to_cbor().to_cbor_data();
+    let bytes = |path: &[PathSegment]| {
+        value.encoded_range(path).map(|range| hex::encode(&data[range]))
+    };
+    assert_eq!(bytes(&[]), Some(hex::encode(&data)));
+    assert_eq!(bytes(&[PathSegment::Index(2)]).as_deref(), Some("03"));
+    assert_eq!(
+        bytes(&[PathSegment::Index(1), PathSegment::Content]).as_deref(),
+        Some("6178")
+    );
+    let y = [PathSegment::Index(0), PathSegment::Key(CBOR::from("y"))];
+    assert_eq!(bytes(&y).as_deref(), Some("d8184100"));
+    let z = [PathSegment::Index(0), PathSegment::Key(CBOR::from("z"))];
+    assert_eq!(bytes(&z).as_deref(), Some("01"));
+    assert_eq!(bytes(&[PathSegment::Index(3)]), None);
+    assert_eq!(bytes(&[PathSegment::Content]), None);
+}
@@ -0,0 +1,403 @@
+use bc_ur::prelude::*;
+use dcbor_parse::{
+    ExponentNotation, FloatPrecision, ParseError, PrintOptions, Redaction,
+    format_diag_value, format_diagnostic, parse_and_format, parse_dcbor_item,
+    parse_diag_value, preview, write_diagnostic,
+};
+
+fn float(value: f64, options: &PrintOptions) -> String {
+    format_diagnostic(&CBOR::from(value), options)
+}
+
+#[test]
+fn test_default_options_match_diagnostic() {
+    dcbor::register_tags();
+    let sources = [
+        "1.5",
+        "-0.0001",
+        "0.00001",
+        "1e15",
+        "1e16",
+        "1.2345e300",
+        "-5e-324",
+        "NaN",
+        "-Infinity",
+        r#"["a", h'0102', 1.5, true, null]"#,
+        r#"{"key": [1, 2, 3], "other": {"nested": 1.25}}"#,
+        r#"[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, "long string value"]"#,
+        r#"1234(["a", "b"])"#,
+        "[[], {}, 1(2)]",
+    ];
+    for src in sources {
+        let cbor = parse_dcbor_item(src).unwrap();
+        let default = PrintOptions::default();
+        assert_eq!(format_diagnostic(&cbor, &default), cbor.diagnostic());
+        let flat = PrintOptions::default().flat(true);
+        assert_eq!(format_diagnostic(&cbor, &flat), cbor.diagnostic_flat());
+    }
+}
+
+#[test]
+fn test_exponent_notation() {
+    let never = PrintOptions::default()
+        .exponent_notation(ExponentNotation::Never);
+    assert_eq!(float(1e20, &never), "100000000000000000000.0");
+    assert_eq!(float(1.5e-7, &never), "0.00000015");
+    assert_eq!(float(-2.5, &never), "-2.5");
+
+    let always = PrintOptions::default()
+        .exponent_notation(ExponentNotation::Always);
+    assert_eq!(float(1234.5, &always), "1.2345e3");
+    assert_eq!(float(0.5, &always), "5e-1");
+
+    let outside = PrintOptions::default()
+        .exponent_notation(ExponentNotation::Outside { min: -2, max: 3 });
+    assert_eq!(float(123.5, &outside), "123.5");
+    assert_eq!(float(1234.5, &outside), "1.2345e3");
+    assert_eq!(float(0.015, &outside), "0.015");
+    assert_eq!(float(0.0015, &outside), "1.5e-3");
+}
+
+#[test]
+fn test_float_precision() {
+    let three = PrintOptions::default()
+        .float_precision(FloatPrecision::Significant(3));
+    assert_eq!(float(1.23456, &three), "1.23");
+    assert_eq!(float(2.0 / 3.0, &three), "0.667");
+    assert_eq!(float(1.5, &three), "1.5");
+    assert_eq!(float(99999.5, &three), "100000.0");
+    assert_eq!(float(f64::NAN, &three), "NaN");
+
+    // More digits than an f64 holds are written as 17.
+    let many = PrintOptions::default()
+        .float_precision(FloatPrecision::Significant(70_000));
+    assert_eq!(float(0.1, &many), "0.10000000000000001");
+    assert_eq!(float(1.5, &many), "1.5");
+    let zero = PrintOptions::default()
+        .float_precision(FloatPrecision::Significant(0));
+    assert_eq!(float(1.5, &zero), "2.0");
+
+    let shortest = PrintOptions::default();
+    assert_eq!(float(0.1 + 0.2, &shortest), "0.30000000000000004");
+    let value: f64 = format_diagnostic(&CBOR::from(0.1 + 0.2), &shortest)
+        .parse()
+        .unwrap();
+    assert_eq!(value, 0.1 + 0.2);
+}
+
+#[test]
+fn test_annotate_known_values() {
+    let cbor = parse_dcbor_item("['isA', '45', 40001(1)]").unwrap();
+    let options = PrintOptions::default().annotate_known_values(true);
+    assert_eq!(
+        format_diagnostic(&cbor, &options),
+        indoc::indoc! {"
+            [
+                40000(1),   / 'isA' (1, General 0-49) /
+                40000(45),   / '45' (unregistered, General 0-49) /
+                40001(1)
+            ]"}
+    );
+    assert_eq!(
+        format_diagnostic(&cbor, &options.flat(true)),
+        "[40000(1) / 'isA' (1, General 0-49) /, 40000(45) / '45' \
+         (unregistered, General 0-49) /, 40001(1)]"
+    );
+}
+
+#[test]
+fn test_deterministic() {
+    let src = r#"{"k": ['isA', 1e-7, 123456789012345680000.0, NaN, -0.5]}"#;
+    let cbor = parse_dcbor_item(src).unwrap();
+    let options = PrintOptions::default()
+        .annotate_known_values(true)
+        .deterministic(true);
+    let output = format_diagnostic(&cbor, &options);
+    assert_eq!(
+        output,
+        "{\n    \"k\":\n    [\n        40000(1),\n        1e-7,\n        \
+         1.2345678901234568e20,\n        NaN,\n        -0.5\n    ]\n}"
+    );
+    assert!(!output.contains('\r'));
+}
+
+/// Records how much text was written, and the largest single write.
+#[derive(Default)]
+struct Sink {
+    len: usize,
+    largest_write: usize,
+}
+
+impl std::fmt::Write for Sink {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.len += s.len();
+        self.largest_write = self.largest_write.max(s.len());
+        Ok(())
+    }
+}
+
+#[test]
+fn test_write_diagnostic_streams_large_values() {
+    let bytes = CBOR::to_byte_string(vec![0xab; 1 << 20]);
+    let text = CBOR::from("\"quoted\" ".repeat(1 << 16));
+    let cbor = CBOR::from(vec![bytes, text, CBOR::from(1)]);
+    let mut sink = Sink::default();
+    write_diagnostic(&cbor, &mut sink, &PrintOptions::default()).unwrap();
+    assert_eq!(sink.len, cbor.diagnostic().len());
+    assert!(sink.largest_write <= 8192, "{}", sink.largest_write);
+}
+
+#[test]
+fn test_write_diagnostic_matches_format_diagnostic() {
+    let cbor = parse_dcbor_item(
+        r#"{"a": [1, 2, {"b": h'0102'}], 1(2): [[]], "q\"uote": 1.5}"#,
+    )
+    .unwrap();
+    for options in [PrintOptions::default(), PrintOptions::default().flat(true)]
+    {
+        let mut output = String::new();
+        write_diagnostic(&cbor, &mut output, &options).unwrap();
+        assert_eq!(output, format_diagnostic(&cbor, &options));
+    }
+    assert_eq!(
+        format_diagnostic(&cbor, &PrintOptions::default()),
+        cbor.diagnostic()
+    );
+}
+
+#[test]
+fn test_parse_and_format() {
+    let src = "[1.5, {\"b\": 2, \"a\": h'0102'}]";
+    let (cbor, text) = parse_and_format(src, &PrintOptions::default()).unwrap();
+    assert_eq!(cbor, parse_dcbor_item(src).unwrap());
+    assert_eq!(text, format_diagnostic(&cbor, &PrintOptions::default()));
+    assert_eq!(
+        parse_and_format("[1 2]", &PrintOptions::default()).map(|r| r.1),
+        Err(ParseError::ExpectedComma((3..4).into()))
+    );
+}
+
+#[test]
+fn test_redaction() {
+    let cbor = CBOR::from(vec![
+        CBOR::to_byte_string(hex::decode("a1b2c3d4e5f60000ff00").unwrap()),
+        CBOR::from("short"),
+        CBOR::from("a \"quoted\" secret phrase"),
+    ]);
+    let elide = PrintOptions::default()
+        .flat(true)
+        .redaction(Redaction::Elide { threshold: 8 });
+    assert_eq!(
+        format_diagnostic(&cbor, &elide),
+        r#"[h'a1b2…(10 bytes)…ff00', "short", "a \"q…(24 bytes)…rase"]"#
+    );
+
+    let hash = PrintOptions::default()
+        .flat(true)
+        .redaction(Redaction::Hash { threshold: 16 });
+    let digest = hex::encode(bc_crypto::sha256("a \"quoted\" secret phrase"));
+    assert_eq!(
+        format_diagnostic(&cbor, &hash),
+        format!(
+            r#"[h'a1b2c3d4e5f60000ff00', "short", ELIDED(sha256: {digest})]"#
+        )
+    );
+
+    let none = PrintOptions::default().flat(true);
+    assert_eq!(format_diagnostic(&cbor, &none), cbor.diagnostic_flat());
+}
+
+#[test]
+fn test_redaction_affects_layout() {
+    let cbor = CBOR::from(vec![CBOR::to_byte_string(vec![0; 100])]);
+    let options =
+        PrintOptions::default().redaction(Redaction::Elide { threshold: 4 });
+    assert_eq!(
+        format_diagnostic(&cbor, &options),
+        indoc::indoc! {"
+            [
+                h'0000…(100 bytes)…0000'
+            ]"}
+    );
+    let cbor = CBOR::from(vec![CBOR::to_byte_string(vec![0; 4])]);
+    assert_eq!(format_diagnostic(&cbor, &options), "[h'00000000']");
+}
+
+#[test]
+fn test_preview() {
+    let cbor = parse_dcbor_item(
+        r#"{"list": [1, 2, 3, 4, 5], "tagged": 1(2(3)), "empty": []}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        preview(&cbor, 1, 2),
+        r#"{"list": […], "empty": [], …}"#
+    );
+    assert_eq!(
+        preview(&cbor, 3, 3),
+        r#"{"list": [1, 2, 3, …], "empty": [], "tagged": 1(2(3))}"#
+    );
+    assert_eq!(preview(&cbor, 10, 10), cbor.diagnostic_flat());
+}
+
+#[test]
+fn test_limits_in_multiline_output() {
+    let cbor = parse_dcbor_item(r#"[[1, 2, 3], "a long string value"]"#)
+        .unwrap();
+    let options = PrintOptions::default().max_items(Some(1));
+    assert_eq!(
+        format_diagnostic(&cbor, &options),
+        indoc::indoc! {"
+            [
+                [1, …],
+                …
+            ]"}
+    );
+    let options = PrintOptions::default().max_depth(Some(1));
+    assert_eq!(
+        format_diagnostic(&cbor, &options),
+        indoc::indoc! {r#"
+            [
+                […],
+                "a long string value"
+            ]"#}
+    );
+}
+
+#[test]
+fn test_format_diag_value_keeps_surface_forms() {
+    dcbor::register_tags();
+    let ur = Date::from_ymd(2025, 5, 15).ur_string();
+    let src = format!("[{ur}, 2023-02-08T15:30:45Z, '', 'isA', 1(0)]");
+    let value = parse_diag_value(&src).unwrap();
+    let flat = PrintOptions::default().flat(true);
+    assert_eq!(format_diag_value(&value, &flat), src);
+
+    // Surface forms are atoms, so they don't force a group onto several
+    // lines.
+    let value = parse_diag_value("['isA', 'isA']").unwrap();
+    let options = PrintOptions::default();
+    assert_eq!(format_diag_value(&value, &options), "['isA', 'isA']");
+    assert_eq!(
+        format_diagnostic(&value.to_cbor(), &options),
+        indoc::indoc! {"
+            [
+                40000(1),
+                40000(1)
+            ]"}
+    );
+
+    // Known values written in their surface form aren't annotated.
+    let value = parse_diag_value("{'1': 40000(1)}").unwrap();
+    let options = PrintOptions::default().annotate_known_values(true);
+    assert_eq!(
+        format_diag_value(&value, &options),
+        indoc::indoc! {"
+            {
+                '1':
+                40000(1)   / 'isA' (1, General 0-49) /
+            }"}
+    );
+}
+
+#[test]
+fn test_format_diag_value_preserves_map_order() {
+    let src = r#"{"z": {3: 'isA', 1: [2, 1]}, "a": 1, 2.0: {}}"#;
+    let value = parse_diag_value(src).unwrap();
+    let flat = PrintOptions::default().flat(true);
+    assert_eq!(
+        format_diag_value(&value, &flat),
+        r#"{2: {}, "a": 1, "z": {1: [2, 1], 3: 'isA'}}"#
+    );
+    let preserved = flat.clone().preserve_map_order(true);
+    assert_eq!(
+        format_diag_value(&value, &preserved),
+        r#"{"z": {3: 'isA', 1: [2, 1]}, "a": 1, 2: {}}"#
+    );
+    // format_diagnostic has no source order to keep.
+    assert_eq!(
+        format_diagnostic(&value.to_cbor(), &preserved),
+        r#"{2: {}, "a": 1, "z": {1: [2, 1], 3: 40000(1)}}"#
+    );
+
+    // Item limits apply to the entries in the order they are written.
+    let options = preserved.max_items(Some(1));
+    assert_eq!(
+        format_diag_value(&value, &options),
+        r#"{"z": {3: 'isA', …}, …}"#
+    );
+
+    let value = parse_diag_value(r#"{"b": [1, 2, 3, 4, 5, 6], "a": 1}"#)
+        .unwrap();
+    let options = PrintOptions::default().preserve_map_order(true);
+    assert_eq!(
+        format_diag_value(&value, &options),
+        indoc::indoc! {r#"
+            {
+                "b":
+                [1, 2, 3, 4, 5, 6],
+                "a":
+                1
+            }"#}
+    );
+}
+
+#[test]
+fn test_known_value_names() {
+    let src = "{1: {'isA': 'Seed', 'note': Unit, 'date': '100000'}, \
+               2: ['isA', 40000(2)]}";
+    let cbor = parse_dcbor_item(src).unwrap();
+    let options = PrintOptions::default().known_value_names(true);
+    let diag = format_diagnostic(&cbor, &options);
+    assert_eq!(
+        diag,
+        "{\n    1:\n    {\n        'isA': 'Seed',\n        \
+         'note': '',\n        'date': '100000'\n    },\n    \
+         2:\n    ['isA', 'id']\n}"
+    );
+    assert_eq!(parse_dcbor_item(&diag).unwrap(), cbor);
+
+    // Short maps of known values fit on one line.
+    let cbor = parse_dcbor_item("{'isA': 'Seed'}").unwrap();
+    assert_eq!(format_diagnostic(&cbor, &options), "{'isA': 'Seed'}");
+    assert_eq!(
+        format_diagnostic(&cbor, &options.clone().deterministic(true)),
+        "{'1': '200'}"
+    );
+    assert_eq!(
+        format_diagnostic(&cbor, &options.clone().annotate_known_values(true)),
+        "{'isA': 'Seed'}"
+    );
+}
+
+#[test]
+fn test_collapse_tag_chains() {
+    dcbor::register_tags();
+    let options = PrintOptions::default().collapse_tag_chains(true);
+    // A chain ends at the first unregistered tag.
+    let cbor = parse_dcbor_item("[date(date(1)), 1(2(1))]").unwrap();
+    let diag = format_diagnostic(&cbor, &options.clone().flat(true));
+    assert_eq!(diag, "[date(date(1)), 1(2(1))]");
+    assert_eq!(parse_dcbor_item(&diag).unwrap(), cbor);
+
+    let cbor = parse_dcbor_item("date(date([1, [2]]))").unwrap();
+    assert_eq!(
+        format_diagnostic(&cbor, &options),
+        "date(date(\n    [\n        1,\n        [2]\n    ]\n))"
+    );
+    assert_eq!(
+        format_diagnostic(&cbor, &options.clone().flat(true)),
+        "date(date([1, [2]]))"
+    );
+    assert_eq!(
+        format_diagnostic(&cbor, &options.clone().deterministic(true)),
+        format_diagnostic(&cbor, &PrintOptions::default())
+    );
+
+    // A date literal within a chain keeps its surface form.
+    let value = parse_diag_value("date(date(2023-02-08))").unwrap();
+    assert_eq!(
+        format_diag_value(&value, &options),
+        "date(date(2023-02-08))"
+    );
+}
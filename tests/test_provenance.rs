@@ -0,0 +1,19 @@
+use dcbor_parse::{UrProvenance, parse_diag_value};
+
+#[test]
+fn test_ur_provenance_in_nested_items() {
+    dcbor::register_tags();
+    let src = "{ur:date/cyisdadmlasgtapttl: [1(ur:date/cyisdadmlasgtapttl)]}";
+    let value = parse_diag_value(src).unwrap();
+    let urs = UrProvenance::collect(&value);
+    let spans: Vec<_> = urs.iter().map(|ur| ur.span).collect();
+    assert_eq!(spans, [1..27, 32..58]);
+    assert!(urs.iter().all(|ur| ur.ur_type == "date" && ur.part.is_none()));
+}
+
+#[test]
+fn test_no_ur_provenance_for_tagged_values() {
+    dcbor::register_tags();
+    let value = parse_diag_value("[1(1675854714), date(1)]").unwrap();
+    assert!(UrProvenance::collect(&value).is_empty());
+}
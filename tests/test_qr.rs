@@ -0,0 +1,67 @@
+use bc_ur::prelude::*;
+use dcbor_parse::{QrErrorCorrection, QrFit, qr_fit};
+
+fn bytes_ur(len: usize) -> UR {
+    UR::new("bytes", CBOR::to_byte_string(vec![0xab; len])).unwrap()
+}
+
+#[test]
+fn test_qr_fit_single() {
+    // "UR:BYTES/" and a one-byte header, four-byte checksum, and two
+    // characters per byte.
+    let ur = bytes_ur(1);
+    assert_eq!(ur.qr_string().len(), 21);
+    assert_eq!(
+        qr_fit(&ur, 40, QrErrorCorrection::Low),
+        Some(QrFit::Single { version: 1 })
+    );
+    assert_eq!(
+        qr_fit(&ur, 40, QrErrorCorrection::Medium),
+        Some(QrFit::Single { version: 2 })
+    );
+    // Versions past 40 are treated as 40.
+    assert_eq!(
+        qr_fit(&ur, 100, QrErrorCorrection::Medium),
+        Some(QrFit::Single { version: 2 })
+    );
+}
+
+#[test]
+fn test_qr_fit_multi_part() {
+    let ur = bytes_ur(5000);
+    for correction in [
+        QrErrorCorrection::Low,
+        QrErrorCorrection::Medium,
+        QrErrorCorrection::Quartile,
+        QrErrorCorrection::High,
+    ] {
+        let Some(QrFit::MultiPart { parts, max_fragment_len, version }) =
+            qr_fit(&ur, 20, correction)
+        else {
+            panic!("expected a multi-part fit for {correction:?}");
+        };
+        assert!(version <= 20);
+        let mut encoder = MultipartEncoder::new(&ur, max_fragment_len).unwrap();
+        assert_eq!(encoder.parts_count(), parts);
+        let longest = (0..parts)
+            .map(|_| encoder.next_part().unwrap().len())
+            .max()
+            .unwrap();
+
+        // One more byte per fragment doesn't fit.
+        let mut larger =
+            MultipartEncoder::new(&ur, max_fragment_len + 1).unwrap();
+        let larger_longest = (0..larger.parts_count())
+            .map(|_| larger.next_part().unwrap().len())
+            .max()
+            .unwrap();
+        assert!(larger_longest > longest);
+    }
+}
+
+#[test]
+fn test_qr_fit_too_small() {
+    let ur = bytes_ur(1000);
+    assert_eq!(qr_fit(&ur, 3, QrErrorCorrection::High), None);
+    assert_eq!(qr_fit(&ur, 0, QrErrorCorrection::Low), None);
+}
@@ -0,0 +1,120 @@
+use dcbor::prelude::*;
+use dcbor_parse::{
+    ParseError, PathSegment, PrintOptions, SourceSpan, parse_dcbor_item,
+    reformat_edited, replace_at,
+};
+use indoc::indoc;
+
+fn reformat(src: &str, edited: &str) -> String {
+    let edited = parse_dcbor_item(edited).unwrap();
+    reformat_edited(src, &edited, &PrintOptions::default()).unwrap()
+}
+
+#[test]
+fn test_reformat_unchanged() {
+    let src = "  # header\n{2: 0x10(1.0), 1: [h'00']}  # trailer\n";
+    assert_eq!(reformat(src, "{1: [h'00'], 2: 16(1)}"), src);
+}
+
+#[test]
+fn test_reformat_arrays() {
+    let src = "[1, # one\n 2, 3]";
+    assert_eq!(reformat(src, "[1, 3]"), "[1, # one\n 3]");
+    assert_eq!(reformat(src, "[2, 3]"), "[2, 3]");
+    assert_eq!(reformat(src, "[1, 2, 3, 4]"), "[1, # one\n 2, 3, 4]");
+    assert_eq!(reformat(src, "[1, 5, 3]"), "[1, # one\n 5, 3]");
+    assert_eq!(reformat(src, "[0, 1, 2, 3]"), "[0, 1, # one\n 2, 3]");
+    assert_eq!(reformat(src, "[]"), "[]");
+    assert_eq!(reformat("[]", "[1]"), "[1]");
+    assert_eq!(reformat("[1]", "[1, 2]"), "[1, 2]");
+}
+
+#[test]
+fn test_reformat_nested() {
+    let src = indoc! {r#"
+        {
+            "name": "Alice",   # aligned
+            "tags": [
+                "a",
+                "b"
+            ],
+            "date": 1(1675814400)
+        }
+    "#};
+    let edited = r#"{
+        "name": "Alice",
+        "tags": ["a", "c", "d"],
+        "date": 1(1675900800)
+    }"#;
+    assert_eq!(
+        reformat(src, edited),
+        indoc! {r#"
+            {
+                "name": "Alice",   # aligned
+                "tags": [
+                    "a",
+                    "c",
+                    "d"
+                ],
+                "date": 1(1675900800)
+            }
+        "#}
+    );
+    let edited = r#"{"name": "Bob", "age": 30}"#;
+    assert_eq!(
+        reformat(src, edited),
+        "{\n    \"name\": \"Bob\",   # aligned\n    \"age\": 30\n}\n"
+    );
+}
+
+#[test]
+fn test_reformat_replaces_changed_forms() {
+    // Values whose shape changed are written afresh, flat.
+    let options = PrintOptions::default();
+    let edited = CBOR::from(vec![vec![1, 2]]);
+    assert_eq!(
+        reformat_edited("[{1: 2}]", &edited, &options).unwrap(),
+        "[[1, 2]]"
+    );
+    assert_eq!(reformat("0x10(1)", "17(1)"), "17(1)");
+    assert!(reformat_edited("[1", &edited, &options).is_err());
+}
+
+#[test]
+fn test_replace_at() {
+    let src = indoc! {r#"
+        # fixture
+        [
+            1,  # first
+            0x10("a"),
+            {"k": [true]}
+        ]
+    "#};
+    let path = [PathSegment::Index(1), PathSegment::Content];
+    let replaced = replace_at(src, &path, "  # ignored\n h'00' # too\n");
+    assert_eq!(replaced.unwrap(), src.replace("\"a\"", "h'00'"));
+    let path = [
+        PathSegment::Index(2),
+        PathSegment::Key(CBOR::from("k")),
+        PathSegment::Index(0),
+    ];
+    let replaced = replace_at(src, &path, "{1: 2}").unwrap();
+    assert_eq!(replaced, src.replace("true", "{1: 2}"));
+    assert_eq!(replace_at(src, &[], "null").unwrap(), "# fixture\nnull\n");
+}
+
+#[test]
+fn test_replace_at_errors() {
+    let src = "[1, 2(3)]";
+    let path = [PathSegment::Index(0), PathSegment::Content];
+    let error = replace_at(src, &path, "4").unwrap_err();
+    assert_eq!(
+        error,
+        ParseError::PathNotFound("[0]()".to_string(), SourceSpan::new(1, 2))
+    );
+    assert_eq!(error.code(), "path-not-found");
+    // Errors in the new item are reported with spans within it.
+    let error = replace_at(src, &[PathSegment::Index(0)], "[1 2]").unwrap_err();
+    assert_eq!(error, ParseError::ExpectedComma(SourceSpan::new(3, 4)));
+    assert!(replace_at("[1", &[], "2").is_err());
+}
@@ -0,0 +1,62 @@
+use dcbor_parse::{
+    ParseError, ParseOptions, known_value_info, parse_dcbor_item,
+    parse_dcbor_item_with_options,
+};
+use known_values::{IS_A, KnownValuesStore};
+
+// Poisoning the registries affects every test in this binary, so this file
+// has a single test.
+#[test]
+fn test_unavailable_registries_are_errors() {
+    dcbor::register_tags();
+    assert!(parse_dcbor_item("['isA', date(1)]").is_ok());
+
+    // A thread that panics while holding a registry's lock poisons it.
+    std::thread::spawn(|| {
+        let _binding = known_values::KNOWN_VALUES.get();
+        panic!("poison the known values registry");
+    })
+    .join()
+    .unwrap_err();
+    let error = parse_dcbor_item("[1, 'isA']").unwrap_err();
+    let span = (4..9).into();
+    assert_eq!(error, ParseError::KnownValuesRegistryUnavailable(span));
+    assert_eq!(error.code(), "known-values-registry-unavailable");
+    assert_eq!(error.to_string(), "The known values registry is unavailable");
+    // Known values written as numbers don't need the registry.
+    assert!(parse_dcbor_item("'1'").is_ok());
+    assert_eq!(known_value_info(1).name, None);
+    // A registry supplied with the options is used instead.
+    let options = ParseOptions::default()
+        .known_values(KnownValuesStore::new([IS_A]))
+        .registered_known_values_only(true);
+    let parse = |src| parse_dcbor_item_with_options(src, &options);
+    assert!(parse("['isA', '1']").is_ok());
+    assert_eq!(
+        parse("'2'").unwrap_err().to_string(),
+        "Known value '2' (unregistered, General 0-49) is not allowed"
+    );
+
+    std::thread::spawn(|| {
+        let _binding = dcbor::GLOBAL_TAGS.get();
+        panic!("poison the tags registry");
+    })
+    .join()
+    .unwrap_err();
+    assert_eq!(
+        parse_dcbor_item("[date(1)]"),
+        Err(ParseError::TagsRegistryUnavailable((1..5).into()))
+    );
+    assert!(parse_dcbor_item("1(1)").is_ok());
+
+    // Data-only parsing never reads either registry.
+    let options = ParseOptions::default()
+        .data_only(true)
+        .registered_known_values_only(true);
+    let parse = |src| parse_dcbor_item_with_options(src, &options);
+    assert!(parse("[1(1), 40000(100000), {\"a\": h'00'}]").is_ok());
+    assert!(matches!(
+        parse("[date(1)]"),
+        Err(ParseError::SyntaxDisabled(_, _))
+    ));
+}
@@ -0,0 +1,64 @@
+use dcbor::prelude::*;
+use dcbor_parse::{parse_dcbor_item, to_rust_literal};
+
+fn literal(src: &str) -> String {
+    to_rust_literal(&parse_dcbor_item(src).unwrap())
+}
+
+#[test]
+fn test_rust_literal_leaves() {
+    assert_eq!(literal("0"), "CBOR::from(0u64)");
+    assert_eq!(literal("-1"), "CBOR::from(-1i64)");
+    assert_eq!(literal("1.5"), "CBOR::from(1.5f64)");
+    assert_eq!(literal("-1e300"), "CBOR::from(-1e300f64)");
+    assert_eq!(literal("NaN"), "CBOR::from(f64::NAN)");
+    assert_eq!(literal("-Infinity"), "CBOR::from(f64::NEG_INFINITY)");
+    assert_eq!(literal("true"), "CBOR::r#true()");
+    assert_eq!(literal("null"), "CBOR::null()");
+    assert_eq!(literal(r#""🌎""#), r#"CBOR::from("🌎")"#);
+    assert_eq!(literal("h''"), r#"CBOR::to_byte_string_from_hex("")"#);
+    assert_eq!(literal("[]"), "CBOR::from(Vec::<CBOR>::new())");
+    assert_eq!(literal("{}"), "CBOR::from(Map::new())");
+}
+
+#[test]
+fn test_rust_literal_large_negative() {
+    let cbor = CBOR::from(CBORCase::Negative(u64::MAX));
+    assert_eq!(
+        to_rust_literal(&cbor),
+        "CBOR::from(CBORCase::Negative(18446744073709551615))"
+    );
+}
+
+#[test]
+fn test_rust_literal_nested() {
+    let src = r#"[1("x"), {2: [3.5, {}]}, 'isA']"#;
+    let code = literal(src);
+    let expected = r#"CBOR::from(vec![
+    CBOR::to_tagged_value(1, CBOR::from("x")),
+    {
+        let mut map = Map::new();
+        map.insert(CBOR::from(2u64), CBOR::from(vec![
+            CBOR::from(3.5f64),
+            CBOR::from(Map::new()),
+        ]));
+        CBOR::from(map)
+    },
+    CBOR::to_tagged_value(40000, CBOR::from(1u64)),
+])"#;
+    assert_eq!(code, expected);
+
+    let rebuilt = CBOR::from(vec![
+        CBOR::to_tagged_value(1, CBOR::from("x")),
+        {
+            let mut map = Map::new();
+            map.insert(
+                CBOR::from(2u64),
+                CBOR::from(vec![CBOR::from(3.5f64), CBOR::from(Map::new())]),
+            );
+            CBOR::from(map)
+        },
+        CBOR::to_tagged_value(40000, CBOR::from(1u64)),
+    ]);
+    assert_eq!(rebuilt, parse_dcbor_item(src).unwrap());
+}
@@ -0,0 +1,121 @@
+use bc_ur::prelude::*;
+use dcbor_parse::{
+    ParseError, ParseOptions, Session, SessionDiagnostic, Warning,
+    WarningKind,
+};
+
+#[test]
+fn test_session_accumulates_diagnostics() {
+    let mut session = Session::new();
+    assert!(session.add("one", "[1, 2]"));
+    assert!(!session.add("two", "[1, 2"));
+    assert!(session.add("three", "['isA', '46', '47']"));
+    let names: Vec<_> = session.names().collect();
+    assert_eq!(names, ["one", "two", "three"]);
+    assert_eq!(session.error_count(), 1);
+    let documents: Vec<_> =
+        session.diagnostics().iter().map(|d| d.document()).collect();
+    assert_eq!(documents, ["two", "three", "three"]);
+    assert_eq!(
+        session.diagnostics()[0],
+        SessionDiagnostic::Error {
+            document: "two".to_string(),
+            error: ParseError::UnexpectedEndOfInput,
+        }
+    );
+}
+
+#[test]
+fn test_session_replace_and_remove() {
+    let mut session = Session::new();
+    session.add("a", "{1: 2, 1: 3}");
+    assert_eq!(session.error_count(), 1);
+    assert!(session.add("a", "{1: 2}"));
+    assert_eq!(session.error_count(), 0);
+    assert_eq!(session.source("a"), Some("{1: 2}"));
+    assert_eq!(session.value("a").unwrap().span(), 0..6);
+    assert!(session.remove("a"));
+    assert!(!session.remove("a"));
+    assert!(session.get("a").is_none());
+    assert_eq!(session.report(), "");
+}
+
+#[test]
+fn test_session_pinned_registries() {
+    dcbor::register_tags();
+    let register = |value, name| {
+        dcbor::with_tags_mut!(|tags: &mut dcbor::TagsStore| {
+            tags.insert(dcbor::Tag::new(value, name));
+        });
+    };
+    register(91001, "session-moved");
+    register(91002, "session-kept");
+    let mut pinned = Session::new().pin_registries();
+    let mut unpinned = Session::new();
+    register(91003, "session-moved");
+    register(91004, "session-added");
+    let ur = UR::new("session-added", CBOR::from(1)).unwrap().string();
+    let src = format!(
+        "[session-kept(1), date(2), 'isA', {{session-moved(3): {ur}}}]"
+    );
+    for session in [&mut pinned, &mut unpinned] {
+        assert!(session.add("doc", src.as_str()));
+    }
+    assert!(unpinned.diagnostics().is_empty());
+    let warnings: Vec<_> = pinned
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| match diagnostic {
+            SessionDiagnostic::Warning { warning, .. } => warning.clone(),
+            SessionDiagnostic::Error { error, .. } => panic!("{error}"),
+        })
+        .collect();
+    let moved = src.find("session-moved").unwrap();
+    let added = src.find("ur:").unwrap() + 3;
+    assert_eq!(
+        warnings,
+        [
+            Warning {
+                kind: WarningKind::RegistryChanged {
+                    name: "session-moved".to_string(),
+                    pinned: Some(91001),
+                    resolved: 91003,
+                },
+                span: (moved..moved + 13).into(),
+            },
+            Warning {
+                kind: WarningKind::RegistryChanged {
+                    name: "session-added".to_string(),
+                    pinned: None,
+                    resolved: 91004,
+                },
+                span: (added..added + 13).into(),
+            },
+        ]
+    );
+    assert_eq!(
+        warnings[1].kind.to_string(),
+        "'session-added' resolves to 91004, but wasn't registered when the \
+         session began"
+    );
+}
+
+#[test]
+fn test_session_options() {
+    let options = ParseOptions::strict()
+        .include_loader(|_| Ok("['isA', 'note']".to_string()));
+    let mut session = Session::new().with_options(options);
+    assert!(session.add("one", r#"[!include("common"), 1]"#));
+    assert!(!session.add("two", "'123456'"));
+    assert!(!session.add("three", r#"[!include("common"), '123456']"#));
+    assert_eq!(session.error_count(), 2);
+    assert_eq!(
+        session.get("one").unwrap().diagnostic_flat(),
+        "[[40000(1), 40000(4)], 1]"
+    );
+
+    // Without the options, neither restriction applies.
+    let mut session = Session::new();
+    assert!(!session.add("one", r#"[!include("common"), 1]"#));
+    assert!(session.add("two", "'123456'"));
+}
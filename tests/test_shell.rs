@@ -0,0 +1,66 @@
+use std::process::Command;
+
+use dcbor_parse::{Shell, escape_for_single_arg, shell_quote_diag};
+
+/// Returns the single argument a POSIX shell passes for `quoted`.
+fn posix_argument(quoted: &str) -> String {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(format!("printf %s {quoted}"))
+        .output()
+        .unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_posix_round_trip() {
+    let sources = [
+        "42",
+        "",
+        "[1, 2, 3]",
+        r#"{"key": "it's $HOME `ls` \"quoted\" \\"}"#,
+        "'isA'",
+        "''",
+        "ur:date/cyisdadmlasgtapttl",
+        "h'00ff' / comment / # comment\nnext line",
+        "* ? ~ ! & | ; < > ( ) \t",
+    ];
+    for src in sources {
+        assert_eq!(posix_argument(&shell_quote_diag(src)), src, "{src}");
+    }
+}
+
+#[test]
+fn test_posix_quoting() {
+    assert_eq!(shell_quote_diag("42"), "42");
+    assert_eq!(shell_quote_diag("-1.5"), "-1.5");
+    assert_eq!(shell_quote_diag(""), "''");
+    assert_eq!(shell_quote_diag("[1]"), "'[1]'");
+    assert_eq!(shell_quote_diag("'isA'"), r"''\''isA'\'''");
+}
+
+#[test]
+fn test_powershell_quoting() {
+    let quote = |text| escape_for_single_arg(text, Shell::PowerShell);
+    assert_eq!(quote("42"), "42");
+    assert_eq!(quote("-1"), "'-1'");
+    assert_eq!(quote(""), "''");
+    assert_eq!(quote("'isA'"), "'''isA'''");
+    assert_eq!(quote(r#"["$env:HOME", `n]"#), r#"'["$env:HOME", `n]'"#);
+    assert_eq!(quote("‘a’"), "'‘‘a’’'");
+    assert_eq!(quote(r#""a""#), r#"'"a"'"#);
+    assert_eq!(Shell::default(), Shell::Posix);
+}
+
+#[test]
+fn test_windows_powershell_quoting() {
+    let quote = |text| escape_for_single_arg(text, Shell::WindowsPowerShell);
+    assert_eq!(quote("42"), "42");
+    assert_eq!(quote("'isA'"), "'''isA'''");
+    assert_eq!(quote(r#"["a", 1]"#), r#"'[\"a\", 1]'"#);
+    // Backslashes before a quote are doubled, and the escaped quote in a
+    // string keeps its backslash.
+    assert_eq!(quote(r#""a\"b""#), r#"'\"a\\\"b\"'"#);
+    assert_eq!(quote(r"[1, h'00'] \"), r"'[1, h''00''] \\'");
+    assert_eq!(quote(r"a\"), r"'a\'");
+}
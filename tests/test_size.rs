@@ -0,0 +1,57 @@
+use dcbor::prelude::*;
+use dcbor_parse::{
+    ParseError, encoded_size, encoded_size_diag, parse_dcbor_item,
+};
+
+#[test]
+fn test_encoded_size_matches_encoding() {
+    for src in [
+        "0",
+        "23",
+        "24",
+        "-1000",
+        "4294967296",
+        "-18446744073709551616",
+        "1.5",
+        "1.1",
+        "2.0",
+        "NaN",
+        "-Infinity",
+        "true",
+        "null",
+        "h''",
+        "b64'AQIDBAUGBwgJCg=='",
+        r#""""#,
+        r#""Café""#,
+        r#""🌎""#,
+        "[]",
+        "[1, [2, [3]]]",
+        r#"{"b": [1, 2], "a": {1: h'00'}}"#,
+        "1234(\"hello\")",
+        "4294967296([])",
+        "'isA'",
+        "2023-02-08",
+    ] {
+        let cbor = parse_dcbor_item(src).unwrap();
+        assert_eq!(encoded_size(&cbor), cbor.to_cbor_data().len(), "{src}");
+        assert_eq!(encoded_size_diag(src).unwrap(), encoded_size(&cbor));
+    }
+}
+
+#[test]
+fn test_encoded_size_of_long_strings() {
+    for len in [23, 24, 255, 256, 65535, 65536] {
+        let cbor = CBOR::to_byte_string(vec![0; len]);
+        assert_eq!(encoded_size(&cbor), cbor.to_cbor_data().len(), "{len}");
+        let cbor = CBOR::from("a".repeat(len));
+        assert_eq!(encoded_size(&cbor), cbor.to_cbor_data().len(), "{len}");
+    }
+}
+
+#[test]
+fn test_encoded_size_diag_error() {
+    assert!(matches!(
+        encoded_size_diag("[1, 2"),
+        Err(ParseError::UnexpectedEndOfInput)
+    ));
+}
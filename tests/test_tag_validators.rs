@@ -0,0 +1,125 @@
+use bc_ur::prelude::*;
+use dcbor_parse::{
+    ParseError, ParseOptions, TagValidators, parse_dcbor_item,
+    parse_dcbor_item_with_options,
+};
+
+fn parse_standard(src: &str) -> Result<CBOR, ParseError> {
+    let options =
+        ParseOptions::default().tag_validators(TagValidators::standard());
+    parse_dcbor_item_with_options(src, &options)
+}
+
+fn content_error(src: &str) -> String {
+    match parse_standard(src) {
+        Err(ParseError::InvalidTagContent(message, span)) => {
+            format!("{message} at {}", &src[span.range()])
+        }
+        result => panic!("expected InvalidTagContent, got {result:?}"),
+    }
+}
+
+#[test]
+fn test_standard_validators_accept() {
+    let uuid = "37(h'0123456789abcdef0123456789abcdef')";
+    let sources = [
+        r#"0("2023-02-08T15:30:45Z")"#,
+        "1(1675854645)",
+        "1(-1.5)",
+        "2(h'010000000000000000')",
+        "3(h'01')",
+        "24(h'83010203')",
+        r#"32("https://example.com/a?b=c")"#,
+        r#"32("urn:isbn:0451450523")"#,
+        uuid,
+        r#"100("anything")"#,
+    ];
+    for src in sources {
+        assert!(parse_standard(src).is_ok(), "{src}");
+    }
+}
+
+#[test]
+fn test_standard_validators_reject() {
+    assert_eq!(
+        content_error("0(1)"),
+        "0: expected a text date/time string at 1"
+    );
+    assert_eq!(
+        content_error(r#"1("now")"#),
+        r#"1: expected a number of seconds at "now""#
+    );
+    assert_eq!(content_error("2(1)"), "2: expected a byte string at 1");
+    assert_eq!(
+        content_error("24(h'ff')"),
+        "24: expected a byte string of encoded dCBOR at h'ff'"
+    );
+    assert_eq!(
+        content_error(r#"[32("example.com")]"#),
+        r#"32: expected a URI at "example.com""#
+    );
+    assert_eq!(
+        content_error(r#"32("https://a b")"#),
+        r#"32: expected a URI at "https://a b""#
+    );
+    assert_eq!(
+        content_error(r#"{"id": 37([1, 2])}"#),
+        "37: expected a 16-byte byte string at [1, 2]"
+    );
+}
+
+#[test]
+fn test_named_tags_are_validated() {
+    dcbor::register_tags();
+    assert_eq!(
+        content_error(r#"date("now")"#),
+        r#"1: expected a number of seconds at "now""#
+    );
+}
+
+#[test]
+fn test_user_validators() {
+    let mut validators = TagValidators::new();
+    assert!(!validators.contains(1));
+    validators.insert(1000, |item| match item.as_array() {
+        Some(items) if !items.is_empty() => Ok(()),
+        _ => Err("expected a non-empty array".to_string()),
+    });
+    assert!(validators.contains(1000));
+    let options = ParseOptions::default().tag_validators(validators.clone());
+    let parse = |src| parse_dcbor_item_with_options(src, &options);
+    assert!(parse("1000([1])").is_ok());
+    assert!(parse(r#"1("now")"#).is_ok());
+    assert_eq!(
+        parse("[1000([])]"),
+        Err(ParseError::InvalidTagContent(
+            "1000: expected a non-empty array".to_string(),
+            (6..8).into()
+        ))
+    );
+
+    assert!(validators.remove(1000));
+    assert!(!validators.remove(1000));
+    assert_eq!(validators.validate(1000, &CBOR::from(1)), Ok(()));
+
+    // Without validators, any content is accepted.
+    assert!(parse_dcbor_item("37(1)").is_ok());
+}
+
+#[test]
+fn test_ur_payloads_are_validated() {
+    dcbor::register_tags();
+    let valid = Date::from_ymd(2025, 5, 15).ur_string();
+    assert!(parse_standard(&valid).is_ok());
+
+    let invalid = UR::new("date", CBOR::from("now")).unwrap().string();
+    let src = format!("[1, {invalid}]");
+    assert_eq!(
+        parse_standard(&src),
+        Err(ParseError::InvalidTagContent(
+            "1: expected a number of seconds".to_string(),
+            (4..src.len() - 1).into()
+        ))
+    );
+    assert!(parse_dcbor_item(&src).is_ok());
+}
@@ -0,0 +1,78 @@
+use dcbor_parse::{
+    ControlCharacters, ParseError, ParseOptions, SourceSpan, WarningKind,
+    parse_dcbor_item, parse_dcbor_item_from_bytes,
+    parse_dcbor_item_from_bytes_lossy, parse_dcbor_item_with_options,
+};
+
+#[test]
+fn test_invalid_utf8() {
+    let parse = parse_dcbor_item_from_bytes;
+    assert_eq!(
+        parse(b"{1: \"caf\xc3\xa9\"}").unwrap().diagnostic_flat(),
+        "{1: \"caf\u{e9}\"}"
+    );
+    assert_eq!(
+        parse(b"[\"ok\", \"\xe2\x82\"]"),
+        Err(ParseError::InvalidUtf8((8..10).into()))
+    );
+    // A truncated sequence at the end is invalid up to the end.
+    assert_eq!(
+        parse(b"\"abc\xe2\x82"),
+        Err(ParseError::InvalidUtf8((4..6).into()))
+    );
+    let error = parse(b"[\xff]").unwrap_err();
+    assert_eq!(error.code(), "invalid-utf8");
+    assert_eq!(error.to_string(), "Invalid UTF-8");
+}
+
+#[test]
+fn test_nul_character() {
+    let nul = |start| {
+        Err(ParseError::NulCharacter(SourceSpan::new(start, start + 1)))
+    };
+    assert_eq!(parse_dcbor_item("[1,\0 2]"), nul(3));
+    assert_eq!(parse_dcbor_item("\"ab\0\""), nul(3));
+    assert_eq!(parse_dcbor_item("[\"unterminated\0"), nul(14));
+    // NUL characters in comments are ignored.
+    assert!(parse_dcbor_item("[1, # \0\n 2]").is_ok());
+    // In strings they follow the control character policy.
+    let options =
+        ParseOptions::default().control_characters(ControlCharacters::Escape);
+    assert_eq!(
+        parse_dcbor_item_with_options("\"ab\0\"", &options)
+            .unwrap()
+            .diagnostic_flat(),
+        r#""ab\u0000""#
+    );
+}
+
+#[test]
+fn test_lossy_replacement() {
+    let (cbor, warnings) =
+        parse_dcbor_item_from_bytes_lossy(b"{\"k\xc3\": [\"\0\", 1]}")
+            .unwrap();
+    assert_eq!(
+        cbor.diagnostic_flat(),
+        "{\"k\u{fffd}\": [\"\u{fffd}\", 1]}"
+    );
+    let warnings: Vec<_> =
+        warnings.into_iter().map(|w| (w.kind, w.span.range())).collect();
+    assert_eq!(
+        warnings,
+        [
+            (WarningKind::InvalidUtf8 { bytes: vec![0xc3] }, 3..4),
+            (WarningKind::NulCharacter, 9..10),
+        ]
+    );
+    assert_eq!(
+        warnings[0].0.to_string(),
+        "invalid UTF-8 h'c3' replaced with U+FFFD"
+    );
+
+    // Errors after a replacement refer to the bytes as they were given.
+    let error = parse_dcbor_item_from_bytes_lossy(b"[\"\xff\xff\" 1]")
+        .unwrap_err();
+    assert_eq!(error, ParseError::ExpectedComma((6..7).into()));
+    // A replacement outside a string is an unrecognized token.
+    assert!(parse_dcbor_item_from_bytes_lossy(b"[1, \xff]").is_err());
+}
@@ -0,0 +1,146 @@
+use dcbor_parse::{
+    InputFormat, ValidateError, forensic_diagnostic, parse_dcbor_item,
+    validate_dcbor,
+};
+
+fn validate_hex(hex: &str) -> Result<u64, ValidateError> {
+    validate_dcbor(hex.as_bytes(), InputFormat::Hex)
+}
+
+fn violation(hex: &str) -> (String, u64) {
+    match validate_hex(hex) {
+        Err(ValidateError::Violation(error, offset)) => {
+            (format!("{error:?}"), offset)
+        }
+        result => panic!("expected a violation for {hex}, got {result:?}"),
+    }
+}
+
+#[test]
+fn test_validate_valid_items() {
+    let mut data = Vec::new();
+    let sources = [
+        "0",
+        "-1000",
+        "1.5",
+        "NaN",
+        r#""Café""#,
+        "h'0102'",
+        r#"{"b": [1, {2: 3}], "a": 1(1.5), [1]: {}}"#,
+        "[[], [[true, null]], false]",
+    ];
+    for src in sources {
+        data.extend(parse_dcbor_item(src).unwrap().to_cbor_data());
+    }
+    assert_eq!(validate_dcbor(&data[..], InputFormat::Binary).unwrap(), 8);
+    let hex = hex::encode(&data);
+    assert_eq!(validate_hex(&hex).unwrap(), 8);
+    assert_eq!(validate_hex("").unwrap(), 0);
+    assert_eq!(validate_hex(" 82\n01 0\t2 ").unwrap(), 1);
+}
+
+#[test]
+fn test_validate_violations() {
+    assert_eq!(violation("1801"), ("NonCanonicalNumeric".into(), 0));
+    let error = violation("83 01 02 190001");
+    assert_eq!(error, ("NonCanonicalNumeric".into(), 3));
+    assert_eq!(violation("1b00000000ffffffff").0, "NonCanonicalNumeric");
+    assert_eq!(violation("f93c00"), ("NonCanonicalNumeric".into(), 0));
+    assert_eq!(violation("f7"), ("InvalidSimpleValue".into(), 0));
+    let error = violation("9f01ff");
+    assert_eq!(error, ("UnsupportedHeaderValue(31)".into(), 0));
+    assert_eq!(violation("82 01"), ("Underrun".into(), 2));
+    let (error, offset) = violation("8101 62 c328");
+    assert!(error.starts_with("InvalidString"));
+    assert_eq!(offset, 2);
+    // "é" as "e" and a combining accent.
+    assert_eq!(violation("63 65cc81"), ("NonCanonicalString".into(), 0));
+}
+
+#[test]
+fn test_validate_map_keys() {
+    assert_eq!(violation("a2 03 04 01 02"), ("MisorderedMapKey".into(), 3));
+    assert_eq!(violation("a2 01 02 01 03"), ("DuplicateMapKey".into(), 3));
+    // Keys are ordered by their encoding, so 10 comes before [0].
+    assert_eq!(validate_hex("a2 0a 01 8100 02").unwrap(), 1);
+    assert_eq!(violation("a2 8100 01 0a 02"), ("MisorderedMapKey".into(), 4));
+    // Maps nested in keys are checked as well.
+    assert_eq!(
+        violation("a1 a2 02 00 01 00 00"),
+        ("MisorderedMapKey".into(), 4)
+    );
+    assert_eq!(validate_hex("a2 a1 01 00 00 a1 02 00 00").unwrap(), 1);
+    assert_eq!(
+        violation("a2 a1 02 00 00 a1 01 00 00"),
+        ("MisorderedMapKey".into(), 5)
+    );
+}
+
+#[test]
+fn test_validate_invalid_hex() {
+    assert!(matches!(
+        validate_hex("82 0x"),
+        Err(ValidateError::InvalidHex(4))
+    ));
+    let error = validate_hex("820").unwrap_err();
+    assert!(matches!(error, ValidateError::InvalidHex(3)));
+    assert_eq!(error.offset(), Some(3));
+}
+
+fn forensic(hex: &str) -> String {
+    forensic_diagnostic(hex.as_bytes(), InputFormat::Hex).unwrap()
+}
+
+#[test]
+fn test_forensic_diagnostic() {
+    // Valid input is written as usual, one item per line.
+    let hex = "a2 01 82 f5 f6 62 6869 1a00010000  c1 01";
+    assert_eq!(forensic(hex), "{1: [true, null], \"hi\": 65536}\n1(1)");
+    let data = parse_dcbor_item(r#"[-1000, 1.5, h'00ff']"#)
+        .unwrap()
+        .to_cbor_data();
+    let text = forensic_diagnostic(data.as_slice(), InputFormat::Binary);
+    assert_eq!(text.unwrap(), "[-1000, 1.5, h'00ff']");
+    assert_eq!(forensic(""), "");
+
+    // Violations that leave the structure readable are annotated in place.
+    let non_canonical = "/ERROR: a CBOR numeric value was encoded in \
+                         non-canonical form/";
+    assert_eq!(forensic("1801"), format!("1 {non_canonical}"));
+    assert_eq!(
+        forensic("83 01 02 190003"),
+        format!("[1, 2, 3 {non_canonical}]")
+    );
+    assert_eq!(forensic("f93c00"), format!("1.0 {non_canonical}"));
+    assert_eq!(forensic("fb3ff8000000000000"), format!("1.5 {non_canonical}"));
+    assert_eq!(
+        forensic("f7"),
+        "simple(23) /ERROR: an invalid CBOR simple value was encountered/"
+    );
+    assert_eq!(
+        forensic("63 65cc81"),
+        "\"e\u{301}\" /ERROR: a CBOR string was not encoded in Unicode \
+         Canonical Normalization Form C/"
+    );
+    assert!(forensic("62 c328").starts_with("h'c328' /ERROR: an invalidly"));
+    assert_eq!(
+        forensic("a2 01 02 01 03"),
+        "{1: 2, 1 /ERROR: the decoded CBOR map has a duplicate key/: 3}"
+    );
+
+    // Violations that don't end the output where they are found.
+    assert_eq!(
+        forensic("82 01 9f01ff 02"),
+        "[1, /ERROR: unsupported value in CBOR header/"
+    );
+    assert_eq!(forensic("01 82 01"), "1\n[1, /ERROR: early end of CBOR data/");
+    assert_eq!(forensic("5a ffffffff 00"), "/ERROR: early end of CBOR data/");
+    let deep = "81".repeat(1000);
+    let text = forensic(&deep);
+    assert!(text.ends_with("[/ERROR: items nested too deeply to write/"));
+
+    assert!(matches!(
+        forensic_diagnostic("82 0x".as_bytes(), InputFormat::Hex),
+        Err(ValidateError::InvalidHex(4))
+    ));
+}
@@ -0,0 +1,101 @@
+use dcbor_parse::{
+    DiagValue, GroupKind, ParseError, ParseVisitor, SourceSpan, VisitAction,
+    parse_diag_value, parse_diag_value_with_visitor,
+};
+
+/// Records every callback as a line of text.
+#[derive(Default)]
+struct Recorder<'a> {
+    src: &'a str,
+    events: Vec<String>,
+}
+
+impl ParseVisitor for Recorder<'_> {
+    fn begin_group(
+        &mut self,
+        kind: GroupKind,
+        span: SourceSpan,
+        depth: usize,
+    ) -> VisitAction {
+        let text = &self.src[span.range()];
+        self.events.push(format!("{depth} begin {kind:?} {text}"));
+        VisitAction::Continue
+    }
+
+    fn item(&mut self, item: &DiagValue, depth: usize) -> VisitAction {
+        let text = &self.src[item.span().range()];
+        self.events.push(format!("{depth} item {text}"));
+        VisitAction::Continue
+    }
+}
+
+#[test]
+fn test_visitor_callback_order() {
+    let src = r#"{"a": [1, 2(3)]}"#;
+    let mut recorder = Recorder { src, ..Default::default() };
+    let value = parse_diag_value_with_visitor(src, &mut recorder).unwrap();
+    assert_eq!(value, parse_diag_value(src).unwrap());
+    assert_eq!(
+        recorder.events,
+        [
+            "0 begin Map {",
+            r#"1 item "a""#,
+            "1 begin Array [",
+            "2 item 1",
+            "2 begin Tagged 2(",
+            "3 item 3",
+            "2 item 2(3)",
+            "1 item [1, 2(3)]",
+            r#"0 item {"a": [1, 2(3)]}"#,
+        ]
+    );
+}
+
+/// Stops parsing once it has seen a given number of items.
+struct Limit(usize);
+
+impl ParseVisitor for Limit {
+    fn item(&mut self, _item: &DiagValue, _depth: usize) -> VisitAction {
+        if self.0 == 0 {
+            return VisitAction::Stop;
+        }
+        self.0 -= 1;
+        VisitAction::Continue
+    }
+}
+
+#[test]
+fn test_visitor_stop() {
+    let src = "[1, 2, 3, 4]";
+    let error = parse_diag_value_with_visitor(src, &mut Limit(2)).unwrap_err();
+    assert_eq!(error, ParseError::VisitorStopped(SourceSpan::new(7, 8)));
+    assert!(parse_diag_value_with_visitor(src, &mut Limit(5)).is_ok());
+}
+
+/// Rejects maps nested more than one level deep.
+struct Shallow;
+
+impl ParseVisitor for Shallow {
+    fn begin_group(
+        &mut self,
+        kind: GroupKind,
+        _span: SourceSpan,
+        depth: usize,
+    ) -> VisitAction {
+        if kind == GroupKind::Map && depth > 0 {
+            return VisitAction::Reject("nested map".to_string());
+        }
+        VisitAction::Continue
+    }
+}
+
+#[test]
+fn test_visitor_reject() {
+    let src = "{1: [{}]}";
+    let error = parse_diag_value_with_visitor(src, &mut Shallow).unwrap_err();
+    assert_eq!(error.code(), "visitor-rejected");
+    assert_eq!(
+        error.full_message(src),
+        "line 1: Rejected by visitor: nested map\n{1: [{}]}\n     ^"
+    );
+}
@@ -0,0 +1,82 @@
+use dcbor_parse::{
+    KNOWN_VALUE_RANGES, ParseError, SourceSpan, WarningKind, known_value_info,
+    parse_dcbor_item_with_warnings, parse_known_value_range,
+};
+
+fn warnings(src: &str) -> Vec<(WarningKind, SourceSpan)> {
+    let (_, warnings) = parse_dcbor_item_with_warnings(src).unwrap();
+    warnings.into_iter().map(|w| (w.kind, w.span)).collect()
+}
+
+#[test]
+fn test_unregistered_known_values() {
+    assert_eq!(warnings("['isA', '1', '', Unit, '1000']"), []);
+    assert_eq!(
+        warnings("{'45': 40000(46)}"),
+        [
+            (
+                WarningKind::UnregisteredKnownValue { value: 45 },
+                SourceSpan::new(1, 5)
+            ),
+            (
+                WarningKind::UnregisteredKnownValue { value: 46 },
+                SourceSpan::new(7, 16)
+            ),
+        ]
+    );
+    let (kind, _) = &warnings("'999'")[0];
+    assert_eq!(kind.code(), "unregistered-known-value");
+    assert_eq!(
+        kind.to_string(),
+        "unregistered codepoint in reserved range: '999' (unregistered, \
+         Reserved 800-999)"
+    );
+}
+
+#[test]
+fn test_known_value_info() {
+    let info = known_value_info(301);
+    assert_eq!(info.name.as_deref(), Some("Bitcoin"));
+    assert_eq!(info.range.unwrap().description, "Cryptocurrency Assets");
+    assert!(info.is_registered() && info.is_reserved());
+
+    let info = known_value_info(100_000);
+    assert_eq!(info.name, None);
+    assert_eq!(info.range, None);
+    assert!(!info.is_reserved());
+    assert_eq!(info.to_string(), "'100000' (unregistered)");
+}
+
+#[test]
+fn test_parse_known_value_range() {
+    assert_eq!(parse_known_value_range("'0..49'").unwrap(), 0..=49);
+    assert_eq!(parse_known_value_range("'0b1..0o7'").unwrap(), 1..=7);
+    let invalid = |literal: &str, span: (usize, usize)| {
+        let span = SourceSpan::new(span.0, span.1);
+        Err(ParseError::InvalidKnownValueRange(literal.to_string(), span))
+    };
+    assert_eq!(parse_known_value_range(" 0..49 "), invalid("0..49", (1, 6)));
+    assert_eq!(parse_known_value_range("'0-49'"), invalid("'0-49'", (0, 6)));
+    assert_eq!(parse_known_value_range("'2..1'"), invalid("'2..1'", (0, 6)));
+    assert_eq!(parse_known_value_range("  "), Err(ParseError::EmptyInput));
+    let invalid_value = |text: &str, span: (usize, usize)| {
+        let span = SourceSpan::new(span.0, span.1);
+        Err(ParseError::InvalidKnownValue(text.to_string(), span))
+    };
+    assert_eq!(parse_known_value_range("'01..2'"), invalid_value("01", (1, 3)));
+    assert_eq!(parse_known_value_range("'1..+2'"), invalid_value("+2", (4, 6)));
+    assert_eq!(parse_known_value_range("'1...2'"), invalid_value(".2", (4, 6)));
+    assert_eq!(
+        parse_known_value_range("'0..18446744073709551616'"),
+        invalid_value("18446744073709551616", (4, 24))
+    );
+}
+
+#[test]
+fn test_known_value_ranges_are_contiguous() {
+    assert_eq!(KNOWN_VALUE_RANGES[0].start, 0);
+    for pair in KNOWN_VALUE_RANGES.windows(2) {
+        assert_eq!(pair[0].end + 1, pair[1].start);
+    }
+    assert_eq!(KNOWN_VALUE_RANGES.last().unwrap().end, 999);
+}